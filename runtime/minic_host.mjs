@@ -0,0 +1,57 @@
+// the minic stdlib for the module of -wat, once assembled by wat2wasm: node runtime/minic_host.mjs out.wasm
+import fs from "fs";
+
+const input = fs.readFileSync(0);
+let position = 0;
+const output = [];
+let memory;
+let heap;
+
+function bytes() {
+    return new Uint8Array(memory.buffer);
+}
+
+const env = {
+    print_s(address) {
+        const memoryBytes = bytes();
+        let end = address;
+        while (memoryBytes[end] !== 0) end++;
+        output.push(Buffer.from(memoryBytes.subarray(address, end)));
+    },
+    print_i(i) {
+        output.push(Buffer.from(String(i)));
+    },
+    print_c(c) {
+        output.push(Buffer.from([c & 0xff]));
+    },
+    read_c() {
+        return position < input.length ? (input[position++] << 24) >> 24 : -1;
+    },
+    read_i() {
+        // as scanf("%d"), 0 when no number follows
+        while (position < input.length && /\s/.test(String.fromCharCode(input[position]))) position++;
+        let text = "";
+        if (position < input.length && (input[position] === 0x2d || input[position] === 0x2b)) text += String.fromCharCode(input[position++]);
+        while (position < input.length && input[position] >= 0x30 && input[position] <= 0x39) text += String.fromCharCode(input[position++]);
+        const i = parseInt(text, 10);
+        return Number.isNaN(i) ? 0 : i | 0;
+    },
+    mcmalloc(size) {
+        const address = heap;
+        heap = (heap + size + 7) & ~7;
+        if (heap > memory.buffer.byteLength) {
+            memory.grow(Math.ceil((heap - memory.buffer.byteLength) / 65536));
+        }
+        return address;
+    },
+};
+
+const module = new WebAssembly.Module(fs.readFileSync(process.argv[2]));
+const instance = new WebAssembly.Instance(module, { env });
+memory = instance.exports.memory;
+heap = (instance.exports.__heap_base.value + 7) & ~7;
+try {
+    instance.exports.main();
+} finally {
+    fs.writeSync(1, Buffer.concat(output));
+}
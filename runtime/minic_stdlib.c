@@ -0,0 +1,10 @@
+/* the minic stdlib on top of libc, to link the output of -llvm: clang out.ll runtime/minic_stdlib.c */
+#include <stdio.h>
+#include <stdlib.h>
+void print_s(const char* s) { fputs(s, stdout); }
+void print_i(int i) { printf("%d", i); }
+void print_c(char c) { putchar(c); }
+char read_c(void) { fflush(stdout); return (char)getchar(); }
+int read_i(void) { int i = 0; fflush(stdout); if (scanf("%d", &i) != 1) return 0; return i; }
+/* sizes are those of minic, where pointers are 4 bytes */
+void* mcmalloc(int size) { return malloc(2 * (size_t)size); }
@@ -1,9 +1,9 @@
 use std::io::{BufWriter, Stdout, Write};
 use crate::ast::ast_visitor::ASTVisitor;
-use crate::ast::decl::{FunDecl, Program, StructTypeDecl, VarDecl};
-use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, BinOp, ChrLiteral, Expr, FieldAccessExpr, FunCallExpr, IntLiteral, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
-use crate::ast::stmt::{Assign, Block, ExprStmt, If, Return, Stmt, While};
-use crate::ast::types::{ArrayType, BaseType, PointerType, StructType, Type};
+use crate::ast::decl::{ClassDecl, EnumDecl, FunDecl, Initializer, Item, Program, StorageClass, StructTypeDecl, TypedefDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
 use crate::ASTNode;
 
 pub struct ASTPrinter {
@@ -16,250 +16,488 @@ impl ASTPrinter {
             writer
         }
     }
+
+    // the storage class, if any, before the type of a declaration
+    fn print_storage(&mut self, storage: StorageClass) {
+        match storage {
+            StorageClass::Static => self.writer.write_all(b"STATIC,").unwrap(),
+            StorageClass::Extern => self.writer.write_all(b"EXTERN,").unwrap(),
+            StorageClass::Auto => {},
+        }
+    }
+
+    fn print_initializer(&mut self, init: &mut Initializer) {
+        match init {
+            Initializer::Expr(expr) => self.visit_expr(expr),
+            Initializer::List(items) => {
+                self.writer.write_all(b"InitList(").unwrap();
+                for (i, item) in items.iter_mut().enumerate() {
+                    if i != 0 {
+                        self.writer.write_all(b",").unwrap();
+                    }
+                    self.print_initializer(item);
+                }
+                self.writer.write_all(b")").unwrap();
+            }
+        }
+    }
+
+    pub fn print_prototype(&mut self, fun_decl: &mut FunDecl) {
+        self.writer.write_all(b"Prototype(").unwrap();
+        self.print_storage(fun_decl.storage);
+        self.visit_type(&mut fun_decl.fun_type);
+        self.writer.write_all(b",").unwrap();
+        self.writer.write_all(fun_decl.name.as_bytes()).unwrap();
+        for var_decl in fun_decl.params.iter_mut() {
+            self.writer.write_all(b",").unwrap();
+            var_decl.accept(self);
+        }
+        self.writer.write_all(b")").unwrap();
+    }
+
+    pub fn print_typedef_decl(&mut self, typedef_decl: &mut TypedefDecl) {
+        self.writer.write_all(b"TypedefDecl(").unwrap();
+        self.visit_type(&mut typedef_decl.typedef_type);
+        self.writer.write_all(b",").unwrap();
+        self.writer.write_all(typedef_decl.name.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
+    }
+
+    pub fn print_enum_decl(&mut self, enum_decl: &mut EnumDecl) {
+        self.writer.write_all(b"EnumDecl(").unwrap();
+        self.writer.write_all(enum_decl.name.as_bytes()).unwrap();
+        for enumerator in enum_decl.enumerators.iter_mut() {
+            self.writer.write_all(b",Enumerator(").unwrap();
+            self.writer.write_all(enumerator.name.as_bytes()).unwrap();
+            if let Some(expr) = &mut enumerator.expr {
+                self.writer.write_all(b",").unwrap();
+                self.visit_expr(expr);
+            }
+            self.writer.write_all(b")").unwrap();
+        }
+        self.writer.write_all(b")").unwrap();
+    }
 }
 
 impl ASTVisitor<()> for ASTPrinter {
-    fn visit_base_type(&mut self, base_type: &mut BaseType) -> () {
-        self.writer.write(base_type.to_string().as_bytes()).unwrap();
+    fn visit_base_type(&mut self, base_type: &mut BaseType) {
+        self.writer.write_all(base_type.to_string().as_bytes()).unwrap();
     }
 
-    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) -> () {
-        self.writer.write(b"PointerType(").unwrap();
+    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) {
+        self.writer.write_all(b"PointerType(").unwrap();
+        if pointer_type.to_const {
+            self.writer.write_all(b"CONST,").unwrap();
+        }
         self.visit_type(&mut pointer_type.pointer_type);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_struct_type(&mut self, struct_type: &mut StructType) -> () {
-        self.writer.write(b"StructType(").unwrap();
-        self.writer.write(struct_type.name.as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+    fn visit_struct_type(&mut self, struct_type: &mut StructType) {
+        self.writer.write_all(b"StructType(").unwrap();
+        self.writer.write_all(struct_type.name.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_array_type(&mut self, array_type: &mut ArrayType) -> () {
-        self.writer.write(b"ArrayType(").unwrap();
+    fn visit_array_type(&mut self, array_type: &mut ArrayType) {
+        self.writer.write_all(b"ArrayType(").unwrap();
         self.visit_type(&mut array_type.array_type);
-        self.writer.write(b",").unwrap();
-        self.writer.write(array_type.i.to_string().as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b",").unwrap();
+        // a size left out prints as nothing
+        if let Some(i) = array_type.i {
+            self.writer.write_all(i.to_string().as_bytes()).unwrap();
+        }
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_program(&mut self, program: &mut Program) -> () {
-        self.writer.write(b"Program(").unwrap();
-        let mut delimiter = String::from("");
+    fn visit_class_type(&mut self, class_type: &mut ClassType) {
+        self.writer.write_all(b"ClassType(").unwrap();
+        self.writer.write_all(class_type.name.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
+    }
 
-        for struct_decl in program.struct_type_decls.iter_mut() {
-            self.writer.write(delimiter.as_bytes()).unwrap();
-            delimiter = ",".to_string();
-            struct_decl.accept(self);
+    fn visit_fun_type(&mut self, fun_type: &mut FunType) {
+        self.writer.write_all(b"FunType(").unwrap();
+        self.visit_type(&mut fun_type.return_type);
+        for param_type in fun_type.param_types.iter_mut() {
+            self.writer.write_all(b",").unwrap();
+            self.visit_type(param_type);
         }
-        for var_decl in program.var_decls.iter_mut() {
-            self.writer.write(delimiter.as_bytes()).unwrap();
-            delimiter = ",".to_string();
-            var_decl.accept(self);
-        }
-        for fun_decl in program.fun_decls.iter_mut() {
-            self.writer.write(delimiter.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        self.writer.write_all(b"Program(").unwrap();
+        let mut delimiter = String::from("");
+
+        for item in program.ordered_items() {
+            self.writer.write_all(delimiter.as_bytes()).unwrap();
             delimiter = ",".to_string();
-            fun_decl.accept(self);
+            match item {
+                Item::Typedef(i) => self.print_typedef_decl(&mut program.typedef_decls[i]),
+                Item::Enum(i) => self.print_enum_decl(&mut program.enum_decls[i]),
+                Item::Struct(i) => program.struct_type_decls[i].accept(self),
+                Item::Class(i) => program.class_decls[i].accept(self),
+                Item::Var(i) => program.var_decls[i].accept(self),
+                Item::Prototype(i) => self.print_prototype(&mut program.prototypes[i]),
+                Item::Fun(i) => program.fun_decls[i].accept(self),
+            }
         }
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
         self.writer.flush().unwrap();
     }
 
-    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) -> () {
-        self.writer.write(b"StructTypeDecl(").unwrap();
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) {
+        self.writer.write_all(b"StructTypeDecl(").unwrap();
         struct_type_decl.struct_type.accept(self);
 
         for var_decl in struct_type_decl.var_decls.iter_mut() {
-            self.writer.write(b",").unwrap();
+            self.writer.write_all(b",").unwrap();
             var_decl.accept(self);
         }
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) -> () {
-        self.writer.write(b"VarDecl(").unwrap();
+    fn visit_class_decl(&mut self, class_decl: &mut ClassDecl) {
+        self.writer.write_all(b"ClassDecl(").unwrap();
+        class_decl.class_type.accept(self);
+        if let Some(parent) = &mut class_decl.parent {
+            self.writer.write_all(b",").unwrap();
+            parent.accept(self);
+        }
+        for var_decl in class_decl.var_decls.iter_mut() {
+            self.writer.write_all(b",").unwrap();
+            var_decl.accept(self);
+        }
+        for fun_decl in class_decl.fun_decls.iter_mut() {
+            self.writer.write_all(b",").unwrap();
+            fun_decl.accept(self);
+        }
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.writer.write_all(b"VarDecl(").unwrap();
+        self.print_storage(var_decl.storage);
+        if var_decl.is_const {
+            self.writer.write_all(b"CONST,").unwrap();
+        }
         self.visit_type(&mut var_decl.var_type);
-        self.writer.write(b",").unwrap();
-        self.writer.write(var_decl.var_name.as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b",").unwrap();
+        self.writer.write_all(var_decl.var_name.as_bytes()).unwrap();
+        if let Some(init) = &mut var_decl.init {
+            self.writer.write_all(b",").unwrap();
+            self.print_initializer(init);
+        }
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) -> () {
-        self.writer.write(b"FunDecl(").unwrap();
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.writer.write_all(b"FunDecl(").unwrap();
+        self.print_storage(fun_decl.storage);
         self.visit_type(&mut fun_decl.fun_type);
 
-        self.writer.write(b",").unwrap();
-        self.writer.write(fun_decl.name.as_bytes()).unwrap();
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
+        self.writer.write_all(fun_decl.name.as_bytes()).unwrap();
+        self.writer.write_all(b",").unwrap();
         for var_decl in fun_decl.params.iter_mut() {
             var_decl.accept(self);
-            self.writer.write(b",").unwrap();
+            self.writer.write_all(b",").unwrap();
         }
         fun_decl.block.accept(self);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_block(&mut self, block: &mut Block) -> () {
-        self.writer.write(b"Block(").unwrap();
+    fn visit_block(&mut self, block: &mut Block) {
+        self.writer.write_all(b"Block(").unwrap();
         let mut i = 0;
         while i < block.var_decls.len() {
             if i != 0 {
-                self.writer.write(b",").unwrap();
+                self.writer.write_all(b",").unwrap();
             }
             block.var_decls.get_mut(i).unwrap().accept(self);
             i += 1;
         }
 
-        if i != 0 && block.stmts.len() != 0 {
-            self.writer.write(b",").unwrap();
+        if i != 0 && !block.stmts.is_empty() {
+            self.writer.write_all(b",").unwrap();
         }
 
         let mut j = 0;
         while j < block.stmts.len() {
-            self.visit_stmt(&mut block.stmts.get_mut(j).unwrap());
+            self.visit_stmt(block.stmts.get_mut(j).unwrap());
 
             if j != block.stmts.len() - 1 {
-                self.writer.write(b",").unwrap();
+                self.writer.write_all(b",").unwrap();
             }
             j += 1;
         }
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_while(&mut self, a_while: &mut While) -> () {
-        self.writer.write(b"While(").unwrap();
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.writer.write_all(b"While(").unwrap();
         self.visit_expr(&mut a_while.expr);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         self.visit_stmt(&mut a_while.stmt);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_if(&mut self, an_if: &mut If) -> () {
-        self.writer.write(b"If(").unwrap();
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.writer.write_all(b"DoWhile(").unwrap();
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.writer.write_all(b",").unwrap();
+        self.visit_expr(&mut a_do_while.expr);
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        // missing parts of the header print as nothing
+        self.writer.write_all(b"For(").unwrap();
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        self.writer.write_all(b",").unwrap();
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        self.writer.write_all(b",").unwrap();
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.writer.write_all(b",").unwrap();
+        self.visit_stmt(&mut a_for.stmt);
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        self.writer.write_all(b"Break()").unwrap();
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        self.writer.write_all(b"Continue()").unwrap();
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.writer.write_all(b"If(").unwrap();
         self.visit_expr(&mut an_if.expr);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         self.visit_stmt(&mut an_if.stmt1);
         if let Some(stmt2) = &mut an_if.stmt2 {
-            self.writer.write(b",").unwrap();
+            self.writer.write_all(b",").unwrap();
             self.visit_stmt(stmt2);
         }
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        // cases print as Case(label,stmts..) and Default(stmts..)
+        self.writer.write_all(b"Switch(").unwrap();
+        self.visit_expr(&mut a_switch.expr);
+        for case in a_switch.cases.iter_mut() {
+            self.writer.write_all(b",").unwrap();
+            match &mut case.expr {
+                Some(expr) => {
+                    self.writer.write_all(b"Case(").unwrap();
+                    self.visit_expr(expr);
+                },
+                None => self.writer.write_all(b"Default(").unwrap(),
+            }
+            for (i, stmt) in case.stmts.iter_mut().enumerate() {
+                if i != 0 || case.expr.is_some() {
+                    self.writer.write_all(b",").unwrap();
+                }
+                self.visit_stmt(stmt);
+            }
+            self.writer.write_all(b")").unwrap();
+        }
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_return(&mut self, a_return: &mut Return) -> () {
-        self.writer.write(b"Return(").unwrap();
+    fn visit_return(&mut self, a_return: &mut Return) {
+        self.writer.write_all(b"Return(").unwrap();
         if let Some(expr) = &mut a_return.expr {
             self.visit_expr(expr);
         }
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_assign(&mut self, assign: &mut Assign) -> () {
-        self.writer.write(b"Assign(").unwrap();
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.writer.write_all(b"Assign(").unwrap();
         self.visit_expr(&mut assign.expr1);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         self.visit_expr(&mut assign.expr2);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) -> () {
-        self.writer.write(b"ExprStmt(").unwrap();
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.writer.write_all(b"ExprStmt(").unwrap();
         self.visit_expr(&mut expr_stmt.expr);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_bin_op(&mut self, bin_op: &mut BinOp) -> () {
-        self.writer.write(b"BinOp(").unwrap();
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.writer.write_all(b"BinOp(").unwrap();
         self.visit_expr(&mut bin_op.expr1);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         bin_op.op.accept(self);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         self.visit_expr(&mut bin_op.expr2);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_op(&mut self, op: &mut Op) -> () {
-        self.writer.write(op.to_string().as_bytes()).unwrap();
+    fn visit_op(&mut self, op: &mut Op) {
+        self.writer.write_all(op.to_string().as_bytes()).unwrap();
     }
 
-    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) -> () {
-        self.writer.write(b"IntLiteral(").unwrap();
-        self.writer.write(int_literal.i.to_string().as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) {
+        self.writer.write_all(b"IntLiteral(").unwrap();
+        self.writer.write_all(int_literal.spelling().as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) -> () {
-        self.writer.write(b"ChrLiteral(").unwrap();
-        self.writer.write(chr_literal.c.to_string().as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) {
+        self.writer.write_all(b"ChrLiteral(").unwrap();
+        self.writer.write_all(chr_literal.c.to_string().as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) -> () {
-        self.writer.write(b"StrLiteral(").unwrap();
-        self.writer.write(str_literal.string.as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) {
+        self.writer.write_all(b"StrLiteral(").unwrap();
+        self.writer.write_all(str_literal.string.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) -> () {
-        self.writer.write(b"VarExpr(").unwrap();
-        self.writer.write(var_expr.name.as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        self.writer.write_all(b"VarExpr(").unwrap();
+        self.writer.write_all(var_expr.name.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) -> () {
-        self.writer.write(b"TypecastExpr(").unwrap();
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.writer.write_all(b"TypecastExpr(").unwrap();
         self.visit_type(&mut typecast_expr.typecast_type);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         self.visit_expr(&mut typecast_expr.expr);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) -> () {
-        self.writer.write(b"SizeOfExpr(").unwrap();
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        self.writer.write_all(b"SizeOfExpr(").unwrap();
         self.visit_type(&mut size_of_expr.sizeof_type);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) -> () {
-        self.writer.write(b"AddressOfExp(").unwrap();
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.writer.write_all(b"AddressOfExp(").unwrap();
         self.visit_expr(&mut address_of_expr.expr);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) -> () {
-        self.writer.write(b"ValueAtExpr(").unwrap();
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.writer.write_all(b"ValueAtExpr(").unwrap();
         self.visit_expr(&mut value_at_expr.expr);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) -> () {
-        self.writer.write(b"FieldAccessExp(").unwrap();
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.writer.write_all(b"FieldAccessExp(").unwrap();
         self.visit_expr(&mut field_access_expr.expr);
-        self.writer.write(b",").unwrap();
-        self.writer.write(field_access_expr.name.as_bytes()).unwrap();
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b",").unwrap();
+        self.writer.write_all(field_access_expr.name.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) -> () {
-        self.writer.write(b"ArrayAccessExpr(").unwrap();
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.writer.write_all(b"ArrayAccessExpr(").unwrap();
         self.visit_expr(&mut array_access_expr.expr1);
-        self.writer.write(b",").unwrap();
+        self.writer.write_all(b",").unwrap();
         self.visit_expr(&mut array_access_expr.expr2);
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) -> () {
-        self.writer.write(b"FunCallExpr(").unwrap();
-        self.writer.write(fun_call_expr.name.as_bytes()).unwrap();
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        self.writer.write_all(b"FunCallExpr(").unwrap();
+        self.writer.write_all(fun_call_expr.name.as_bytes()).unwrap();
         for expr in fun_call_expr.exprs.iter_mut() {
-            self.writer.write(b",").unwrap();
+            self.writer.write_all(b",").unwrap();
+            self.visit_expr(expr);
+        }
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_new_expr(&mut self, new_expr: &mut NewExpr) {
+        self.writer.write_all(b"NewExpr(").unwrap();
+        new_expr.class_type.accept(self);
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.writer.write_all(b"MethodCallExpr(").unwrap();
+        self.visit_expr(&mut method_call_expr.expr);
+        self.writer.write_all(b",").unwrap();
+        self.writer.write_all(method_call_expr.name.as_bytes()).unwrap();
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.writer.write_all(b",").unwrap();
+            self.visit_expr(expr);
+        }
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        self.writer.write_all(b"FunAddrExpr(").unwrap();
+        self.writer.write_all(fun_addr_expr.name.as_bytes()).unwrap();
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.writer.write_all(b"IndirectCallExpr(").unwrap();
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.writer.write_all(b",").unwrap();
             self.visit_expr(expr);
         }
-        self.writer.write(b")").unwrap();
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.writer.write_all(b"AssignExpr(").unwrap();
+        self.visit_expr(&mut assign_expr.expr1);
+        self.writer.write_all(b",").unwrap();
+        if let Some(op) = &mut assign_expr.op {
+            op.accept(self);
+            self.writer.write_all(b",").unwrap();
+        }
+        self.visit_expr(&mut assign_expr.expr2);
+        self.writer.write_all(b")").unwrap();
+    }
+
+    // the operator is written on the side where it appears in the source, e.g. IncDecExpr(ADD,VarExpr(i)) for ++i
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.writer.write_all(b"IncDecExpr(").unwrap();
+        if inc_dec_expr.prefix {
+            inc_dec_expr.op.accept(self);
+            self.writer.write_all(b",").unwrap();
+            self.visit_expr(&mut inc_dec_expr.expr);
+        } else {
+            self.visit_expr(&mut inc_dec_expr.expr);
+            self.writer.write_all(b",").unwrap();
+            inc_dec_expr.op.accept(self);
+        }
+        self.writer.write_all(b")").unwrap();
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.writer.write_all(b"ConditionalExpr(").unwrap();
+        self.visit_expr(&mut conditional_expr.expr);
+        self.writer.write_all(b",").unwrap();
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.writer.write_all(b",").unwrap();
+        self.visit_expr(&mut conditional_expr.expr2);
+        self.writer.write_all(b")").unwrap();
     }
 
-    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) -> () {
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) {
         if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<BaseType>() {
             cast_type.accept(self);
         } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<PointerType>() {
@@ -268,16 +506,30 @@ impl ASTVisitor<()> for ASTPrinter {
             cast_type.accept(self);
         } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ArrayType>() {
             cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ClassType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<FunType>() {
+            cast_type.accept(self);
         }
     }
 
-    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) -> () {
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
         if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
             cast_stmt.accept(self);
         } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
             cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
         } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
             cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
         } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
             cast_stmt.accept(self);
         } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
@@ -287,7 +539,7 @@ impl ASTVisitor<()> for ASTPrinter {
         }
     }
 
-    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) -> () {
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
         if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
             cast_expr.accept(self);
         } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
@@ -312,6 +564,20 @@ impl ASTVisitor<()> for ASTPrinter {
             cast_expr.accept(self);
         } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
             cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
         }
     }
 }
\ No newline at end of file
@@ -1,20 +1,28 @@
-use crate::ast::decl::{FunDecl, Program, StructTypeDecl, VarDecl};
-use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, BinOp, ChrLiteral, Expr, FieldAccessExpr, FunCallExpr, IntLiteral, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
-use crate::ast::stmt::{Assign, Block, ExprStmt, If, Return, Stmt, While};
-use crate::ast::types::{ArrayType, BaseType, PointerType, StructType, Type};
+use crate::ast::decl::{ClassDecl, FunDecl, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
 
 pub trait ASTVisitor<T> {
     fn visit_base_type(&mut self, base_type: &mut BaseType) -> T;
     fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) -> T;
     fn visit_struct_type(&mut self, struct_type: &mut StructType) -> T;
     fn visit_array_type(&mut self, array_type: &mut ArrayType) -> T;
+    fn visit_class_type(&mut self, class_type: &mut ClassType) -> T;
+    fn visit_fun_type(&mut self, fun_type: &mut FunType) -> T;
     fn visit_program(&mut self, program: &mut Program) -> T;
     fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) -> T;
+    fn visit_class_decl(&mut self, class_decl: &mut ClassDecl) -> T;
     fn visit_var_decl(&mut self, var_decl: &mut VarDecl) -> T;
     fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) -> T;
     fn visit_block(&mut self, block: &mut Block) -> T;
     fn visit_while(&mut self, a_while: &mut While) -> T;
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) -> T;
+    fn visit_for(&mut self, a_for: &mut For) -> T;
+    fn visit_break(&mut self, a_break: &mut Break) -> T;
+    fn visit_continue(&mut self, a_continue: &mut Continue) -> T;
     fn visit_if(&mut self, an_if: &mut If) -> T;
+    fn visit_switch(&mut self, a_switch: &mut Switch) -> T;
     fn visit_return(&mut self, a_return: &mut Return) -> T;
     fn visit_assign(&mut self, assign: &mut Assign) -> T;
     fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) -> T;
@@ -31,6 +39,13 @@ pub trait ASTVisitor<T> {
     fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) -> T;
     fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) -> T;
     fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) -> T;
+    fn visit_new_expr(&mut self, new_expr: &mut NewExpr) -> T;
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) -> T;
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) -> T;
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) -> T;
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) -> T;
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) -> T;
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) -> T;
 
     fn visit_type(&mut self, a_type: &mut Box<dyn Type>) -> T;
     fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) -> T;
@@ -1,23 +1,78 @@
 use crate::ast::ast_node::ASTNode;
 use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::expr::Expr;
 use crate::ast::stmt::Block;
-use crate::ast::types::{StructType, Type};
+use crate::ast::types::{declaration, ClassType, FunType, StructType, Type};
+use crate::lexer::position::Position;
 
 /// Program
+/// The top-level declarations by kind, with `items` recording the order in which they appear in the
+/// source. Prototypes only declare a function ahead of its definition in `fun_decls`. Typedefs and
+/// enums are resolved by the parser and the name analyser, so are only kept for the tools printing
+/// the source.
+#[derive(Clone)]
 pub struct Program {
+    pub typedef_decls: Vec<TypedefDecl>,
+    pub enum_decls: Vec<EnumDecl>,
     pub struct_type_decls: Vec<StructTypeDecl>,
+    pub class_decls: Vec<ClassDecl>,
     pub var_decls: Vec<VarDecl>,
+    pub prototypes: Vec<FunDecl>,
     pub fun_decls: Vec<FunDecl>,
+    pub items: Vec<Item>,
+}
+
+/// Item
+/// A top-level declaration, by its index in the list of its kind.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Item {
+    Typedef(usize),
+    Enum(usize),
+    Struct(usize),
+    Class(usize),
+    Var(usize),
+    Prototype(usize),
+    Fun(usize),
 }
 
 impl Program {
     pub fn new(struct_type_decls: Vec<StructTypeDecl>, var_decls: Vec<VarDecl>, fun_decls: Vec<FunDecl>) -> Self {
         Program {
+            typedef_decls: Vec::new(),
+            enum_decls: Vec::new(),
             struct_type_decls,
+            class_decls: Vec::new(),
             var_decls,
-            fun_decls
+            prototypes: Vec::new(),
+            fun_decls,
+            items: Vec::new(),
         }
     }
+
+    /// The declarations in source order. The ones missing from `items`, such as those added by the
+    /// compiler, follow by kind: typedefs, enums, structs, classes, globals, prototypes then functions.
+    pub fn ordered_items(&self) -> Vec<Item> {
+        let mut items: Vec<Item> = self.items.iter().copied().filter(|item| match *item {
+            Item::Typedef(i) => i < self.typedef_decls.len(),
+            Item::Enum(i) => i < self.enum_decls.len(),
+            Item::Struct(i) => i < self.struct_type_decls.len(),
+            Item::Class(i) => i < self.class_decls.len(),
+            Item::Var(i) => i < self.var_decls.len(),
+            Item::Prototype(i) => i < self.prototypes.len(),
+            Item::Fun(i) => i < self.fun_decls.len(),
+        }).collect();
+        let missing: Vec<Item> = (0..self.typedef_decls.len()).map(Item::Typedef)
+            .chain((0..self.enum_decls.len()).map(Item::Enum))
+            .chain((0..self.struct_type_decls.len()).map(Item::Struct))
+            .chain((0..self.class_decls.len()).map(Item::Class))
+            .chain((0..self.var_decls.len()).map(Item::Var))
+            .chain((0..self.prototypes.len()).map(Item::Prototype))
+            .chain((0..self.fun_decls.len()).map(Item::Fun))
+            .filter(|item| !items.contains(item))
+            .collect();
+        items.extend(missing);
+        items
+    }
 }
 
 impl ASTNode for Program {
@@ -26,7 +81,68 @@ impl ASTNode for Program {
     }
 }
 
+/// TypedefDecl
+/// `typedef type name;`, after which the parser reads the name as the type. Only the typedefs of the
+/// program are kept, one in a block naming its type in the parser alone.
+#[derive(Clone)]
+pub struct TypedefDecl {
+    pub typedef_type: Box<dyn Type>,
+    pub name: String,
+    pub position: Position, // of the name
+}
+
+impl TypedefDecl {
+    pub fn new(typedef_type: Box<dyn Type>, name: String) -> Self {
+        TypedefDecl {
+            typedef_type,
+            name,
+            position: Position::default()
+        }
+    }
+}
+
+/// EnumDecl
+/// Named int constants, an enum type being int. The name of the enum may be left out.
+#[derive(Clone)]
+pub struct EnumDecl {
+    pub name: String,
+    pub enumerators: Vec<Enumerator>,
+    pub position: Position, // of the name, or of `enum` when there is none
+}
+
+impl EnumDecl {
+    pub fn new(name: String, enumerators: Vec<Enumerator>) -> Self {
+        EnumDecl {
+            name,
+            enumerators,
+            position: Position::default()
+        }
+    }
+}
+
+/// Enumerator
+/// A constant of an enum, whose value is the constant expression `expr` or else the value of the
+/// previous one plus one, the first one being 0. Its uses are replaced by the value during name
+/// analysis.
+#[derive(Clone)]
+pub struct Enumerator {
+    pub name: String,
+    pub expr: Option<Box<dyn Expr>>,
+    pub position: Position, // of the name
+}
+
+impl Enumerator {
+    pub fn new(name: String, expr: Option<Box<dyn Expr>>) -> Self {
+        Enumerator {
+            name,
+            expr,
+            position: Position::default()
+        }
+    }
+}
+
 /// StructTypeDecl
+#[derive(Clone)]
 pub struct StructTypeDecl {
     pub struct_type: StructType,
     pub var_decls: Vec<VarDecl>,
@@ -47,17 +163,119 @@ impl ASTNode for StructTypeDecl {
     }
 }
 
+/// ClassDecl
+/// A class with its fields and methods, inheriting those of its parent. Every method is virtual, the
+/// ones with the name of a method of an ancestor override it.
+#[derive(Clone)]
+pub struct ClassDecl {
+    pub class_type: ClassType,
+    pub parent: Option<ClassType>,
+    pub var_decls: Vec<VarDecl>,
+    pub fun_decls: Vec<FunDecl>,
+}
+
+impl ClassDecl {
+    pub fn new(class_type: ClassType, parent: Option<ClassType>, var_decls: Vec<VarDecl>, fun_decls: Vec<FunDecl>) -> Self {
+        ClassDecl {
+            class_type,
+            parent,
+            var_decls,
+            fun_decls
+        }
+    }
+}
+
+impl ASTNode for ClassDecl {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_class_decl(self)
+    }
+}
+
+/// StorageClass
+/// A global or a function which is `static` is only visible in its file, and an `extern` one is
+/// declared there but may be defined in another file. A `static` local keeps its value between
+/// calls, as a global only visible in its block would.
+#[derive(Clone, Copy, PartialEq)]
+pub enum StorageClass {
+    Auto,
+    Static,
+    Extern,
+}
+
+impl StorageClass {
+    /// The keyword, followed by a space, empty for `Auto`.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            StorageClass::Auto => "",
+            StorageClass::Static => "static ",
+            StorageClass::Extern => "extern ",
+        }
+    }
+}
+
 /// VarDecl
+/// A variable which `is_const` cannot be assigned after its initialisation; for an array or a
+/// struct, neither can its elements or fields.
+#[derive(Clone)]
 pub struct VarDecl {
     pub var_type: Box<dyn Type>,
     pub var_name: String,
+    pub init: Option<Initializer>,
+    pub is_const: bool,
+    pub storage: StorageClass,
+    pub position: Position, // of the name
 }
 
 impl VarDecl {
     pub fn new(var_type: Box<dyn Type>, var_name: String) -> Self {
         VarDecl {
             var_type,
-            var_name
+            var_name,
+            init: None,
+            is_const: false,
+            storage: StorageClass::Auto,
+            position: Position::default()
+        }
+    }
+
+    /// The minic declaration of the variable with its qualifiers, e.g. `static const int x` or
+    /// `char* const p`, where the `const` of a pointer goes after its `*`.
+    pub fn declaration(&self) -> String {
+        let mut element_type = self.var_type.as_ref();
+        while let Some(array_type) = element_type.as_array() {
+            element_type = array_type.array_type.as_ref();
+        }
+        let declaration = match (self.is_const, element_type.as_pointer().is_some()) {
+            (true, true) => declaration(self.var_type.as_ref(), &format!("const {}", self.var_name)),
+            (true, false) => format!("const {}", declaration(self.var_type.as_ref(), &self.var_name)),
+            _ => declaration(self.var_type.as_ref(), &self.var_name),
+        };
+        format!("{}{}", self.storage.keyword(), declaration)
+    }
+}
+
+/// Initializer
+/// The initial value of a variable: an expression, or a list in braces for an array or a struct,
+/// whose missing trailing elements or fields are zero.
+#[derive(Clone)]
+pub enum Initializer {
+    Expr(Box<dyn Expr>),
+    List(Vec<Initializer>),
+}
+
+impl Initializer {
+    /// its expressions in order
+    pub fn exprs(&self) -> Vec<&dyn Expr> {
+        match self {
+            Initializer::Expr(expr) => vec![expr.as_ref()],
+            Initializer::List(items) => items.iter().flat_map(|item| item.exprs()).collect(),
+        }
+    }
+
+    pub fn exprs_mut(&mut self) -> Vec<&mut Box<dyn Expr>> {
+        match self {
+            Initializer::Expr(expr) => vec![expr],
+            Initializer::List(items) => items.iter_mut().flat_map(|item| item.exprs_mut()).collect(),
         }
     }
 }
@@ -69,11 +287,14 @@ impl ASTNode for VarDecl {
 }
 
 /// FunDecl
+#[derive(Clone)]
 pub struct FunDecl {
     pub fun_type: Box<dyn Type>,
     pub name: String,
     pub params: Vec<VarDecl>,
     pub block: Block,
+    pub storage: StorageClass,
+    pub position: Position, // of the name
 }
 
 impl FunDecl {
@@ -82,9 +303,22 @@ impl FunDecl {
             fun_type,
             name,
             params,
-            block
+            block,
+            storage: StorageClass::Auto,
+            position: Position::default()
         }
     }
+
+    /// The type of the function, that a pointer to it points to.
+    pub fn signature(&self) -> FunType {
+        FunType::new(self.fun_type.clone(), self.params.iter().map(|param| param.var_type.clone()).collect())
+    }
+
+    /// A declaration without a body of a function of the given type, whose parameters have no name.
+    pub fn of_signature(fun_type: &FunType) -> Self {
+        let params = fun_type.param_types.iter().map(|param_type| VarDecl::new(param_type.clone(), "".to_owned())).collect();
+        FunDecl::new(fun_type.return_type.clone(), "".to_owned(), params, Block::new(vec![], vec![]))
+    }
 }
 
 impl ASTNode for FunDecl {
@@ -4,23 +4,57 @@ use crate::ast::ast_node::{ASTNode, AToAny};
 use crate::ast::ast_visitor::ASTVisitor;
 use crate::ast::decl::{FunDecl, VarDecl};
 use crate::ast::stmt::Block;
-use crate::ast::types::{BaseType, Type};
+use crate::ast::types::{BaseType, ClassType, Type};
+use crate::lexer::position::Position;
 
 /// IntLiteral
+/// The type follows from the value and the suffix as in C: `int`, or `long` if it does not fit,
+/// `unsigned int` or `unsigned long` with a `U`, and `long` or `unsigned long` with an `L`.
+#[derive(Clone)]
 pub struct IntLiteral {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
-    pub i: i32
+    pub i: i64,
+    pub int_type: BaseType
 }
 
 impl IntLiteral {
     pub fn new(data: String) -> Box<Self> {
+        let (i, int_type) = IntLiteral::parse(&data).unwrap_or((0, BaseType::INT));
         Box::new(IntLiteral {
             expr_type: Box::new(BaseType::VOID),
             is_grouped: false,
-            i: data.parse::<i32>().unwrap()
+            i,
+            int_type
         })
     }
+
+    /// The value and type of a literal such as `42` or `42UL`, none if the suffix is not valid or
+    /// the value does not fit its type.
+    pub fn parse(data: &str) -> Option<(i64, BaseType)> {
+        let digits = data.trim_end_matches(['u', 'U', 'l', 'L']);
+        let value = digits.parse::<i128>().ok()?;
+        let fits_int = value >= i32::MIN as i128 && value <= i32::MAX as i128;
+        let fits_long = value >= i64::MIN as i128 && value <= i64::MAX as i128;
+        let int_type = match data[digits.len()..].to_ascii_lowercase().as_str() {
+            "" if fits_int => BaseType::INT,
+            "" | "l" | "ll" if fits_long => BaseType::LONG,
+            "u" if (0..=u32::MAX as i128).contains(&value) => BaseType::UINT,
+            "u" | "ul" | "lu" | "ull" | "llu" if (0..=u64::MAX as i128).contains(&value) => BaseType::ULONG,
+            _ => return None,
+        };
+        Some((value as i64, int_type))
+    }
+
+    /// The literal with the shortest suffix giving it its type, e.g. `42UL`.
+    pub fn spelling(&self) -> String {
+        match self.int_type {
+            BaseType::LONG if self.i as i32 as i64 == self.i => format!("{}L", self.i),
+            BaseType::UINT => format!("{}U", self.i),
+            BaseType::ULONG => format!("{}UL", self.i as u64),
+            _ => self.i.to_string(),
+        }
+    }
 }
 
 impl ASTNode for IntLiteral {
@@ -30,6 +64,7 @@ impl ASTNode for IntLiteral {
 }
 
 /// StrLiteral
+#[derive(Clone)]
 pub struct StrLiteral {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
@@ -44,6 +79,29 @@ impl StrLiteral {
             string
         })
     }
+
+    /// The characters of the literal with its escape sequences decoded, the tokenizer keeps them as written.
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut chars = self.string.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                bytes.push(c as u8);
+                continue;
+            }
+            bytes.push(match chars.next() {
+                Some('n') => b'\n',
+                Some('t') => b'\t',
+                Some('r') => b'\r',
+                Some('b') => 8,
+                Some('f') => 12,
+                Some('0') => 0,
+                Some(c) => c as u8,
+                None => b'\\',
+            });
+        }
+        bytes
+    }
 }
 
 impl ASTNode for StrLiteral {
@@ -53,6 +111,7 @@ impl ASTNode for StrLiteral {
 }
 
 /// ChrLiteral
+#[derive(Clone)]
 pub struct ChrLiteral {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
@@ -76,10 +135,12 @@ impl ASTNode for ChrLiteral {
 }
 
 /// VarExpr
+#[derive(Clone)]
 pub struct VarExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
     pub name: String,
+    pub position: Position,
     pub var_decl: VarDecl // to be filled in by the name analyser
 }
 
@@ -89,6 +150,7 @@ impl VarExpr {
             expr_type: Box::new(BaseType::VOID),
             is_grouped: false,
             name,
+            position: Position::default(),
             var_decl: VarDecl::new(Box::new(BaseType::VOID), "".to_string())
         })
     }
@@ -101,11 +163,13 @@ impl ASTNode for VarExpr {
 }
 
 /// FunCallExpr
+#[derive(Clone)]
 pub struct FunCallExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
     pub name: String,
     pub exprs: Vec<Box<dyn Expr>>,
+    pub position: Position, // of the name
     pub fun_decl: FunDecl
 }
 
@@ -116,6 +180,7 @@ impl FunCallExpr {
             is_grouped: false,
             name,
             exprs,
+            position: Position::default(),
             fun_decl: FunDecl::new(Box::new(BaseType::VOID), "".to_string(), vec![], Block { var_decls: vec![], stmts: vec![] })
         })
     }
@@ -128,12 +193,16 @@ impl ASTNode for FunCallExpr {
 }
 
 /// BinOp
+#[derive(Clone)]
 pub struct BinOp {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
     pub op: Op,
     pub expr1: Box<dyn Expr>,
-    pub expr2: Box<dyn Expr>
+    pub expr2: Box<dyn Expr>,
+    // written as a prefix operator, which the parser desugars: -e, +e, !e and ~e are 0 - e, 0 + e,
+    // 0 == e and -1 ^ e
+    pub prefix: bool,
 }
 
 impl BinOp {
@@ -143,9 +212,26 @@ impl BinOp {
             is_grouped: false,
             op,
             expr1,
-            expr2
+            expr2,
+            prefix: false,
         })
     }
+
+    pub fn prefix(op: Op, expr: Box<dyn Expr>) -> Box<Self> {
+        let literal = if op == Op::BXOR { "-1" } else { "0" };
+        let mut bin_op = BinOp::new(IntLiteral::new(literal.to_owned()), op, expr);
+        bin_op.prefix = true;
+        bin_op
+    }
+
+    /// The operator as written in the source.
+    pub fn symbol(&self) -> &'static str {
+        match self.op {
+            Op::EQ if self.prefix => "!",
+            Op::BXOR if self.prefix => "~",
+            op => op.symbol(),
+        }
+    }
 }
 
 impl ASTNode for BinOp {
@@ -155,9 +241,43 @@ impl ASTNode for BinOp {
 }
 
 /// Op
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Op {
-    ADD , SUB , MUL , DIV , MOD , GT , LT , GE , LE , NE , EQ , OR , AND
+    ADD , SUB , MUL , DIV , MOD , GT , LT , GE , LE , NE , EQ , OR , AND ,
+    // bitwise operators and shifts, >> keeping the sign as int is signed
+    BOR , BAND , BXOR , SHL , SHR
+}
+
+impl Op {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Op::ADD => "+",
+            Op::SUB => "-",
+            Op::MUL => "*",
+            Op::DIV => "/",
+            Op::MOD => "%",
+            Op::GT => ">",
+            Op::GE => ">=",
+            Op::LT => "<",
+            Op::LE => "<=",
+            Op::NE => "!=",
+            Op::EQ => "==",
+            Op::OR => "||",
+            Op::AND => "&&",
+            Op::BOR => "|",
+            Op::BAND => "&",
+            Op::BXOR => "^",
+            Op::SHL => "<<",
+            Op::SHR => ">>",
+        }
+    }
+
+    /// AND and OR short-circuit: their right operand is only evaluated when the left one does not
+    /// decide the result, so every backend lowers them to branches rather than to an instruction.
+    pub fn is_short_circuit(&self) -> bool {
+        matches!(self, Op::AND | Op::OR)
+    }
 }
 
 impl Display for Op {
@@ -176,6 +296,11 @@ impl Display for Op {
             Op::EQ => write!(f, "EQ"),
             Op::OR => write!(f, "OR"),
             Op::AND => write!(f, "AND"),
+            Op::BOR => write!(f, "BOR"),
+            Op::BAND => write!(f, "BAND"),
+            Op::BXOR => write!(f, "BXOR"),
+            Op::SHL => write!(f, "SHL"),
+            Op::SHR => write!(f, "SHR"),
         }
     }
 }
@@ -187,6 +312,7 @@ impl ASTNode for Op{
 }
 
 /// ArrayAccessExpr
+#[derive(Clone)]
 pub struct ArrayAccessExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
@@ -212,11 +338,13 @@ impl ASTNode for ArrayAccessExpr {
 }
 
 /// FieldAccessExpr
+#[derive(Clone)]
 pub struct FieldAccessExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
     pub expr: Box<dyn Expr>,
-    pub name: String
+    pub name: String,
+    pub position: Position // of the field name
 }
 
 impl FieldAccessExpr {
@@ -225,9 +353,16 @@ impl FieldAccessExpr {
             expr_type: Box::new(BaseType::VOID),
             is_grouped: false,
             expr,
-            name
+            name,
+            position: Position::default()
         })
     }
+
+    /// Whether the access was written `p->f`, which is parsed as `(*p).f` with the dereference left
+    /// ungrouped
+    pub fn is_arrow(&self) -> bool {
+        !self.expr.get_is_grouped() && self.expr.as_any().is::<ValueAtExpr>()
+    }
 }
 
 impl ASTNode for FieldAccessExpr {
@@ -237,6 +372,7 @@ impl ASTNode for FieldAccessExpr {
 }
 
 /// ValueAtExpr
+#[derive(Clone)]
 pub struct ValueAtExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
@@ -260,6 +396,7 @@ impl ASTNode for ValueAtExpr {
 }
 
 /// AddressOfExpr
+#[derive(Clone)]
 pub struct AddressOfExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
@@ -283,10 +420,12 @@ impl ASTNode for AddressOfExpr {
 }
 
 /// SizeOfExpr
+#[derive(Clone)]
 pub struct SizeOfExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
     pub sizeof_type: Box<dyn Type>,
+    pub size: Option<i32> // to be filled in by the type analyser
 }
 
 impl SizeOfExpr {
@@ -294,7 +433,8 @@ impl SizeOfExpr {
         Box::new(SizeOfExpr {
             expr_type: Box::new(BaseType::VOID),
             is_grouped: false,
-            sizeof_type
+            sizeof_type,
+            size: None
         })
     }
 }
@@ -306,6 +446,7 @@ impl ASTNode for SizeOfExpr {
 }
 
 /// TypecastExpr
+#[derive(Clone)]
 pub struct TypecastExpr {
     pub expr_type: Box<dyn Type>,
     pub is_grouped: bool,
@@ -330,10 +471,238 @@ impl ASTNode for TypecastExpr {
     }
 }
 
+/// NewExpr
+/// Allocates an object of a class on the heap.
+#[derive(Clone)]
+pub struct NewExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub class_type: ClassType,
+}
+
+impl NewExpr {
+    pub fn new(class_type: ClassType) -> Box<Self> {
+        Box::new(NewExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            class_type
+        })
+    }
+}
+
+impl ASTNode for NewExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_new_expr(self)
+    }
+}
+
+/// MethodCallExpr
+/// A call of a method of the object `expr` refers to, chosen by the class of the object at run time.
+#[derive(Clone)]
+pub struct MethodCallExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub expr: Box<dyn Expr>,
+    pub name: String,
+    pub exprs: Vec<Box<dyn Expr>>,
+    pub position: Position, // of the method name
+    pub fun_decl: FunDecl // to be filled in by the type analyser
+}
+
+impl MethodCallExpr {
+    pub fn new(expr: Box<dyn Expr>, name: String, exprs: Vec<Box<dyn Expr>>) -> Box<Self> {
+        Box::new(MethodCallExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            expr,
+            name,
+            exprs,
+            position: Position::default(),
+            fun_decl: FunDecl::new(Box::new(BaseType::VOID), "".to_string(), vec![], Block { var_decls: vec![], stmts: vec![] })
+        })
+    }
+}
+
+impl ASTNode for MethodCallExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_method_call_expr(self)
+    }
+}
+
+/// FunAddrExpr
+/// The address of a function, written `f` or `&f`, whose type is a pointer to the signature of the
+/// function. Also built by the compiler, e.g. to fill the vtables of classes.
+#[derive(Clone)]
+pub struct FunAddrExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub name: String,
+    pub position: Position,
+    pub fun_decl: FunDecl // to be filled in by the name analyser
+}
+
+impl FunAddrExpr {
+    pub fn new(name: String) -> Box<Self> {
+        Box::new(FunAddrExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            name,
+            position: Position::default(),
+            fun_decl: FunDecl::new(Box::new(BaseType::VOID), "".to_string(), vec![], Block { var_decls: vec![], stmts: vec![] })
+        })
+    }
+}
+
+impl ASTNode for FunAddrExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_fun_addr_expr(self)
+    }
+}
+
+/// IndirectCallExpr
+/// A call of the function whose address `expr` evaluates to, e.g. `cmp(a, b)` through a function
+/// pointer `cmp`, `(*cmp)(a, b)` or `ops[i](x)`. Also built by the compiler to call a method through
+/// a vtable.
+#[derive(Clone)]
+pub struct IndirectCallExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub expr: Box<dyn Expr>,
+    pub exprs: Vec<Box<dyn Expr>>,
+    pub position: Position, // of the arguments
+    pub fun_decl: FunDecl // the signature of the callee, to be filled in by the type analyser
+}
+
+impl IndirectCallExpr {
+    pub fn new(expr: Box<dyn Expr>, exprs: Vec<Box<dyn Expr>>) -> Box<Self> {
+        Box::new(IndirectCallExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            expr,
+            exprs,
+            position: Position::default(),
+            fun_decl: FunDecl::new(Box::new(BaseType::VOID), "".to_string(), vec![], Block { var_decls: vec![], stmts: vec![] })
+        })
+    }
+}
+
+impl ASTNode for IndirectCallExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_indirect_call_expr(self)
+    }
+}
+
+/// AssignExpr
+/// An assignment used as an expression, `op` is the operator of a compound assignment such as `+=`.
+/// Its value is the one stored, and `expr1` is evaluated once.
+#[derive(Clone)]
+pub struct AssignExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub expr1: Box<dyn Expr>,
+    pub op: Option<Op>,
+    pub expr2: Box<dyn Expr>
+}
+
+impl AssignExpr {
+    pub fn new(expr1: Box<dyn Expr>, op: Option<Op>, expr2: Box<dyn Expr>) -> Box<Self> {
+        Box::new(AssignExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            expr1,
+            op,
+            expr2
+        })
+    }
+}
+
+impl ASTNode for AssignExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_assign_expr(self)
+    }
+}
+
+/// IncDecExpr
+/// `++` (op ADD) or `--` (op SUB) of an int lvalue, a prefix one evaluates to the new value and a
+/// postfix one to the old value.
+#[derive(Clone)]
+pub struct IncDecExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub expr: Box<dyn Expr>,
+    pub op: Op,
+    pub prefix: bool
+}
+
+impl IncDecExpr {
+    pub fn new(expr: Box<dyn Expr>, op: Op, prefix: bool) -> Box<Self> {
+        Box::new(IncDecExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            expr,
+            op,
+            prefix
+        })
+    }
+}
+
+impl ASTNode for IncDecExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_inc_dec_expr(self)
+    }
+}
+
+/// ConditionalExpr
+/// `expr ? expr1 : expr2`, only the branch selected by the int condition is evaluated.
+#[derive(Clone)]
+pub struct ConditionalExpr {
+    pub expr_type: Box<dyn Type>,
+    pub is_grouped: bool,
+    pub expr: Box<dyn Expr>,
+    pub expr1: Box<dyn Expr>,
+    pub expr2: Box<dyn Expr>
+}
+
+impl ConditionalExpr {
+    pub fn new(expr: Box<dyn Expr>, expr1: Box<dyn Expr>, expr2: Box<dyn Expr>) -> Box<Self> {
+        Box::new(ConditionalExpr {
+            expr_type: Box::new(BaseType::VOID),
+            is_grouped: false,
+            expr,
+            expr1,
+            expr2
+        })
+    }
+}
+
+impl ASTNode for ConditionalExpr {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_conditional_expr(self)
+    }
+}
+
 /// Expr
-pub trait Expr: AToAny {
+pub trait Expr: AToAny + ExprClone {
     fn get_is_grouped(&self) -> bool;
     fn set_is_grouped(&mut self, is_grouped: bool);
+    fn get_expr_type(&self) -> &dyn Type;
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>);
+}
+
+pub trait ExprClone {
+    fn clone_box(&self) -> Box<dyn Expr>;
+}
+
+impl<T: 'static + Expr + Clone> ExprClone for T {
+    fn clone_box(&self) -> Box<dyn Expr> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Expr> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
 }
 
 impl AToAny for IntLiteral {
@@ -354,6 +723,14 @@ impl Expr for IntLiteral {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for StrLiteral {
@@ -374,6 +751,14 @@ impl Expr for StrLiteral {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for ChrLiteral {
@@ -394,6 +779,14 @@ impl Expr for ChrLiteral {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for VarExpr {
@@ -414,6 +807,14 @@ impl Expr for VarExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for FunCallExpr {
@@ -434,6 +835,14 @@ impl Expr for FunCallExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for BinOp {
@@ -454,6 +863,14 @@ impl Expr for BinOp {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for ArrayAccessExpr {
@@ -474,6 +891,14 @@ impl Expr for ArrayAccessExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for FieldAccessExpr {
@@ -494,6 +919,14 @@ impl Expr for FieldAccessExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for ValueAtExpr {
@@ -514,6 +947,14 @@ impl Expr for ValueAtExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for AddressOfExpr {
@@ -534,6 +975,14 @@ impl Expr for AddressOfExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for SizeOfExpr {
@@ -554,6 +1003,14 @@ impl Expr for SizeOfExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
 
 impl AToAny for TypecastExpr {
@@ -574,4 +1031,208 @@ impl Expr for TypecastExpr {
     fn set_is_grouped(&mut self, is_grouped: bool) {
         self.is_grouped = is_grouped
     }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for NewExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for NewExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for MethodCallExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for MethodCallExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for FunAddrExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for FunAddrExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for IndirectCallExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for IndirectCallExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for AssignExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for AssignExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for IncDecExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for IncDecExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
+}
+
+impl AToAny for ConditionalExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Expr for ConditionalExpr {
+    fn get_is_grouped(&self) -> bool {
+        self.is_grouped
+    }
+
+    fn set_is_grouped(&mut self, is_grouped: bool) {
+        self.is_grouped = is_grouped
+    }
+
+    fn get_expr_type(&self) -> &dyn Type {
+        self.expr_type.as_ref()
+    }
+
+    fn set_expr_type(&mut self, expr_type: Box<dyn Type>) {
+        self.expr_type = expr_type
+    }
 }
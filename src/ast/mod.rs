@@ -1,6 +1,7 @@
 pub mod ast_node;
 pub mod ast_visitor;
 pub mod ast_printer;
+pub mod unparser;
 pub mod decl;
 pub mod types;
 pub mod expr;
@@ -3,8 +3,10 @@ use crate::ast::ast_node::{ASTNode, AToAny};
 use crate::ast::ast_visitor::ASTVisitor;
 use crate::ast::decl::VarDecl;
 use crate::ast::expr::Expr;
+use crate::lexer::position::Position;
 
 /// Block
+#[derive(Clone)]
 pub struct Block {
     pub var_decls: Vec<VarDecl>,
     pub stmts: Vec<Box<dyn Stmt>>
@@ -26,6 +28,7 @@ impl ASTNode for Block {
 }
 
 /// While
+#[derive(Clone)]
 pub struct While {
     pub expr: Box<dyn Expr>,
     pub stmt: Box<dyn Stmt>
@@ -46,7 +49,90 @@ impl ASTNode for While {
     }
 }
 
+/// DoWhile
+/// `do stmt while (expr);`, the body runs once before the condition is first evaluated.
+#[derive(Clone)]
+pub struct DoWhile {
+    pub stmt: Box<dyn Stmt>,
+    pub expr: Box<dyn Expr>
+}
+
+impl DoWhile {
+    pub fn new(stmt: Box<dyn Stmt>, expr: Box<dyn Expr>) -> Box<Self> {
+        Box::new(DoWhile {
+            stmt,
+            expr
+        })
+    }
+}
+
+impl ASTNode for DoWhile {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_do_while(self)
+    }
+}
+
+/// For
+/// `for (init; expr; step) stmt`, each part of the header being optional; a missing condition is true.
+#[derive(Clone)]
+pub struct For {
+    pub init: Option<Box<dyn Stmt>>,
+    pub expr: Option<Box<dyn Expr>>,
+    pub step: Option<Box<dyn Stmt>>,
+    pub stmt: Box<dyn Stmt>
+}
+
+impl For {
+    pub fn new(init: Option<Box<dyn Stmt>>, expr: Option<Box<dyn Expr>>, step: Option<Box<dyn Stmt>>, stmt: Box<dyn Stmt>) -> Box<Self> {
+        Box::new(For {
+            init,
+            expr,
+            step,
+            stmt
+        })
+    }
+}
+
+impl ASTNode for For {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_for(self)
+    }
+}
+
+/// Break
+#[derive(Clone)]
+pub struct Break { }
+
+impl Break {
+    pub fn new() -> Box<Self> {
+        Box::new(Break { })
+    }
+}
+
+impl ASTNode for Break {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_break(self)
+    }
+}
+
+/// Continue
+#[derive(Clone)]
+pub struct Continue { }
+
+impl Continue {
+    pub fn new() -> Box<Self> {
+        Box::new(Continue { })
+    }
+}
+
+impl ASTNode for Continue {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_continue(self)
+    }
+}
+
 /// If
+#[derive(Clone)]
 pub struct If {
     pub expr: Box<dyn Expr>,
     pub stmt1: Box<dyn Stmt>,
@@ -69,7 +155,51 @@ impl ASTNode for If {
     }
 }
 
+/// Switch
+/// Control jumps to the case whose label equals the value of expr, or to the default one, and falls
+/// through the following cases until a `break`.
+#[derive(Clone)]
+pub struct Switch {
+    pub expr: Box<dyn Expr>,
+    pub cases: Vec<Case>
+}
+
+impl Switch {
+    pub fn new(expr: Box<dyn Expr>, cases: Vec<Case>) -> Box<Self> {
+        Box::new(Switch {
+            expr,
+            cases
+        })
+    }
+}
+
+impl ASTNode for Switch {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_switch(self)
+    }
+}
+
+/// Case
+/// A `case expr:` label, or `default:` when expr is none, with the statements following it.
+#[derive(Clone)]
+pub struct Case {
+    pub expr: Option<Box<dyn Expr>>,
+    pub stmts: Vec<Box<dyn Stmt>>,
+    pub position: Position
+}
+
+impl Case {
+    pub fn new(expr: Option<Box<dyn Expr>>, stmts: Vec<Box<dyn Stmt>>) -> Self {
+        Case {
+            expr,
+            stmts,
+            position: Position::default()
+        }
+    }
+}
+
 /// Assign
+#[derive(Clone)]
 pub struct Assign {
     pub expr1: Box<dyn Expr>,
     pub expr2: Box<dyn Expr>
@@ -91,6 +221,7 @@ impl ASTNode for Assign {
 }
 
 /// Return
+#[derive(Clone)]
 pub struct Return {
     pub expr: Option<Box<dyn Expr>>
 }
@@ -110,6 +241,7 @@ impl ASTNode for Return {
 }
 
 /// ExprStmt
+#[derive(Clone)]
 pub struct ExprStmt {
     pub expr: Box<dyn Expr>,
 }
@@ -129,7 +261,23 @@ impl ASTNode for ExprStmt {
 }
 
 /// Stmt
-pub trait Stmt: AToAny { }
+pub trait Stmt: AToAny + StmtClone { }
+
+pub trait StmtClone {
+    fn clone_box(&self) -> Box<dyn Stmt>;
+}
+
+impl<T: 'static + Stmt + Clone> StmtClone for T {
+    fn clone_box(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Stmt> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
 
 impl AToAny for Block {
     fn as_any(&self) -> &dyn Any {
@@ -155,6 +303,54 @@ impl AToAny for While {
 
 impl Stmt for While { }
 
+impl AToAny for DoWhile {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Stmt for DoWhile { }
+
+impl AToAny for For {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Stmt for For { }
+
+impl AToAny for Break {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Stmt for Break { }
+
+impl AToAny for Continue {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Stmt for Continue { }
+
 impl AToAny for If {
     fn as_any(&self) -> &dyn Any {
         self
@@ -167,6 +363,18 @@ impl AToAny for If {
 
 impl Stmt for If { }
 
+impl AToAny for Switch {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Stmt for Switch { }
+
 impl AToAny for Assign {
     fn as_any(&self) -> &dyn Any {
         self
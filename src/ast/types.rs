@@ -2,10 +2,71 @@ use std::any::Any;
 use std::fmt::{Display, Formatter};
 use crate::ast::ast_node::{ASTNode, AToAny};
 use crate::ast::ast_visitor::ASTVisitor;
+use crate::lexer::position::Position;
 
 /// BaseType
+/// The integer types are 8, 16, 32 and 64 bits wide, a plain `char` being unsigned like an
+/// `unsigned char`.
+#[derive(Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum BaseType {
-    INT, CHAR, VOID
+    INT, CHAR, VOID, SCHAR, UCHAR, SHORT, USHORT, UINT, LONG, ULONG
+}
+
+impl BaseType {
+    pub fn is_integer(&self) -> bool {
+        *self != BaseType::VOID
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, BaseType::INT | BaseType::SCHAR | BaseType::SHORT | BaseType::LONG)
+    }
+
+    /// The size in bytes.
+    pub fn size(&self) -> i32 {
+        match self {
+            BaseType::CHAR | BaseType::SCHAR | BaseType::UCHAR => 1,
+            BaseType::SHORT | BaseType::USHORT => 2,
+            BaseType::INT | BaseType::UINT => 4,
+            BaseType::LONG | BaseType::ULONG => 8,
+            BaseType::VOID => 0,
+        }
+    }
+
+    /// The integer promotion, types narrower than `int` becoming `int`.
+    pub fn promote(&self) -> BaseType {
+        if self.size() < 4 { BaseType::INT } else { *self }
+    }
+
+    /// The type both operands of an arithmetic operator are converted to, by the usual arithmetic
+    /// conversions: the wider of the promoted types, unsigned if the unsigned one is at least as
+    /// wide as the signed one.
+    pub fn common(&self, other: BaseType) -> BaseType {
+        let (t1, t2) = (self.promote(), other.promote());
+        if t1 == t2 {
+            t1
+        } else if t1.is_signed() == t2.is_signed() {
+            if t1.size() > t2.size() { t1 } else { t2 }
+        } else {
+            let (unsigned, signed) = if t1.is_signed() { (t2, t1) } else { (t1, t2) };
+            if unsigned.size() >= signed.size() { unsigned } else { signed }
+        }
+    }
+
+    /// The value converted to the type, i.e. truncated to its width and sign or zero extended
+    /// again. 64 bit values are kept as their bits, so an `unsigned long` above `i64::MAX` is
+    /// negative.
+    pub fn wrap(&self, value: i64) -> i64 {
+        match self {
+            BaseType::CHAR | BaseType::UCHAR => value as u8 as i64,
+            BaseType::SCHAR => value as i8 as i64,
+            BaseType::SHORT => value as i16 as i64,
+            BaseType::USHORT => value as u16 as i64,
+            BaseType::INT => value as i32 as i64,
+            BaseType::UINT => value as u32 as i64,
+            BaseType::LONG | BaseType::ULONG | BaseType::VOID => value,
+        }
+    }
 }
 
 impl Display for BaseType {
@@ -13,7 +74,14 @@ impl Display for BaseType {
         match self {
             BaseType::INT => write!(f, "INT"),
             BaseType::CHAR => write!(f, "CHAR"),
-            BaseType::VOID => write!(f, "VOID")
+            BaseType::VOID => write!(f, "VOID"),
+            BaseType::SCHAR => write!(f, "SCHAR"),
+            BaseType::UCHAR => write!(f, "UCHAR"),
+            BaseType::SHORT => write!(f, "SHORT"),
+            BaseType::USHORT => write!(f, "USHORT"),
+            BaseType::UINT => write!(f, "UINT"),
+            BaseType::LONG => write!(f, "LONG"),
+            BaseType::ULONG => write!(f, "ULONG")
         }
     }
 }
@@ -25,14 +93,27 @@ impl ASTNode for BaseType {
 }
 
 /// PointerType
+/// What a pointer `to_const` points to cannot be assigned through it, e.g. with `const char* s`
+/// the pointer `s` can be assigned but not `*s`. This does not take part in the equality of types,
+/// but such a pointer only converts to a plain one with a cast.
+#[derive(Clone)]
 pub struct PointerType {
-    pub pointer_type: Box<dyn Type>
+    pub pointer_type: Box<dyn Type>,
+    pub to_const: bool
 }
 
 impl PointerType {
     pub fn new(pointer_type: Box<dyn Type>) -> Box<Self> {
         Box::new(PointerType {
-            pointer_type
+            pointer_type,
+            to_const: false
+        })
+    }
+
+    pub fn to_const(pointer_type: Box<dyn Type>) -> Box<Self> {
+        Box::new(PointerType {
+            pointer_type,
+            to_const: true
         })
     }
 }
@@ -44,14 +125,17 @@ impl ASTNode for PointerType {
 }
 
 /// StructType
+#[derive(Clone)]
 pub struct StructType {
-    pub name: String
+    pub name: String,
+    pub position: Position // of the name
 }
 
 impl StructType {
     pub fn new(name: String) -> Self {
         StructType {
-            name
+            name,
+            position: Position::default()
         }
     }
 }
@@ -61,19 +145,56 @@ impl ASTNode for StructType {
         v.visit_struct_type(self)
     }
 }
+/// ClassType
+/// A reference to an object of the class, which lives on the heap.
+#[derive(Clone)]
+pub struct ClassType {
+    pub name: String,
+    pub position: Position // of the name
+}
+
+impl ClassType {
+    pub fn new(name: String) -> Self {
+        ClassType {
+            name,
+            position: Position::default()
+        }
+    }
+}
+
+impl ASTNode for ClassType {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_class_type(self)
+    }
+}
+
 /// ArrayType
+/// The size is none when it is left out and no initialiser gives it, which semantic analysis reports.
+#[derive(Clone)]
 pub struct ArrayType {
     pub array_type: Box<dyn Type>,
-    pub i: i32
+    pub i: Option<i32>
 }
 
 impl ArrayType {
     pub fn new(array_type: Box<dyn Type>, i: i32) -> Self {
+        ArrayType {
+            array_type,
+            i: Some(i)
+        }
+    }
+
+    pub fn of_size(array_type: Box<dyn Type>, i: Option<i32>) -> Self {
         ArrayType {
             array_type,
             i
         }
     }
+
+    // the number of elements, known once semantic analysis has checked the program
+    pub fn size(&self) -> i32 {
+        self.i.expect("array size missing")
+    }
 }
 
 impl ASTNode for ArrayType {
@@ -82,8 +203,48 @@ impl ASTNode for ArrayType {
     }
 }
 
+/// FunType
+/// The type of a function, only found behind a pointer: `int (*cmp)(int, int)` is a pointer to a
+/// function taking two ints and returning an int.
+#[derive(Clone)]
+pub struct FunType {
+    pub return_type: Box<dyn Type>,
+    pub param_types: Vec<Box<dyn Type>>
+}
+
+impl FunType {
+    pub fn new(return_type: Box<dyn Type>, param_types: Vec<Box<dyn Type>>) -> Self {
+        FunType {
+            return_type,
+            param_types
+        }
+    }
+}
+
+impl ASTNode for FunType {
+    fn accept<T>(&mut self, v: &mut dyn ASTVisitor<T>) -> T {
+        v.visit_fun_type(self)
+    }
+}
+
 /// Type
-pub trait Type: AToAny { }
+pub trait Type: AToAny + TypeClone { }
+
+pub trait TypeClone {
+    fn clone_box(&self) -> Box<dyn Type>;
+}
+
+impl<T: 'static + Type + Clone> TypeClone for T {
+    fn clone_box(&self) -> Box<dyn Type> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Type> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
 
 impl AToAny for BaseType {
     fn as_any(&self) -> &dyn Any {
@@ -131,4 +292,203 @@ impl AToAny for ArrayType {
     }
 }
 
-impl Type for ArrayType { }
\ No newline at end of file
+impl Type for ArrayType { }
+
+impl AToAny for ClassType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Type for ClassType { }
+
+impl AToAny for FunType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Type for FunType { }
+
+/// ErrorType
+/// The type of an expression which failed to check. It is compatible with every type so that the
+/// error is reported once, not again by each expression using its value.
+#[derive(Clone)]
+pub struct ErrorType;
+
+impl AToAny for ErrorType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Type for ErrorType { }
+impl<'a> dyn Type + 'a {
+    pub fn is_base(&self, base_type: BaseType) -> bool {
+        self.as_any().downcast_ref::<BaseType>() == Some(&base_type)
+    }
+
+    /// The base type of an integer, `char` included.
+    pub fn as_integer(&self) -> Option<BaseType> {
+        self.as_any().downcast_ref::<BaseType>().copied().filter(|t| t.is_integer())
+    }
+
+    pub fn as_pointer(&self) -> Option<&PointerType> {
+        self.as_any().downcast_ref::<PointerType>()
+    }
+
+    pub fn as_struct(&self) -> Option<&StructType> {
+        self.as_any().downcast_ref::<StructType>()
+    }
+
+    pub fn as_array(&self) -> Option<&ArrayType> {
+        self.as_any().downcast_ref::<ArrayType>()
+    }
+
+    pub fn as_class(&self) -> Option<&ClassType> {
+        self.as_any().downcast_ref::<ClassType>()
+    }
+
+    pub fn as_fun(&self) -> Option<&FunType> {
+        self.as_any().downcast_ref::<FunType>()
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.as_any().is::<ErrorType>()
+    }
+
+    /// The function a function pointer points to.
+    pub fn as_fun_pointer(&self) -> Option<&FunType> {
+        self.as_pointer().and_then(|t| t.pointer_type.as_fun())
+    }
+
+    // structural equality, e.g. used to check assignments and function arguments
+    pub fn eq_type(&self, other: &dyn Type) -> bool {
+        if self.is_error() || other.is_error() {
+            true
+        } else if let (Some(t1), Some(t2)) = (self.as_any().downcast_ref::<BaseType>(), other.as_any().downcast_ref::<BaseType>()) {
+            t1 == t2
+        } else if let (Some(t1), Some(t2)) = (self.as_pointer(), other.as_pointer()) {
+            t1.pointer_type.eq_type(t2.pointer_type.as_ref())
+        } else if let (Some(t1), Some(t2)) = (self.as_struct(), other.as_struct()) {
+            t1.name == t2.name
+        } else if let (Some(t1), Some(t2)) = (self.as_array(), other.as_array()) {
+            t1.i == t2.i && t1.array_type.eq_type(t2.array_type.as_ref())
+        } else if let (Some(t1), Some(t2)) = (self.as_class(), other.as_class()) {
+            t1.name == t2.name
+        } else if let (Some(t1), Some(t2)) = (self.as_fun(), other.as_fun()) {
+            t1.return_type.eq_type(t2.return_type.as_ref())
+                && t1.param_types.len() == t2.param_types.len()
+                && t1.param_types.iter().zip(t2.param_types.iter()).all(|(p1, p2)| p1.eq_type(p2.as_ref()))
+        } else {
+            false
+        }
+    }
+}
+
+// minic syntax of a type, used in error messages
+impl<'a> Display for dyn Type + 'a {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(t) = self.as_any().downcast_ref::<BaseType>() {
+            match t {
+                BaseType::INT => write!(f, "int"),
+                BaseType::CHAR => write!(f, "char"),
+                BaseType::VOID => write!(f, "void"),
+                BaseType::SCHAR => write!(f, "signed char"),
+                BaseType::UCHAR => write!(f, "unsigned char"),
+                BaseType::SHORT => write!(f, "short"),
+                BaseType::USHORT => write!(f, "unsigned short"),
+                BaseType::UINT => write!(f, "unsigned int"),
+                BaseType::LONG => write!(f, "long"),
+                BaseType::ULONG => write!(f, "unsigned long")
+            }
+        } else if declares_fun(self) {
+            write!(f, "{}", declaration(self, "").trim_end())
+        } else if let Some(t) = self.as_pointer() {
+            if t.pointer_type.as_array().is_some() {
+                let (element_type, dimensions) = split_array(t.pointer_type.as_ref());
+                write!(f, "{}(*){}", element_type, dimensions)
+            } else if !t.to_const {
+                write!(f, "{}*", t.pointer_type)
+            } else if t.pointer_type.as_pointer().is_some() {
+                // the const qualifies the pointer pointed to, e.g. `char* const*`
+                write!(f, "{} const*", t.pointer_type)
+            } else {
+                write!(f, "const {}*", t.pointer_type)
+            }
+        } else if let Some(t) = self.as_struct() {
+            write!(f, "struct {}", t.name)
+        } else if let Some(t) = self.as_class() {
+            write!(f, "class {}", t.name)
+        } else if self.as_array().is_some() {
+            let (element_type, dimensions) = split_array(self);
+            write!(f, "{}{}", element_type, dimensions)
+        } else if self.is_error() {
+            write!(f, "<error>")
+        } else {
+            write!(f, "?")
+        }
+    }
+}
+
+// the element type of a possibly multidimensional array and its dimensions, e.g. `int` and `[2][3]`
+fn split_array(a_type: &dyn Type) -> (&dyn Type, String) {
+    let mut element_type = a_type;
+    let mut dimensions = String::new();
+    while let Some(t) = element_type.as_array() {
+        dimensions.push_str(&t.i.map_or("[]".to_owned(), |i| format!("[{}]", i)));
+        element_type = t.array_type.as_ref();
+    }
+    (element_type, dimensions)
+}
+
+// whether the declarator of the type has parameters, for a function pointer or an array of them
+fn declares_fun(a_type: &dyn Type) -> bool {
+    let (mut element_type, _) = split_array(a_type);
+    while let Some(t) = element_type.as_pointer() {
+        element_type = t.pointer_type.as_ref();
+    }
+    element_type.as_fun().is_some()
+}
+
+/// The minic declaration of `name` with the given type, e.g. `int* p` or `char grid[3][3]`. A pointer
+/// to an array, which can only be a parameter, is written as an array whose first size is left out.
+/// The name of a function pointer goes between the return type and the parameters, as in
+/// `int (*ops[2])(int)`, and an empty name gives the type of a cast, e.g. `int (*)(int)`.
+pub fn declaration(a_type: &dyn Type, name: &str) -> String {
+    match a_type.as_pointer().filter(|t| t.pointer_type.as_array().is_some()) {
+        Some(t) => {
+            let (element_type, dimensions) = split_array(t.pointer_type.as_ref());
+            format!("{} {}[]{}", element_type, name, dimensions)
+        },
+        None => {
+            let (element_type, dimensions) = split_array(a_type);
+            let mut pointers = String::new();
+            let mut pointee = element_type;
+            while let Some(t) = pointee.as_pointer() {
+                pointers.push('*');
+                pointee = t.pointer_type.as_ref();
+            }
+            match pointee.as_fun() {
+                Some(fun_type) => {
+                    let params: Vec<String> = fun_type.param_types.iter().map(|t| declaration(t.as_ref(), "").trim_end().to_owned()).collect();
+                    let declarator = if pointers.is_empty() { name.to_owned() } else { format!("({}{}{})", pointers, name, dimensions) };
+                    declaration(fun_type.return_type.as_ref(), &format!("{}({})", declarator, params.join(", ")))
+                },
+                None => format!("{} {}{}", element_type, name, dimensions),
+            }
+        }
+    }
+}
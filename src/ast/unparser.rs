@@ -0,0 +1,696 @@
+use std::io::Write;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, EnumDecl, FunDecl, Initializer, Item, Program, StructTypeDecl, TypedefDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{declaration, ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::parser::parser::Parser;
+use crate::ASTNode;
+
+// precedences of the expressions which are not binary operators, lower binds tighter (see Parser::parse_pre)
+const PRIMARY: i32 = 0;
+const POSTFIX: i32 = 1;
+const PREFIX: i32 = 2;
+// the condition of a ?: may be any binary operation, and the left operand of an assignment any
+// conditional but not another assignment
+const BINARY: i32 = 12;
+const CONDITIONAL: i32 = 13;
+const ANY: i32 = 14;
+
+/// Unparser
+/// Prints a Program as minic source, indented by four spaces with the bodies of if and while braced
+/// on the same line. Parentheses are only written where the precedence of the operators requires them
+/// or where the source had them (`is_grouped`), so parsing the output gives back the same AST.
+pub struct Unparser<'a> {
+    writer: &'a mut dyn Write,
+    indent: usize,
+}
+
+impl<'a> Unparser<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        Unparser {
+            writer,
+            indent: 0,
+        }
+    }
+
+    fn write(&mut self, s: &str) {
+        self.writer.write_all(s.as_bytes()).unwrap();
+    }
+
+    fn write_indent(&mut self) {
+        let indent = "    ".repeat(self.indent);
+        self.write(&indent);
+    }
+
+    // `0 - e`, `0 + e`, `0 == e` and `-1 ^ e` are how the parser represents the unary `-e`, `+e`, `!e` and `~e`
+    fn as_unary(bin_op: &BinOp) -> Option<&'static str> {
+        let literal = match bin_op.expr1.as_any().downcast_ref::<IntLiteral>() {
+            Some(int_literal) if !int_literal.is_grouped && int_literal.int_type == BaseType::INT => int_literal.i,
+            _ => return None,
+        };
+        if Unparser::precedence(bin_op.expr2.as_ref()) > PREFIX {
+            return None;
+        }
+        match (literal, bin_op.op) {
+            (0, Op::SUB) => Some("-"),
+            (0, Op::ADD) => Some("+"),
+            (0, Op::EQ) => Some("!"),
+            (-1, Op::BXOR) => Some("~"),
+            _ => None,
+        }
+    }
+
+    // whether an operand written right after a prefix `-` or `+` starts with the same sign, which would
+    // read back as `--` or `++`; `!!a` and `~~a` need no space
+    fn starts_with_sign(expr: &dyn Expr, sign: &str) -> bool {
+        if !matches!(sign, "-" | "+") || expr.get_is_grouped() {
+            false
+        } else if let Some(bin_op) = expr.as_any().downcast_ref::<BinOp>() {
+            Unparser::as_unary(bin_op) == Some(sign)
+        } else if let Some(inc_dec_expr) = expr.as_any().downcast_ref::<IncDecExpr>() {
+            inc_dec_expr.prefix && inc_dec_expr.op.symbol() == sign
+        } else {
+            false
+        }
+    }
+
+    fn precedence(expr: &dyn Expr) -> i32 {
+        if expr.get_is_grouped() {
+            PRIMARY
+        } else if let Some(bin_op) = expr.as_any().downcast_ref::<BinOp>() {
+            if Unparser::as_unary(bin_op).is_some() { PREFIX } else { Parser::parse_pre(bin_op.op) }
+        } else if let Some(inc_dec_expr) = expr.as_any().downcast_ref::<IncDecExpr>() {
+            if inc_dec_expr.prefix { PREFIX } else { POSTFIX }
+        } else if expr.as_any().is::<ConditionalExpr>() {
+            CONDITIONAL
+        } else if expr.as_any().is::<AssignExpr>() {
+            ANY
+        } else if expr.as_any().is::<ValueAtExpr>() || expr.as_any().is::<AddressOfExpr>() || expr.as_any().is::<TypecastExpr>() {
+            PREFIX
+        } else if expr.as_any().is::<FieldAccessExpr>() || expr.as_any().is::<ArrayAccessExpr>() || expr.as_any().is::<MethodCallExpr>()
+            || expr.as_any().is::<IndirectCallExpr>() {
+            POSTFIX
+        } else {
+            PRIMARY
+        }
+    }
+
+    fn write_initializer(&mut self, init: &mut Initializer) {
+        match init {
+            Initializer::Expr(expr) => self.write_expr(expr, ANY),
+            Initializer::List(items) => {
+                self.write("{");
+                for (i, item) in items.iter_mut().enumerate() {
+                    if i != 0 {
+                        self.write(", ");
+                    }
+                    self.write_initializer(item);
+                }
+                self.write("}");
+            }
+        }
+    }
+
+    // writes an expression in a position where only operators binding at least as tight as max_pre may appear
+    fn write_expr(&mut self, expr: &mut Box<dyn Expr>, max_pre: i32) {
+        let parens = expr.get_is_grouped() || Unparser::precedence(expr.as_ref()) > max_pre;
+        if parens {
+            self.write("(");
+        }
+        self.visit_expr(expr);
+        if parens {
+            self.write(")");
+        }
+    }
+
+    fn write_declaration(&mut self, a_type: &dyn Type, name: &str) {
+        self.write(&declaration(a_type, name));
+    }
+
+    fn write_typedef_decl(&mut self, typedef_decl: &TypedefDecl) {
+        self.write("typedef ");
+        self.write_declaration(typedef_decl.typedef_type.as_ref(), &typedef_decl.name);
+        self.write(";\n");
+    }
+
+    // on one line, as in `enum Cell { EMPTY, X = 3, O };`
+    fn write_enum_decl(&mut self, enum_decl: &mut EnumDecl) {
+        self.write("enum ");
+        if !enum_decl.name.is_empty() {
+            self.write(&format!("{} ", enum_decl.name));
+        }
+        self.write("{ ");
+        for (i, enumerator) in enum_decl.enumerators.iter_mut().enumerate() {
+            if i != 0 {
+                self.write(", ");
+            }
+            self.write(&enumerator.name);
+            if let Some(expr) = &mut enumerator.expr {
+                self.write(" = ");
+                self.write_expr(expr, CONDITIONAL);
+            }
+        }
+        self.write(" };\n");
+    }
+
+    // the return type, name and parameters of a function, whose names may be left out in a prototype
+    fn write_signature(&mut self, fun_decl: &FunDecl) {
+        self.write_indent();
+        let params: Vec<String> = fun_decl.params.iter()
+            .map(|param| param.declaration().trim_end().to_owned())
+            .collect();
+        self.write(fun_decl.storage.keyword());
+        // the parameters follow the name, inside the declarator of a returned function pointer
+        self.write(&declaration(fun_decl.fun_type.as_ref(), &format!("{}({})", fun_decl.name, params.join(", "))));
+    }
+
+    // an assignment or an expression statement of a for header, without its ';'
+    fn write_simple_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(assign) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            self.write_expr(&mut assign.expr1, CONDITIONAL);
+            self.write(" = ");
+            self.write_expr(&mut assign.expr2, ANY);
+        } else if let Some(expr_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            self.write_expr(&mut expr_stmt.expr, ANY);
+        }
+    }
+
+    // the body of an if or a loop: a block stays on the line of its header, other statements are indented
+    fn write_body(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(block) = stmt.as_mut_any().downcast_mut::<Block>() {
+            self.write(" ");
+            block.accept(self);
+        } else {
+            self.write("\n");
+            self.indent += 1;
+            self.write_indent();
+            self.visit_stmt(stmt);
+            self.indent -= 1;
+        }
+    }
+
+    // ends the body of an if before its else
+    fn write_line_end(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if stmt.as_any().is::<Block>() {
+            self.write(" ");
+        } else {
+            self.write_indent();
+        }
+    }
+}
+
+/// The source form of a character, escaped as the tokenizer expects it.
+pub fn escape_char(c: char) -> String {
+    match c {
+        '\t' => "\\t".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\'' => "\\'".to_owned(),
+        '\\' => "\\\\".to_owned(),
+        '\0' => "\\0".to_owned(),
+        c => c.to_string(),
+    }
+}
+
+impl<'a> ASTVisitor<()> for Unparser<'a> {
+    fn visit_base_type(&mut self, base_type: &mut BaseType) {
+        let a_type: &dyn Type = base_type;
+        self.write(&a_type.to_string());
+    }
+
+    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) {
+        self.visit_type(&mut pointer_type.pointer_type);
+        self.write("*");
+    }
+
+    fn visit_struct_type(&mut self, struct_type: &mut StructType) {
+        self.write(&format!("struct {}", struct_type.name));
+    }
+
+    fn visit_array_type(&mut self, array_type: &mut ArrayType) {
+        self.visit_type(&mut array_type.array_type);
+        self.write(&array_type.i.map_or("[]".to_owned(), |i| format!("[{}]", i)));
+    }
+
+    fn visit_class_type(&mut self, class_type: &mut ClassType) {
+        self.write(&format!("class {}", class_type.name));
+    }
+
+    fn visit_fun_type(&mut self, fun_type: &mut FunType) {
+        let a_type: &dyn Type = fun_type;
+        self.write(&a_type.to_string());
+    }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        let mut previous = None;
+        for item in program.ordered_items() {
+            // consecutive globals, prototypes or typedefs are not separated by a blank line
+            let grouped = matches!((previous, item), (Some(Item::Var(_)), Item::Var(_)) | (Some(Item::Prototype(_)), Item::Prototype(_)) | (Some(Item::Typedef(_)), Item::Typedef(_)));
+            if previous.is_some() && !grouped {
+                self.write("\n");
+            }
+            match item {
+                Item::Typedef(i) => self.write_typedef_decl(&program.typedef_decls[i]),
+                Item::Enum(i) => self.write_enum_decl(&mut program.enum_decls[i]),
+                Item::Struct(i) => program.struct_type_decls[i].accept(self),
+                Item::Class(i) => program.class_decls[i].accept(self),
+                Item::Var(i) => program.var_decls[i].accept(self),
+                Item::Prototype(i) => {
+                    self.write_signature(&program.prototypes[i]);
+                    self.write(";\n");
+                },
+                Item::Fun(i) => program.fun_decls[i].accept(self),
+            }
+            previous = Some(item);
+        }
+        self.writer.flush().unwrap();
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) {
+        self.write(&format!("struct {} {{\n", struct_type_decl.struct_type.name));
+        self.indent += 1;
+        for var_decl in struct_type_decl.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        self.indent -= 1;
+        self.write("};\n");
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &mut ClassDecl) {
+        self.write(&format!("class {}", class_decl.class_type.name));
+        if let Some(parent) = &class_decl.parent {
+            self.write(&format!(" extends {}", parent.name));
+        }
+        self.write(" {\n");
+        self.indent += 1;
+        for var_decl in class_decl.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        let mut separator = if class_decl.var_decls.is_empty() { "" } else { "\n" };
+        for fun_decl in class_decl.fun_decls.iter_mut() {
+            self.write(separator);
+            fun_decl.accept(self);
+            separator = "\n";
+        }
+        self.indent -= 1;
+        self.write("}\n");
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.write_indent();
+        self.write(&var_decl.declaration());
+        if let Some(init) = &mut var_decl.init {
+            self.write(" = ");
+            self.write_initializer(init);
+        }
+        self.write(";\n");
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.write_signature(fun_decl);
+        self.write(" ");
+        fun_decl.block.accept(self);
+        self.write("\n");
+    }
+
+    // writes the block from its opening brace to its closing one, the caller ends the line
+    fn visit_block(&mut self, block: &mut Block) {
+        self.write("{\n");
+        self.indent += 1;
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.write_indent();
+            self.visit_stmt(stmt);
+            if stmt.as_any().is::<Block>() {
+                self.write("\n");
+            }
+        }
+        self.indent -= 1;
+        self.write_indent();
+        self.write("}");
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.write("while (");
+        self.write_expr(&mut a_while.expr, ANY);
+        self.write(")");
+        self.write_body(&mut a_while.stmt);
+        if a_while.stmt.as_any().is::<Block>() {
+            self.write("\n");
+        }
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.write("do");
+        self.write_body(&mut a_do_while.stmt);
+        self.write_line_end(&mut a_do_while.stmt);
+        self.write("while (");
+        self.write_expr(&mut a_do_while.expr, ANY);
+        self.write(");\n");
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        self.write("for (");
+        if let Some(init) = &mut a_for.init {
+            self.write_simple_stmt(init);
+        }
+        self.write(";");
+        if let Some(expr) = &mut a_for.expr {
+            self.write(" ");
+            self.write_expr(expr, ANY);
+        }
+        self.write(";");
+        if let Some(step) = &mut a_for.step {
+            self.write(" ");
+            self.write_simple_stmt(step);
+        }
+        self.write(")");
+        self.write_body(&mut a_for.stmt);
+        if a_for.stmt.as_any().is::<Block>() {
+            self.write("\n");
+        }
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        self.write("break;\n");
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        self.write("continue;\n");
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.write("if (");
+        self.write_expr(&mut an_if.expr, ANY);
+        self.write(")");
+        self.write_body(&mut an_if.stmt1);
+        match &mut an_if.stmt2 {
+            Some(stmt2) => {
+                self.write_line_end(&mut an_if.stmt1);
+                self.write("else");
+                if stmt2.as_any().is::<If>() {
+                    // else if chains stay flat
+                    self.write(" ");
+                    self.visit_stmt(stmt2);
+                } else {
+                    self.write_body(stmt2);
+                    if stmt2.as_any().is::<Block>() {
+                        self.write("\n");
+                    }
+                }
+            },
+            None => if an_if.stmt1.as_any().is::<Block>() {
+                self.write("\n");
+            },
+        }
+    }
+
+    // the labels are indented inside the braces and their statements one level further
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.write("switch (");
+        self.write_expr(&mut a_switch.expr, ANY);
+        self.write(") {\n");
+        self.indent += 1;
+        for case in a_switch.cases.iter_mut() {
+            self.write_indent();
+            match &mut case.expr {
+                Some(expr) => {
+                    self.write("case ");
+                    self.write_expr(expr, ANY);
+                    self.write(":\n");
+                },
+                None => self.write("default:\n"),
+            }
+            self.indent += 1;
+            for stmt in case.stmts.iter_mut() {
+                self.write_indent();
+                self.visit_stmt(stmt);
+                if stmt.as_any().is::<Block>() {
+                    self.write("\n");
+                }
+            }
+            self.indent -= 1;
+        }
+        self.indent -= 1;
+        self.write_indent();
+        self.write("}\n");
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        match &mut a_return.expr {
+            Some(expr) => {
+                self.write("return ");
+                self.write_expr(expr, ANY);
+                self.write(";\n");
+            },
+            None => self.write("return;\n"),
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.write_expr(&mut assign.expr1, CONDITIONAL);
+        self.write(" = ");
+        self.write_expr(&mut assign.expr2, ANY);
+        self.write(";\n");
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.write_expr(&mut expr_stmt.expr, ANY);
+        self.write(";\n");
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        if let Some(unary) = Unparser::as_unary(bin_op) {
+            self.write(unary);
+            if Unparser::starts_with_sign(bin_op.expr2.as_ref(), unary) {
+                self.write(" ");
+            }
+            self.write_expr(&mut bin_op.expr2, PREFIX);
+            return;
+        }
+        // all binary operators are left associative
+        let pre = Parser::parse_pre(bin_op.op);
+        self.write_expr(&mut bin_op.expr1, pre);
+        self.write(&format!(" {} ", bin_op.op.symbol()));
+        self.write_expr(&mut bin_op.expr2, pre - 1);
+    }
+
+    fn visit_op(&mut self, op: &mut Op) {
+        self.write(op.symbol());
+    }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) {
+        self.write(&int_literal.spelling());
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) {
+        self.write(&format!("'{}'", escape_char(chr_literal.c)));
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) {
+        self.write(&format!("\"{}\"", str_literal.string));
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        self.write(&var_expr.name);
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.write(&format!("({}) ", typecast_expr.typecast_type));
+        self.write_expr(&mut typecast_expr.expr, PREFIX);
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        self.write(&format!("sizeof({})", size_of_expr.sizeof_type));
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.write("&");
+        self.write_expr(&mut address_of_expr.expr, PREFIX);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.write("*");
+        self.write_expr(&mut value_at_expr.expr, PREFIX);
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        if field_access_expr.is_arrow() {
+            let value_at_expr = field_access_expr.expr.as_mut_any().downcast_mut::<ValueAtExpr>().unwrap();
+            self.write_expr(&mut value_at_expr.expr, POSTFIX);
+            self.write(&format!("->{}", field_access_expr.name));
+            return;
+        }
+        self.write_expr(&mut field_access_expr.expr, POSTFIX);
+        self.write(&format!(".{}", field_access_expr.name));
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.write_expr(&mut array_access_expr.expr1, POSTFIX);
+        self.write("[");
+        self.write_expr(&mut array_access_expr.expr2, ANY);
+        self.write("]");
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        self.write(&format!("{}(", fun_call_expr.name));
+        for (i, expr) in fun_call_expr.exprs.iter_mut().enumerate() {
+            if i != 0 {
+                self.write(", ");
+            }
+            self.write_expr(expr, ANY);
+        }
+        self.write(")");
+    }
+
+    fn visit_new_expr(&mut self, new_expr: &mut NewExpr) {
+        self.write(&format!("new class {}()", new_expr.class_type.name));
+    }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.write_expr(&mut method_call_expr.expr, POSTFIX);
+        self.write(&format!(".{}(", method_call_expr.name));
+        for (i, expr) in method_call_expr.exprs.iter_mut().enumerate() {
+            if i != 0 {
+                self.write(", ");
+            }
+            self.write_expr(expr, ANY);
+        }
+        self.write(")");
+    }
+
+    // `&f` is written as `f`, which has the same meaning
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        self.write(&fun_addr_expr.name);
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.write_expr(&mut indirect_call_expr.expr, POSTFIX);
+        self.write("(");
+        for (i, expr) in indirect_call_expr.exprs.iter_mut().enumerate() {
+            if i != 0 {
+                self.write(", ");
+            }
+            self.write_expr(expr, ANY);
+        }
+        self.write(")");
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.write_expr(&mut assign_expr.expr1, CONDITIONAL);
+        match assign_expr.op {
+            Some(op) => self.write(&format!(" {}= ", op.symbol())),
+            None => self.write(" = "),
+        }
+        self.write_expr(&mut assign_expr.expr2, ANY);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        let symbol = if inc_dec_expr.op == Op::ADD { "++" } else { "--" };
+        if inc_dec_expr.prefix {
+            self.write(symbol);
+            if Unparser::starts_with_sign(inc_dec_expr.expr.as_ref(), inc_dec_expr.op.symbol()) {
+                self.write(" ");
+            }
+            self.write_expr(&mut inc_dec_expr.expr, PREFIX);
+        } else {
+            self.write_expr(&mut inc_dec_expr.expr, POSTFIX);
+            self.write(symbol);
+        }
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.write_expr(&mut conditional_expr.expr, BINARY);
+        self.write(" ? ");
+        self.write_expr(&mut conditional_expr.expr1, ANY);
+        self.write(" : ");
+        self.write_expr(&mut conditional_expr.expr2, CONDITIONAL);
+    }
+
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) {
+        if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<BaseType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<PointerType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<StructType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ArrayType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ClassType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<FunType>() {
+            cast_type.accept(self);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+    }
+}
@@ -0,0 +1,665 @@
+use std::collections::HashSet;
+use std::io::Write;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::gen::layout::Layout;
+use crate::sem::stdlib;
+use crate::ASTNode;
+
+// the minic stdlib on top of libc, local to each unit so that several can be linked together; sizes
+// are those of minic, where pointers are 4 bytes, so mcmalloc allocates twice as many
+const PRELUDE: &str = "\
+/* generated by minic -emit-c */
+#include <stdio.h>
+#include <stdlib.h>
+
+static inline void print_s(const unsigned char* s) { fputs((const char*)s, stdout); }
+static inline void print_i(int i) { printf(\"%d\", i); }
+static inline void print_c(unsigned char c) { putchar(c); }
+static inline unsigned char read_c(void) { fflush(stdout); return (unsigned char)getchar(); }
+static inline int read_i(void) { int i = 0; fflush(stdout); if (scanf(\"%d\", &i) != 1) return 0; return i; }
+static inline void* mcmalloc(int size) { return malloc(2 * (size_t)size); }
+";
+
+// identifiers of minic programs that cannot be used as is in the generated C
+const RESERVED: [&str; 44] = [
+    "auto", "break", "case", "const", "continue", "default", "do", "double", "else", "enum", "extern",
+    "float", "for", "goto", "inline", "long", "register", "restrict", "short", "signed", "static",
+    "switch", "typedef", "union", "unsigned", "volatile", "_Bool", "_Complex", "_Imaginary",
+    "fputs", "stdout", "printf", "putchar", "fflush", "getchar", "scanf", "malloc", "FILE", "NULL",
+    "EOF", "size_t", "stdin", "stderr", "exit",
+];
+
+/// CEmitter
+/// Prints a checked Program as a standalone C99 translation unit: the stdlib prelude, then the
+/// struct declarations, globals, prototypes of every function, external or not, and their
+/// definitions. `static` and `extern` are kept but `const` is not, the program being checked already.
+/// Expressions are fully parenthesised and the bodies of if and while are always braced. Functions
+/// and globals are prefixed by `f_` and `g_` so that none of them clashes with libc, and `f_main`
+/// is called by the C `main`.
+pub struct CEmitter<'a> {
+    writer: &'a mut dyn Write,
+    indent: usize,
+    // the locals and parameters in scope, which shadow the globals, the innermost last
+    scopes: Vec<HashSet<String>>,
+    layout: Layout,
+}
+
+impl<'a> CEmitter<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        CEmitter {
+            writer,
+            indent: 0,
+            scopes: Vec::new(),
+            layout: Layout::new(&[]),
+        }
+    }
+
+    fn write(&mut self, s: &str) {
+        self.writer.write_all(s.as_bytes()).unwrap();
+    }
+
+    fn write_indent(&mut self) {
+        let indent = "    ".repeat(self.indent);
+        self.write(&indent);
+    }
+
+    fn write_body(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(block) = stmt.as_mut_any().downcast_mut::<Block>() {
+            block.accept(self);
+        } else {
+            self.write("{\n");
+            self.indent += 1;
+            self.write_indent();
+            self.visit_stmt(stmt);
+            self.indent -= 1;
+            self.write_indent();
+            self.write("}");
+        }
+    }
+
+    // the init or step of a for loop, without the terminating semicolon
+    fn write_simple_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(assign) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            self.visit_expr(&mut assign.expr1);
+            self.write(" = ");
+            self.visit_expr(&mut assign.expr2);
+        } else if let Some(expr_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            self.visit_expr(&mut expr_stmt.expr);
+        } else if let Some(an_if) = stmt.as_mut_any().downcast_mut::<If>() {
+            // the step of an inlined loop which may return is guarded by an if
+            self.write("(");
+            self.visit_expr(&mut an_if.expr);
+            self.write(") ? (void) (");
+            self.write_simple_stmt(&mut an_if.stmt1);
+            self.write(") : (void) 0");
+        }
+    }
+
+    fn write_var_decl(&mut self, var_decl: &mut VarDecl, name: &str) {
+        let declaration = declaration(var_decl.var_type.as_ref(), name);
+        self.write(var_decl.storage.keyword());
+        self.write(&declaration);
+        if let Some(init) = &mut var_decl.init {
+            self.write(" = ");
+            self.write_initializer(init);
+        }
+        self.write(";\n");
+    }
+
+    fn write_initializer(&mut self, init: &mut Initializer) {
+        match init {
+            Initializer::Expr(expr) => self.visit_expr(expr),
+            Initializer::List(items) => {
+                self.write("{");
+                for (i, item) in items.iter_mut().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.write_initializer(item);
+                }
+                self.write("}");
+            }
+        }
+    }
+
+    fn write_prototype(&mut self, fun_decl: &FunDecl) {
+        let params: Vec<String> = fun_decl.params.iter()
+            .map(|p| declaration(p.var_type.as_ref(), &identifier(&p.var_name)))
+            .collect();
+        let params = if params.is_empty() { "void".to_owned() } else { params.join(", ") };
+        let prototype = declaration(fun_decl.fun_type.as_ref(), &format!("{}({})", function(&fun_decl.name), params));
+        self.write(fun_decl.storage.keyword());
+        self.write(&prototype);
+    }
+}
+
+/// Renames the identifiers that are C keywords or used by the prelude.
+pub fn identifier(name: &str) -> String {
+    if RESERVED.contains(&name) { format!("{}_", name) } else { name.to_owned() }
+}
+
+// the C name of a function, the stdlib ones being defined by the prelude
+fn function(name: &str) -> String {
+    if stdlib::is_stdlib(name) { name.to_owned() } else { format!("f_{}", name) }
+}
+
+fn global(name: &str) -> String {
+    format!("g_{}", name)
+}
+
+/// The C declaration of `name` with the given type, e.g. `int* a[3]` or `int (*cmp)(int, int)`; an empty
+/// name gives the type name.
+pub fn declaration(a_type: &dyn Type, name: &str) -> String {
+    if let Some(t) = a_type.as_fun() {
+        let params: Vec<String> = t.param_types.iter().map(|p| declaration(p.as_ref(), "")).collect();
+        let params = if params.is_empty() { "void".to_owned() } else { params.join(", ") };
+        declaration(t.return_type.as_ref(), &format!("({})({})", name, params))
+    } else if let Some(t) = a_type.as_pointer() {
+        if t.pointer_type.as_array().is_some() {
+            declaration(t.pointer_type.as_ref(), &format!("(*{})", name))
+        } else {
+            declaration(t.pointer_type.as_ref(), &format!("*{}", name))
+        }
+    } else if let Some(t) = a_type.as_array() {
+        declaration(t.array_type.as_ref(), &format!("{}[{}]", name, t.size()))
+    } else {
+        // a minic long is 64 bits wide, which only a long long is on every C target, and a plain char
+        // is unsigned, which depends on the target in C
+        let type_name = match (a_type.as_struct(), a_type.as_integer()) {
+            (Some(t), _) => format!("struct {}", identifier(&t.name)),
+            (_, Some(BaseType::CHAR)) => "unsigned char".to_owned(),
+            (_, Some(BaseType::LONG)) => "long long".to_owned(),
+            (_, Some(BaseType::ULONG)) => "unsigned long long".to_owned(),
+            _ => a_type.to_string(),
+        };
+        if name.is_empty() { type_name } else { format!("{} {}", type_name, name) }
+    }
+}
+
+pub fn c_char(c: u8) -> String {
+    match c {
+        b'\n' => "\\n".to_owned(),
+        b'\t' => "\\t".to_owned(),
+        b'\r' => "\\r".to_owned(),
+        b'\\' => "\\\\".to_owned(),
+        b'\'' => "\\'".to_owned(),
+        b'"' => "\\\"".to_owned(),
+        c if c.is_ascii_graphic() || c == b' ' => (c as char).to_string(),
+        c => format!("\\{:03o}", c),
+    }
+}
+
+impl<'a> ASTVisitor<()> for CEmitter<'a> {
+    fn visit_base_type(&mut self, base_type: &mut BaseType) {
+        let name = declaration(base_type, "");
+        self.write(&name);
+    }
+
+    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) {
+        let name = declaration(pointer_type, "");
+        self.write(&name);
+    }
+
+    fn visit_struct_type(&mut self, struct_type: &mut StructType) {
+        let name = declaration(struct_type, "");
+        self.write(&name);
+    }
+
+    fn visit_array_type(&mut self, array_type: &mut ArrayType) {
+        let name = declaration(array_type, "");
+        self.write(&name);
+    }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        self.write(PRELUDE);
+        self.layout = Layout::new(&program.struct_type_decls);
+
+        for struct_type_decl in program.struct_type_decls.iter_mut() {
+            self.write("\n");
+            struct_type_decl.accept(self);
+        }
+        if !program.var_decls.is_empty() {
+            self.write("\n");
+        }
+        for var_decl in program.var_decls.iter_mut() {
+            self.write_var_decl(var_decl, &global(&var_decl.var_name));
+        }
+
+        self.write("\n");
+        for fun_decl in program.prototypes.iter().chain(program.fun_decls.iter()) {
+            self.write_prototype(fun_decl);
+            self.write(";\n");
+        }
+        for fun_decl in program.fun_decls.iter_mut() {
+            self.write("\n");
+            fun_decl.accept(self);
+        }
+
+        let main = program.fun_decls.iter().find(|f| f.name == "main");
+        if let Some(main) = main {
+            if main.fun_type.is_base(BaseType::INT) {
+                self.write("\nint main(void) { return f_main(); }\n");
+            } else {
+                self.write("\nint main(void) { f_main(); return 0; }\n");
+            }
+        }
+        self.writer.flush().unwrap();
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) {
+        let name = format!("struct {} {{\n", identifier(&struct_type_decl.struct_type.name));
+        self.write(&name);
+        self.indent += 1;
+        for var_decl in struct_type_decl.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        self.indent -= 1;
+        self.write("};\n");
+    }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.write_indent();
+        let name = identifier(&var_decl.var_name);
+        self.write_var_decl(var_decl, &name);
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.write_prototype(fun_decl);
+        self.write(" ");
+        self.scopes.push(fun_decl.params.iter().map(|p| p.var_name.to_owned()).collect());
+        fun_decl.block.accept(self);
+        self.scopes.pop();
+        self.write("\n");
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        self.write("{\n");
+        self.indent += 1;
+        self.scopes.push(HashSet::new());
+        for var_decl in block.var_decls.iter_mut() {
+            self.scopes.last_mut().unwrap().insert(var_decl.var_name.to_owned());
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.write_indent();
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+        self.indent -= 1;
+        self.write_indent();
+        self.write("}");
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.write("while (");
+        self.visit_expr(&mut a_while.expr);
+        self.write(") ");
+        self.write_body(&mut a_while.stmt);
+        self.write("\n");
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.write("do ");
+        self.write_body(&mut a_do_while.stmt);
+        self.write(" while (");
+        self.visit_expr(&mut a_do_while.expr);
+        self.write(");\n");
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        self.write("for (");
+        if let Some(init) = &mut a_for.init {
+            self.write_simple_stmt(init);
+        }
+        self.write("; ");
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        self.write("; ");
+        if let Some(step) = &mut a_for.step {
+            self.write_simple_stmt(step);
+        }
+        self.write(") ");
+        self.write_body(&mut a_for.stmt);
+        self.write("\n");
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        self.write("break;\n");
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        self.write("continue;\n");
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.write("if (");
+        self.visit_expr(&mut an_if.expr);
+        self.write(") ");
+        self.write_body(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.write(" else ");
+            self.write_body(stmt2);
+        }
+        self.write("\n");
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.write("switch (");
+        self.visit_expr(&mut a_switch.expr);
+        self.write(") {\n");
+        for case in a_switch.cases.iter_mut() {
+            self.write_indent();
+            match &mut case.expr {
+                Some(expr) => {
+                    self.write("case ");
+                    self.visit_expr(expr);
+                    self.write(":\n");
+                },
+                None => self.write("default:\n"),
+            }
+            self.indent += 1;
+            for stmt in case.stmts.iter_mut() {
+                self.write_indent();
+                self.visit_stmt(stmt);
+            }
+            self.indent -= 1;
+        }
+        self.write_indent();
+        self.write("}\n");
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr1);
+        self.write(" = ");
+        self.visit_expr(&mut assign.expr2);
+        self.write(";\n");
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        self.write("return");
+        if let Some(expr) = &mut a_return.expr {
+            self.write(" ");
+            self.visit_expr(expr);
+        }
+        self.write(";\n");
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+        self.write(";\n");
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.write("(");
+        self.visit_expr(&mut bin_op.expr1);
+        self.write(" ");
+        bin_op.op.accept(self);
+        self.write(" ");
+        if bin_op.op == Op::SHL || bin_op.op == Op::SHR {
+            // shifting by the width of the value or more is undefined in C, the other backends take the
+            // amount modulo the width
+            let wide = bin_op.expr1.get_expr_type().as_integer().is_some_and(|t| t.size() == 8);
+            self.write("(");
+            self.visit_expr(&mut bin_op.expr2);
+            self.write(if wide { " & 63))" } else { " & 31))" });
+            return;
+        }
+        self.visit_expr(&mut bin_op.expr2);
+        self.write(")");
+    }
+
+    fn visit_op(&mut self, op: &mut Op) {
+        self.write(match op {
+            Op::ADD => "+",
+            Op::SUB => "-",
+            Op::MUL => "*",
+            Op::DIV => "/",
+            Op::MOD => "%",
+            Op::GT => ">",
+            Op::LT => "<",
+            Op::GE => ">=",
+            Op::LE => "<=",
+            Op::NE => "!=",
+            Op::EQ => "==",
+            Op::OR => "||",
+            Op::AND => "&&",
+            Op::BOR => "|",
+            Op::BAND => "&",
+            Op::BXOR => "^",
+            Op::SHL => "<<",
+            Op::SHR => ">>",
+        });
+    }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) {
+        let literal = match int_literal.int_type {
+            BaseType::LONG => format!("{}LL", int_literal.i),
+            BaseType::UINT => format!("{}U", int_literal.i),
+            BaseType::ULONG => format!("{}ULL", int_literal.i as u64),
+            _ => int_literal.i.to_string(),
+        };
+        self.write(&literal);
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) {
+        let literal = format!("'{}'", c_char(chr_literal.c as u8));
+        self.write(&literal);
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) {
+        let literal: String = str_literal.bytes().into_iter().map(c_char).collect();
+        self.write(&format!("\"{}\"", literal));
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        let name = if self.scopes.iter().any(|scope| scope.contains(&var_expr.name)) {
+            identifier(&var_expr.name)
+        } else {
+            global(&var_expr.name)
+        };
+        self.write(&name);
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.write("((");
+        self.visit_type(&mut typecast_expr.typecast_type);
+        self.write(")");
+        self.visit_expr(&mut typecast_expr.expr);
+        self.write(")");
+    }
+
+    // the size of the minic layout, which the constant expressions of the program were folded with
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        let size = self.layout.size_of(size_of_expr.sizeof_type.as_ref());
+        self.write(&size.to_string());
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.write("(&");
+        self.visit_expr(&mut address_of_expr.expr);
+        self.write(")");
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.write("(*");
+        self.visit_expr(&mut value_at_expr.expr);
+        self.write(")");
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.write("(");
+        self.visit_expr(&mut field_access_expr.expr);
+        self.write(&format!(".{})", identifier(&field_access_expr.name)));
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.write("(");
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.write("[");
+        self.visit_expr(&mut array_access_expr.expr2);
+        self.write("])");
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        self.write(&function(&fun_call_expr.name));
+        self.write("(");
+        let mut delimiter = "";
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.write(delimiter);
+            delimiter = ", ";
+            self.visit_expr(expr);
+        }
+        self.write(")");
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_method_call_expr(&mut self, _method_call_expr: &mut MethodCallExpr) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        self.write(&function(&fun_addr_expr.name));
+    }
+
+    // the address is cast to a pointer to a function of the signature, e.g. `((int (*)(int))f)(1)`
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        let pointer_type = declaration(PointerType::new(Box::new(indirect_call_expr.fun_decl.signature())).as_ref(), "");
+        self.write(&format!("(({})", pointer_type));
+        self.visit_expr(&mut indirect_call_expr.expr);
+        self.write(")(");
+        let mut delimiter = "";
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.write(delimiter);
+            delimiter = ", ";
+            self.visit_expr(expr);
+        }
+        self.write(")");
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.write("(");
+        self.visit_expr(&mut assign_expr.expr1);
+        self.write(" ");
+        if let Some(op) = &mut assign_expr.op {
+            op.accept(self);
+        }
+        self.write("= ");
+        self.visit_expr(&mut assign_expr.expr2);
+        self.write(")");
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        let symbol = if inc_dec_expr.op == Op::ADD { "++" } else { "--" };
+        self.write("(");
+        if inc_dec_expr.prefix {
+            self.write(symbol);
+            self.visit_expr(&mut inc_dec_expr.expr);
+        } else {
+            self.visit_expr(&mut inc_dec_expr.expr);
+            self.write(symbol);
+        }
+        self.write(")");
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.write("(");
+        self.visit_expr(&mut conditional_expr.expr);
+        self.write(" ? ");
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.write(" : ");
+        self.visit_expr(&mut conditional_expr.expr2);
+        self.write(")");
+    }
+
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) {
+        let name = declaration(a_type.as_ref(), "");
+        self.write(&name);
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+            self.write("\n");
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self)
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self)
+        }
+    }
+}
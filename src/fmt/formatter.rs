@@ -0,0 +1,573 @@
+use std::collections::HashSet;
+use crate::ast::unparser::escape_char;
+use crate::lexer::token::{Token, TokenClass, Trivia};
+
+/// SourceFormatter
+/// Rewrites a token stream, read with its trivia, in the canonical minic style: four spaces of
+/// indentation, opening braces on the line of their header, `} else {` on one line, one statement or
+/// declaration per line and spaces around binary operators but not after unary ones. Comments are kept
+/// where they were, either on a line of their own or at the end of a line, and single blank lines
+/// between statements are preserved. The input is expected to parse, only its layout is looked at.
+pub struct SourceFormatter {
+    tokens: Vec<Token>,
+    pos: usize,
+    out: String,
+    level: usize,
+    at_line_start: bool,
+    pending_blank: bool,
+    // the parentheses opened in the current statement, true for the ones of a cast
+    parens: Vec<bool>,
+    // the `?` written in the current statement whose `:` has not been reached yet
+    conditionals: usize,
+    // the names declared by typedefs, which are spaced as type keywords
+    type_names: HashSet<String>,
+    prev: Prev,
+}
+
+// what the spacing rules need to know about the last token written
+#[derive(Default)]
+struct Prev {
+    class: Option<TokenClass>,
+    unary: bool,
+    // the token ends an operand, so that a following operator is binary
+    operand: bool,
+    // the token ends a type, so that a following `*` makes a pointer type
+    type_name: bool,
+}
+
+impl SourceFormatter {
+    pub fn format(tokens: Vec<Token>) -> String {
+        let type_names = SourceFormatter::typedef_names(&tokens);
+        let mut formatter = SourceFormatter {
+            tokens,
+            pos: 0,
+            out: String::new(),
+            level: 0,
+            at_line_start: true,
+            pending_blank: false,
+            parens: Vec::new(),
+            conditionals: 0,
+            type_names,
+            prev: Prev::default(),
+        };
+        while !formatter.at(TokenClass::EOF) && formatter.pos < formatter.tokens.len() {
+            formatter.item();
+        }
+        formatter.trivia();
+        formatter.newline();
+        formatter.out
+    }
+
+    // the name of a typedef is the last identifier before its `;`, or the `[` of its dimensions
+    fn typedef_names(tokens: &[Token]) -> HashSet<String> {
+        let mut names = HashSet::new();
+        for (i, token) in tokens.iter().enumerate() {
+            if token.token_class != TokenClass::TYPEDEF {
+                continue;
+            }
+            let name = tokens[i..].iter()
+                .take_while(|t| !matches!(t.token_class, TokenClass::SC | TokenClass::LSBR | TokenClass::EOF))
+                .filter(|t| t.token_class == TokenClass::IDENTIFIER)
+                .last();
+            if let Some(name) = name {
+                names.insert(name.data.to_owned());
+            }
+        }
+        names
+    }
+
+    fn class(&self, k: usize) -> TokenClass {
+        self.tokens.get(self.pos + k).map_or(TokenClass::EOF, |t| t.token_class.clone())
+    }
+
+    fn at(&self, token_class: TokenClass) -> bool {
+        self.class(0) == token_class
+    }
+
+    fn newline(&mut self) {
+        if !self.at_line_start {
+            self.out.push('\n');
+            self.at_line_start = true;
+        }
+    }
+
+    fn write(&mut self, text: &str, space: bool) {
+        if self.at_line_start {
+            if self.pending_blank && !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            self.pending_blank = false;
+            self.out.push_str(&"    ".repeat(self.level));
+        } else if space {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.at_line_start = false;
+    }
+
+    // writes the comments preceding the current token and notes the blank lines before it
+    fn trivia(&mut self) {
+        let trivia = match self.tokens.get_mut(self.pos) {
+            Some(token) => std::mem::take(&mut token.trivia),
+            None => return,
+        };
+        let mut newlines = 0;
+        let mut after_comment = false;
+        for t in trivia {
+            let comment = match t {
+                Trivia::Whitespace(s) => {
+                    newlines += s.matches('\n').count();
+                    continue;
+                },
+                Trivia::LineComment(c) | Trivia::BlockComment(c) => c,
+            };
+            if newlines == 0 && !self.out.is_empty() {
+                // at the end of the line of the previous token, even if a new line was already started, the
+                // next token still starting one
+                let ended = self.at_line_start;
+                if ended {
+                    self.out.pop();
+                }
+                self.out.push(' ');
+                self.out.push_str(&comment);
+                self.at_line_start = false;
+                if ended {
+                    self.newline();
+                }
+            } else {
+                self.newline();
+                self.blank_lines(newlines);
+                self.write(&comment, false);
+            }
+            // nothing can follow a line comment on its line
+            if comment.starts_with("//") {
+                self.newline();
+            }
+            // the token after a comment is always separated from it
+            self.prev.class = None;
+            newlines = 0;
+            after_comment = true;
+        }
+        if newlines > 0 && after_comment {
+            self.newline();
+        }
+        if self.at_line_start {
+            self.blank_lines(newlines);
+        }
+    }
+
+    // keeps a single blank line where the source had some, except at the start and end of a block
+    fn blank_lines(&mut self, newlines: usize) {
+        if newlines >= 2 && !self.at(TokenClass::RBRA) && !self.out.trim_end().ends_with('{') {
+            self.pending_blank = true;
+        }
+    }
+
+    fn text(token: &Token) -> String {
+        match token.token_class {
+            TokenClass::IDENTIFIER | TokenClass::INTLITERAL => token.data.to_owned(),
+            TokenClass::STRINGLITERAL => format!("\"{}\"", token.data),
+            // the tokenizer keeps \b and \f escaped
+            TokenClass::CHARLITERAL if token.data.chars().count() == 1 => format!("'{}'", escape_char(token.data.chars().next().unwrap())),
+            TokenClass::CHARLITERAL => format!("'{}'", token.data),
+            TokenClass::ASSIGN => "=".to_owned(),
+            TokenClass::ADDASSIGN => "+=".to_owned(),
+            TokenClass::SUBASSIGN => "-=".to_owned(),
+            TokenClass::MULASSIGN => "*=".to_owned(),
+            TokenClass::DIVASSIGN => "/=".to_owned(),
+            TokenClass::REMASSIGN => "%=".to_owned(),
+            TokenClass::LBRA => "{".to_owned(),
+            TokenClass::RBRA => "}".to_owned(),
+            TokenClass::LPAR => "(".to_owned(),
+            TokenClass::RPAR => ")".to_owned(),
+            TokenClass::LSBR => "[".to_owned(),
+            TokenClass::RSBR => "]".to_owned(),
+            TokenClass::SC => ";".to_owned(),
+            TokenClass::COMMA => ",".to_owned(),
+            TokenClass::COLON => ":".to_owned(),
+            TokenClass::INT => "int".to_owned(),
+            TokenClass::VOID => "void".to_owned(),
+            TokenClass::CHAR => "char".to_owned(),
+            TokenClass::SHORT => "short".to_owned(),
+            TokenClass::LONG => "long".to_owned(),
+            TokenClass::SIGNED => "signed".to_owned(),
+            TokenClass::UNSIGNED => "unsigned".to_owned(),
+            TokenClass::IF => "if".to_owned(),
+            TokenClass::ELSE => "else".to_owned(),
+            TokenClass::WHILE => "while".to_owned(),
+            TokenClass::DO => "do".to_owned(),
+            TokenClass::FOR => "for".to_owned(),
+            TokenClass::BREAK => "break".to_owned(),
+            TokenClass::CONTINUE => "continue".to_owned(),
+            TokenClass::SWITCH => "switch".to_owned(),
+            TokenClass::CASE => "case".to_owned(),
+            TokenClass::DEFAULT => "default".to_owned(),
+            TokenClass::RETURN => "return".to_owned(),
+            TokenClass::STRUCT => "struct".to_owned(),
+            TokenClass::TYPEDEF => "typedef".to_owned(),
+            TokenClass::ENUM => "enum".to_owned(),
+            TokenClass::CONST => "const".to_owned(),
+            TokenClass::STATIC => "static".to_owned(),
+            TokenClass::EXTERN => "extern".to_owned(),
+            TokenClass::SIZEOF => "sizeof".to_owned(),
+            TokenClass::INCLUDE => "#include".to_owned(),
+            TokenClass::LOGAND => "&&".to_owned(),
+            TokenClass::LOGOR => "||".to_owned(),
+            TokenClass::EQ => "==".to_owned(),
+            TokenClass::NE => "!=".to_owned(),
+            TokenClass::LT => "<".to_owned(),
+            TokenClass::GT => ">".to_owned(),
+            TokenClass::LE => "<=".to_owned(),
+            TokenClass::GE => ">=".to_owned(),
+            TokenClass::PLUS => "+".to_owned(),
+            TokenClass::MINUS => "-".to_owned(),
+            TokenClass::ASTERIX => "*".to_owned(),
+            TokenClass::DIV => "/".to_owned(),
+            TokenClass::REM => "%".to_owned(),
+            TokenClass::AND => "&".to_owned(),
+            TokenClass::OR => "|".to_owned(),
+            TokenClass::XOR => "^".to_owned(),
+            TokenClass::SHL => "<<".to_owned(),
+            TokenClass::SHR => ">>".to_owned(),
+            TokenClass::NOT => "!".to_owned(),
+            TokenClass::TILDE => "~".to_owned(),
+            TokenClass::INC => "++".to_owned(),
+            TokenClass::DEC => "--".to_owned(),
+            TokenClass::QMARK => "?".to_owned(),
+            TokenClass::DOT => ".".to_owned(),
+            TokenClass::ARROW => "->".to_owned(),
+            TokenClass::CLASS => "class".to_owned(),
+            TokenClass::EXTENDS => "extends".to_owned(),
+            TokenClass::NEW => "new".to_owned(),
+            TokenClass::EOF | TokenClass::INVALID => String::new(),
+        }
+    }
+
+    fn is_prefix_operator(token_class: &TokenClass) -> bool {
+        matches!(token_class, TokenClass::MINUS | TokenClass::PLUS | TokenClass::NOT | TokenClass::TILDE | TokenClass::ASTERIX | TokenClass::AND
+            | TokenClass::INC | TokenClass::DEC)
+    }
+
+    fn is_type_keyword(token_class: &TokenClass) -> bool {
+        matches!(token_class, TokenClass::INT | TokenClass::CHAR | TokenClass::VOID | TokenClass::SHORT | TokenClass::LONG | TokenClass::SIGNED
+            | TokenClass::UNSIGNED | TokenClass::STRUCT | TokenClass::CLASS | TokenClass::ENUM | TokenClass::CONST)
+    }
+
+    fn is_type_name(&self, k: usize) -> bool {
+        self.class(k) == TokenClass::IDENTIFIER && self.type_names.contains(&self.tokens[self.pos + k].data)
+    }
+
+    // whether a space separates the previous token from the current one, inside a line
+    fn space_before(&self, token_class: &TokenClass, unary: bool, pointer: bool) -> bool {
+        let prev = match &self.prev.class {
+            Some(prev) => prev,
+            None => return true,
+        };
+        // the `:` of a ?: is spaced as a binary operator, unlike the one of a case label
+        if *token_class == TokenClass::COLON && self.conditionals > 0 {
+            return true;
+        }
+        if matches!(token_class, TokenClass::RPAR | TokenClass::RSBR | TokenClass::COMMA | TokenClass::SC | TokenClass::COLON | TokenClass::DOT
+            | TokenClass::ARROW | TokenClass::LSBR) {
+            return false;
+        }
+        if *token_class == TokenClass::LPAR && matches!(prev, TokenClass::IDENTIFIER | TokenClass::SIZEOF) {
+            return false;
+        }
+        // a call through a function pointer, e.g. ops[i](x) or (*cmp)(a, b), unlike a cast and its operand
+        if *token_class == TokenClass::LPAR && self.prev.operand {
+            return false;
+        }
+        if matches!(prev, TokenClass::LPAR | TokenClass::LSBR | TokenClass::DOT | TokenClass::ARROW | TokenClass::LBRA) {
+            return false;
+        }
+        if matches!(token_class, TokenClass::INC | TokenClass::DEC) && !unary {
+            return false;
+        }
+        if self.prev.unary {
+            // - -x must not become --x, nor + ++x become +++x or & &x become &&x, while **p or !!x
+            // have no such ambiguity
+            let minus = |class: &TokenClass| matches!(class, TokenClass::MINUS | TokenClass::DEC);
+            let plus = |class: &TokenClass| matches!(class, TokenClass::PLUS | TokenClass::INC);
+            return unary && ((minus(prev) && minus(token_class)) || (plus(prev) && plus(token_class))
+                || (*prev == TokenClass::AND && *token_class == TokenClass::AND));
+        }
+        !pointer
+    }
+
+    // writes the current token with the spacing rules of expressions and declarations
+    fn emit(&mut self) {
+        self.trivia();
+        let token_class = self.class(0);
+        let unary = SourceFormatter::is_prefix_operator(&token_class) && !self.prev.operand && !self.prev.type_name;
+        let pointer = token_class == TokenClass::ASTERIX && self.prev.type_name;
+        let space = self.space_before(&token_class, unary, pointer);
+        let text = SourceFormatter::text(&self.tokens[self.pos]);
+        self.write(&text, space);
+
+        let mut cast_end = false;
+        match token_class {
+            TokenClass::LPAR => {
+                let cast = (SourceFormatter::is_type_keyword(&self.class(1)) || self.is_type_name(1))
+                    && !matches!(self.prev.class, Some(TokenClass::SIZEOF) | Some(TokenClass::IDENTIFIER));
+                self.parens.push(cast);
+            },
+            TokenClass::RPAR => cast_end = self.parens.pop().unwrap_or(false),
+            TokenClass::QMARK => self.conditionals += 1,
+            TokenClass::COLON => self.conditionals = self.conditionals.saturating_sub(1),
+            _ => { },
+        }
+        let struct_name = token_class == TokenClass::IDENTIFIER && matches!(self.prev.class, Some(TokenClass::STRUCT) | Some(TokenClass::CLASS) | Some(TokenClass::ENUM));
+        let type_name = struct_name || self.is_type_name(0);
+        self.prev = Prev {
+            operand: matches!(token_class, TokenClass::IDENTIFIER | TokenClass::INTLITERAL | TokenClass::CHARLITERAL
+                | TokenClass::STRINGLITERAL | TokenClass::RSBR) || (token_class == TokenClass::RPAR && !cast_end)
+                || (matches!(token_class, TokenClass::INC | TokenClass::DEC) && !unary),
+            type_name: pointer || type_name || matches!(token_class, TokenClass::INT | TokenClass::CHAR | TokenClass::VOID
+                | TokenClass::SHORT | TokenClass::LONG | TokenClass::SIGNED | TokenClass::UNSIGNED | TokenClass::CONST),
+            unary,
+            class: Some(token_class),
+        };
+        self.pos += 1;
+    }
+
+    // writes a structural token, such as a brace, with or without a space before it
+    fn emit_with_space(&mut self, space: bool) {
+        self.trivia();
+        let text = SourceFormatter::text(&self.tokens[self.pos]);
+        self.write(&text, space);
+        self.prev = Prev { class: Some(self.class(0)), ..Prev::default() };
+        self.pos += 1;
+    }
+
+    // writes tokens up to the end of the parenthesis opened by the current token
+    fn parenthesised(&mut self) {
+        loop {
+            let end = self.at(TokenClass::RPAR) && self.parens.len() == 1;
+            if self.at(TokenClass::EOF) {
+                return;
+            }
+            self.emit();
+            if end {
+                return;
+            }
+        }
+    }
+
+    // writes tokens up to a `;` or a `{` outside parentheses and initialiser braces, e.g. `{1, {2, 3}}`
+    fn run(&mut self) {
+        self.parens.clear();
+        self.conditionals = 0;
+        self.prev = Prev::default();
+        let mut braces = 0;
+        while !self.at(TokenClass::EOF) {
+            let outside = self.parens.is_empty() && braces == 0;
+            if self.at(TokenClass::LBRA) && (braces > 0 || self.prev.class == Some(TokenClass::ASSIGN)) {
+                braces += 1;
+                self.emit_with_space(self.prev.class != Some(TokenClass::LBRA));
+            } else if self.at(TokenClass::RBRA) && braces > 0 {
+                braces -= 1;
+                self.emit_with_space(false);
+            } else if outside && (self.at(TokenClass::SC) || self.at(TokenClass::LBRA)) {
+                break;
+            } else {
+                self.emit();
+            }
+        }
+    }
+
+    fn end_statement(&mut self) {
+        if self.at(TokenClass::SC) {
+            self.emit_with_space(false);
+        }
+        self.newline();
+    }
+
+    fn item(&mut self) {
+        if self.at(TokenClass::INCLUDE) {
+            self.emit_with_space(false);
+            self.emit_with_space(true);
+            self.newline();
+        } else if self.at(TokenClass::STRUCT) && self.class(2) == TokenClass::LBRA {
+            self.emit();
+            self.emit();
+            self.emit_with_space(true);
+            self.newline();
+            self.level += 1;
+            while !self.at(TokenClass::RBRA) && !self.at(TokenClass::EOF) {
+                self.run();
+                self.end_statement();
+            }
+            self.trivia();
+            self.level -= 1;
+            self.emit_with_space(false);
+            self.end_statement();
+            self.pending_blank = true;
+        } else if self.at(TokenClass::ENUM) && (self.class(1) == TokenClass::LBRA || self.class(2) == TokenClass::LBRA) {
+            // the enumerators stay on one line, as in `enum Cell { EMPTY, X = 3, O };`
+            self.parens.clear();
+            self.conditionals = 0;
+            while !self.at(TokenClass::LBRA) {
+                self.emit();
+            }
+            self.emit_with_space(true);
+            self.prev = Prev::default();
+            while !self.at(TokenClass::RBRA) && !self.at(TokenClass::EOF) {
+                self.emit();
+            }
+            self.trivia();
+            if self.at(TokenClass::RBRA) {
+                self.emit_with_space(true);
+            }
+            self.end_statement();
+            self.pending_blank = true;
+        } else if self.at(TokenClass::CLASS) && matches!(self.class(2), TokenClass::LBRA | TokenClass::EXTENDS) {
+            while !self.at(TokenClass::LBRA) && !self.at(TokenClass::EOF) {
+                self.emit();
+            }
+            self.emit_with_space(true);
+            self.newline();
+            self.level += 1;
+            while !self.at(TokenClass::RBRA) && !self.at(TokenClass::EOF) {
+                self.declaration();
+            }
+            // no blank line between the last method and the closing brace
+            self.pending_blank = false;
+            self.trivia();
+            self.level -= 1;
+            self.emit_with_space(false);
+            self.newline();
+            self.pending_blank = true;
+        } else {
+            self.declaration();
+        }
+    }
+
+    // writes a variable declaration up to its semicolon, or a function with its body
+    fn declaration(&mut self) {
+        self.run();
+        if self.at(TokenClass::LBRA) {
+            self.block();
+            self.newline();
+            self.pending_blank = true;
+        } else {
+            self.end_statement();
+        }
+    }
+
+    // writes a block from its opening brace, on the current line, to its closing one
+    fn block(&mut self) {
+        self.emit_with_space(true);
+        self.newline();
+        self.level += 1;
+        while !self.at(TokenClass::RBRA) && !self.at(TokenClass::EOF) {
+            self.statement();
+        }
+        self.trivia();
+        self.level -= 1;
+        self.emit_with_space(false);
+    }
+
+    fn statement(&mut self) {
+        match self.class(0) {
+            TokenClass::LBRA => {
+                self.block();
+                self.newline();
+            },
+            TokenClass::IF => self.if_statement(false),
+            TokenClass::WHILE | TokenClass::FOR => {
+                self.emit_with_space(false);
+                self.parens.clear();
+                self.parenthesised();
+                if self.body() {
+                    self.newline();
+                }
+            },
+            TokenClass::DO => {
+                self.emit_with_space(false);
+                let block = self.body();
+                if self.at(TokenClass::WHILE) {
+                    // } while (..); stays on the line of the closing brace
+                    self.emit_with_space(block);
+                    self.parens.clear();
+                    self.parenthesised();
+                }
+                self.end_statement();
+            },
+            TokenClass::SWITCH => {
+                self.emit_with_space(false);
+                self.parens.clear();
+                self.parenthesised();
+                if self.at(TokenClass::LBRA) {
+                    self.switch_body();
+                }
+                self.newline();
+            },
+            _ => {
+                self.run();
+                self.end_statement();
+            },
+        }
+    }
+
+    // writes the braces of a switch, with the labels one level in and their statements two
+    fn switch_body(&mut self) {
+        self.emit_with_space(true);
+        self.newline();
+        self.level += 1;
+        while !self.at(TokenClass::RBRA) && !self.at(TokenClass::EOF) {
+            if self.at(TokenClass::CASE) || self.at(TokenClass::DEFAULT) {
+                self.emit_with_space(false);
+                self.parens.clear();
+                self.conditionals = 0;
+                while (!self.at(TokenClass::COLON) || self.conditionals > 0) && !self.at(TokenClass::EOF) {
+                    self.emit();
+                }
+                if self.at(TokenClass::COLON) {
+                    self.emit_with_space(false);
+                }
+                self.newline();
+            } else {
+                self.level += 1;
+                self.statement();
+                self.level -= 1;
+            }
+        }
+        self.trivia();
+        self.level -= 1;
+        self.emit_with_space(false);
+    }
+
+    fn if_statement(&mut self, space: bool) {
+        self.emit_with_space(space);
+        self.parens.clear();
+        self.parenthesised();
+        let block = self.body();
+        if !self.at(TokenClass::ELSE) {
+            if block {
+                self.newline();
+            }
+            return;
+        }
+        self.emit_with_space(block);
+        if self.at(TokenClass::IF) {
+            // else if chains stay flat
+            self.if_statement(true);
+        } else if self.body() {
+            self.newline();
+        }
+    }
+
+    // the body of an if, else or loop; returns whether it is a block, left open on its closing brace
+    fn body(&mut self) -> bool {
+        if self.at(TokenClass::LBRA) {
+            self.block();
+            return true;
+        }
+        self.newline();
+        self.level += 1;
+        self.statement();
+        self.level -= 1;
+        false
+    }
+}
@@ -0,0 +1,47 @@
+use std::io::Write;
+use crate::ast::decl::Program;
+use crate::gen::convention;
+use crate::gen::ir_generator::IrGenerator;
+use crate::gen::mips::MipsEmitter;
+use crate::gen::reg_alloc;
+use crate::gen::register_file::RegisterFile;
+use crate::gen::riscv::RiscvEmitter;
+
+#[derive(Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Target {
+    MIPS, RISCV
+}
+
+/// CodeGenerator
+/// Compiles a program that passed semantic analysis to MIPS or RV32IM assembly: the program is lowered
+/// to the IR, the calling convention of the target applied, registers allocated by graph colouring and
+/// the result printed.
+pub struct CodeGenerator {
+    target: Target,
+    regs: RegisterFile,
+}
+
+impl CodeGenerator {
+    pub fn new(target: Target) -> Self {
+        CodeGenerator {
+            target,
+            regs: match target {
+                Target::MIPS => RegisterFile::mips(),
+                Target::RISCV => RegisterFile::riscv(),
+            },
+        }
+    }
+
+    pub fn emit_program(&mut self, program: &mut Program, writer: &mut dyn Write) {
+        let mut ir = IrGenerator::generate(program);
+        for function in ir.functions.iter_mut() {
+            convention::apply(function, &self.regs);
+            reg_alloc::allocate(function, &self.regs);
+        }
+        match self.target {
+            Target::MIPS => MipsEmitter::new(&self.regs, writer).emit_program(&ir),
+            Target::RISCV => RiscvEmitter::new(&self.regs, writer).emit_program(&ir),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::gen::ir::{Base, Callee, Function, Inst, Width};
+use crate::gen::layout::WORD_SIZE;
+use crate::gen::register_file::RegisterFile;
+use crate::sem::stdlib;
+
+/// Applies the calling convention to a function: parameters are received in the argument registers
+/// and then on the stack, results are returned in the return register, and every call is marked
+/// with the registers it clobbers so that values live across it end up in callee saved registers.
+pub fn apply(function: &mut Function, regs: &RegisterFile) {
+    let mut insts = Vec::new();
+
+    for (i, param) in function.params.iter().enumerate() {
+        if i < regs.args.len() {
+            insts.push(Inst::Move(*param, regs.args[i]));
+        } else {
+            insts.push(Inst::Load(Width::Word, *param, Base::Incoming, stack_offset(i, regs)));
+        }
+    }
+
+    for inst in std::mem::take(&mut function.insts) {
+        match inst {
+            Inst::Call { callee, args, ret, .. } => {
+                let mut arg_regs = Vec::new();
+                for (i, arg) in args.iter().enumerate() {
+                    if i < regs.args.len() {
+                        insts.push(Inst::Move(regs.args[i], *arg));
+                        arg_regs.push(regs.args[i]);
+                    } else {
+                        insts.push(Inst::Store(Width::Word, *arg, Base::Outgoing, stack_offset(i, regs)));
+                        function.outgoing = function.outgoing.max(stack_offset(i, regs) + WORD_SIZE);
+                    }
+                }
+
+                let clobbers = if matches!(&callee, Callee::Named(name) if stdlib::is_stdlib(name)) {
+                    regs.intrinsic_clobbers.clone()
+                } else {
+                    let mut clobbers = regs.caller_saved();
+                    clobbers.retain(|r| *r != regs.ret);
+                    clobbers.push(regs.ret);
+                    clobbers
+                };
+                insts.push(Inst::Call { callee, args: arg_regs, ret: None, clobbers });
+                if let Some(ret) = ret {
+                    insts.push(Inst::Move(ret, regs.ret));
+                }
+            },
+            Inst::Ret(Some(r)) => {
+                insts.push(Inst::Move(regs.ret, r));
+                insts.push(Inst::Ret(Some(regs.ret)));
+            },
+            inst => insts.push(inst),
+        }
+    }
+
+    function.insts = insts;
+}
+
+fn stack_offset(i: usize, regs: &RegisterFile) -> i32 {
+    (i - regs.args.len()) as i32 * WORD_SIZE
+}
@@ -0,0 +1,288 @@
+use std::fmt::{Display, Formatter};
+use crate::ast::decl::StorageClass;
+
+/// Registers below FIRST_VREG are the physical registers of the target (see RegisterFile),
+/// the others are virtual registers to be allocated.
+pub type Reg = usize;
+pub const FIRST_VREG: Reg = 64;
+
+pub fn is_virtual(reg: Reg) -> bool {
+    reg >= FIRST_VREG
+}
+
+/// Width of a memory access; bytes and halves are zero extended when loaded, unless signed.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Width {
+    Byte, SignedByte, Half, SignedHalf, Word
+}
+
+/// The operations on registers; the unsigned ones end in `u`, and Mulhu gives the high word of the
+/// unsigned product.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BinKind {
+    Add, Sub, Mul, Div, Rem, Slt, Sgt, Sle, Sge, Seq, Sne, And, Or, Xor, Sll, Sra,
+    Divu, Remu, Sltu, Sgtu, Sleu, Sgeu, Srl, Mulhu
+}
+
+/// Base of a memory access: a register, a slot of the current stack frame, or the stack areas
+/// holding the arguments that do not fit in registers, received (Incoming) or passed (Outgoing).
+#[derive(Clone, Copy, PartialEq)]
+pub enum Base {
+    Reg(Reg),
+    Frame(usize),
+    Incoming,
+    Outgoing,
+}
+
+/// The function a call jumps to: one of the program or of the stdlib, or the one whose address a
+/// register holds.
+#[derive(Clone)]
+pub enum Callee {
+    Named(String),
+    Reg(Reg),
+}
+
+#[derive(Clone)]
+pub enum Inst {
+    Li(Reg, i32),
+    La(Reg, String),
+    Move(Reg, Reg),
+    AddImm(Reg, Reg, i32),
+    Bin(BinKind, Reg, Reg, Reg),
+    FrameAddr(Reg, usize),
+    Load(Width, Reg, Base, i32),
+    Store(Width, Reg, Base, i32),
+    // before the calling convention is applied, `args` and `ret` are virtual registers;
+    // afterwards `args` are the physical registers read by the callee and `clobbers` the ones it may write
+    Call { callee: Callee, args: Vec<Reg>, ret: Option<Reg>, clobbers: Vec<Reg> },
+    Ret(Option<Reg>),
+    Label(String),
+    Jump(String),
+    BranchZero(Reg, String),
+    BranchNonZero(Reg, String),
+}
+
+impl Inst {
+    pub fn uses(&self) -> Vec<Reg> {
+        match self {
+            Inst::Move(_, s) | Inst::AddImm(_, s, _) => vec![*s],
+            Inst::Bin(_, _, s1, s2) => vec![*s1, *s2],
+            Inst::Load(_, _, Base::Reg(b), _) => vec![*b],
+            Inst::Store(_, s, Base::Reg(b), _) => vec![*s, *b],
+            Inst::Store(_, s, _, _) => vec![*s],
+            Inst::Call { callee, args, .. } => {
+                let mut uses = args.clone();
+                if let Callee::Reg(r) = callee {
+                    uses.push(*r);
+                }
+                uses
+            },
+            Inst::Ret(Some(r)) | Inst::BranchZero(r, _) | Inst::BranchNonZero(r, _) => vec![*r],
+            _ => vec![],
+        }
+    }
+
+    pub fn defs(&self) -> Vec<Reg> {
+        match self {
+            Inst::Li(d, _) | Inst::La(d, _) | Inst::Move(d, _) | Inst::AddImm(d, _, _) | Inst::Bin(_, d, _, _)
+            | Inst::FrameAddr(d, _) | Inst::Load(_, d, _, _) => vec![*d],
+            Inst::Call { ret, clobbers, .. } => {
+                let mut defs = clobbers.clone();
+                defs.extend(ret.iter());
+                defs
+            },
+            _ => vec![],
+        }
+    }
+
+    /// Renames the registers read by the instruction with `on_use` and the written ones with `on_def`.
+    pub fn rename(&mut self, on_use: &mut dyn FnMut(Reg) -> Reg, on_def: &mut dyn FnMut(Reg) -> Reg) {
+        match self {
+            Inst::Li(d, _) | Inst::La(d, _) | Inst::FrameAddr(d, _) => *d = on_def(*d),
+            Inst::Move(d, s) | Inst::AddImm(d, s, _) => {
+                *s = on_use(*s);
+                *d = on_def(*d);
+            },
+            Inst::Bin(_, d, s1, s2) => {
+                *s1 = on_use(*s1);
+                *s2 = on_use(*s2);
+                *d = on_def(*d);
+            },
+            Inst::Load(_, d, base, _) => {
+                if let Base::Reg(b) = base {
+                    *b = on_use(*b);
+                }
+                *d = on_def(*d);
+            },
+            Inst::Store(_, s, base, _) => {
+                *s = on_use(*s);
+                if let Base::Reg(b) = base {
+                    *b = on_use(*b);
+                }
+            },
+            Inst::Call { callee, args, ret, clobbers } => {
+                if let Callee::Reg(r) = callee {
+                    *r = on_use(*r);
+                }
+                for a in args.iter_mut() {
+                    *a = on_use(*a);
+                }
+                if let Some(r) = ret {
+                    *r = on_def(*r);
+                }
+                for c in clobbers.iter_mut() {
+                    *c = on_def(*c);
+                }
+            },
+            Inst::Ret(Some(r)) | Inst::BranchZero(r, _) | Inst::BranchNonZero(r, _) => *r = on_use(*r),
+            Inst::Ret(None) | Inst::Label(_) | Inst::Jump(_) => { },
+        }
+    }
+
+    pub fn is_move(&self) -> Option<(Reg, Reg)> {
+        match self {
+            Inst::Move(d, s) => Some((*d, *s)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for BinKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BinKind::Add => "add", BinKind::Sub => "sub", BinKind::Mul => "mul", BinKind::Div => "div",
+            BinKind::Rem => "rem", BinKind::Slt => "slt", BinKind::Sgt => "sgt", BinKind::Sle => "sle",
+            BinKind::Sge => "sge", BinKind::Seq => "seq", BinKind::Sne => "sne", BinKind::And => "and",
+            BinKind::Or => "or", BinKind::Xor => "xor", BinKind::Sll => "sll", BinKind::Sra => "sra",
+            BinKind::Divu => "divu", BinKind::Remu => "remu", BinKind::Sltu => "sltu", BinKind::Sgtu => "sgtu",
+            BinKind::Sleu => "sleu", BinKind::Sgeu => "sgeu", BinKind::Srl => "srl", BinKind::Mulhu => "mulhu",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for Base {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base::Reg(r) => write!(f, "r{}", r),
+            Base::Frame(slot) => write!(f, "slot{}", slot),
+            Base::Incoming => write!(f, "in"),
+            Base::Outgoing => write!(f, "out"),
+        }
+    }
+}
+
+impl Display for Inst {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = |w: &Width| match w {
+            Width::Byte => "b",
+            Width::SignedByte => "sb",
+            Width::Half => "h",
+            Width::SignedHalf => "sh",
+            Width::Word => "w",
+        };
+        match self {
+            Inst::Li(d, i) => write!(f, "  r{} = {}", d, i),
+            Inst::La(d, l) => write!(f, "  r{} = &{}", d, l),
+            Inst::Move(d, s) => write!(f, "  r{} = r{}", d, s),
+            Inst::AddImm(d, s, i) => write!(f, "  r{} = r{} + {}", d, s, i),
+            Inst::Bin(k, d, s1, s2) => write!(f, "  r{} = {} r{}, r{}", d, k, s1, s2),
+            Inst::FrameAddr(d, slot) => write!(f, "  r{} = &slot{}", d, slot),
+            Inst::Load(w, d, b, o) => write!(f, "  r{} = load.{} {}+{}", d, width(w), b, o),
+            Inst::Store(w, s, b, o) => write!(f, "  store.{} {}+{}, r{}", width(w), b, o, s),
+            Inst::Call { callee, args, ret, .. } => {
+                let args: Vec<String> = args.iter().map(|a| format!("r{}", a)).collect();
+                let callee = match callee {
+                    Callee::Named(name) => name.to_owned(),
+                    Callee::Reg(r) => format!("*r{}", r),
+                };
+                match ret {
+                    Some(r) => write!(f, "  r{} = call {}({})", r, callee, args.join(", ")),
+                    None => write!(f, "  call {}({})", callee, args.join(", ")),
+                }
+            },
+            Inst::Ret(Some(r)) => write!(f, "  ret r{}", r),
+            Inst::Ret(None) => write!(f, "  ret"),
+            Inst::Label(l) => write!(f, "{}:", l),
+            Inst::Jump(l) => write!(f, "  jump {}", l),
+            Inst::BranchZero(r, l) => write!(f, "  if r{} == 0 jump {}", r, l),
+            Inst::BranchNonZero(r, l) => write!(f, "  if r{} != 0 jump {}", r, l),
+        }
+    }
+}
+
+/// A stack slot holding a local variable that lives in memory or a spilled register.
+#[derive(Clone, Copy)]
+pub struct FrameSlot {
+    pub size: i32,
+    pub align: i32,
+}
+
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Reg>,
+    pub insts: Vec<Inst>,
+    pub frame: Vec<FrameSlot>,
+    // size in bytes of the stack area holding the arguments that do not fit in registers
+    pub outgoing: i32,
+    pub storage: StorageClass,
+    next_reg: Reg,
+}
+
+impl Function {
+    pub fn new(name: String) -> Self {
+        Function {
+            name,
+            params: Vec::new(),
+            insts: Vec::new(),
+            frame: Vec::new(),
+            outgoing: 0,
+            storage: StorageClass::Auto,
+            next_reg: FIRST_VREG,
+        }
+    }
+
+    pub fn new_reg(&mut self) -> Reg {
+        self.next_reg += 1;
+        self.next_reg - 1
+    }
+
+    pub fn new_slot(&mut self, size: i32, align: i32) -> usize {
+        self.frame.push(FrameSlot { size, align });
+        self.frame.len() - 1
+    }
+
+    pub fn emit(&mut self, inst: Inst) {
+        self.insts.push(inst);
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|p| format!("r{}", p)).collect();
+        writeln!(f, "function {}({})", self.name, params.join(", "))?;
+        for inst in &self.insts {
+            writeln!(f, "{}", inst)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Global {
+    pub label: String,
+    pub size: i32,
+    pub align: i32,
+    // the bytes of an initialised global, the others are zero
+    pub init: Option<Vec<u8>>,
+    // an extern global is defined in another file, a static one is not visible from the others
+    pub storage: StorageClass,
+}
+
+/// IrProgram
+/// The lowered form of a checked Program, shared by the native backends.
+pub struct IrProgram {
+    pub globals: Vec<Global>,
+    // (label, contents) of the string literals, contents keep the escape sequences of the source
+    pub strings: Vec<(String, String)>,
+    pub functions: Vec<Function>,
+}
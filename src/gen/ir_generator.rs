@@ -0,0 +1,1234 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::gen::ir::{Base, BinKind, Callee, Function, Global, Inst, IrProgram, Reg, Width};
+use crate::gen::layout::{InitValue, Layout};
+use crate::ASTNode;
+
+/// Where a variable lives.
+#[derive(Clone)]
+enum Location {
+    // scalar local whose address is never taken
+    Reg(Reg),
+    Frame(usize),
+    Global(String),
+    // struct parameter, the register holds the address of a copy made by the caller
+    Indirect(Reg),
+}
+
+/// IrGenerator
+/// Lowers a checked Program to the IR. Expressions evaluate to a register holding their value,
+/// except structs, arrays and longs which evaluate to their address. Structs and longs are passed to
+/// functions by address of a copy, and returned through a hidden first parameter pointing to the
+/// caller's buffer. The integers narrower than a word are kept sign or zero extended in registers,
+/// and the arithmetic on longs is done on their two words, the least significant first.
+pub struct IrGenerator {
+    layout: Layout,
+    scopes: Vec<HashMap<String, Location>>,
+    function: Function,
+    address_taken: HashSet<String>,
+    ret_ptr: Option<Reg>,
+    ir: IrProgram,
+    labels: i32,
+    // the break and continue labels of the enclosing loops and switches, the innermost last
+    loops: Vec<(String, String)>,
+}
+
+impl IrGenerator {
+    pub fn new(struct_type_decls: &[StructTypeDecl]) -> Self {
+        IrGenerator {
+            layout: Layout::new(struct_type_decls),
+            scopes: vec![HashMap::new()],
+            function: Function::new(String::new()),
+            address_taken: HashSet::new(),
+            ret_ptr: None,
+            ir: IrProgram { globals: Vec::new(), strings: Vec::new(), functions: Vec::new() },
+            labels: 0,
+            loops: Vec::new(),
+        }
+    }
+
+    pub fn generate(program: &mut Program) -> IrProgram {
+        let mut generator = IrGenerator::new(&program.struct_type_decls);
+        program.accept(&mut generator);
+        generator.ir
+    }
+
+    fn new_label(&mut self) -> String {
+        self.labels += 1;
+        format!("L{}", self.labels)
+    }
+
+    fn emit(&mut self, inst: Inst) {
+        self.function.emit(inst);
+    }
+
+    fn lookup(&self, name: &str) -> Location {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned().unwrap()
+    }
+
+    fn width(a_type: &dyn Type) -> Width {
+        match a_type.as_integer() {
+            Some(BaseType::SCHAR) => Width::SignedByte,
+            Some(t) if t.size() == 1 => Width::Byte,
+            Some(BaseType::SHORT) => Width::SignedHalf,
+            Some(BaseType::USHORT) => Width::Half,
+            _ => Width::Word,
+        }
+    }
+
+    // the values which do not fit in a register
+    fn in_memory(a_type: &dyn Type) -> bool {
+        Layout::is_aggregate(a_type) || is_long(a_type)
+    }
+
+    // the values passed and returned by the address of a copy
+    fn by_copy(a_type: &dyn Type) -> bool {
+        a_type.as_struct().is_some() || is_long(a_type)
+    }
+
+    fn declare(&mut self, var_decl: &VarDecl) {
+        let var_type = var_decl.var_type.as_ref();
+        let location = if !IrGenerator::in_memory(var_type) && !self.address_taken.contains(&var_decl.var_name) {
+            Location::Reg(self.function.new_reg())
+        } else {
+            Location::Frame(self.function.new_slot(self.layout.size_of(var_type), self.layout.align_of(var_type)))
+        };
+        self.scopes.last_mut().unwrap().insert(var_decl.var_name.to_owned(), location.clone());
+        if let Some(init) = &var_decl.init {
+            self.initialize(var_type, init, &location);
+        }
+    }
+
+    fn initialize(&mut self, var_type: &dyn Type, init: &Initializer, location: &Location) {
+        for (offset, value_type, value) in self.layout.flatten(var_type, init) {
+            let v = match value {
+                InitValue::Expr(mut expr) => self.value(&mut expr),
+                InitValue::Const(c) if is_long(value_type.as_ref()) => self.long_const(c as i64),
+                InitValue::Const(c) => {
+                    let v = self.function.new_reg();
+                    self.emit(Inst::Li(v, c));
+                    v
+                }
+            };
+            match location {
+                Location::Reg(r) => self.emit(Inst::Move(*r, v)),
+                Location::Frame(slot) if IrGenerator::by_copy(value_type.as_ref()) => {
+                    self.copy(value_type.as_ref(), (Base::Frame(*slot), offset), (Base::Reg(v), 0));
+                },
+                Location::Frame(slot) => self.emit(Inst::Store(IrGenerator::width(value_type.as_ref()), v, Base::Frame(*slot), offset)),
+                Location::Global(_) | Location::Indirect(_) => unreachable!(),
+            }
+        }
+    }
+
+    fn value(&mut self, expr: &mut Box<dyn Expr>) -> Reg {
+        self.visit_expr(expr).unwrap()
+    }
+
+    fn address(&mut self, place: (Base, i32)) -> Reg {
+        let d = self.function.new_reg();
+        match place {
+            (Base::Reg(r), 0) => return r,
+            (Base::Reg(r), offset) => self.emit(Inst::AddImm(d, r, offset)),
+            (Base::Frame(slot), 0) => self.emit(Inst::FrameAddr(d, slot)),
+            (Base::Frame(slot), offset) => {
+                let a = self.function.new_reg();
+                self.emit(Inst::FrameAddr(a, slot));
+                self.emit(Inst::AddImm(d, a, offset));
+            },
+            (Base::Incoming, _) | (Base::Outgoing, _) => unreachable!(),
+        }
+        d
+    }
+
+    fn load(&mut self, a_type: &dyn Type, place: (Base, i32)) -> Reg {
+        if IrGenerator::in_memory(a_type) {
+            return self.address(place);
+        }
+        let d = self.function.new_reg();
+        self.emit(Inst::Load(IrGenerator::width(a_type), d, place.0, place.1));
+        d
+    }
+
+    fn copy(&mut self, a_type: &dyn Type, dst: (Base, i32), src: (Base, i32)) {
+        let (width, step) = if self.layout.align_of(a_type) >= 4 { (Width::Word, 4) } else { (Width::Byte, 1) };
+        for offset in (0..self.layout.size_of(a_type)).step_by(step) {
+            let t = self.function.new_reg();
+            self.emit(Inst::Load(width, t, src.0, src.1 + offset));
+            self.emit(Inst::Store(width, t, dst.0, dst.1 + offset));
+        }
+    }
+
+    /// Returns the memory location of an lvalue, or of a struct or array valued expression.
+    fn place(&mut self, expr: &mut Box<dyn Expr>) -> (Base, i32) {
+        if let Some(var_expr) = expr.as_any().downcast_ref::<VarExpr>() {
+            self.place_var(var_expr)
+        } else if let Some(field_access_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            self.place_field(field_access_expr)
+        } else if let Some(array_access_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            self.place_element(array_access_expr)
+        } else if let Some(value_at_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            (Base::Reg(self.value(&mut value_at_expr.expr)), 0)
+        } else {
+            // struct valued calls and string literals evaluate to their address
+            (Base::Reg(self.value(expr)), 0)
+        }
+    }
+
+    fn place_var(&mut self, var_expr: &VarExpr) -> (Base, i32) {
+        match self.lookup(&var_expr.name) {
+            Location::Frame(slot) => (Base::Frame(slot), 0),
+            Location::Indirect(r) => (Base::Reg(r), 0),
+            Location::Global(label) => {
+                let d = self.function.new_reg();
+                self.emit(Inst::La(d, label));
+                (Base::Reg(d), 0)
+            },
+            Location::Reg(_) => unreachable!("variable {} is not in memory", var_expr.name),
+        }
+    }
+
+    fn place_field(&mut self, field_access_expr: &mut FieldAccessExpr) -> (Base, i32) {
+        let struct_name = field_access_expr.expr.get_expr_type().as_struct().unwrap().name.to_owned();
+        let (offset, _) = self.layout.field(&struct_name, &field_access_expr.name);
+        let (base, base_offset) = self.place(&mut field_access_expr.expr);
+        (base, base_offset + offset)
+    }
+
+    fn place_element(&mut self, array_access_expr: &mut ArrayAccessExpr) -> (Base, i32) {
+        let array_type = array_access_expr.expr1.get_expr_type();
+        let element_size = match (array_type.as_array(), array_type.as_pointer()) {
+            (Some(t), _) => self.layout.size_of(t.array_type.as_ref()),
+            (_, Some(t)) => self.layout.size_of(t.pointer_type.as_ref()),
+            _ => unreachable!(),
+        };
+        // arrays evaluate to their address
+        let base = self.value(&mut array_access_expr.expr1);
+        let index = self.value(&mut array_access_expr.expr2);
+
+        let offset = if element_size == 1 {
+            index
+        } else {
+            let size = self.function.new_reg();
+            let offset = self.function.new_reg();
+            self.emit(Inst::Li(size, element_size));
+            self.emit(Inst::Bin(BinKind::Mul, offset, index, size));
+            offset
+        };
+        let d = self.function.new_reg();
+        self.emit(Inst::Bin(BinKind::Add, d, base, offset));
+        (Base::Reg(d), 0)
+    }
+
+    // the operators which are not short circuit, on operands of a type as wide as a word
+    fn bin_kind(op: Op, signed: bool) -> BinKind {
+        match (op, signed) {
+            (Op::ADD, _) => BinKind::Add,
+            (Op::SUB, _) => BinKind::Sub,
+            (Op::MUL, _) => BinKind::Mul,
+            (Op::DIV, true) => BinKind::Div,
+            (Op::DIV, false) => BinKind::Divu,
+            (Op::MOD, true) => BinKind::Rem,
+            (Op::MOD, false) => BinKind::Remu,
+            (Op::GT, true) => BinKind::Sgt,
+            (Op::GT, false) => BinKind::Sgtu,
+            (Op::LT, true) => BinKind::Slt,
+            (Op::LT, false) => BinKind::Sltu,
+            (Op::GE, true) => BinKind::Sge,
+            (Op::GE, false) => BinKind::Sgeu,
+            (Op::LE, true) => BinKind::Sle,
+            (Op::LE, false) => BinKind::Sleu,
+            (Op::NE, _) => BinKind::Sne,
+            (Op::EQ, _) => BinKind::Seq,
+            (Op::BAND, _) => BinKind::And,
+            (Op::BOR, _) => BinKind::Or,
+            (Op::BXOR, _) => BinKind::Xor,
+            (Op::SHL, _) => BinKind::Sll,
+            (Op::SHR, true) => BinKind::Sra,
+            (Op::SHR, false) => BinKind::Srl,
+            (Op::OR, _) | (Op::AND, _) => unreachable!(),
+        }
+    }
+
+    fn li(&mut self, i: i32) -> Reg {
+        let d = self.function.new_reg();
+        self.emit(Inst::Li(d, i));
+        d
+    }
+
+    fn bin(&mut self, kind: BinKind, s1: Reg, s2: Reg) -> Reg {
+        let d = self.function.new_reg();
+        self.emit(Inst::Bin(kind, d, s1, s2));
+        d
+    }
+
+    // applies an operator to operands of an integer type, the amount of a shift being an int
+    fn operation(&mut self, op: Op, int_type: BaseType, lhs: Reg, rhs: Reg) -> Reg {
+        if int_type.size() == 8 {
+            self.long_op(op, int_type.is_signed(), lhs, rhs)
+        } else {
+            self.bin(IrGenerator::bin_kind(op, int_type.is_signed()), lhs, rhs)
+        }
+    }
+
+    // the value of a compound assignment, done in the type the right hand side was converted to
+    fn compound(&mut self, op: Op, lhs_type: BaseType, rhs_type: BaseType, old: Reg, rhs: Reg) -> Reg {
+        let old = self.convert(old, lhs_type, rhs_type);
+        let result = self.operation(op, rhs_type, old, rhs);
+        self.convert(result, rhs_type, lhs_type)
+    }
+
+    // converts an integer to another integer type
+    fn convert(&mut self, v: Reg, from: BaseType, to: BaseType) -> Reg {
+        if to.size() == 8 {
+            if from.size() == 8 {
+                return v;
+            }
+            let hi = if from.is_signed() {
+                let bits = self.li(31);
+                self.bin(BinKind::Sra, v, bits)
+            } else {
+                self.li(0)
+            };
+            return self.long(v, hi);
+        }
+        let v = if from.size() == 8 { self.halves(v).0 } else { v };
+        // a wider type, or one as wide, holds every value of the type converted from
+        let widening = to.size() > from.size() && (to.is_signed() || !from.is_signed());
+        if to.size() == 4 || widening || from == to {
+            return v;
+        }
+        if to.is_signed() {
+            let bits = self.li(32 - to.size() * 8);
+            let t = self.bin(BinKind::Sll, v, bits);
+            self.bin(BinKind::Sra, t, bits)
+        } else {
+            let mask = self.li((1 << (to.size() * 8)) - 1);
+            self.bin(BinKind::And, v, mask)
+        }
+    }
+
+    // the two words of the long at an address, the least significant first
+    fn halves(&mut self, address: Reg) -> (Reg, Reg) {
+        let lo = self.function.new_reg();
+        let hi = self.function.new_reg();
+        self.emit(Inst::Load(Width::Word, lo, Base::Reg(address), 0));
+        self.emit(Inst::Load(Width::Word, hi, Base::Reg(address), 4));
+        (lo, hi)
+    }
+
+    // the address of a new long made of two words
+    fn long(&mut self, lo: Reg, hi: Reg) -> Reg {
+        let slot = self.function.new_slot(8, 8);
+        self.emit(Inst::Store(Width::Word, lo, Base::Frame(slot), 0));
+        self.emit(Inst::Store(Width::Word, hi, Base::Frame(slot), 4));
+        self.address((Base::Frame(slot), 0))
+    }
+
+    fn long_const(&mut self, value: i64) -> Reg {
+        let lo = self.li(value as i32);
+        let hi = self.li((value >> 32) as i32);
+        self.long(lo, hi)
+    }
+
+    // adds or subtracts two longs given by their words, carrying between the words
+    fn long_add(&mut self, kind: BinKind, a: (Reg, Reg), b: (Reg, Reg)) -> (Reg, Reg) {
+        let lo = self.bin(kind, a.0, b.0);
+        let carry = if kind == BinKind::Add { self.bin(BinKind::Sltu, lo, a.0) } else { self.bin(BinKind::Sltu, a.0, b.0) };
+        let hi = self.bin(kind, a.1, b.1);
+        (lo, self.bin(kind, hi, carry))
+    }
+
+    // whether a long is less than another, giving an int
+    fn long_less(&mut self, signed: bool, a: (Reg, Reg), b: (Reg, Reg)) -> Reg {
+        let hi_less = self.bin(if signed { BinKind::Slt } else { BinKind::Sltu }, a.1, b.1);
+        let hi_equal = self.bin(BinKind::Seq, a.1, b.1);
+        let lo_less = self.bin(BinKind::Sltu, a.0, b.0);
+        let t = self.bin(BinKind::And, hi_equal, lo_less);
+        self.bin(BinKind::Or, hi_less, t)
+    }
+
+    // the operators on longs, comparisons giving an int and the amount of a shift being an int
+    fn long_op(&mut self, op: Op, signed: bool, lhs: Reg, rhs: Reg) -> Reg {
+        let a = self.halves(lhs);
+        if op == Op::SHL || op == Op::SHR {
+            return self.long_shift(op, signed, a, rhs);
+        }
+        let b = self.halves(rhs);
+        let (lo, hi) = match op {
+            Op::ADD => self.long_add(BinKind::Add, a, b),
+            Op::SUB => self.long_add(BinKind::Sub, a, b),
+            Op::MUL => {
+                let lo = self.bin(BinKind::Mul, a.0, b.0);
+                let carry = self.bin(BinKind::Mulhu, a.0, b.0);
+                let t1 = self.bin(BinKind::Mul, a.0, b.1);
+                let t2 = self.bin(BinKind::Mul, a.1, b.0);
+                let t = self.bin(BinKind::Add, carry, t1);
+                (lo, self.bin(BinKind::Add, t, t2))
+            },
+            Op::DIV | Op::MOD => self.long_divide(op, signed, a, b),
+            Op::BAND | Op::BOR | Op::BXOR => {
+                let kind = IrGenerator::bin_kind(op, signed);
+                (self.bin(kind, a.0, b.0), self.bin(kind, a.1, b.1))
+            },
+            Op::EQ | Op::NE => {
+                let lo = self.bin(BinKind::Xor, a.0, b.0);
+                let hi = self.bin(BinKind::Xor, a.1, b.1);
+                let differ = self.bin(BinKind::Or, lo, hi);
+                let zero = self.li(0);
+                return self.bin(if op == Op::EQ { BinKind::Seq } else { BinKind::Sne }, differ, zero);
+            },
+            Op::LT => return self.long_less(signed, a, b),
+            Op::GT => return self.long_less(signed, b, a),
+            Op::GE | Op::LE => {
+                let less = if op == Op::GE { self.long_less(signed, a, b) } else { self.long_less(signed, b, a) };
+                let one = self.li(1);
+                return self.bin(BinKind::Xor, less, one);
+            },
+            Op::OR | Op::AND | Op::SHL | Op::SHR => unreachable!(),
+        };
+        self.long(lo, hi)
+    }
+
+    // shifts a long by an amount taken modulo 64, the words moving across each other
+    fn long_shift(&mut self, op: Op, signed: bool, a: (Reg, Reg), amount: Reg) -> Reg {
+        let (lo, hi) = (self.function.new_reg(), self.function.new_reg());
+        let small = self.new_label();
+        let end = self.new_label();
+        let one = self.li(1);
+        let mask = self.li(63);
+        let n = self.bin(BinKind::And, amount, mask);
+        let word = self.li(32);
+        let big = self.bin(BinKind::And, n, word);
+        self.emit(Inst::BranchZero(big, small.to_owned()));
+
+        // by 32 or more, the machine shifts taking the amount modulo 32
+        let right = if signed { BinKind::Sra } else { BinKind::Srl };
+        if op == Op::SHL {
+            self.emit(Inst::Bin(BinKind::Sll, hi, a.0, n));
+            self.emit(Inst::Li(lo, 0));
+        } else {
+            self.emit(Inst::Bin(right, lo, a.1, n));
+            if signed {
+                let bits = self.li(31);
+                self.emit(Inst::Bin(BinKind::Sra, hi, a.1, bits));
+            } else {
+                self.emit(Inst::Li(hi, 0));
+            }
+        }
+        self.emit(Inst::Jump(end.to_owned()));
+
+        // by less than 32, the bits crossing over are shifted by 31 - n after a shift by one, which
+        // leaves none for n = 0
+        self.emit(Inst::Label(small));
+        let bits = self.li(31);
+        let rest = self.bin(BinKind::Sub, bits, n);
+        if op == Op::SHL {
+            let t = self.bin(BinKind::Srl, a.0, one);
+            let crossing = self.bin(BinKind::Srl, t, rest);
+            let t = self.bin(BinKind::Sll, a.1, n);
+            self.emit(Inst::Bin(BinKind::Or, hi, t, crossing));
+            self.emit(Inst::Bin(BinKind::Sll, lo, a.0, n));
+        } else {
+            let t = self.bin(BinKind::Sll, a.1, one);
+            let crossing = self.bin(BinKind::Sll, t, rest);
+            let t = self.bin(BinKind::Srl, a.0, n);
+            self.emit(Inst::Bin(BinKind::Or, lo, t, crossing));
+            self.emit(Inst::Bin(right, hi, a.1, n));
+        }
+        self.emit(Inst::Label(end));
+        self.long(lo, hi)
+    }
+
+    // negates a long if the mask is all ones, leaving it unchanged if the mask is zero
+    fn long_negate_if(&mut self, a: (Reg, Reg), mask: Reg) -> (Reg, Reg) {
+        let lo = self.bin(BinKind::Xor, a.0, mask);
+        let hi = self.bin(BinKind::Xor, a.1, mask);
+        self.long_add(BinKind::Sub, (lo, hi), (mask, mask))
+    }
+
+    // divides longs a bit at a time: the dividend is shifted into the remainder, and the bits of the
+    // quotient take its place. Signed operands are divided as their magnitudes, the quotient being
+    // negative if their signs differ and the remainder having the sign of the dividend.
+    fn long_divide(&mut self, op: Op, signed: bool, a: (Reg, Reg), b: (Reg, Reg)) -> (Reg, Reg) {
+        let zero = self.li(0);
+        let (mut a, mut b) = (a, b);
+        let (mut a_sign, mut b_sign) = (zero, zero);
+        if signed {
+            let t = self.bin(BinKind::Slt, a.1, zero);
+            a_sign = self.bin(BinKind::Sub, zero, t);
+            let t = self.bin(BinKind::Slt, b.1, zero);
+            b_sign = self.bin(BinKind::Sub, zero, t);
+            a = self.long_negate_if(a, a_sign);
+            b = self.long_negate_if(b, b_sign);
+        }
+
+        let (n_lo, n_hi, r_lo, r_hi, count) = (self.function.new_reg(), self.function.new_reg(), self.function.new_reg(), self.function.new_reg(), self.function.new_reg());
+        self.emit(Inst::Move(n_lo, a.0));
+        self.emit(Inst::Move(n_hi, a.1));
+        self.emit(Inst::Li(r_lo, 0));
+        self.emit(Inst::Li(r_hi, 0));
+        self.emit(Inst::Li(count, 64));
+        let one = self.li(1);
+        let top = self.li(31);
+        let start = self.new_label();
+        let next = self.new_label();
+
+        self.emit(Inst::Label(start.to_owned()));
+        for (hi, lo) in [(r_hi, r_lo), (r_lo, n_hi), (n_hi, n_lo)] {
+            let bit = self.bin(BinKind::Srl, lo, top);
+            let t = self.bin(BinKind::Sll, hi, one);
+            self.emit(Inst::Bin(BinKind::Or, hi, t, bit));
+        }
+        self.emit(Inst::Bin(BinKind::Sll, n_lo, n_lo, one));
+        let less = self.long_less(false, (r_lo, r_hi), b);
+        self.emit(Inst::BranchNonZero(less, next.to_owned()));
+        let (lo, hi) = self.long_add(BinKind::Sub, (r_lo, r_hi), b);
+        self.emit(Inst::Move(r_lo, lo));
+        self.emit(Inst::Move(r_hi, hi));
+        self.emit(Inst::Bin(BinKind::Or, n_lo, n_lo, one));
+        self.emit(Inst::Label(next));
+        self.emit(Inst::Bin(BinKind::Sub, count, count, one));
+        self.emit(Inst::BranchNonZero(count, start));
+
+        if op == Op::DIV {
+            let sign = self.bin(BinKind::Xor, a_sign, b_sign);
+            self.long_negate_if((n_lo, n_hi), sign)
+        } else {
+            self.long_negate_if((r_lo, r_hi), a_sign)
+        }
+    }
+
+    // the register of a local kept in one
+    fn reg_of(&self, expr: &dyn Expr) -> Option<Reg> {
+        let var_expr = expr.as_any().downcast_ref::<VarExpr>()?;
+        match self.lookup(&var_expr.name) {
+            Location::Reg(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    // structs are passed by the address of a copy, and returned in a slot of the caller whose address
+    // is passed first
+    fn call(&mut self, callee: Callee, fun_type: &dyn Type, exprs: &mut [Box<dyn Expr>]) -> Option<Reg> {
+        let mut args = Vec::new();
+        for expr in exprs.iter_mut() {
+            let a_type = expr.get_expr_type().clone_box();
+            let v = self.value(expr);
+            if IrGenerator::by_copy(a_type.as_ref()) {
+                let slot = self.function.new_slot(self.layout.size_of(a_type.as_ref()), self.layout.align_of(a_type.as_ref()));
+                self.copy(a_type.as_ref(), (Base::Frame(slot), 0), (Base::Reg(v), 0));
+                args.push(self.address((Base::Frame(slot), 0)));
+            } else {
+                args.push(v);
+            }
+        }
+
+        if IrGenerator::by_copy(fun_type) {
+            let slot = self.function.new_slot(self.layout.size_of(fun_type), self.layout.align_of(fun_type));
+            let ret_ptr = self.address((Base::Frame(slot), 0));
+            args.insert(0, ret_ptr);
+            self.emit(Inst::Call { callee, args, ret: None, clobbers: vec![] });
+            return Some(self.address((Base::Frame(slot), 0)));
+        } else if fun_type.is_base(BaseType::VOID) {
+            self.emit(Inst::Call { callee, args, ret: None, clobbers: vec![] });
+            return None;
+        }
+
+        let d = self.function.new_reg();
+        self.emit(Inst::Call { callee, args, ret: Some(d), clobbers: vec![] });
+        Some(d)
+    }
+}
+
+impl ASTVisitor<Option<Reg>> for IrGenerator {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) -> Option<Reg> {
+        None
+    }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) -> Option<Reg> {
+        None
+    }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) -> Option<Reg> {
+        None
+    }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) -> Option<Reg> {
+        None
+    }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) -> Option<Reg> {
+        None
+    }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) -> Option<Reg> {
+        None
+    }
+
+    fn visit_program(&mut self, program: &mut Program) -> Option<Reg> {
+        for var_decl in program.var_decls.iter() {
+            let label = format!("g_{}", var_decl.var_name);
+            self.ir.globals.push(Global {
+                label: label.to_owned(),
+                size: self.layout.size_of(var_decl.var_type.as_ref()),
+                align: self.layout.align_of(var_decl.var_type.as_ref()),
+                init: var_decl.init.as_ref().map(|init| self.layout.image(var_decl.var_type.as_ref(), init)),
+                storage: var_decl.storage,
+            });
+            self.scopes[0].insert(var_decl.var_name.to_owned(), Location::Global(label));
+        }
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+        None
+    }
+
+    fn visit_struct_type_decl(&mut self, _struct_type_decl: &mut StructTypeDecl) -> Option<Reg> {
+        None
+    }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) -> Option<Reg> {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) -> Option<Reg> {
+        self.declare(var_decl);
+        None
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) -> Option<Reg> {
+        self.function = Function::new(fun_decl.name.to_owned());
+        self.function.storage = fun_decl.storage;
+        self.address_taken = HashSet::new();
+        collect_address_taken(&fun_decl.block, &mut self.address_taken);
+        self.scopes.push(HashMap::new());
+
+        self.ret_ptr = None;
+        if IrGenerator::by_copy(fun_decl.fun_type.as_ref()) {
+            let r = self.function.new_reg();
+            self.function.params.push(r);
+            self.ret_ptr = Some(r);
+        }
+
+        for param in fun_decl.params.iter() {
+            let r = self.function.new_reg();
+            self.function.params.push(r);
+            let param_type = param.var_type.as_ref();
+
+            let location = if IrGenerator::by_copy(param_type) {
+                Location::Indirect(r)
+            } else if self.address_taken.contains(&param.var_name) {
+                let slot = self.function.new_slot(self.layout.size_of(param_type), self.layout.align_of(param_type));
+                self.emit(Inst::Store(IrGenerator::width(param_type), r, Base::Frame(slot), 0));
+                Location::Frame(slot)
+            } else {
+                Location::Reg(r)
+            };
+            self.scopes.last_mut().unwrap().insert(param.var_name.to_owned(), location);
+        }
+
+        // parameters and the outermost locals share the same scope
+        for var_decl in fun_decl.block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in fun_decl.block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.emit(Inst::Ret(None));
+
+        self.scopes.pop();
+        let function = std::mem::replace(&mut self.function, Function::new(String::new()));
+        self.ir.functions.push(function);
+        None
+    }
+
+    fn visit_block(&mut self, block: &mut Block) -> Option<Reg> {
+        self.scopes.push(HashMap::new());
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+        None
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) -> Option<Reg> {
+        let start = self.new_label();
+        let end = self.new_label();
+
+        self.emit(Inst::Label(start.to_owned()));
+        let c = self.value(&mut a_while.expr);
+        self.emit(Inst::BranchZero(c, end.to_owned()));
+        self.loops.push((end.to_owned(), start.to_owned()));
+        self.visit_stmt(&mut a_while.stmt);
+        self.loops.pop();
+        self.emit(Inst::Jump(start));
+        self.emit(Inst::Label(end));
+        None
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) -> Option<Reg> {
+        let start = self.new_label();
+        let cond = self.new_label();
+        let end = self.new_label();
+
+        self.emit(Inst::Label(start.to_owned()));
+        self.loops.push((end.to_owned(), cond.to_owned()));
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.loops.pop();
+        self.emit(Inst::Label(cond));
+        let c = self.value(&mut a_do_while.expr);
+        self.emit(Inst::BranchNonZero(c, start));
+        self.emit(Inst::Label(end));
+        None
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) -> Option<Reg> {
+        let start = self.new_label();
+        let step = self.new_label();
+        let end = self.new_label();
+
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        self.emit(Inst::Label(start.to_owned()));
+        if let Some(expr) = &mut a_for.expr {
+            let c = self.value(expr);
+            self.emit(Inst::BranchZero(c, end.to_owned()));
+        }
+        self.loops.push((end.to_owned(), step.to_owned()));
+        self.visit_stmt(&mut a_for.stmt);
+        self.loops.pop();
+        self.emit(Inst::Label(step));
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.emit(Inst::Jump(start));
+        self.emit(Inst::Label(end));
+        None
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) -> Option<Reg> {
+        let (end, _) = self.loops.last().unwrap().clone();
+        self.emit(Inst::Jump(end));
+        None
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) -> Option<Reg> {
+        let (_, next) = self.loops.last().unwrap().clone();
+        self.emit(Inst::Jump(next));
+        None
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) -> Option<Reg> {
+        let else_label = self.new_label();
+        let c = self.value(&mut an_if.expr);
+        self.emit(Inst::BranchZero(c, else_label.to_owned()));
+        self.visit_stmt(&mut an_if.stmt1);
+
+        match &mut an_if.stmt2 {
+            Some(stmt2) => {
+                let end = self.new_label();
+                self.emit(Inst::Jump(end.to_owned()));
+                self.emit(Inst::Label(else_label));
+                self.visit_stmt(stmt2);
+                self.emit(Inst::Label(end));
+            },
+            None => self.emit(Inst::Label(else_label)),
+        }
+        None
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) -> Option<Reg> {
+        let end = self.new_label();
+        let labels: Vec<String> = a_switch.cases.iter().map(|_| self.new_label()).collect();
+
+        // compares the value with each label in turn, then goes to the default case or past the switch
+        let int_type = a_switch.expr.get_expr_type().as_integer().unwrap();
+        let v = self.value(&mut a_switch.expr);
+        let mut default = end.to_owned();
+        for (case, label) in a_switch.cases.iter_mut().zip(labels.iter()) {
+            match &mut case.expr {
+                Some(expr) => {
+                    let c = self.value(expr);
+                    let t = self.operation(Op::EQ, int_type, v, c);
+                    self.emit(Inst::BranchNonZero(t, label.to_owned()));
+                },
+                None => default = label.to_owned(),
+            }
+        }
+        self.emit(Inst::Jump(default));
+
+        // break leaves the switch while continue goes on with the enclosing loop
+        let next = self.loops.last().map(|(_, next)| next.to_owned()).unwrap_or_default();
+        self.loops.push((end.to_owned(), next));
+        for (case, label) in a_switch.cases.iter_mut().zip(labels) {
+            self.emit(Inst::Label(label));
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+        self.loops.pop();
+        self.emit(Inst::Label(end));
+        None
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) -> Option<Reg> {
+        match (&mut a_return.expr, self.ret_ptr) {
+            (Some(expr), Some(ret_ptr)) => {
+                let a_type = expr.get_expr_type().clone_box();
+                let src = self.value(expr);
+                self.copy(a_type.as_ref(), (Base::Reg(ret_ptr), 0), (Base::Reg(src), 0));
+                self.emit(Inst::Ret(None));
+            },
+            (Some(expr), None) => {
+                let v = self.value(expr);
+                self.emit(Inst::Ret(Some(v)));
+            },
+            (None, _) => self.emit(Inst::Ret(None)),
+        }
+        None
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) -> Option<Reg> {
+        let a_type = assign.expr1.get_expr_type().clone_box();
+        let v = self.value(&mut assign.expr2);
+
+        if IrGenerator::by_copy(a_type.as_ref()) {
+            let dst = self.place(&mut assign.expr1);
+            self.copy(a_type.as_ref(), dst, (Base::Reg(v), 0));
+            return None;
+        }
+
+        if let Some(var_expr) = assign.expr1.as_any().downcast_ref::<VarExpr>() {
+            if let Location::Reg(r) = self.lookup(&var_expr.name) {
+                self.emit(Inst::Move(r, v));
+                return None;
+            }
+        }
+        let (base, offset) = self.place(&mut assign.expr1);
+        self.emit(Inst::Store(IrGenerator::width(a_type.as_ref()), v, base, offset));
+        None
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) -> Option<Reg> {
+        self.visit_expr(&mut expr_stmt.expr);
+        None
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) -> Option<Reg> {
+        let d = self.function.new_reg();
+
+        if bin_op.op.is_short_circuit() {
+            // the right operand is only evaluated if the left one does not decide
+            let end = self.new_label();
+            self.emit(Inst::Li(d, if bin_op.op == Op::AND { 0 } else { 1 }));
+            let lhs = self.value(&mut bin_op.expr1);
+            if bin_op.op == Op::AND {
+                self.emit(Inst::BranchZero(lhs, end.to_owned()));
+            } else {
+                self.emit(Inst::BranchNonZero(lhs, end.to_owned()));
+            }
+            let rhs = self.value(&mut bin_op.expr2);
+            let zero = self.function.new_reg();
+            self.emit(Inst::Li(zero, 0));
+            self.emit(Inst::Bin(BinKind::Sne, d, rhs, zero));
+            self.emit(Inst::Label(end));
+            return Some(d);
+        }
+
+        // the operands were converted to a common type, or the left one is shifted by an int
+        let int_type = bin_op.expr1.get_expr_type().as_integer().unwrap_or(BaseType::INT);
+        let lhs = self.value(&mut bin_op.expr1);
+        let rhs = self.value(&mut bin_op.expr2);
+        if int_type.size() == 8 {
+            return Some(self.long_op(bin_op.op, int_type.is_signed(), lhs, rhs));
+        }
+        let kind = IrGenerator::bin_kind(bin_op.op, int_type.is_signed());
+        self.emit(Inst::Bin(kind, d, lhs, rhs));
+        Some(d)
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) -> Option<Reg> {
+        None
+    }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) -> Option<Reg> {
+        if int_literal.int_type.size() == 8 {
+            return Some(self.long_const(int_literal.i));
+        }
+        Some(self.li(int_literal.i as i32))
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) -> Option<Reg> {
+        let d = self.function.new_reg();
+        self.emit(Inst::Li(d, chr_literal.c as i32));
+        Some(d)
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) -> Option<Reg> {
+        let label = format!("str{}", self.ir.strings.len());
+        self.ir.strings.push((label.to_owned(), str_literal.string.to_owned()));
+        let d = self.function.new_reg();
+        self.emit(Inst::La(d, label));
+        Some(d)
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) -> Option<Reg> {
+        if let Location::Reg(r) = self.lookup(&var_expr.name) {
+            return Some(r);
+        }
+        let place = self.place_var(var_expr);
+        Some(self.load(var_expr.expr_type.as_ref(), place))
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) -> Option<Reg> {
+        // arrays already evaluate to their address
+        let v = self.visit_expr(&mut typecast_expr.expr)?;
+        match (typecast_expr.expr.get_expr_type().as_integer(), typecast_expr.typecast_type.as_integer()) {
+            (Some(from), Some(to)) => Some(self.convert(v, from, to)),
+            _ => Some(v),
+        }
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) -> Option<Reg> {
+        let d = self.function.new_reg();
+        self.emit(Inst::Li(d, self.layout.size_of(size_of_expr.sizeof_type.as_ref())));
+        Some(d)
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) -> Option<Reg> {
+        let place = self.place(&mut address_of_expr.expr);
+        Some(self.address(place))
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) -> Option<Reg> {
+        let a_type = value_at_expr.expr_type.clone();
+        let p = self.value(&mut value_at_expr.expr);
+        Some(self.load(a_type.as_ref(), (Base::Reg(p), 0)))
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) -> Option<Reg> {
+        let place = self.place_field(field_access_expr);
+        Some(self.load(field_access_expr.expr_type.as_ref(), place))
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) -> Option<Reg> {
+        let place = self.place_element(array_access_expr);
+        Some(self.load(array_access_expr.expr_type.as_ref(), place))
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) -> Option<Reg> {
+        let fun_type = fun_call_expr.fun_decl.fun_type.clone();
+        self.call(Callee::Named(fun_call_expr.name.to_owned()), fun_type.as_ref(), &mut fun_call_expr.exprs)
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) -> Option<Reg> {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_method_call_expr(&mut self, _method_call_expr: &mut MethodCallExpr) -> Option<Reg> {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) -> Option<Reg> {
+        let d = self.function.new_reg();
+        self.emit(Inst::La(d, format!("f_{}", fun_addr_expr.name)));
+        Some(d)
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) -> Option<Reg> {
+        let target = self.value(&mut indirect_call_expr.expr);
+        let fun_type = indirect_call_expr.fun_decl.fun_type.clone();
+        self.call(Callee::Reg(target), fun_type.as_ref(), &mut indirect_call_expr.exprs)
+    }
+
+    // the left hand side is evaluated first, and the value is copied so that it is not changed by
+    // a later assignment to the same local
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) -> Option<Reg> {
+        let a_type = assign_expr.expr_type.clone();
+        let d = self.function.new_reg();
+
+        // the right hand side of a compound assignment was converted to the type the operation is done in
+        let types = a_type.as_integer().zip(assign_expr.expr2.get_expr_type().as_integer());
+
+        if let Some(r) = self.reg_of(assign_expr.expr1.as_ref()) {
+            let rhs = self.value(&mut assign_expr.expr2);
+            match (assign_expr.op, types) {
+                (Some(op), Some((lhs_type, rhs_type))) => {
+                    let new = self.compound(op, lhs_type, rhs_type, r, rhs);
+                    self.emit(Inst::Move(r, new));
+                },
+                _ => self.emit(Inst::Move(r, rhs)),
+            }
+            self.emit(Inst::Move(d, r));
+            return Some(d);
+        }
+
+        let (base, offset) = self.place(&mut assign_expr.expr1);
+        if IrGenerator::by_copy(a_type.as_ref()) && assign_expr.op.is_none() {
+            let src = self.value(&mut assign_expr.expr2);
+            self.copy(a_type.as_ref(), (base, offset), (Base::Reg(src), 0));
+            return Some(self.address((base, offset)));
+        }
+        let new = match (assign_expr.op, types) {
+            (Some(op), Some((lhs_type, rhs_type))) => {
+                let old = self.load(a_type.as_ref(), (base, offset));
+                let rhs = self.value(&mut assign_expr.expr2);
+                self.compound(op, lhs_type, rhs_type, old, rhs)
+            },
+            _ => self.value(&mut assign_expr.expr2),
+        };
+        if is_long(a_type.as_ref()) {
+            self.copy(a_type.as_ref(), (base, offset), (Base::Reg(new), 0));
+            return Some(new);
+        }
+        self.emit(Inst::Move(d, new));
+        self.emit(Inst::Store(IrGenerator::width(a_type.as_ref()), d, base, offset));
+        Some(d)
+    }
+
+    // the value is incremented in its promoted type and converted back
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) -> Option<Reg> {
+        let int_type = inc_dec_expr.expr.get_expr_type().as_integer().unwrap();
+        let promoted = int_type.promote();
+        let old = self.function.new_reg();
+        let new = self.function.new_reg();
+
+        if let Some(r) = self.reg_of(inc_dec_expr.expr.as_ref()) {
+            self.emit(Inst::Move(old, r));
+            let one = self.li(1);
+            let t = self.operation(inc_dec_expr.op, promoted, r, one);
+            let t = self.convert(t, promoted, int_type);
+            self.emit(Inst::Move(r, t));
+            self.emit(Inst::Move(new, r));
+        } else if int_type.size() == 8 {
+            // the old value is copied before the long is overwritten
+            let place = self.place(&mut inc_dec_expr.expr);
+            let address = self.address(place);
+            let (lo, hi) = self.halves(address);
+            let old = self.long(lo, hi);
+            let one = self.long_const(1);
+            let new = self.long_op(inc_dec_expr.op, int_type.is_signed(), old, one);
+            self.copy(&int_type, (Base::Reg(address), 0), (Base::Reg(new), 0));
+            return Some(if inc_dec_expr.prefix { new } else { old });
+        } else {
+            let (base, offset) = self.place(&mut inc_dec_expr.expr);
+            let width = IrGenerator::width(&int_type);
+            self.emit(Inst::Load(width, old, base, offset));
+            let one = self.li(1);
+            let t = self.operation(inc_dec_expr.op, promoted, old, one);
+            let t = self.convert(t, promoted, int_type);
+            self.emit(Inst::Move(new, t));
+            self.emit(Inst::Store(width, new, base, offset));
+        }
+        Some(if inc_dec_expr.prefix { new } else { old })
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) -> Option<Reg> {
+        // both branches move their value, if any, to the same register
+        let void = conditional_expr.expr_type.is_base(BaseType::VOID);
+        let d = self.function.new_reg();
+        let else_label = self.new_label();
+        let end = self.new_label();
+        let c = self.value(&mut conditional_expr.expr);
+        self.emit(Inst::BranchZero(c, else_label.to_owned()));
+        if let Some(r) = self.visit_expr(&mut conditional_expr.expr1) {
+            self.emit(Inst::Move(d, r));
+        }
+        self.emit(Inst::Jump(end.to_owned()));
+        self.emit(Inst::Label(else_label));
+        if let Some(r) = self.visit_expr(&mut conditional_expr.expr2) {
+            self.emit(Inst::Move(d, r));
+        }
+        self.emit(Inst::Label(end));
+        if void { None } else { Some(d) }
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) -> Option<Reg> {
+        None
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) -> Option<Reg> {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self)
+        } else {
+            None
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) -> Option<Reg> {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self)
+        } else {
+            None
+        }
+    }
+}
+
+fn is_long(a_type: &dyn Type) -> bool {
+    a_type.as_integer().is_some_and(|t| t.size() == 8)
+}
+
+// variables whose address is taken must live in memory
+pub fn collect_address_taken(block: &Block, names: &mut HashSet<String>) {
+    for init in block.var_decls.iter().filter_map(|var_decl| var_decl.init.as_ref()) {
+        for expr in init.exprs() {
+            collect_address_taken_expr(expr, names);
+        }
+    }
+    for stmt in block.stmts.iter() {
+        collect_address_taken_stmt(stmt.as_ref(), names);
+    }
+}
+
+fn collect_address_taken_stmt(stmt: &dyn Stmt, names: &mut HashSet<String>) {
+    let any = stmt.as_any();
+    if let Some(block) = any.downcast_ref::<Block>() {
+        collect_address_taken(block, names);
+    } else if let Some(a_while) = any.downcast_ref::<While>() {
+        collect_address_taken_expr(a_while.expr.as_ref(), names);
+        collect_address_taken_stmt(a_while.stmt.as_ref(), names);
+    } else if let Some(a_do_while) = any.downcast_ref::<DoWhile>() {
+        collect_address_taken_stmt(a_do_while.stmt.as_ref(), names);
+        collect_address_taken_expr(a_do_while.expr.as_ref(), names);
+    } else if let Some(a_for) = any.downcast_ref::<For>() {
+        if let Some(init) = &a_for.init {
+            collect_address_taken_stmt(init.as_ref(), names);
+        }
+        if let Some(expr) = &a_for.expr {
+            collect_address_taken_expr(expr.as_ref(), names);
+        }
+        if let Some(step) = &a_for.step {
+            collect_address_taken_stmt(step.as_ref(), names);
+        }
+        collect_address_taken_stmt(a_for.stmt.as_ref(), names);
+    } else if let Some(a_switch) = any.downcast_ref::<Switch>() {
+        collect_address_taken_expr(a_switch.expr.as_ref(), names);
+        for case in a_switch.cases.iter() {
+            for stmt in case.stmts.iter() {
+                collect_address_taken_stmt(stmt.as_ref(), names);
+            }
+        }
+    } else if let Some(an_if) = any.downcast_ref::<If>() {
+        collect_address_taken_expr(an_if.expr.as_ref(), names);
+        collect_address_taken_stmt(an_if.stmt1.as_ref(), names);
+        if let Some(stmt2) = &an_if.stmt2 {
+            collect_address_taken_stmt(stmt2.as_ref(), names);
+        }
+    } else if let Some(a_return) = any.downcast_ref::<Return>() {
+        if let Some(expr) = &a_return.expr {
+            collect_address_taken_expr(expr.as_ref(), names);
+        }
+    } else if let Some(assign) = any.downcast_ref::<Assign>() {
+        collect_address_taken_expr(assign.expr1.as_ref(), names);
+        collect_address_taken_expr(assign.expr2.as_ref(), names);
+    } else if let Some(expr_stmt) = any.downcast_ref::<ExprStmt>() {
+        collect_address_taken_expr(expr_stmt.expr.as_ref(), names);
+    }
+}
+
+fn collect_address_taken_expr(expr: &dyn Expr, names: &mut HashSet<String>) {
+    let any = expr.as_any();
+    if let Some(address_of_expr) = any.downcast_ref::<AddressOfExpr>() {
+        // &x, &x.f and &x[i] all need x in memory, the last two already are
+        if let Some(var_expr) = address_of_expr.expr.as_any().downcast_ref::<VarExpr>() {
+            names.insert(var_expr.name.to_owned());
+        }
+        collect_address_taken_expr(address_of_expr.expr.as_ref(), names);
+    } else if let Some(bin_op) = any.downcast_ref::<BinOp>() {
+        collect_address_taken_expr(bin_op.expr1.as_ref(), names);
+        collect_address_taken_expr(bin_op.expr2.as_ref(), names);
+    } else if let Some(fun_call_expr) = any.downcast_ref::<FunCallExpr>() {
+        for arg in fun_call_expr.exprs.iter() {
+            collect_address_taken_expr(arg.as_ref(), names);
+        }
+    } else if let Some(indirect_call_expr) = any.downcast_ref::<IndirectCallExpr>() {
+        collect_address_taken_expr(indirect_call_expr.expr.as_ref(), names);
+        for arg in indirect_call_expr.exprs.iter() {
+            collect_address_taken_expr(arg.as_ref(), names);
+        }
+    } else if let Some(array_access_expr) = any.downcast_ref::<ArrayAccessExpr>() {
+        collect_address_taken_expr(array_access_expr.expr1.as_ref(), names);
+        collect_address_taken_expr(array_access_expr.expr2.as_ref(), names);
+    } else if let Some(field_access_expr) = any.downcast_ref::<FieldAccessExpr>() {
+        collect_address_taken_expr(field_access_expr.expr.as_ref(), names);
+    } else if let Some(value_at_expr) = any.downcast_ref::<ValueAtExpr>() {
+        collect_address_taken_expr(value_at_expr.expr.as_ref(), names);
+    } else if let Some(typecast_expr) = any.downcast_ref::<TypecastExpr>() {
+        collect_address_taken_expr(typecast_expr.expr.as_ref(), names);
+    } else if let Some(assign_expr) = any.downcast_ref::<AssignExpr>() {
+        collect_address_taken_expr(assign_expr.expr1.as_ref(), names);
+        collect_address_taken_expr(assign_expr.expr2.as_ref(), names);
+    } else if let Some(inc_dec_expr) = any.downcast_ref::<IncDecExpr>() {
+        collect_address_taken_expr(inc_dec_expr.expr.as_ref(), names);
+    } else if let Some(conditional_expr) = any.downcast_ref::<ConditionalExpr>() {
+        collect_address_taken_expr(conditional_expr.expr.as_ref(), names);
+        collect_address_taken_expr(conditional_expr.expr1.as_ref(), names);
+        collect_address_taken_expr(conditional_expr.expr2.as_ref(), names);
+    }
+}
+
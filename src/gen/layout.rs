@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use crate::ast::decl::{Initializer, StructTypeDecl};
+use crate::ast::expr::{Expr, StrLiteral};
+use crate::ast::types::{BaseType, Type};
+use crate::sem::constant::constant_value;
+
+/// Layout
+/// Sizes, alignments and field offsets of the types of a program on a 32 bit target:
+/// pointers take 4 bytes, the integer types 1, 2, 4 or 8 bytes aligned to their size, and
+/// struct fields are laid out in declaration order, each aligned to its natural alignment.
+pub struct Layout {
+    structs: HashMap<String, StructLayout>,
+}
+
+struct StructLayout {
+    size: i32,
+    align: i32,
+    fields: Vec<(String, i32, Box<dyn Type>)>,
+}
+
+pub const WORD_SIZE: i32 = 4;
+
+/// InitValue
+/// A value stored by an initialiser: an expression to evaluate, or a constant for the characters of a
+/// string literal and the elements and fields left out, which are zero.
+pub enum InitValue {
+    Expr(Box<dyn Expr>),
+    Const(i32),
+}
+
+pub fn align_to(offset: i32, align: i32) -> i32 {
+    (offset + align - 1) / align * align
+}
+
+impl Layout {
+    pub fn new(struct_type_decls: &[StructTypeDecl]) -> Self {
+        let mut layout = Layout {
+            structs: HashMap::new(),
+        };
+        // a struct can only contain structs declared before it
+        for struct_type_decl in struct_type_decls {
+            let mut offset = 0;
+            let mut align = 1;
+            let mut fields = Vec::new();
+            for var_decl in &struct_type_decl.var_decls {
+                let field_align = layout.align_of(var_decl.var_type.as_ref());
+                offset = align_to(offset, field_align);
+                fields.push((var_decl.var_name.to_owned(), offset, var_decl.var_type.clone()));
+                offset += layout.size_of(var_decl.var_type.as_ref());
+                align = align.max(field_align);
+            }
+            layout.structs.insert(struct_type_decl.struct_type.name.to_owned(), StructLayout {
+                size: align_to(offset, align),
+                align,
+                fields,
+            });
+        }
+        layout
+    }
+
+    pub fn size_of(&self, a_type: &dyn Type) -> i32 {
+        if let Some(int_type) = a_type.as_integer() {
+            int_type.size()
+        } else if a_type.is_base(BaseType::VOID) {
+            0
+        } else if let Some(array_type) = a_type.as_array() {
+            array_type.size() * self.size_of(array_type.array_type.as_ref())
+        } else if let Some(struct_type) = a_type.as_struct() {
+            self.structs.get(&struct_type.name).map_or(0, |s| s.size)
+        } else {
+            WORD_SIZE
+        }
+    }
+
+    pub fn align_of(&self, a_type: &dyn Type) -> i32 {
+        if let Some(int_type) = a_type.as_integer() {
+            int_type.size()
+        } else if a_type.is_base(BaseType::VOID) {
+            1
+        } else if let Some(array_type) = a_type.as_array() {
+            self.align_of(array_type.array_type.as_ref())
+        } else if let Some(struct_type) = a_type.as_struct() {
+            self.structs.get(&struct_type.name).map_or(1, |s| s.align)
+        } else {
+            WORD_SIZE
+        }
+    }
+
+    /// Returns the offset and the type of a field.
+    pub fn field(&self, struct_name: &str, field_name: &str) -> (i32, &dyn Type) {
+        let fields = &self.structs.get(struct_name).unwrap().fields;
+        let (_, offset, field_type) = fields.iter().find(|(name, _, _)| name == field_name).unwrap();
+        (*offset, field_type.as_ref())
+    }
+
+    /// Returns the values written by an initialiser with their offset from the start of the variable and
+    /// their type, in increasing offsets, zero filling every element and field left out. The values are
+    /// scalars, except for a struct initialised with an expression.
+    pub fn flatten(&self, a_type: &dyn Type, init: &Initializer) -> Vec<(i32, Box<dyn Type>, InitValue)> {
+        let mut values = Vec::new();
+        self.flatten_at(0, a_type, Some(init), &mut values);
+        values
+    }
+
+    fn flatten_at(&self, offset: i32, a_type: &dyn Type, init: Option<&Initializer>, values: &mut Vec<(i32, Box<dyn Type>, InitValue)>) {
+        if let Some(array_type) = a_type.as_array() {
+            let element_type = array_type.array_type.as_ref();
+            let element_size = self.size_of(element_type);
+            match init {
+                Some(Initializer::Expr(expr)) => {
+                    let bytes = expr.as_any().downcast_ref::<StrLiteral>().unwrap().bytes();
+                    for i in 0..array_type.size() {
+                        let c = bytes.get(i as usize).map_or(0, |c| *c as i32);
+                        values.push((offset + i, Box::new(BaseType::CHAR), InitValue::Const(c)));
+                    }
+                },
+                Some(Initializer::List(items)) => {
+                    for i in 0..array_type.size() {
+                        self.flatten_at(offset + i * element_size, element_type, items.get(i as usize), values);
+                    }
+                },
+                None => {
+                    for i in 0..array_type.size() {
+                        self.flatten_at(offset + i * element_size, element_type, None, values);
+                    }
+                }
+            }
+        } else if let (Some(struct_type), None | Some(Initializer::List(_))) = (a_type.as_struct(), init) {
+            let items = match init {
+                Some(Initializer::List(items)) => items.iter().collect(),
+                _ => vec![],
+            };
+            for (i, (_, field_offset, field_type)) in self.structs.get(&struct_type.name).unwrap().fields.iter().enumerate() {
+                self.flatten_at(offset + field_offset, field_type.as_ref(), items.get(i).copied(), values);
+            }
+        } else {
+            let value = match init {
+                Some(Initializer::Expr(expr)) => InitValue::Expr(expr.clone()),
+                _ => InitValue::Const(0),
+            };
+            values.push((offset, a_type.clone_box(), value));
+        }
+    }
+
+    /// Returns the bytes of a global initialised before the program runs, little endian, padding included.
+    pub fn image(&self, a_type: &dyn Type, init: &Initializer) -> Vec<u8> {
+        let mut bytes = vec![0; self.size_of(a_type) as usize];
+        for (offset, value_type, value) in self.flatten(a_type, init) {
+            let value = match value {
+                InitValue::Expr(expr) => constant_value(expr.as_ref()).unwrap_or(0),
+                InitValue::Const(c) => c as i64,
+            };
+            let size = self.size_of(value_type.as_ref()) as usize;
+            bytes[offset as usize..offset as usize + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        }
+        bytes
+    }
+
+    /// Structs and arrays do not fit in a register and are manipulated through their address.
+    pub fn is_aggregate(a_type: &dyn Type) -> bool {
+        a_type.as_struct().is_some() || a_type.as_array().is_some()
+    }
+}
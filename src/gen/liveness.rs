@@ -0,0 +1,122 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::gen::ir::{Inst, Reg};
+
+/// Liveness
+/// Registers live after each instruction of a function, computed on basic blocks and then
+/// propagated backwards through each block.
+pub struct Liveness {
+    pub live_out: Vec<BTreeSet<Reg>>,
+}
+
+struct BasicBlock {
+    start: usize,
+    end: usize,
+    successors: Vec<usize>,
+    uses: BTreeSet<Reg>,
+    defs: BTreeSet<Reg>,
+    live_in: BTreeSet<Reg>,
+    live_out: BTreeSet<Reg>,
+}
+
+impl Liveness {
+    pub fn new(insts: &[Inst]) -> Self {
+        let mut blocks = basic_blocks(insts);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for b in (0..blocks.len()).rev() {
+                let mut live_out = BTreeSet::new();
+                for s in blocks[b].successors.iter() {
+                    live_out.extend(blocks[*s].live_in.iter());
+                }
+                let mut live_in: BTreeSet<Reg> = live_out.difference(&blocks[b].defs).copied().collect();
+                live_in.extend(blocks[b].uses.iter());
+
+                if live_in != blocks[b].live_in || live_out != blocks[b].live_out {
+                    blocks[b].live_in = live_in;
+                    blocks[b].live_out = live_out;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut live_out = vec![BTreeSet::new(); insts.len()];
+        for block in blocks.iter() {
+            let mut live = block.live_out.clone();
+            for i in (block.start..block.end).rev() {
+                live_out[i] = live.clone();
+                for d in insts[i].defs() {
+                    live.remove(&d);
+                }
+                live.extend(insts[i].uses());
+            }
+        }
+        Liveness { live_out }
+    }
+}
+
+fn ends_block(inst: &Inst) -> bool {
+    matches!(inst, Inst::Jump(_) | Inst::BranchZero(_, _) | Inst::BranchNonZero(_, _) | Inst::Ret(_))
+}
+
+fn basic_blocks(insts: &[Inst]) -> Vec<BasicBlock> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for i in 0..insts.len() {
+        let next_is_label = matches!(insts.get(i + 1), Some(Inst::Label(_)));
+        if ends_block(&insts[i]) || next_is_label || i + 1 == insts.len() {
+            blocks.push(BasicBlock {
+                start,
+                end: i + 1,
+                successors: Vec::new(),
+                uses: BTreeSet::new(),
+                defs: BTreeSet::new(),
+                live_in: BTreeSet::new(),
+                live_out: BTreeSet::new(),
+            });
+            start = i + 1;
+        }
+    }
+
+    let mut labels = HashMap::new();
+    for (b, block) in blocks.iter().enumerate() {
+        if let Inst::Label(l) = &insts[block.start] {
+            labels.insert(l.to_owned(), b);
+        }
+    }
+
+    for b in 0..blocks.len() {
+        let last = &insts[blocks[b].end - 1];
+        let mut successors = Vec::new();
+        match last {
+            Inst::Jump(l) => successors.push(labels[l]),
+            Inst::BranchZero(_, l) | Inst::BranchNonZero(_, l) => {
+                successors.push(labels[l]);
+                if b + 1 < blocks.len() {
+                    successors.push(b + 1);
+                }
+            },
+            Inst::Ret(_) => { },
+            _ => if b + 1 < blocks.len() {
+                successors.push(b + 1);
+            },
+        }
+        blocks[b].successors = successors;
+
+        // uses are the registers read before being written in the block
+        let mut uses = BTreeSet::new();
+        let mut defs = BTreeSet::new();
+        for inst in &insts[blocks[b].start..blocks[b].end] {
+            for u in inst.uses() {
+                if !defs.contains(&u) {
+                    uses.insert(u);
+                }
+            }
+            defs.extend(inst.defs());
+        }
+        blocks[b].uses = uses;
+        blocks[b].defs = defs;
+    }
+    blocks
+}
@@ -0,0 +1,240 @@
+use std::io::Write;
+use crate::ast::decl::StorageClass;
+use crate::gen::ir::{Base, BinKind, Callee, Function, Inst, IrProgram, Width};
+use crate::gen::layout::{align_to, WORD_SIZE};
+use crate::gen::register_file::RegisterFile;
+
+const RA: &str = "$ra";
+const FP: &str = "$fp";
+const SP: &str = "$sp";
+// reserved for an exception handler, which the programs run on MARS do not have; $at would do but
+// the assemblers warn about each use of it
+const SCRATCH: &str = "$k0";
+
+fn fits(imm: i32) -> bool {
+    (-32768..32768).contains(&imm)
+}
+
+/// MipsEmitter
+/// Prints an allocated IrProgram as MIPS assembly for the MARS simulator; the stdlib functions are
+/// expanded to syscalls. A frame holds, from the stack pointer up: the outgoing arguments, the frame
+/// slots, then the saved registers. The frame pointer holds the stack pointer of the caller. Offsets
+/// which do not fit in an immediate are added through `$k0`.
+pub struct MipsEmitter<'a> {
+    regs: &'a RegisterFile,
+    writer: &'a mut dyn Write,
+}
+
+struct Frame {
+    size: i32,
+    slots: Vec<i32>,
+    saved: Vec<(&'static str, i32)>,
+}
+
+impl<'a> MipsEmitter<'a> {
+    pub fn new(regs: &'a RegisterFile, writer: &'a mut dyn Write) -> Self {
+        MipsEmitter {
+            regs,
+            writer,
+        }
+    }
+
+    fn emit(&mut self, line: String) {
+        writeln!(self.writer, "{}", line).unwrap();
+    }
+
+    pub fn emit_program(&mut self, ir: &IrProgram) {
+        self.emit(".data".to_owned());
+        for global in ir.globals.iter().filter(|global| global.storage != StorageClass::Extern) {
+            if global.storage != StorageClass::Static {
+                self.emit(format!(".globl {}", global.label));
+            }
+            self.emit("    .align 2".to_owned());
+            match &global.init {
+                Some(bytes) if !bytes.is_empty() => {
+                    let bytes: Vec<String> = bytes.iter().map(|b| b.to_string()).collect();
+                    self.emit(format!("{}: .byte {}", global.label, bytes.join(", ")));
+                },
+                _ => self.emit(format!("{}: .space {}", global.label, global.size.max(1))),
+            }
+        }
+        for (label, contents) in ir.strings.iter() {
+            self.emit(format!("{}: .asciiz \"{}\"", label, contents));
+        }
+
+        self.emit("".to_owned());
+        self.emit(".text".to_owned());
+        self.emit(".globl main".to_owned());
+        self.emit("main:".to_owned());
+        self.emit("    jal f_main".to_owned());
+        self.emit("    li $v0, 10".to_owned());
+        self.emit("    syscall".to_owned());
+
+        for function in ir.functions.iter() {
+            self.emit_function(function);
+        }
+        self.writer.flush().unwrap();
+    }
+
+    fn frame(&self, function: &Function) -> Frame {
+        let mut offset = function.outgoing;
+        let mut slots = Vec::new();
+        for slot in function.frame.iter() {
+            offset = align_to(offset, slot.align);
+            slots.push(offset);
+            offset += slot.size;
+        }
+        offset = align_to(offset, WORD_SIZE);
+
+        let mut saved = vec![(RA, offset), (FP, offset + WORD_SIZE)];
+        offset += 2 * WORD_SIZE;
+        for r in self.regs.callee_saved.iter() {
+            if function.insts.iter().any(|inst| inst.defs().contains(r)) {
+                saved.push((self.regs.name(*r), offset));
+                offset += WORD_SIZE;
+            }
+        }
+        Frame { size: align_to(offset, 8), slots, saved }
+    }
+
+    fn emit_function(&mut self, function: &Function) {
+        let frame = self.frame(function);
+        let exit = format!("f_{}_exit", function.name);
+
+        self.emit("".to_owned());
+        if function.storage != StorageClass::Static {
+            self.emit(format!(".globl f_{}", function.name));
+        }
+        self.emit(format!("f_{}:", function.name));
+        self.add_imm(SP, SP, -frame.size);
+        for (name, offset) in frame.saved.iter() {
+            self.memory("sw", name, SP, *offset);
+        }
+        self.add_imm(FP, SP, frame.size);
+
+        for inst in function.insts.iter() {
+            self.emit_inst(inst, &frame, &exit);
+        }
+
+        self.emit(format!("{}:", exit));
+        for (name, offset) in frame.saved.iter() {
+            self.memory("lw", name, SP, *offset);
+        }
+        self.add_imm(SP, SP, frame.size);
+        self.emit(format!("    jr {}", RA));
+    }
+
+    // adds an immediate to a register, through the scratch register when it does not fit
+    fn add_imm(&mut self, d: &str, s: &str, imm: i32) {
+        if fits(imm) {
+            self.emit(format!("    addiu {}, {}, {}", d, s, imm));
+        } else {
+            self.emit(format!("    lui {}, {}", SCRATCH, (imm as u32) >> 16));
+            self.emit(format!("    ori {}, {}, {}", SCRATCH, SCRATCH, imm as u32 & 0xffff));
+            self.emit(format!("    addu {}, {}, {}", d, s, SCRATCH));
+        }
+    }
+
+    fn memory(&mut self, op: &str, r: &str, base: &str, offset: i32) {
+        if fits(offset) {
+            self.emit(format!("    {} {}, {}({})", op, r, offset, base));
+        } else {
+            self.add_imm(SCRATCH, base, offset);
+            self.emit(format!("    {} {}, 0({})", op, r, SCRATCH));
+        }
+    }
+
+    fn base(&self, base: &Base, offset: i32, frame: &Frame) -> (&'static str, i32) {
+        match base {
+            Base::Reg(r) => (self.regs.name(*r), offset),
+            Base::Frame(slot) => (SP, frame.slots[*slot] + offset),
+            Base::Incoming => (FP, offset),
+            Base::Outgoing => (SP, offset),
+        }
+    }
+
+    fn emit_inst(&mut self, inst: &Inst, frame: &Frame, exit: &str) {
+        let regs = self.regs;
+        let r = |reg: &usize| regs.name(*reg);
+        let line = match inst {
+            Inst::Li(d, i) => format!("    li {}, {}", r(d), i),
+            Inst::La(d, label) => format!("    la {}, {}", r(d), label),
+            Inst::Move(d, s) => format!("    move {}, {}", r(d), r(s)),
+            Inst::AddImm(d, s, i) => return self.add_imm(r(d), r(s), *i),
+            Inst::Bin(BinKind::Div, d, s1, s2) => format!("    div {}, {}\n    mflo {}", r(s1), r(s2), r(d)),
+            Inst::Bin(BinKind::Rem, d, s1, s2) => format!("    div {}, {}\n    mfhi {}", r(s1), r(s2), r(d)),
+            Inst::Bin(BinKind::Divu, d, s1, s2) => format!("    divu {}, {}\n    mflo {}", r(s1), r(s2), r(d)),
+            Inst::Bin(BinKind::Remu, d, s1, s2) => format!("    divu {}, {}\n    mfhi {}", r(s1), r(s2), r(d)),
+            Inst::Bin(BinKind::Mulhu, d, s1, s2) => format!("    multu {}, {}\n    mfhi {}", r(s1), r(s2), r(d)),
+            Inst::Bin(kind, d, s1, s2) => {
+                let op = match kind {
+                    BinKind::Add => "addu",
+                    BinKind::Sub => "subu",
+                    BinKind::Mul => "mul",
+                    BinKind::Slt => "slt",
+                    BinKind::Sgt => "sgt",
+                    BinKind::Sle => "sle",
+                    BinKind::Sge => "sge",
+                    BinKind::Seq => "seq",
+                    BinKind::Sne => "sne",
+                    BinKind::And => "and",
+                    BinKind::Or => "or",
+                    BinKind::Xor => "xor",
+                    BinKind::Sll => "sllv",
+                    BinKind::Sra => "srav",
+                    BinKind::Sltu => "sltu",
+                    BinKind::Sgtu => "sgtu",
+                    BinKind::Sleu => "sleu",
+                    BinKind::Sgeu => "sgeu",
+                    BinKind::Srl => "srlv",
+                    BinKind::Div | BinKind::Rem | BinKind::Divu | BinKind::Remu | BinKind::Mulhu => unreachable!(),
+                };
+                format!("    {} {}, {}, {}", op, r(d), r(s1), r(s2))
+            },
+            Inst::FrameAddr(d, slot) => return self.add_imm(r(d), SP, frame.slots[*slot]),
+            Inst::Load(width, d, base, offset) => {
+                let op = match width {
+                    Width::Byte => "lbu",
+                    Width::SignedByte => "lb",
+                    Width::Half => "lhu",
+                    Width::SignedHalf => "lh",
+                    Width::Word => "lw",
+                };
+                let (base, offset) = self.base(base, *offset, frame);
+                return self.memory(op, r(d), base, offset);
+            },
+            Inst::Store(width, s, base, offset) => {
+                let op = match width {
+                    Width::Byte | Width::SignedByte => "sb",
+                    Width::Half | Width::SignedHalf => "sh",
+                    Width::Word => "sw",
+                };
+                let (base, offset) = self.base(base, *offset, frame);
+                return self.memory(op, r(s), base, offset);
+            },
+            Inst::Call { callee: Callee::Named(name), .. } => match syscall(name) {
+                Some(code) => format!("    li $v0, {}\n    syscall", code),
+                None => format!("    jal f_{}", name),
+            },
+            Inst::Call { callee: Callee::Reg(s), .. } => format!("    jalr {}", r(s)),
+            Inst::Ret(_) => format!("    j {}", exit),
+            Inst::Label(label) => format!("{}:", label),
+            Inst::Jump(label) => format!("    j {}", label),
+            Inst::BranchZero(s, label) => format!("    beqz {}, {}", r(s), label),
+            Inst::BranchNonZero(s, label) => format!("    bnez {}, {}", r(s), label),
+        };
+        self.emit(line);
+    }
+}
+
+fn syscall(name: &str) -> Option<i32> {
+    match name {
+        "print_i" => Some(1),
+        "print_s" => Some(4),
+        "mcmalloc" => Some(9),
+        "read_i" => Some(5),
+        "print_c" => Some(11),
+        "read_c" => Some(12),
+        _ => None,
+    }
+}
@@ -0,0 +1,10 @@
+pub mod ir;
+pub mod layout;
+pub mod ir_generator;
+pub mod liveness;
+pub mod register_file;
+pub mod convention;
+pub mod reg_alloc;
+pub mod mips;
+pub mod riscv;
+pub mod code_generator;
@@ -0,0 +1,384 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::gen::ir::{is_virtual, Base, Function, Inst, Reg, Width};
+use crate::gen::layout::WORD_SIZE;
+use crate::gen::liveness::Liveness;
+use crate::gen::register_file::RegisterFile;
+
+/// Assigns a physical register to every virtual register of a function by graph colouring, using
+/// the iterated register coalescing of George and Appel. Registers that cannot be coloured are
+/// spilled to the stack frame and the allocation starts over on the rewritten function.
+pub fn allocate(function: &mut Function, regs: &RegisterFile) {
+    let mut spill_temps = HashSet::new();
+    loop {
+        let mut allocator = RegAlloc::new(regs, &spill_temps);
+        allocator.build(function);
+        allocator.make_worklist();
+        allocator.run();
+        allocator.assign_colors();
+
+        if allocator.spilled_nodes.is_empty() {
+            for inst in function.insts.iter_mut() {
+                let mut colour = |r: Reg| if is_virtual(r) { allocator.color[&r] } else { r };
+                inst.rename(&mut colour.clone(), &mut colour);
+            }
+            function.insts.retain(|inst| !matches!(inst.is_move(), Some((d, s)) if d == s));
+            return;
+        }
+        rewrite_program(function, &allocator.spilled_nodes, &mut spill_temps);
+    }
+}
+
+// loads spilled registers into fresh temporaries before each use and stores them after each definition
+fn rewrite_program(function: &mut Function, spilled_nodes: &BTreeSet<Reg>, spill_temps: &mut HashSet<Reg>) {
+    let slots: HashMap<Reg, usize> = spilled_nodes.iter().map(|n| (*n, function.new_slot(WORD_SIZE, WORD_SIZE))).collect();
+
+    let mut insts = Vec::new();
+    for mut inst in std::mem::take(&mut function.insts) {
+        let mut after = Vec::new();
+        for (n, slot) in slots.iter() {
+            let used = inst.uses().contains(n);
+            let defined = inst.defs().contains(n);
+            if !used && !defined {
+                continue;
+            }
+            let t = function.new_reg();
+            spill_temps.insert(t);
+            let mut rename = |r: Reg| if r == *n { t } else { r };
+            inst.rename(&mut rename.clone(), &mut rename);
+            if used {
+                insts.push(Inst::Load(Width::Word, t, Base::Frame(*slot), 0));
+            }
+            if defined {
+                after.push(Inst::Store(Width::Word, t, Base::Frame(*slot), 0));
+            }
+        }
+        insts.push(inst);
+        insts.extend(after);
+    }
+    function.insts = insts;
+}
+
+struct RegAlloc<'a> {
+    regs: &'a RegisterFile,
+    k: usize,
+    spill_temps: &'a HashSet<Reg>,
+    // number of uses and definitions, the cost of spilling
+    occurrences: HashMap<Reg, usize>,
+
+    initial: BTreeSet<Reg>,
+    simplify_worklist: BTreeSet<Reg>,
+    freeze_worklist: BTreeSet<Reg>,
+    spill_worklist: BTreeSet<Reg>,
+    spilled_nodes: BTreeSet<Reg>,
+    coalesced_nodes: BTreeSet<Reg>,
+    colored_nodes: BTreeSet<Reg>,
+    select_stack: Vec<Reg>,
+
+    moves: Vec<(Reg, Reg)>,
+    coalesced_moves: BTreeSet<usize>,
+    constrained_moves: BTreeSet<usize>,
+    frozen_moves: BTreeSet<usize>,
+    worklist_moves: BTreeSet<usize>,
+    active_moves: BTreeSet<usize>,
+
+    adj_set: HashSet<(Reg, Reg)>,
+    adj_list: HashMap<Reg, BTreeSet<Reg>>,
+    degree: HashMap<Reg, usize>,
+    move_list: HashMap<Reg, BTreeSet<usize>>,
+    alias: HashMap<Reg, Reg>,
+    color: HashMap<Reg, Reg>,
+}
+
+impl<'a> RegAlloc<'a> {
+    fn new(regs: &'a RegisterFile, spill_temps: &'a HashSet<Reg>) -> Self {
+        RegAlloc {
+            regs,
+            k: regs.allocatable.len(),
+            spill_temps,
+            occurrences: HashMap::new(),
+            initial: BTreeSet::new(),
+            simplify_worklist: BTreeSet::new(),
+            freeze_worklist: BTreeSet::new(),
+            spill_worklist: BTreeSet::new(),
+            spilled_nodes: BTreeSet::new(),
+            coalesced_nodes: BTreeSet::new(),
+            colored_nodes: BTreeSet::new(),
+            select_stack: Vec::new(),
+            moves: Vec::new(),
+            coalesced_moves: BTreeSet::new(),
+            constrained_moves: BTreeSet::new(),
+            frozen_moves: BTreeSet::new(),
+            worklist_moves: BTreeSet::new(),
+            active_moves: BTreeSet::new(),
+            adj_set: HashSet::new(),
+            adj_list: HashMap::new(),
+            degree: HashMap::new(),
+            move_list: HashMap::new(),
+            alias: HashMap::new(),
+            color: HashMap::new(),
+        }
+    }
+
+    fn precolored(n: Reg) -> bool {
+        !is_virtual(n)
+    }
+
+    fn degree(&self, n: Reg) -> usize {
+        if RegAlloc::precolored(n) { usize::MAX } else { self.degree[&n] }
+    }
+
+    fn build(&mut self, function: &Function) {
+        let liveness = Liveness::new(&function.insts);
+
+        for inst in function.insts.iter() {
+            for r in inst.uses().into_iter().chain(inst.defs()) {
+                if is_virtual(r) {
+                    *self.occurrences.entry(r).or_insert(0) += 1;
+                    if self.initial.insert(r) {
+                        self.degree.insert(r, 0);
+                        self.adj_list.insert(r, BTreeSet::new());
+                    }
+                } else {
+                    self.color.insert(r, r);
+                }
+            }
+        }
+
+        for (i, inst) in function.insts.iter().enumerate() {
+            let mut live = liveness.live_out[i].clone();
+            if let Some((d, s)) = inst.is_move() {
+                live.remove(&s);
+                let m = self.moves.len();
+                self.moves.push((d, s));
+                self.move_list.entry(d).or_default().insert(m);
+                self.move_list.entry(s).or_default().insert(m);
+                self.worklist_moves.insert(m);
+            }
+            let defs = inst.defs();
+            live.extend(defs.iter());
+            for d in defs.iter() {
+                for l in live.iter() {
+                    self.add_edge(*l, *d);
+                }
+            }
+        }
+    }
+
+    fn add_edge(&mut self, u: Reg, v: Reg) {
+        if u == v || self.adj_set.contains(&(u, v)) {
+            return;
+        }
+        self.adj_set.insert((u, v));
+        self.adj_set.insert((v, u));
+        if !RegAlloc::precolored(u) {
+            self.adj_list.get_mut(&u).unwrap().insert(v);
+            *self.degree.get_mut(&u).unwrap() += 1;
+        }
+        if !RegAlloc::precolored(v) {
+            self.adj_list.get_mut(&v).unwrap().insert(u);
+            *self.degree.get_mut(&v).unwrap() += 1;
+        }
+    }
+
+    fn make_worklist(&mut self) {
+        for n in std::mem::take(&mut self.initial) {
+            if self.degree(n) >= self.k {
+                self.spill_worklist.insert(n);
+            } else if self.move_related(n) {
+                self.freeze_worklist.insert(n);
+            } else {
+                self.simplify_worklist.insert(n);
+            }
+        }
+    }
+
+    fn run(&mut self) {
+        loop {
+            if let Some(n) = self.simplify_worklist.pop_first() {
+                self.simplify(n);
+            } else if let Some(m) = self.worklist_moves.pop_first() {
+                self.coalesce(m);
+            } else if let Some(n) = self.freeze_worklist.pop_first() {
+                self.freeze(n);
+            } else if !self.spill_worklist.is_empty() {
+                self.select_spill();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn adjacent(&self, n: Reg) -> Vec<Reg> {
+        self.adj_list.get(&n).map_or(vec![], |adj| {
+            adj.iter().filter(|m| !self.select_stack.contains(m) && !self.coalesced_nodes.contains(m)).copied().collect()
+        })
+    }
+
+    fn node_moves(&self, n: Reg) -> Vec<usize> {
+        self.move_list.get(&n).map_or(vec![], |moves| {
+            moves.iter().filter(|m| self.active_moves.contains(m) || self.worklist_moves.contains(m)).copied().collect()
+        })
+    }
+
+    fn move_related(&self, n: Reg) -> bool {
+        !self.node_moves(n).is_empty()
+    }
+
+    fn simplify(&mut self, n: Reg) {
+        self.select_stack.push(n);
+        for m in self.adjacent(n) {
+            self.decrement_degree(m);
+        }
+    }
+
+    fn decrement_degree(&mut self, m: Reg) {
+        if RegAlloc::precolored(m) {
+            return;
+        }
+        let d = self.degree[&m];
+        self.degree.insert(m, d - 1);
+        if d == self.k {
+            let mut nodes = self.adjacent(m);
+            nodes.push(m);
+            self.enable_moves(&nodes);
+            self.spill_worklist.remove(&m);
+            if self.move_related(m) {
+                self.freeze_worklist.insert(m);
+            } else {
+                self.simplify_worklist.insert(m);
+            }
+        }
+    }
+
+    fn enable_moves(&mut self, nodes: &[Reg]) {
+        for n in nodes {
+            for m in self.node_moves(*n) {
+                if self.active_moves.remove(&m) {
+                    self.worklist_moves.insert(m);
+                }
+            }
+        }
+    }
+
+    fn add_work_list(&mut self, u: Reg) {
+        if !RegAlloc::precolored(u) && !self.move_related(u) && self.degree(u) < self.k {
+            self.freeze_worklist.remove(&u);
+            self.simplify_worklist.insert(u);
+        }
+    }
+
+    // George's test for coalescing with a precolored register
+    fn ok(&self, t: Reg, r: Reg) -> bool {
+        self.degree(t) < self.k || RegAlloc::precolored(t) || self.adj_set.contains(&(t, r))
+    }
+
+    // Briggs' test
+    fn conservative(&self, nodes: &BTreeSet<Reg>) -> bool {
+        nodes.iter().filter(|n| self.degree(**n) >= self.k).count() < self.k
+    }
+
+    fn get_alias(&self, n: Reg) -> Reg {
+        if self.coalesced_nodes.contains(&n) { self.get_alias(self.alias[&n]) } else { n }
+    }
+
+    fn coalesce(&mut self, m: usize) {
+        let (x, y) = self.moves[m];
+        let (x, y) = (self.get_alias(x), self.get_alias(y));
+        let (u, v) = if RegAlloc::precolored(y) { (y, x) } else { (x, y) };
+
+        if u == v {
+            self.coalesced_moves.insert(m);
+            self.add_work_list(u);
+        } else if RegAlloc::precolored(v) || self.adj_set.contains(&(u, v)) {
+            self.constrained_moves.insert(m);
+            self.add_work_list(u);
+            self.add_work_list(v);
+        } else if (RegAlloc::precolored(u) && self.adjacent(v).iter().all(|t| self.ok(*t, u)))
+            || (!RegAlloc::precolored(u) && self.conservative(&self.adjacent(u).into_iter().chain(self.adjacent(v)).collect())) {
+            self.coalesced_moves.insert(m);
+            self.combine(u, v);
+            self.add_work_list(u);
+        } else {
+            self.active_moves.insert(m);
+        }
+    }
+
+    fn combine(&mut self, u: Reg, v: Reg) {
+        if !self.freeze_worklist.remove(&v) {
+            self.spill_worklist.remove(&v);
+        }
+        self.coalesced_nodes.insert(v);
+        self.alias.insert(v, u);
+        let v_moves = self.move_list.get(&v).cloned().unwrap_or_default();
+        self.move_list.entry(u).or_default().extend(v_moves);
+        self.enable_moves(&[v]);
+        for t in self.adjacent(v) {
+            self.add_edge(t, u);
+            self.decrement_degree(t);
+        }
+        if self.degree(u) >= self.k && self.freeze_worklist.remove(&u) {
+            self.spill_worklist.insert(u);
+        }
+    }
+
+    fn freeze(&mut self, u: Reg) {
+        self.simplify_worklist.insert(u);
+        self.freeze_moves(u);
+    }
+
+    fn freeze_moves(&mut self, u: Reg) {
+        for m in self.node_moves(u) {
+            let (x, y) = self.moves[m];
+            let v = if self.get_alias(y) == self.get_alias(u) { self.get_alias(x) } else { self.get_alias(y) };
+            self.active_moves.remove(&m);
+            self.frozen_moves.insert(m);
+            if !RegAlloc::precolored(v) && self.node_moves(v).is_empty() && self.degree(v) < self.k {
+                self.freeze_worklist.remove(&v);
+                self.simplify_worklist.insert(v);
+            }
+        }
+    }
+
+    // spills the register used the least relative to the number of registers it interferes with,
+    // temporaries introduced by previous spills are only chosen as a last resort
+    fn select_spill(&mut self) {
+        let cost = |n: &Reg| -> f64 {
+            if self.spill_temps.contains(n) {
+                f64::INFINITY
+            } else {
+                self.occurrences[n] as f64 / self.degree(*n) as f64
+            }
+        };
+        let m = *self.spill_worklist.iter().min_by(|a, b| cost(a).total_cmp(&cost(b))).unwrap();
+        self.spill_worklist.remove(&m);
+        self.simplify_worklist.insert(m);
+        self.freeze_moves(m);
+    }
+
+    fn assign_colors(&mut self) {
+        while let Some(n) = self.select_stack.pop() {
+            let mut ok_colors = self.regs.allocatable.clone();
+            for w in self.adj_list[&n].iter() {
+                let a = self.get_alias(*w);
+                if RegAlloc::precolored(a) || self.colored_nodes.contains(&a) {
+                    ok_colors.retain(|c| *c != self.color[&a]);
+                }
+            }
+            match ok_colors.first() {
+                Some(c) => {
+                    self.colored_nodes.insert(n);
+                    self.color.insert(n, *c);
+                },
+                None => {
+                    self.spilled_nodes.insert(n);
+                },
+            }
+        }
+        for n in self.coalesced_nodes.iter() {
+            let a = self.get_alias(*n);
+            if let Some(c) = self.color.get(&a).copied() {
+                self.color.insert(*n, c);
+            }
+        }
+    }
+}
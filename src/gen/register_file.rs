@@ -0,0 +1,68 @@
+use crate::gen::ir::Reg;
+
+/// RegisterFile
+/// Describes the registers of a target to the calling convention pass and the register allocator.
+/// Physical registers are numbered as in the target's encoding.
+pub struct RegisterFile {
+    pub names: Vec<&'static str>,
+    // registers available to the allocator, in order of preference
+    pub allocatable: Vec<Reg>,
+    // registers a callee must preserve, the others are clobbered by calls
+    pub callee_saved: Vec<Reg>,
+    pub args: Vec<Reg>,
+    pub ret: Reg,
+    // registers written by the stdlib functions, which are expanded inline
+    pub intrinsic_clobbers: Vec<Reg>,
+}
+
+impl RegisterFile {
+    pub fn mips() -> Self {
+        let names = vec![
+            "$zero", "$at", "$v0", "$v1", "$a0", "$a1", "$a2", "$a3",
+            "$t0", "$t1", "$t2", "$t3", "$t4", "$t5", "$t6", "$t7",
+            "$s0", "$s1", "$s2", "$s3", "$s4", "$s5", "$s6", "$s7",
+            "$t8", "$t9", "$k0", "$k1", "$gp", "$sp", "$fp", "$ra",
+        ];
+        let caller_saved: Vec<Reg> = vec![8, 9, 10, 11, 12, 13, 14, 15, 24, 25, 2, 3, 4, 5, 6, 7];
+        let callee_saved: Vec<Reg> = (16..24).collect();
+
+        RegisterFile {
+            names,
+            allocatable: caller_saved.iter().chain(callee_saved.iter()).copied().collect(),
+            callee_saved,
+            args: vec![4, 5, 6, 7],
+            ret: 2,
+            intrinsic_clobbers: vec![2, 4],
+        }
+    }
+
+    /// The standard RISC-V calling convention; s0 is the frame pointer and t6 is kept free as a
+    /// scratch register for offsets that do not fit in an immediate.
+    pub fn riscv() -> Self {
+        let names = vec![
+            "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+            "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+            "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+            "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+        ];
+        let caller_saved: Vec<Reg> = vec![5, 6, 7, 28, 29, 30, 10, 11, 12, 13, 14, 15, 16, 17];
+        let callee_saved: Vec<Reg> = std::iter::once(9).chain(18..28).collect();
+
+        RegisterFile {
+            names,
+            allocatable: caller_saved.iter().chain(callee_saved.iter()).copied().collect(),
+            callee_saved,
+            args: (10..18).collect(),
+            ret: 10,
+            intrinsic_clobbers: vec![10, 17],
+        }
+    }
+
+    pub fn caller_saved(&self) -> Vec<Reg> {
+        self.allocatable.iter().filter(|r| !self.callee_saved.contains(r)).copied().collect()
+    }
+
+    pub fn name(&self, reg: Reg) -> &'static str {
+        self.names[reg]
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::Write;
+use crate::ast::decl::StorageClass;
+use crate::gen::ir::{Base, BinKind, Callee, Function, Inst, IrProgram, Width};
+use crate::gen::layout::{align_to, WORD_SIZE};
+use crate::gen::register_file::RegisterFile;
+
+const RA: &str = "ra";
+const FP: &str = "s0";
+const SP: &str = "sp";
+// not allocated, used to build addresses whose offset does not fit in 12 bits
+const SCRATCH: &str = "t6";
+
+/// RiscvEmitter
+/// Prints an allocated IrProgram as RV32IM assembly for GNU as and RARS; the stdlib functions are
+/// expanded to ecalls using the RARS numbering. The saved registers are pushed first, below the
+/// frame pointer which holds the stack pointer of the caller, then the stack pointer is lowered over
+/// the frame slots and the outgoing arguments, keeping it 16 byte aligned.
+pub struct RiscvEmitter<'a> {
+    regs: &'a RegisterFile,
+    writer: &'a mut dyn Write,
+}
+
+struct Frame {
+    // size of the area below the saved registers
+    size: i32,
+    slots: Vec<i32>,
+    saved_size: i32,
+    saved: Vec<(&'static str, i32)>,
+}
+
+fn fits(imm: i32) -> bool {
+    (-2048..2048).contains(&imm)
+}
+
+impl<'a> RiscvEmitter<'a> {
+    pub fn new(regs: &'a RegisterFile, writer: &'a mut dyn Write) -> Self {
+        RiscvEmitter {
+            regs,
+            writer,
+        }
+    }
+
+    fn emit(&mut self, line: String) {
+        writeln!(self.writer, "{}", line).unwrap();
+    }
+
+    pub fn emit_program(&mut self, ir: &IrProgram) {
+        self.emit(".data".to_owned());
+        for global in ir.globals.iter().filter(|global| global.storage != StorageClass::Extern) {
+            if global.storage != StorageClass::Static {
+                self.emit(format!(".globl {}", global.label));
+            }
+            self.emit("    .align 2".to_owned());
+            match &global.init {
+                Some(bytes) if !bytes.is_empty() => {
+                    let bytes: Vec<String> = bytes.iter().map(|b| b.to_string()).collect();
+                    self.emit(format!("{}: .byte {}", global.label, bytes.join(", ")));
+                },
+                _ => self.emit(format!("{}: .space {}", global.label, global.size.max(1))),
+            }
+        }
+        for (label, contents) in ir.strings.iter() {
+            self.emit(format!("{}: .asciz \"{}\"", label, contents));
+        }
+
+        self.emit("".to_owned());
+        self.emit(".text".to_owned());
+        self.emit(".globl main".to_owned());
+        self.emit("main:".to_owned());
+        self.emit("    call f_main".to_owned());
+        self.emit("    li a7, 10".to_owned());
+        self.emit("    ecall".to_owned());
+
+        for function in ir.functions.iter() {
+            self.emit_function(function);
+        }
+        self.writer.flush().unwrap();
+    }
+
+    fn frame(&self, function: &Function) -> Frame {
+        let mut saved = vec![RA, FP];
+        for r in self.regs.callee_saved.iter() {
+            if function.insts.iter().any(|inst| inst.defs().contains(r)) {
+                saved.push(self.regs.name(*r));
+            }
+        }
+        let saved_size = align_to(saved.len() as i32 * WORD_SIZE, 16);
+        let saved = saved.into_iter().enumerate().map(|(i, name)| (name, saved_size - (i as i32 + 1) * WORD_SIZE)).collect();
+
+        let mut offset = function.outgoing;
+        let mut slots = Vec::new();
+        for slot in function.frame.iter() {
+            offset = align_to(offset, slot.align);
+            slots.push(offset);
+            offset += slot.size;
+        }
+        Frame { size: align_to(offset, 16), slots, saved_size, saved }
+    }
+
+    // adds an immediate to a register, through the scratch register when it does not fit
+    fn add_imm(&mut self, d: &str, s: &str, imm: i32) {
+        if fits(imm) {
+            self.emit(format!("    addi {}, {}, {}", d, s, imm));
+        } else {
+            self.emit(format!("    li {}, {}", SCRATCH, imm));
+            self.emit(format!("    add {}, {}, {}", d, s, SCRATCH));
+        }
+    }
+
+    fn memory(&mut self, op: &str, r: &str, base: &str, offset: i32) {
+        if fits(offset) {
+            self.emit(format!("    {} {}, {}({})", op, r, offset, base));
+        } else {
+            self.add_imm(SCRATCH, base, offset);
+            self.emit(format!("    {} {}, 0({})", op, r, SCRATCH));
+        }
+    }
+
+    fn emit_function(&mut self, function: &Function) {
+        let frame = self.frame(function);
+        let exit = format!("f_{}_exit", function.name);
+
+        self.emit("".to_owned());
+        if function.storage != StorageClass::Static {
+            self.emit(format!(".globl f_{}", function.name));
+        }
+        self.emit(format!("f_{}:", function.name));
+        self.emit(format!("    addi {}, {}, -{}", SP, SP, frame.saved_size));
+        for (name, offset) in frame.saved.iter() {
+            self.emit(format!("    sw {}, {}({})", name, offset, SP));
+        }
+        self.emit(format!("    addi {}, {}, {}", FP, SP, frame.saved_size));
+        if frame.size != 0 {
+            self.add_imm(SP, SP, -frame.size);
+        }
+
+        for inst in function.insts.iter() {
+            self.emit_inst(inst, &frame, &exit);
+        }
+
+        self.emit(format!("{}:", exit));
+        self.emit(format!("    addi {}, {}, -{}", SP, FP, frame.saved_size));
+        for (name, offset) in frame.saved.iter() {
+            self.emit(format!("    lw {}, {}({})", name, offset, SP));
+        }
+        self.emit(format!("    addi {}, {}, {}", SP, SP, frame.saved_size));
+        self.emit("    ret".to_owned());
+    }
+
+    fn base(&self, base: &Base, offset: i32, frame: &Frame) -> (&'static str, i32) {
+        match base {
+            Base::Reg(r) => (self.regs.name(*r), offset),
+            Base::Frame(slot) => (SP, frame.slots[*slot] + offset),
+            Base::Incoming => (FP, offset),
+            Base::Outgoing => (SP, offset),
+        }
+    }
+
+    fn emit_inst(&mut self, inst: &Inst, frame: &Frame, exit: &str) {
+        let regs = self.regs;
+        let r = |reg: &usize| regs.name(*reg);
+        let line = match inst {
+            Inst::Li(d, i) => format!("    li {}, {}", r(d), i),
+            Inst::La(d, label) => format!("    la {}, {}", r(d), label),
+            Inst::Move(d, s) => format!("    mv {}, {}", r(d), r(s)),
+            Inst::AddImm(d, s, i) => return self.add_imm(r(d), r(s), *i),
+            // RV32I only compares with slt and sltu, the other comparisons are derived from it or from a subtraction
+            Inst::Bin(BinKind::Sle, d, s1, s2) => format!("    slt {}, {}, {}\n    xori {}, {}, 1", r(d), r(s2), r(s1), r(d), r(d)),
+            Inst::Bin(BinKind::Sge, d, s1, s2) => format!("    slt {}, {}, {}\n    xori {}, {}, 1", r(d), r(s1), r(s2), r(d), r(d)),
+            Inst::Bin(BinKind::Sleu, d, s1, s2) => format!("    sltu {}, {}, {}\n    xori {}, {}, 1", r(d), r(s2), r(s1), r(d), r(d)),
+            Inst::Bin(BinKind::Sgeu, d, s1, s2) => format!("    sltu {}, {}, {}\n    xori {}, {}, 1", r(d), r(s1), r(s2), r(d), r(d)),
+            Inst::Bin(BinKind::Seq, d, s1, s2) => format!("    sub {}, {}, {}\n    seqz {}, {}", r(d), r(s1), r(s2), r(d), r(d)),
+            Inst::Bin(BinKind::Sne, d, s1, s2) => format!("    sub {}, {}, {}\n    snez {}, {}", r(d), r(s1), r(s2), r(d), r(d)),
+            Inst::Bin(kind, d, s1, s2) => {
+                let op = match kind {
+                    BinKind::Add => "add",
+                    BinKind::Sub => "sub",
+                    BinKind::Mul => "mul",
+                    BinKind::Div => "div",
+                    BinKind::Rem => "rem",
+                    BinKind::Slt => "slt",
+                    BinKind::Sgt => "sgt",
+                    BinKind::And => "and",
+                    BinKind::Or => "or",
+                    BinKind::Xor => "xor",
+                    BinKind::Sll => "sll",
+                    BinKind::Sra => "sra",
+                    BinKind::Divu => "divu",
+                    BinKind::Remu => "remu",
+                    BinKind::Sltu => "sltu",
+                    BinKind::Sgtu => "sgtu",
+                    BinKind::Srl => "srl",
+                    BinKind::Mulhu => "mulhu",
+                    BinKind::Sle | BinKind::Sge | BinKind::Seq | BinKind::Sne | BinKind::Sleu | BinKind::Sgeu => unreachable!(),
+                };
+                format!("    {} {}, {}, {}", op, r(d), r(s1), r(s2))
+            },
+            Inst::FrameAddr(d, slot) => return self.add_imm(r(d), SP, frame.slots[*slot]),
+            Inst::Load(width, d, base, offset) => {
+                let op = match width {
+                    Width::Byte => "lbu",
+                    Width::SignedByte => "lb",
+                    Width::Half => "lhu",
+                    Width::SignedHalf => "lh",
+                    Width::Word => "lw",
+                };
+                let (base, offset) = self.base(base, *offset, frame);
+                return self.memory(op, r(d), base, offset);
+            },
+            Inst::Store(width, s, base, offset) => {
+                let op = match width {
+                    Width::Byte | Width::SignedByte => "sb",
+                    Width::Half | Width::SignedHalf => "sh",
+                    Width::Word => "sw",
+                };
+                let (base, offset) = self.base(base, *offset, frame);
+                return self.memory(op, r(s), base, offset);
+            },
+            Inst::Call { callee: Callee::Named(name), .. } => match ecall(name) {
+                Some(code) => format!("    li a7, {}\n    ecall", code),
+                None => format!("    call f_{}", name),
+            },
+            Inst::Call { callee: Callee::Reg(s), .. } => format!("    jalr {}", r(s)),
+            Inst::Ret(_) => format!("    j {}", exit),
+            Inst::Label(label) => format!("{}:", label),
+            Inst::Jump(label) => format!("    j {}", label),
+            Inst::BranchZero(s, label) => format!("    beqz {}, {}", r(s), label),
+            Inst::BranchNonZero(s, label) => format!("    bnez {}, {}", r(s), label),
+        };
+        self.emit(line);
+    }
+}
+
+fn ecall(name: &str) -> Option<i32> {
+    match name {
+        "print_i" => Some(1),
+        "print_s" => Some(4),
+        "read_i" => Some(5),
+        "mcmalloc" => Some(9),
+        "print_c" => Some(11),
+        "read_c" => Some(12),
+        _ => None,
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Position {
     line: i32,
     column: i32,
@@ -12,10 +13,35 @@ impl Position {
             column,
         }
     }
+
+    pub fn line(&self) -> i32 {
+        self.line
+    }
+
+    pub fn column(&self) -> i32 {
+        self.column
+    }
 }
 
 impl Display for Position {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}: {}", self.line, self.column)
     }
-}
\ No newline at end of file
+}
+
+/// Diagnostic
+/// An error reported by the tokenizer, the parser or the semantic analysis.
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub position: Position,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(position: Position, message: String) -> Self {
+        Diagnostic {
+            position,
+            message,
+        }
+    }
+}
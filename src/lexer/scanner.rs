@@ -1,9 +1,9 @@
 use std::fs::File;
 use std::io;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Cursor, Read};
 
 pub struct Scanner {
-    input: BufReader<File>,
+    input: BufReader<Box<dyn Read>>,
     peeked: Option<char>,
     line: i32,
     column: i32,
@@ -11,11 +11,21 @@ pub struct Scanner {
 
 impl Scanner {
     pub fn new(source: File) -> Self {
+        Scanner::from_reader(Box::new(source))
+    }
+
+    /// Scans a source held in memory, such as a document open in an editor.
+    pub fn from_string(source: &str) -> Self {
+        Scanner::from_reader(Box::new(Cursor::new(source.as_bytes().to_vec())))
+    }
+
+    fn from_reader(source: Box<dyn Read>) -> Self {
         Scanner {
             input: BufReader::new(source),
             peeked: None,
             line: 1,
-            column: 0
+            // columns count from 1, the column of a token is the one of its first character
+            column: 1
         }
     }
 
@@ -2,6 +2,7 @@ use std::fmt::{Display, Formatter};
 use crate::lexer::position::Position;
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum TokenClass {
     // the \ (backslash) is used as an escape character in the regular expression below
     // ' is used to enclose character while " is used to enclose strings
@@ -10,6 +11,13 @@ pub enum TokenClass {
 
     ASSIGN, // '='
 
+    // compound assignments
+    ADDASSIGN, // "+="
+    SUBASSIGN, // "-="
+    MULASSIGN, // "*="
+    DIVASSIGN, // "/="
+    REMASSIGN, // "%="
+
     // delimiters
     LBRA,  // '{' // left brace
     RBRA,  // '}' // right brace
@@ -19,26 +27,43 @@ pub enum TokenClass {
     RSBR,  // ']' // left square brace
     SC,    // ';' // semicolon
     COMMA, // ','
+    COLON, // ':'
 
     // types
     INT,  // "int"
     VOID, // "void"
     CHAR, // "char"
+    SHORT,    // "short"
+    LONG,     // "long"
+    SIGNED,   // "signed"
+    UNSIGNED, // "unsigned"
 
     // keywords
     IF,     // "if"
     ELSE,   // "else"
     WHILE,  // "while"
+    DO,     // "do"
+    FOR,    // "for"
+    BREAK,  // "break"
+    CONTINUE, // "continue"
+    SWITCH, // "switch"
+    CASE,   // "case"
+    DEFAULT, // "default"
     RETURN, // "return"
     STRUCT, // "struct"
     SIZEOF, // "sizeof"
+    TYPEDEF, // "typedef"
+    ENUM,   // "enum"
+    CONST,  // "const"
+    STATIC, // "static"
+    EXTERN, // "extern"
 
     // include
     INCLUDE, // "#include"
 
     // literals
     STRINGLITERAL, // \".*\"  any sequence of characters enclosed within two double quote " (please be aware of the escape character backslash \)
-    INTLITERAL,    // ('0'|...|'9')+
+    INTLITERAL,    // ('0'|...|'9')+ ('u'|'U'|'l'|'L')*
     CHARLITERAL,   // \'('a'|...|'z'|'A'|...|'Z'|'\t'|'\b'|'\n'|'\r'|'\f'|'\''|'\"'|'\\'|'\0'|'.'|','|'_'|...)\'  a character starts and end with a single quote '
 
     // logical operators
@@ -59,10 +84,20 @@ pub enum TokenClass {
     ASTERIX, // '*'  // can be used for multiplication or pointers
     DIV,     // '/'
     REM,     // '%'
-    AND,     // '&'
+    AND,     // '&'  // can be used for bitwise and or address-of
+    OR,      // '|'
+    XOR,     // '^'
+    SHL,     // "<<"
+    SHR,     // ">>"
+    NOT,     // '!'
+    TILDE,   // '~'
+    INC,     // "++"
+    DEC,     // "--"
+    QMARK,   // '?'  // with COLON, the conditional operator
 
     // struct member access
-    DOT, // '.'
+    DOT,   // '.'
+    ARROW, // "->" // member access through a pointer
 
     // special tokens
     EOF,    // signal end of file
@@ -80,10 +115,21 @@ impl Display for TokenClass {
     }
 }
 
+/// Trivia
+/// Source text between tokens, only kept when the tokenizer is asked to (see Tokenizer::keep_trivia).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    LineComment(String),  // from // to the end of the line, excluded
+    BlockComment(String), // from /* to */, included
+}
+
 pub struct Token {
     pub token_class: TokenClass,
     pub data: String,
     pub position: Position,
+    // the trivia preceding the token
+    pub trivia: Vec<Trivia>,
 }
 
 impl Token {
@@ -92,13 +138,14 @@ impl Token {
             token_class,
             data: data.to_string(),
             position: Position::new(line_num, col_num),
+            trivia: Vec::new(),
         }
     }
 }
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        if self.data.eq("") {
+        if self.data.is_empty() {
             write!(f, "{:?}", self.token_class)
         } else {
             write!(f, "{:?}({})", self.token_class, self.data)
@@ -1,5 +1,5 @@
 use std::{collections::HashMap, io::ErrorKind};
-use crate::{lexer::token::{Token, TokenClass}, Scanner};
+use crate::{lexer::position::{Diagnostic, Position}, lexer::token::{Token, TokenClass, Trivia}, Scanner};
 
 macro_rules! unwrap_or_return {
     ( $e:expr ) => {
@@ -10,12 +10,24 @@ macro_rules! unwrap_or_return {
     }
 }
 
+// a line comment without the \r of a \r\n line ending, comments being written back with \n
+fn line_comment(mut comment: String) -> String {
+    if comment.ends_with('\r') {
+        comment.pop();
+    }
+    comment
+}
+
 pub struct Tokenizer {
     scanner: Scanner,
     error: i32,
     char_map: HashMap<char, TokenClass>,
     key_map: HashMap<&'static str, TokenClass>,
     escape_map: HashMap<&'static str, &'static str>,
+    keep_trivia: bool,
+    trivia: Vec<Trivia>,
+    quiet: bool,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Tokenizer {
@@ -27,13 +39,21 @@ impl Tokenizer {
                 ('{', TokenClass::LBRA), ('}', TokenClass::RBRA), ('(', TokenClass::LPAR), (')', TokenClass::RPAR),
                 ('[', TokenClass::LSBR), (']', TokenClass::RSBR), (';', TokenClass::SC), (',', TokenClass::COMMA),
                 ('+', TokenClass::PLUS), ('-', TokenClass::MINUS), ('*', TokenClass::ASTERIX), ('/', TokenClass::DIV),
-                ('%', TokenClass::REM), ('.', TokenClass::DOT)
+                ('%', TokenClass::REM), ('.', TokenClass::DOT), (':', TokenClass::COLON), ('^', TokenClass::XOR),
+                ('~', TokenClass::TILDE), ('?', TokenClass::QMARK)
             ]),
             key_map: HashMap::from([
                 ("int", TokenClass::INT), ("void", TokenClass::VOID), ("char", TokenClass::CHAR),
+                ("short", TokenClass::SHORT), ("long", TokenClass::LONG), ("signed", TokenClass::SIGNED),
+                ("unsigned", TokenClass::UNSIGNED),
                 // keywords
                 ("if",TokenClass::IF), ("else",TokenClass::ELSE), ("while",TokenClass::WHILE),
-                ("return",TokenClass::RETURN), ("struct",TokenClass::STRUCT), ("sizeof",TokenClass::SIZEOF)
+                ("do",TokenClass::DO), ("for",TokenClass::FOR), ("break",TokenClass::BREAK), ("continue",TokenClass::CONTINUE),
+                ("switch",TokenClass::SWITCH), ("case",TokenClass::CASE), ("default",TokenClass::DEFAULT),
+                ("return",TokenClass::RETURN), ("struct",TokenClass::STRUCT), ("sizeof",TokenClass::SIZEOF),
+                ("class",TokenClass::CLASS), ("extends",TokenClass::EXTENDS), ("new",TokenClass::NEW),
+                ("typedef",TokenClass::TYPEDEF), ("enum",TokenClass::ENUM), ("const",TokenClass::CONST),
+                ("static",TokenClass::STATIC), ("extern",TokenClass::EXTERN)
             ]),
             escape_map: HashMap::from([
                 ("\\t", "\t"),
@@ -45,7 +65,36 @@ impl Tokenizer {
                 ("\\\"" , "\""),
                 ("\\\\", "\\"),
                 ("\\0", "\0")
-            ])
+            ]),
+            keep_trivia: false,
+            trivia: Vec::new(),
+            quiet: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Only collects the errors instead of also printing them, for tools owning the standard output.
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Makes the tokenizer attach the whitespace and comments preceding each token to it, for tools
+    /// such as the formatter which must give them back.
+    pub fn keep_trivia(mut self) -> Self {
+        self.keep_trivia = true;
+        self
+    }
+
+    fn push_trivia(&mut self, trivia: Trivia) {
+        if !self.keep_trivia {
+            return;
+        }
+        // consecutive white spaces make a single trivia
+        if let (Trivia::Whitespace(s), Some(Trivia::Whitespace(last))) = (&trivia, self.trivia.last_mut()) {
+            last.push_str(s);
+        } else {
+            self.trivia.push(trivia);
         }
     }
 
@@ -53,18 +102,28 @@ impl Tokenizer {
         self.error
     }
 
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     fn error(&mut self, c: char, line: i32, col: i32) {
-        println!("Lexing error: unrecognised character ({}) at {}: {}", c, line, col);
+        let message = format!("unrecognised character ({})", c);
+        if !self.quiet {
+            println!("Lexing error: {} at {}: {}", message, line, col);
+        }
+        self.diagnostics.push(Diagnostic::new(Position::new(line, col), message));
         self.error += 1;
     }
 
     pub fn next_token(&mut self) -> Token {
-        match self.next() {
-            Ok(t) => return t,
-            Err(_e@ErrorKind::UnexpectedEof) => 
-                return Token::new(TokenClass::EOF, "", self.scanner.get_line(), self.scanner.get_column()),
-            Err(_) => std::process::exit(-1),   
+        let mut token = match self.next() {
+            Ok(t) => t,
+            Err(_e@ErrorKind::UnexpectedEof) =>
+                Token::new(TokenClass::EOF, "", self.scanner.get_line(), self.scanner.get_column()),
+            Err(_) => std::process::exit(-1),
         };
+        token.trivia = std::mem::take(&mut self.trivia);
+        token
     }
 
     fn next(&mut self) -> Result<Token, ErrorKind> {
@@ -78,29 +137,62 @@ impl Tokenizer {
 
         // skip white spaces
         if c.is_whitespace() {
+            self.push_trivia(Trivia::Whitespace(c.to_string()));
             return self.next()
         }
 
         if c == '/' {
             let t = unwrap_or_return!(scanner.peek());
             if t == '/' {
-                while scanner.get_line() == line { 
-                    unwrap_or_return!(scanner.next());
+                let mut comment = String::from("/");
+                loop {
+                    match scanner.next() {
+                        Ok('\n') => break,
+                        Ok(c) => comment.push(c),
+                        Err(e) => {
+                            // a comment on the last line of the file
+                            self.push_trivia(Trivia::LineComment(line_comment(comment)));
+                            return Err(e);
+                        },
+                    }
                 };
+                self.push_trivia(Trivia::LineComment(line_comment(comment)));
+                self.push_trivia(Trivia::Whitespace("\n".to_owned()));
                 return self.next();
             } else if t == '*' {
+                let mut comment = String::from("/*");
                 unwrap_or_return!(scanner.next());
                 loop {
                     c = unwrap_or_return!(scanner.next());
+                    comment.push(c);
                     if c == '*' && unwrap_or_return!(scanner.peek()) == '/' {
                         break;
                     }
                 };
                 unwrap_or_return!(scanner.next());
+                comment.push('/');
+                self.push_trivia(Trivia::BlockComment(comment.replace("\r\n", "\n")));
                 return self.next();
             }
         }
 
+        // compound assignments, increments and ->, before their first character is taken as an operator
+        let compound = match (c, scanner.peek()) {
+            ('+', Ok('=')) => Some(TokenClass::ADDASSIGN),
+            ('-', Ok('=')) => Some(TokenClass::SUBASSIGN),
+            ('*', Ok('=')) => Some(TokenClass::MULASSIGN),
+            ('/', Ok('=')) => Some(TokenClass::DIVASSIGN),
+            ('%', Ok('=')) => Some(TokenClass::REMASSIGN),
+            ('+', Ok('+')) => Some(TokenClass::INC),
+            ('-', Ok('-')) => Some(TokenClass::DEC),
+            ('-', Ok('>')) => Some(TokenClass::ARROW),
+            _ => None,
+        };
+        if let Some(token_class) = compound {
+            unwrap_or_return!(scanner.next());
+            return Ok(Token::new(token_class, "", line, column));
+        }
+
         if self.char_map.contains_key(&c) {
             return Ok(Token::new(self.char_map.get(&c).unwrap().clone(), "", line, column));
         }
@@ -112,7 +204,11 @@ impl Tokenizer {
             ('!','=') => { unwrap_or_return!(scanner.next()); return Ok(Token::new(TokenClass::NE, "", line, column)) },
             ('<','=') => { unwrap_or_return!(scanner.next()); return Ok(Token::new(TokenClass::LE, "", line, column)) },
             ('>','=') => { unwrap_or_return!(scanner.next()); return Ok(Token::new(TokenClass::GE, "", line, column)) },
+            ('<','<') => { unwrap_or_return!(scanner.next()); return Ok(Token::new(TokenClass::SHL, "", line, column)) },
+            ('>','>') => { unwrap_or_return!(scanner.next()); return Ok(Token::new(TokenClass::SHR, "", line, column)) },
             ('&', _ ) => return Ok(Token::new(TokenClass::AND, "", line, column)),
+            ('|', _ ) => return Ok(Token::new(TokenClass::OR, "", line, column)),
+            ('!', _ ) => return Ok(Token::new(TokenClass::NOT, "", line, column)),
             ('=', _ ) => return Ok(Token::new(TokenClass::ASSIGN, "", line, column)),
             ('<', _ ) => return Ok(Token::new(TokenClass::LT, "", line, column)),
             ('>', _ ) => return Ok(Token::new(TokenClass::GT, "", line, column)),
@@ -128,6 +224,12 @@ impl Tokenizer {
                 unwrap_or_return!(scanner.next());
                 c = unwrap_or_return!(scanner.peek());
             }
+            // the suffix, checked by the parser
+            while matches!(c, 'u' | 'U' | 'l' | 'L') {
+                data.push(c);
+                unwrap_or_return!(scanner.next());
+                c = unwrap_or_return!(scanner.peek());
+            }
 
             return Ok(Token::new(TokenClass::INTLITERAL, data.as_str(), line, column));
         }
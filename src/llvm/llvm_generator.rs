@@ -0,0 +1,1004 @@
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StorageClass, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::gen::layout::Layout;
+use crate::sem::constant::constant_value;
+use crate::sem::stdlib;
+use crate::ASTNode;
+
+/// A typed LLVM value, e.g. `i32` and `%t3`.
+#[derive(Clone)]
+pub struct Value {
+    ty: String,
+    repr: String,
+}
+
+impl Value {
+    fn new(ty: &str, repr: &str) -> Self {
+        Value { ty: ty.to_owned(), repr: repr.to_owned() }
+    }
+
+    fn typed(&self) -> String {
+        format!("{} {}", self.ty, self.repr)
+    }
+}
+
+/// The LLVM type of a minic type; `void*` and function pointers become `i8*`, a call casting the address
+/// to the type of the function.
+pub fn ll_type(a_type: &dyn Type) -> String {
+    if a_type.is_base(BaseType::VOID) {
+        "void".to_owned()
+    } else if let Some(t) = a_type.as_integer() {
+        format!("i{}", t.size() * 8)
+    } else if let Some(t) = a_type.as_pointer() {
+        if t.pointer_type.is_base(BaseType::VOID) || t.pointer_type.as_fun().is_some() { "i8*".to_owned() } else { format!("{}*", ll_type(t.pointer_type.as_ref())) }
+    } else if let Some(t) = a_type.as_struct() {
+        format!("%struct.{}", t.name)
+    } else if let Some(t) = a_type.as_array() {
+        format!("[{} x {}]", t.size(), ll_type(t.array_type.as_ref()))
+    } else {
+        unreachable!()
+    }
+}
+
+// an integer constant of a type, LLVM reading the bits of a constant as a signed number
+fn ll_int(value: i64, int_type: BaseType) -> String {
+    match int_type.size() {
+        1 => (value as i8).to_string(),
+        2 => (value as i16).to_string(),
+        4 => (value as i32).to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn ll_string(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    for b in bytes {
+        if b.is_ascii_graphic() && *b != b'"' && *b != b'\\' || *b == b' ' {
+            s.push(*b as char);
+        } else {
+            write!(s, "\\{:02X}", b).unwrap();
+        }
+    }
+    s
+}
+
+// the linkage of a global or function, which is external unless it is static
+fn linkage(storage: StorageClass) -> &'static str {
+    if storage == StorageClass::Static { "internal " } else { "" }
+}
+
+/// LlvmGenerator
+/// Writes a checked Program as textual LLVM IR. Every variable is an alloca or a global, left for
+/// mem2reg to promote; structs and arrays are first class values, field and element accesses are
+/// getelementptr instructions and the stdlib functions are declared externals, as are the functions
+/// and globals declared extern. Static ones have internal linkage and const globals are constants.
+/// The functions and globals of the program are prefixed by `f_` and `g_` so that none of them
+/// clashes with libc, and `f_main` is called by a generated `i32 @main()`.
+pub struct LlvmGenerator {
+    out: String,
+    allocas: String,
+    strings: String,
+    string_count: usize,
+    struct_fields: HashMap<String, Vec<VarDecl>>,
+    scopes: Vec<HashMap<String, Value>>,
+    temps: usize,
+    labels: usize,
+    terminated: bool,
+    current_label: String,
+    fun_type: Box<dyn Type>,
+    // the break and continue labels of the enclosing loops and switches, the innermost last
+    loops: Vec<(String, String)>,
+    layout: Layout,
+}
+
+impl LlvmGenerator {
+    pub fn new() -> Self {
+        LlvmGenerator {
+            out: String::new(),
+            allocas: String::new(),
+            strings: String::new(),
+            string_count: 0,
+            struct_fields: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            temps: 0,
+            labels: 0,
+            terminated: false,
+            current_label: String::new(),
+            fun_type: Box::new(BaseType::VOID),
+            loops: Vec::new(),
+            layout: Layout::new(&[]),
+        }
+    }
+
+    pub fn emit_program(program: &mut Program, writer: &mut dyn Write) {
+        let mut generator = LlvmGenerator::new();
+        program.accept(&mut generator);
+        writer.write_all(generator.out.as_bytes()).unwrap();
+        writer.flush().unwrap();
+    }
+
+    fn emit(&mut self, inst: &str) {
+        writeln!(self.out, "  {}", inst).unwrap();
+    }
+
+    fn new_temp(&mut self) -> String {
+        self.temps += 1;
+        format!("%t{}", self.temps)
+    }
+
+    fn new_label(&mut self) -> String {
+        self.labels += 1;
+        format!("L{}", self.labels)
+    }
+
+    fn start_block(&mut self, label: &str) {
+        writeln!(self.out, "{}:", label).unwrap();
+        self.current_label = label.to_owned();
+        self.terminated = false;
+    }
+
+    fn terminate(&mut self, inst: &str) {
+        if !self.terminated {
+            self.emit(inst);
+            self.terminated = true;
+        }
+    }
+
+    // code following a return or a branch goes to a fresh block which nothing jumps to
+    fn ensure_block(&mut self) {
+        if self.terminated {
+            let label = self.new_label();
+            self.start_block(&label);
+        }
+    }
+
+    fn fun_name(name: &str) -> String {
+        if stdlib::is_stdlib(name) { format!("@{}", name) } else { format!("@f_{}", name) }
+    }
+
+    fn alloca(&mut self, ty: &str, name: &str) -> Value {
+        self.temps += 1;
+        let repr = format!("%{}.{}", name, self.temps);
+        writeln!(self.allocas, "  {} = alloca {}", repr, ty).unwrap();
+        Value::new(&format!("{}*", ty), &repr)
+    }
+
+    fn declare(&mut self, var_decl: &VarDecl) -> Value {
+        let ptr = self.alloca(&ll_type(var_decl.var_type.as_ref()), &var_decl.var_name);
+        self.scopes.last_mut().unwrap().insert(var_decl.var_name.to_owned(), ptr.clone());
+        if let Some(init) = &var_decl.init {
+            self.initialize(&ptr, var_decl.var_type.as_ref(), Some(init));
+        }
+        ptr
+    }
+
+    // stores an initialiser to the variable pointed to, zero filling the elements and fields left out
+    fn initialize(&mut self, ptr: &Value, a_type: &dyn Type, init: Option<&Initializer>) {
+        let ty = ll_type(a_type);
+        match init {
+            Some(Initializer::List(items)) if a_type.as_array().is_some() => {
+                let array_type = a_type.as_array().unwrap();
+                let element_ty = ll_type(array_type.array_type.as_ref());
+                for i in 0..array_type.size() {
+                    let t = self.new_temp();
+                    self.emit(&format!("{} = getelementptr inbounds {}, {}, i32 0, i32 {}", t, ty, ptr.typed(), i));
+                    self.initialize(&Value::new(&format!("{}*", element_ty), &t), array_type.array_type.as_ref(), items.get(i as usize));
+                }
+            },
+            Some(Initializer::List(items)) => {
+                let fields = self.struct_fields[&a_type.as_struct().unwrap().name].clone();
+                for (i, field) in fields.iter().enumerate() {
+                    let t = self.new_temp();
+                    self.emit(&format!("{} = getelementptr inbounds {}, {}, i32 0, i32 {}", t, ty, ptr.typed(), i));
+                    self.initialize(&Value::new(&format!("{}*", ll_type(field.var_type.as_ref())), &t), field.var_type.as_ref(), items.get(i));
+                }
+            },
+            // a string literal
+            Some(Initializer::Expr(_)) if a_type.as_array().is_some() => {
+                let value = self.constant(a_type, init);
+                self.emit(&format!("store {} {}, {}", ty, value, ptr.typed()));
+            },
+            Some(Initializer::Expr(expr)) => {
+                let value = self.value(&mut expr.clone());
+                self.emit(&format!("store {}, {}", value.typed(), ptr.typed()));
+            },
+            None => self.emit(&format!("store {} zeroinitializer, {}", ty, ptr.typed())),
+        }
+    }
+
+    // the constant of a global initialiser, whose values are checked to be constants
+    fn constant(&self, a_type: &dyn Type, init: Option<&Initializer>) -> String {
+        match init {
+            Some(Initializer::List(items)) if a_type.as_array().is_some() => {
+                let array_type = a_type.as_array().unwrap();
+                let element_ty = ll_type(array_type.array_type.as_ref());
+                let elements: Vec<String> = (0..array_type.size() as usize)
+                    .map(|i| format!("{} {}", element_ty, self.constant(array_type.array_type.as_ref(), items.get(i))))
+                    .collect();
+                format!("[{}]", elements.join(", "))
+            },
+            Some(Initializer::List(items)) => {
+                let fields = &self.struct_fields[&a_type.as_struct().unwrap().name];
+                let values: Vec<String> = fields.iter().enumerate()
+                    .map(|(i, field)| format!("{} {}", ll_type(field.var_type.as_ref()), self.constant(field.var_type.as_ref(), items.get(i))))
+                    .collect();
+                format!("{{ {} }}", values.join(", "))
+            },
+            Some(Initializer::Expr(expr)) if a_type.as_array().is_some() => {
+                let mut bytes = expr.as_any().downcast_ref::<StrLiteral>().unwrap().bytes();
+                bytes.resize(a_type.as_array().unwrap().size() as usize, 0);
+                format!("c\"{}\"", ll_string(&bytes))
+            },
+            Some(Initializer::Expr(expr)) => {
+                let value = constant_value(expr.as_ref()).unwrap_or(0);
+                ll_int(value, a_type.as_integer().unwrap())
+            },
+            None if a_type.as_integer().is_some() => "0".to_owned(),
+            None if a_type.as_pointer().is_some() => "null".to_owned(),
+            None => "zeroinitializer".to_owned(),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Value {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned().unwrap()
+    }
+
+    fn value(&mut self, expr: &mut Box<dyn Expr>) -> Value {
+        self.visit_expr(expr).unwrap()
+    }
+
+    fn load(&mut self, ptr: &Value) -> Value {
+        let ty = ptr.ty[..ptr.ty.len() - 1].to_owned();
+        if ty.ends_with(']') {
+            return self.decay(ptr);
+        }
+        let t = self.new_temp();
+        self.emit(&format!("{} = load {}, {}", t, ty, ptr.typed()));
+        Value::new(&ty, &t)
+    }
+
+    // the address of the first element of the array pointed to
+    fn decay(&mut self, ptr: &Value) -> Value {
+        let array_ty = &ptr.ty[..ptr.ty.len() - 1];
+        let element_ty = array_ty[array_ty.find(" x ").unwrap() + 3..array_ty.len() - 1].to_owned();
+        let t = self.new_temp();
+        self.emit(&format!("{} = getelementptr inbounds {}, {}, i32 0, i32 0", t, array_ty, ptr.typed()));
+        Value::new(&format!("{}*", element_ty), &t)
+    }
+
+    // the instruction of an operator whose result has the type of its operands, comparisons giving an i1
+    fn arithmetic(op: Op, signed: bool) -> Option<&'static str> {
+        match op {
+            Op::ADD => Some("add"),
+            Op::SUB => Some("sub"),
+            Op::MUL => Some("mul"),
+            Op::DIV => Some(if signed { "sdiv" } else { "udiv" }),
+            Op::MOD => Some(if signed { "srem" } else { "urem" }),
+            Op::BAND => Some("and"),
+            Op::BOR => Some("or"),
+            Op::BXOR => Some("xor"),
+            Op::SHL => Some("shl"),
+            Op::SHR => Some(if signed { "ashr" } else { "lshr" }),
+            _ => None,
+        }
+    }
+
+    // applies an arithmetic operator to operands of an integer type, the amount of a shift being an int
+    fn operation(&mut self, op: Op, int_type: BaseType, lhs: &Value, rhs: &Value) -> Value {
+        let mut rhs = rhs.clone();
+        if op == Op::SHL || op == Op::SHR {
+            // a shift by the width of the value or more is poison in LLVM, the other backends take the
+            // amount modulo the width
+            rhs = self.convert(&rhs, BaseType::INT, int_type);
+            let amount = self.new_temp();
+            self.emit(&format!("{} = and {}, {}", amount, rhs.typed(), int_type.size() * 8 - 1));
+            rhs = Value::new(&lhs.ty, &amount);
+        }
+        let t = self.new_temp();
+        let inst = LlvmGenerator::arithmetic(op, int_type.is_signed()).unwrap();
+        self.emit(&format!("{} = {} {}, {}", t, inst, lhs.typed(), rhs.repr));
+        Value::new(&lhs.ty, &t)
+    }
+
+    // converts an integer to another integer type, extending it as the type converted from is signed
+    fn convert(&mut self, value: &Value, from: BaseType, to: BaseType) -> Value {
+        let inst = if to.size() < from.size() {
+            "trunc"
+        } else if to.size() > from.size() {
+            if from.is_signed() { "sext" } else { "zext" }
+        } else {
+            return value.clone();
+        };
+        let ty = ll_type(&to);
+        let t = self.new_temp();
+        self.emit(&format!("{} = {} {} to {}", t, inst, value.typed(), ty));
+        Value::new(&ty, &t)
+    }
+
+    fn truth(&mut self, value: &Value) -> String {
+        let t = self.new_temp();
+        let zero = if value.ty.ends_with('*') { "null" } else { "0" };
+        self.emit(&format!("{} = icmp ne {}, {}", t, value.typed(), zero));
+        t
+    }
+
+    /// Returns a pointer to an lvalue; other expressions, such as a struct returned by a call, are
+    /// stored to a temporary first.
+    fn address(&mut self, expr: &mut Box<dyn Expr>) -> Value {
+        if let Some(var_expr) = expr.as_any().downcast_ref::<VarExpr>() {
+            self.lookup(&var_expr.name)
+        } else if let Some(field_access_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            self.field_address(field_access_expr)
+        } else if let Some(array_access_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            self.element_address(array_access_expr)
+        } else if let Some(value_at_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            self.value(&mut value_at_expr.expr)
+        } else {
+            let value = self.value(expr);
+            let ptr = self.alloca(&value.ty, "tmp");
+            self.emit(&format!("store {}, {}", value.typed(), ptr.typed()));
+            ptr
+        }
+    }
+
+    fn field_address(&mut self, field_access_expr: &mut FieldAccessExpr) -> Value {
+        let struct_name = field_access_expr.expr.get_expr_type().as_struct().unwrap().name.to_owned();
+        let index = self.struct_fields[&struct_name].iter().position(|f| f.var_name == field_access_expr.name).unwrap();
+        let base = self.address(&mut field_access_expr.expr);
+        let t = self.new_temp();
+        self.emit(&format!("{} = getelementptr inbounds %struct.{}, {}, i32 0, i32 {}", t, struct_name, base.typed(), index));
+        Value::new(&format!("{}*", ll_type(field_access_expr.expr_type.as_ref())), &t)
+    }
+
+    fn element_address(&mut self, array_access_expr: &mut ArrayAccessExpr) -> Value {
+        let element_ty = ll_type(array_access_expr.expr_type.as_ref());
+        let t = self.new_temp();
+        if array_access_expr.expr1.get_expr_type().as_array().is_some() {
+            let base = self.address(&mut array_access_expr.expr1);
+            let index = self.value(&mut array_access_expr.expr2);
+            let array_ty = base.ty[..base.ty.len() - 1].to_owned();
+            self.emit(&format!("{} = getelementptr inbounds {}, {}, i32 0, {}", t, array_ty, base.typed(), index.typed()));
+        } else {
+            let base = self.value(&mut array_access_expr.expr1);
+            let index = self.value(&mut array_access_expr.expr2);
+            self.emit(&format!("{} = getelementptr inbounds {}, {}, {}", t, element_ty, base.typed(), index.typed()));
+        }
+        Value::new(&format!("{}*", element_ty), &t)
+    }
+
+    fn signature(fun_decl: &FunDecl, named: bool) -> String {
+        let params: Vec<String> = fun_decl.params.iter().enumerate().map(|(i, p)| {
+            let ty = ll_type(p.var_type.as_ref());
+            if named { format!("{} %arg{}", ty, i) } else { ty }
+        }).collect();
+        format!("{} {}({})", ll_type(fun_decl.fun_type.as_ref()), LlvmGenerator::fun_name(&fun_decl.name), params.join(", "))
+    }
+
+    // the LLVM function type of a function, e.g. `i32 (i8*, i32)`
+    fn fun_type(fun_decl: &FunDecl) -> String {
+        let params: Vec<String> = fun_decl.params.iter().map(|p| ll_type(p.var_type.as_ref())).collect();
+        format!("{} ({})", ll_type(fun_decl.fun_type.as_ref()), params.join(", "))
+    }
+
+    fn call(&mut self, ty: &str, callee: &str, exprs: &mut [Box<dyn Expr>]) -> Option<Value> {
+        let mut args = Vec::new();
+        for expr in exprs.iter_mut() {
+            let value = self.value(expr);
+            args.push(value.typed());
+        }
+        let call = format!("call {} {}({})", ty, callee, args.join(", "));
+        if ty == "void" {
+            self.emit(&call);
+            return None;
+        }
+        let t = self.new_temp();
+        self.emit(&format!("{} = {}", t, call));
+        Some(Value::new(ty, &t))
+    }
+}
+
+impl ASTVisitor<Option<Value>> for LlvmGenerator {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) -> Option<Value> {
+        None
+    }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) -> Option<Value> {
+        None
+    }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) -> Option<Value> {
+        None
+    }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) -> Option<Value> {
+        None
+    }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) -> Option<Value> {
+        None
+    }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) -> Option<Value> {
+        None
+    }
+
+    fn visit_program(&mut self, program: &mut Program) -> Option<Value> {
+        let mut header = String::new();
+        self.layout = Layout::new(&program.struct_type_decls);
+        for struct_type_decl in program.struct_type_decls.iter_mut() {
+            let fields: Vec<String> = struct_type_decl.var_decls.iter().map(|v| ll_type(v.var_type.as_ref())).collect();
+            writeln!(header, "%struct.{} = type {{ {} }}", struct_type_decl.struct_type.name, fields.join(", ")).unwrap();
+            struct_type_decl.accept(self);
+        }
+        if !program.struct_type_decls.is_empty() {
+            header.push('\n');
+        }
+
+        for var_decl in program.var_decls.iter() {
+            let ty = ll_type(var_decl.var_type.as_ref());
+            let kind = if var_decl.is_const { "constant" } else { "global" };
+            if var_decl.storage == StorageClass::Extern {
+                writeln!(header, "@g_{} = external {} {}", var_decl.var_name, kind, ty).unwrap();
+            } else {
+                let init = self.constant(var_decl.var_type.as_ref(), var_decl.init.as_ref());
+                writeln!(header, "@g_{} = {}{} {} {}", var_decl.var_name, linkage(var_decl.storage), kind, ty, init).unwrap();
+            }
+            self.scopes[0].insert(var_decl.var_name.to_owned(), Value::new(&format!("{}*", ty), &format!("@g_{}", var_decl.var_name)));
+        }
+        if !program.var_decls.is_empty() {
+            header.push('\n');
+        }
+
+        for fun_decl in stdlib::fun_decls().iter().chain(program.prototypes.iter()) {
+            writeln!(header, "declare {}", LlvmGenerator::signature(fun_decl, false)).unwrap();
+        }
+
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+
+        if let Some(main) = program.fun_decls.iter().find(|f| f.name == "main") {
+            self.out.push_str("\ndefine i32 @main() {\n");
+            if main.fun_type.is_base(BaseType::INT) {
+                self.out.push_str("  %r = call i32 @f_main()\n  ret i32 %r\n}\n");
+            } else {
+                let call = format!("  call {} @f_main()\n  ret i32 0\n}}\n", ll_type(main.fun_type.as_ref()));
+                self.out.push_str(&call);
+            }
+        }
+
+        let body = std::mem::take(&mut self.out);
+        self.out = header;
+        if !self.strings.is_empty() {
+            self.out.push('\n');
+            let strings = std::mem::take(&mut self.strings);
+            self.out.push_str(&strings);
+        }
+        self.out.push_str(&body);
+        None
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) -> Option<Value> {
+        self.struct_fields.insert(struct_type_decl.struct_type.name.to_owned(), struct_type_decl.var_decls.clone());
+        None
+    }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) -> Option<Value> {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) -> Option<Value> {
+        self.declare(var_decl);
+        None
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) -> Option<Value> {
+        self.temps = 0;
+        self.labels = 0;
+        self.allocas = String::new();
+        self.terminated = false;
+        self.current_label = "entry".to_owned();
+        self.fun_type = fun_decl.fun_type.clone();
+        self.scopes.push(HashMap::new());
+
+        let header = format!("\ndefine {}{} {{\nentry:\n", linkage(fun_decl.storage), LlvmGenerator::signature(fun_decl, true));
+        let outer = std::mem::take(&mut self.out);
+
+        for (i, param) in fun_decl.params.iter().enumerate() {
+            let ptr = self.declare(param);
+            let arg = Value::new(&ll_type(param.var_type.as_ref()), &format!("%arg{}", i));
+            self.emit(&format!("store {}, {}", arg.typed(), ptr.typed()));
+        }
+        for var_decl in fun_decl.block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in fun_decl.block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        if fun_decl.fun_type.is_base(BaseType::VOID) {
+            self.terminate("ret void");
+        } else {
+            // falling off the end of a non void function is undefined, return zero
+            let ret = format!("ret {} zeroinitializer", ll_type(fun_decl.fun_type.as_ref()));
+            self.terminate(&ret);
+        }
+        self.scopes.pop();
+
+        let body = std::mem::replace(&mut self.out, outer);
+        self.out.push_str(&header);
+        let allocas = std::mem::take(&mut self.allocas);
+        self.out.push_str(&allocas);
+        self.out.push_str(&body);
+        self.out.push_str("}\n");
+        None
+    }
+
+    fn visit_block(&mut self, block: &mut Block) -> Option<Value> {
+        self.scopes.push(HashMap::new());
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+        None
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) -> Option<Value> {
+        let cond = self.new_label();
+        let body = self.new_label();
+        let end = self.new_label();
+
+        self.terminate(&format!("br label %{}", cond));
+        self.start_block(&cond);
+        let c = self.value(&mut a_while.expr);
+        let b = self.truth(&c);
+        self.terminate(&format!("br i1 {}, label %{}, label %{}", b, body, end));
+        self.start_block(&body);
+        self.loops.push((end.to_owned(), cond.to_owned()));
+        self.visit_stmt(&mut a_while.stmt);
+        self.loops.pop();
+        self.terminate(&format!("br label %{}", cond));
+        self.start_block(&end);
+        None
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) -> Option<Value> {
+        let body = self.new_label();
+        let cond = self.new_label();
+        let end = self.new_label();
+
+        self.terminate(&format!("br label %{}", body));
+        self.start_block(&body);
+        self.loops.push((end.to_owned(), cond.to_owned()));
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.loops.pop();
+        self.terminate(&format!("br label %{}", cond));
+        self.start_block(&cond);
+        let c = self.value(&mut a_do_while.expr);
+        let b = self.truth(&c);
+        self.terminate(&format!("br i1 {}, label %{}, label %{}", b, body, end));
+        self.start_block(&end);
+        None
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) -> Option<Value> {
+        let cond = self.new_label();
+        let body = self.new_label();
+        let step = self.new_label();
+        let end = self.new_label();
+
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        self.terminate(&format!("br label %{}", cond));
+        self.start_block(&cond);
+        match &mut a_for.expr {
+            Some(expr) => {
+                let c = self.value(expr);
+                let b = self.truth(&c);
+                self.terminate(&format!("br i1 {}, label %{}, label %{}", b, body, end));
+            },
+            None => self.terminate(&format!("br label %{}", body)),
+        }
+        self.start_block(&body);
+        self.loops.push((end.to_owned(), step.to_owned()));
+        self.visit_stmt(&mut a_for.stmt);
+        self.loops.pop();
+        self.terminate(&format!("br label %{}", step));
+        self.start_block(&step);
+        if let Some(a_step) = &mut a_for.step {
+            self.visit_stmt(a_step);
+        }
+        self.terminate(&format!("br label %{}", cond));
+        self.start_block(&end);
+        None
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) -> Option<Value> {
+        let (end, _) = self.loops.last().unwrap().clone();
+        self.terminate(&format!("br label %{}", end));
+        None
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) -> Option<Value> {
+        let (_, next) = self.loops.last().unwrap().clone();
+        self.terminate(&format!("br label %{}", next));
+        None
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) -> Option<Value> {
+        let then_label = self.new_label();
+        let else_label = self.new_label();
+        let end = if an_if.stmt2.is_some() { self.new_label() } else { else_label.to_owned() };
+
+        let c = self.value(&mut an_if.expr);
+        let b = self.truth(&c);
+        self.terminate(&format!("br i1 {}, label %{}, label %{}", b, then_label, else_label));
+        self.start_block(&then_label);
+        self.visit_stmt(&mut an_if.stmt1);
+        self.terminate(&format!("br label %{}", end));
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.start_block(&else_label);
+            self.visit_stmt(stmt2);
+            self.terminate(&format!("br label %{}", end));
+        }
+        self.start_block(&end);
+        None
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) -> Option<Value> {
+        let labels: Vec<String> = a_switch.cases.iter().map(|_| self.new_label()).collect();
+        let end = self.new_label();
+
+        let v = self.value(&mut a_switch.expr);
+        let int_type = a_switch.expr.get_expr_type().as_integer().unwrap();
+        let mut default = end.to_owned();
+        let mut targets = Vec::new();
+        for (case, label) in a_switch.cases.iter().zip(labels.iter()) {
+            match &case.expr {
+                // the labels were checked to be constants of the type of the value
+                Some(expr) => {
+                    let value = constant_value(expr.as_ref()).unwrap();
+                    targets.push(format!("{} {}, label %{}", v.ty, ll_int(value, int_type), label));
+                },
+                None => default = label.to_owned(),
+            }
+        }
+        self.terminate(&format!("switch {}, label %{} [ {} ]", v.typed(), default, targets.join(" ")));
+
+        // break leaves the switch while continue goes on with the enclosing loop
+        let next = self.loops.last().map(|(_, next)| next.to_owned()).unwrap_or_default();
+        self.loops.push((end.to_owned(), next));
+        for (i, case) in a_switch.cases.iter_mut().enumerate() {
+            // the previous case falls through
+            self.terminate(&format!("br label %{}", labels[i]));
+            self.start_block(&labels[i]);
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+        self.loops.pop();
+        self.terminate(&format!("br label %{}", end));
+        self.start_block(&end);
+        None
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) -> Option<Value> {
+        match &mut a_return.expr {
+            Some(expr) => {
+                let value = self.value(expr);
+                self.terminate(&format!("ret {}", value.typed()));
+            },
+            None => self.terminate("ret void"),
+        }
+        None
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) -> Option<Value> {
+        self.ensure_block();
+        let ptr = self.address(&mut assign.expr1);
+        let value = self.value(&mut assign.expr2);
+        self.emit(&format!("store {}, {}", value.typed(), ptr.typed()));
+        None
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) -> Option<Value> {
+        self.visit_expr(&mut expr_stmt.expr);
+        None
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) -> Option<Value> {
+        if bin_op.op.is_short_circuit() {
+            // the right operand is only evaluated if the left one does not decide
+            let lhs = self.value(&mut bin_op.expr1);
+            let lhs = self.truth(&lhs);
+            let lhs_label = self.current_label.to_owned();
+            let rhs_label = self.new_label();
+            let end = self.new_label();
+            if bin_op.op == Op::AND {
+                self.terminate(&format!("br i1 {}, label %{}, label %{}", lhs, rhs_label, end));
+            } else {
+                self.terminate(&format!("br i1 {}, label %{}, label %{}", lhs, end, rhs_label));
+            }
+            self.start_block(&rhs_label);
+            let rhs = self.value(&mut bin_op.expr2);
+            let rhs = self.truth(&rhs);
+            let rhs_end = self.current_label.to_owned();
+            self.terminate(&format!("br label %{}", end));
+            self.start_block(&end);
+
+            let decided = if bin_op.op == Op::AND { "false" } else { "true" };
+            let phi = self.new_temp();
+            self.emit(&format!("{} = phi i1 [ {}, %{} ], [ {}, %{} ]", phi, decided, lhs_label, rhs, rhs_end));
+            let t = self.new_temp();
+            self.emit(&format!("{} = zext i1 {} to i32", t, phi));
+            return Some(Value::new("i32", &t));
+        }
+
+        // the operands were converted to a common type, or the left one is shifted
+        let int_type = bin_op.expr1.get_expr_type().as_integer().unwrap_or(BaseType::INT);
+        let lhs = self.value(&mut bin_op.expr1);
+        let rhs = self.value(&mut bin_op.expr2);
+        if LlvmGenerator::arithmetic(bin_op.op, true).is_some() {
+            return Some(self.operation(bin_op.op, int_type, &lhs, &rhs));
+        }
+
+        let signed = int_type.is_signed();
+        let predicate = match bin_op.op {
+            Op::GT => if signed { "sgt" } else { "ugt" },
+            Op::LT => if signed { "slt" } else { "ult" },
+            Op::GE => if signed { "sge" } else { "uge" },
+            Op::LE => if signed { "sle" } else { "ule" },
+            Op::NE => "ne",
+            Op::EQ => "eq",
+            _ => unreachable!(),
+        };
+        let t = self.new_temp();
+        self.emit(&format!("{} = icmp {} {}, {}", t, predicate, lhs.typed(), rhs.repr));
+        let r = self.new_temp();
+        self.emit(&format!("{} = zext i1 {} to i32", r, t));
+        Some(Value::new("i32", &r))
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) -> Option<Value> {
+        None
+    }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) -> Option<Value> {
+        let ty = ll_type(&int_literal.int_type);
+        Some(Value::new(&ty, &ll_int(int_literal.i, int_literal.int_type)))
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) -> Option<Value> {
+        Some(Value::new("i8", &(chr_literal.c as u8).to_string()))
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) -> Option<Value> {
+        let mut bytes = str_literal.bytes();
+        bytes.push(0);
+        let name = format!("@.str.{}", self.string_count);
+        self.string_count += 1;
+        writeln!(self.strings, "{} = private unnamed_addr constant [{} x i8] c\"{}\"", name, bytes.len(), ll_string(&bytes)).unwrap();
+        let ptr = Value::new(&format!("[{} x i8]*", bytes.len()), &name);
+        Some(self.decay(&ptr))
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) -> Option<Value> {
+        let ptr = self.lookup(&var_expr.name);
+        Some(self.load(&ptr))
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) -> Option<Value> {
+        let value = self.value(&mut typecast_expr.expr);
+        if let (Some(from), Some(to)) = (typecast_expr.expr.get_expr_type().as_integer(), typecast_expr.typecast_type.as_integer()) {
+            return Some(self.convert(&value, from, to));
+        }
+        let ty = ll_type(typecast_expr.typecast_type.as_ref());
+        if value.ty == ty {
+            return Some(value);
+        }
+        let t = self.new_temp();
+        if value.ty == "i8" {
+            self.emit(&format!("{} = zext {} to {}", t, value.typed(), ty));
+        } else {
+            self.emit(&format!("{} = bitcast {} to {}", t, value.typed(), ty));
+        }
+        Some(Value::new(&ty, &t))
+    }
+
+    // the size of the minic layout, which the constant expressions of the program were folded with
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) -> Option<Value> {
+        let size = self.layout.size_of(size_of_expr.sizeof_type.as_ref());
+        Some(Value::new("i32", &size.to_string()))
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) -> Option<Value> {
+        Some(self.address(&mut address_of_expr.expr))
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) -> Option<Value> {
+        let ptr = self.value(&mut value_at_expr.expr);
+        Some(self.load(&ptr))
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) -> Option<Value> {
+        let ptr = self.field_address(field_access_expr);
+        Some(self.load(&ptr))
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) -> Option<Value> {
+        let ptr = self.element_address(array_access_expr);
+        Some(self.load(&ptr))
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) -> Option<Value> {
+        self.ensure_block();
+        let ty = ll_type(fun_call_expr.fun_decl.fun_type.as_ref());
+        self.call(&ty, &LlvmGenerator::fun_name(&fun_call_expr.name), &mut fun_call_expr.exprs)
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) -> Option<Value> {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_method_call_expr(&mut self, _method_call_expr: &mut MethodCallExpr) -> Option<Value> {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) -> Option<Value> {
+        let fun_type = LlvmGenerator::fun_type(&fun_addr_expr.fun_decl);
+        let address = format!("bitcast ({}* {} to i8*)", fun_type, LlvmGenerator::fun_name(&fun_addr_expr.name));
+        Some(Value::new("i8*", &address))
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) -> Option<Value> {
+        self.ensure_block();
+        let address = self.value(&mut indirect_call_expr.expr);
+        let fun_type = LlvmGenerator::fun_type(&indirect_call_expr.fun_decl);
+        let target = self.new_temp();
+        self.emit(&format!("{} = bitcast {} to {}*", target, address.typed(), fun_type));
+        let ty = ll_type(indirect_call_expr.fun_decl.fun_type.as_ref());
+        self.call(&ty, &target, &mut indirect_call_expr.exprs)
+    }
+
+    // the left hand side is evaluated first, and the assignment evaluates to the value stored
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) -> Option<Value> {
+        let ptr = self.address(&mut assign_expr.expr1);
+        let value = match assign_expr.op {
+            // the right hand side was converted to the type the operation is done in
+            Some(op) => {
+                let lhs_type = assign_expr.expr1.get_expr_type().as_integer().unwrap();
+                let rhs_type = assign_expr.expr2.get_expr_type().as_integer().unwrap();
+                let old = self.load(&ptr);
+                let old = self.convert(&old, lhs_type, rhs_type);
+                let rhs = self.value(&mut assign_expr.expr2);
+                let result = self.operation(op, rhs_type, &old, &rhs);
+                self.convert(&result, rhs_type, lhs_type)
+            },
+            None => self.value(&mut assign_expr.expr2),
+        };
+        self.emit(&format!("store {}, {}", value.typed(), ptr.typed()));
+        Some(value)
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) -> Option<Value> {
+        let ptr = self.address(&mut inc_dec_expr.expr);
+        let old = self.load(&ptr);
+        // adding in the type of the value wraps it as adding in the promoted type and converting back would
+        let t = self.new_temp();
+        self.emit(&format!("{} = {} {}, 1", t, LlvmGenerator::arithmetic(inc_dec_expr.op, true).unwrap(), old.typed()));
+        let new = Value::new(&old.ty, &t);
+        self.emit(&format!("store {}, {}", new.typed(), ptr.typed()));
+        Some(if inc_dec_expr.prefix { new } else { old })
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) -> Option<Value> {
+        let c = self.value(&mut conditional_expr.expr);
+        let c = self.truth(&c);
+        let then_label = self.new_label();
+        let else_label = self.new_label();
+        let end = self.new_label();
+        self.terminate(&format!("br i1 {}, label %{}, label %{}", c, then_label, else_label));
+
+        self.start_block(&then_label);
+        let value1 = self.visit_expr(&mut conditional_expr.expr1);
+        let then_end = self.current_label.to_owned();
+        self.terminate(&format!("br label %{}", end));
+        self.start_block(&else_label);
+        let value2 = self.visit_expr(&mut conditional_expr.expr2);
+        let else_end = self.current_label.to_owned();
+        self.terminate(&format!("br label %{}", end));
+        self.start_block(&end);
+
+        // void branches have no value to merge
+        let (value1, value2) = (value1?, value2?);
+        let phi = self.new_temp();
+        self.emit(&format!("{} = phi {} [ {}, %{} ], [ {}, %{} ]", phi, value1.ty, value1.repr, then_end, value2.repr, else_end));
+        Some(Value::new(&value1.ty, &phi))
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) -> Option<Value> {
+        None
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) -> Option<Value> {
+        self.ensure_block();
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self)
+        } else {
+            None
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) -> Option<Value> {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self)
+        } else {
+            None
+        }
+    }
+}
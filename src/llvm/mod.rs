@@ -0,0 +1 @@
+pub mod llvm_generator;
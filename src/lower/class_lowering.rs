@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::sem::stdlib;
+use crate::sem::type_analyzer::TypeAnalyzer;
+use crate::ASTNode;
+
+/// ClassLowering
+/// Rewrites the classes of an analysed program into structs and functions, so that the code
+/// generators never see them. An object of class `C` is a pointer to a `struct __class__C` holding
+/// the address of the vtable `__vtable__C` followed by the fields of its ancestors and its own.
+/// A method `m` of `C` becomes the function `__C__m` taking the object as its first parameter `this`,
+/// and a call of `m` goes through `__virtual__R__m`, where `R` is the class first declaring `m`,
+/// which calls the function in the slot of `m` in the vtable of the object. `new class C()` calls
+/// `__new__C`, which fills the vtable and allocates a zeroed object pointing to it.
+pub struct ClassLowering {
+    class_decls: HashMap<String, ClassDecl>,
+}
+
+// a slot of a vtable: the method, the class implementing it and the class first declaring it
+struct Slot {
+    name: String,
+    class_name: String,
+    root: String,
+}
+
+fn struct_name(class_name: &str) -> String {
+    format!("__class__{}", class_name)
+}
+
+fn object_type(class_name: &str) -> Box<dyn Type> {
+    PointerType::new(Box::new(StructType::new(struct_name(class_name))))
+}
+
+fn var(var_decl: &VarDecl) -> Box<dyn Expr> {
+    let mut var_expr = VarExpr::new(var_decl.var_name.to_owned());
+    var_expr.var_decl = var_decl.clone();
+    var_expr
+}
+
+fn call(fun_decl: &FunDecl, exprs: Vec<Box<dyn Expr>>) -> Box<FunCallExpr> {
+    let mut fun_call_expr = FunCallExpr::new(fun_decl.name.to_owned(), exprs);
+    fun_call_expr.fun_decl = fun_decl.clone();
+    fun_call_expr
+}
+
+// the `__vtable` field of the object `this` points to
+fn vtable_of(this: &VarDecl) -> Box<dyn Expr> {
+    FieldAccessExpr::new(ValueAtExpr::new(var(this)), "__vtable".to_owned())
+}
+
+impl ClassLowering {
+    /// Lowers the classes of the program, if any, and types the nodes built.
+    pub fn run(program: &mut Program) {
+        if program.class_decls.is_empty() {
+            return;
+        }
+        program.accept(&mut ClassLowering { class_decls: HashMap::new() });
+
+        let mut type_analyzer = TypeAnalyzer::new();
+        program.accept(&mut type_analyzer);
+        assert_eq!(type_analyzer.get_error_count(), 0, "the lowered classes must be well typed");
+    }
+
+    fn lower_type(a_type: &mut Box<dyn Type>) {
+        if let Some(class_type) = a_type.as_class() {
+            *a_type = object_type(&class_type.name);
+        } else if let Some(pointer_type) = a_type.as_mut_any().downcast_mut::<PointerType>() {
+            ClassLowering::lower_type(&mut pointer_type.pointer_type);
+        } else if let Some(array_type) = a_type.as_mut_any().downcast_mut::<ArrayType>() {
+            ClassLowering::lower_type(&mut array_type.array_type);
+        } else if let Some(fun_type) = a_type.as_mut_any().downcast_mut::<FunType>() {
+            ClassLowering::lower_type(&mut fun_type.return_type);
+            for param_type in fun_type.param_types.iter_mut() {
+                ClassLowering::lower_type(param_type);
+            }
+        }
+    }
+
+    fn lower_signature(fun_decl: &mut FunDecl) {
+        ClassLowering::lower_type(&mut fun_decl.fun_type);
+        for param in fun_decl.params.iter_mut() {
+            ClassLowering::lower_type(&mut param.var_type);
+        }
+    }
+
+    // the class and its ancestors, starting with the root
+    fn ancestry(&self, class_name: &str) -> Vec<&ClassDecl> {
+        let mut ancestry = Vec::new();
+        let mut current = self.class_decls.get(class_name);
+        while let Some(class_decl) = current {
+            ancestry.push(class_decl);
+            current = class_decl.parent.as_ref().and_then(|parent| self.class_decls.get(&parent.name));
+        }
+        ancestry.reverse();
+        ancestry
+    }
+
+    // an overriding method takes the slot of the method it overrides
+    fn vtable(&self, class_name: &str) -> Vec<Slot> {
+        let mut slots: Vec<Slot> = Vec::new();
+        for class_decl in self.ancestry(class_name) {
+            let name = &class_decl.class_type.name;
+            for method in class_decl.fun_decls.iter() {
+                match slots.iter_mut().find(|slot| slot.name == method.name) {
+                    Some(slot) => slot.class_name = name.to_owned(),
+                    None => slots.push(Slot { name: method.name.to_owned(), class_name: name.to_owned(), root: name.to_owned() }),
+                }
+            }
+        }
+        slots
+    }
+
+    // the signature of a method as a function of class `class_name` named `name`, receiving the object first
+    fn method_header(&self, class_name: &str, method: &str, name: String) -> FunDecl {
+        let method = self.class_decls[class_name].fun_decls.iter().find(|fun_decl| fun_decl.name == method).unwrap();
+        let mut params = vec![VarDecl::new(object_type(class_name), "this".to_owned())];
+        params.extend(method.params.iter().cloned());
+        let mut header = FunDecl::new(method.fun_type.clone(), name, params, Block::new(vec![], vec![]));
+        ClassLowering::lower_signature(&mut header);
+        header
+    }
+
+    fn implementation(&self, class_name: &str, method: &str) -> FunDecl {
+        self.method_header(class_name, method, format!("__{}__{}", class_name, method))
+    }
+
+    fn dispatcher(&self, root: &str, method: &str) -> FunDecl {
+        self.method_header(root, method, format!("__virtual__{}__{}", root, method))
+    }
+
+    fn constructor(class_name: &str) -> FunDecl {
+        FunDecl::new(object_type(class_name), format!("__new__{}", class_name), vec![], Block::new(vec![], vec![]))
+    }
+
+    fn vtable_decl(&self, class_name: &str) -> VarDecl {
+        // an array cannot be empty
+        let size = self.vtable(class_name).len().max(1) as i32;
+        VarDecl::new(Box::new(ArrayType::new(PointerType::new(Box::new(BaseType::VOID)), size)), format!("__vtable__{}", class_name))
+    }
+
+    fn struct_decl(&self, class_name: &str) -> StructTypeDecl {
+        let vtable_type = PointerType::new(PointerType::new(Box::new(BaseType::VOID)));
+        let mut fields = vec![VarDecl::new(vtable_type, "__vtable".to_owned())];
+        for class_decl in self.ancestry(class_name) {
+            for field in class_decl.var_decls.iter() {
+                let mut field = VarDecl::new(field.var_type.clone(), field.var_name.to_owned());
+                ClassLowering::lower_type(&mut field.var_type);
+                fields.push(field);
+            }
+        }
+        StructTypeDecl::new(StructType::new(struct_name(class_name)), fields)
+    }
+
+    // `return ((ret (*)(this, params..)) (*this).__vtable[k])(this, params..);`
+    fn dispatcher_decl(&self, root: &str, method: &str) -> FunDecl {
+        let mut fun_decl = self.dispatcher(root, method);
+        let slot = self.vtable(root).iter().position(|slot| slot.name == method).unwrap();
+        let target = ArrayAccessExpr::new(vtable_of(&fun_decl.params[0]), IntLiteral::new(slot.to_string()));
+        let target = TypecastExpr::new(PointerType::new(Box::new(fun_decl.signature())), target);
+        let args = fun_decl.params.iter().map(var).collect();
+        let call = IndirectCallExpr::new(target, args);
+        let stmt: Box<dyn Stmt> = if fun_decl.fun_type.is_base(BaseType::VOID) { ExprStmt::new(call) } else { Return::new(Some(call)) };
+        fun_decl.block.stmts.push(stmt);
+        fun_decl
+    }
+
+    fn constructor_decl(&self, class_name: &str) -> FunDecl {
+        let mut fun_decl = ClassLowering::constructor(class_name);
+        let object_struct: Box<dyn Type> = Box::new(StructType::new(struct_name(class_name)));
+        let mut object = VarDecl::new(object_struct.clone(), "object".to_owned());
+        object.init = Some(Initializer::List(vec![]));
+        let this = VarDecl::new(object_type(class_name), "this".to_owned());
+        let vtable = self.vtable_decl(class_name);
+        let mcmalloc = stdlib::fun_decls().into_iter().find(|fun_decl| fun_decl.name == "mcmalloc").unwrap();
+
+        let mut stmts: Vec<Box<dyn Stmt>> = Vec::new();
+        for (i, slot) in self.vtable(class_name).iter().enumerate() {
+            let mut fun_addr_expr = FunAddrExpr::new(format!("__{}__{}", slot.class_name, slot.name));
+            fun_addr_expr.fun_decl = self.implementation(&slot.class_name, &slot.name);
+            let fun_addr = TypecastExpr::new(PointerType::new(Box::new(BaseType::VOID)), fun_addr_expr);
+            stmts.push(Assign::new(ArrayAccessExpr::new(var(&vtable), IntLiteral::new(i.to_string())), fun_addr));
+        }
+        let size = call(&mcmalloc, vec![SizeOfExpr::new(object_struct)]);
+        stmts.push(Assign::new(var(&this), TypecastExpr::new(object_type(class_name), size)));
+        stmts.push(Assign::new(ValueAtExpr::new(var(&this)), var(&object)));
+        let vtable_type = PointerType::new(PointerType::new(Box::new(BaseType::VOID)));
+        stmts.push(Assign::new(vtable_of(&this), TypecastExpr::new(vtable_type, var(&vtable))));
+        stmts.push(Return::new(Some(var(&this))));
+
+        fun_decl.block = Block::new(vec![object, this], stmts);
+        fun_decl
+    }
+
+    fn lower_method_call(&mut self, method_call_expr: &mut MethodCallExpr) -> Box<dyn Expr> {
+        // the type of the object is only known before it is lowered
+        let class_name = method_call_expr.expr.get_expr_type().as_class().unwrap().name.to_owned();
+        let root = self.vtable(&class_name).into_iter().find(|slot| slot.name == method_call_expr.name).unwrap().root;
+
+        self.visit_expr(&mut method_call_expr.expr);
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+        let mut receiver = std::mem::replace(&mut method_call_expr.expr, IntLiteral::new("0".to_owned()));
+        if root != class_name {
+            receiver = TypecastExpr::new(object_type(&root), receiver);
+        }
+        let mut args = vec![receiver];
+        args.append(&mut method_call_expr.exprs);
+        call(&self.dispatcher(&root, &method_call_expr.name), args)
+    }
+}
+
+impl ASTVisitor<()> for ClassLowering {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) { }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) { }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) { }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) { }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        let class_decls = std::mem::take(&mut program.class_decls);
+        for class_decl in class_decls.iter() {
+            self.class_decls.insert(class_decl.class_type.name.to_owned(), class_decl.clone());
+        }
+
+        for struct_type_decl in program.struct_type_decls.iter_mut() {
+            struct_type_decl.accept(self);
+        }
+        for var_decl in program.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+
+        for mut class_decl in class_decls {
+            let class_name = class_decl.class_type.name.to_owned();
+            program.struct_type_decls.push(self.struct_decl(&class_name));
+            program.var_decls.push(self.vtable_decl(&class_name));
+            for method in class_decl.fun_decls.iter_mut() {
+                let mut fun_decl = self.implementation(&class_name, &method.name);
+                fun_decl.block = std::mem::replace(&mut method.block, Block::new(vec![], vec![]));
+                fun_decl.accept(self);
+                program.fun_decls.push(fun_decl);
+            }
+            for slot in self.vtable(&class_name).iter().filter(|slot| slot.root == class_name) {
+                program.fun_decls.push(self.dispatcher_decl(&class_name, &slot.name));
+            }
+            program.fun_decls.push(self.constructor_decl(&class_name));
+        }
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) {
+        for var_decl in struct_type_decl.var_decls.iter_mut() {
+            ClassLowering::lower_type(&mut var_decl.var_type);
+        }
+    }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) { }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        ClassLowering::lower_type(&mut var_decl.var_type);
+        if let Some(init) = &mut var_decl.init {
+            for expr in init.exprs_mut() {
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        ClassLowering::lower_type(&mut fun_decl.fun_type);
+        for param in fun_decl.params.iter_mut() {
+            param.accept(self);
+        }
+        fun_decl.block.accept(self);
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.visit_expr(&mut a_while.expr);
+        self.visit_stmt(&mut a_while.stmt);
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.visit_expr(&mut a_do_while.expr);
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.visit_stmt(&mut a_for.stmt);
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) { }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) { }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.visit_expr(&mut a_switch.expr);
+        for case in a_switch.cases.iter_mut() {
+            if let Some(expr) = &mut case.expr {
+                self.visit_expr(expr);
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, _int_literal: &mut IntLiteral) { }
+
+    fn visit_chr_literal(&mut self, _chr_literal: &mut ChrLiteral) { }
+
+    fn visit_str_literal(&mut self, _str_literal: &mut StrLiteral) { }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        ClassLowering::lower_type(&mut var_expr.var_decl.var_type);
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        ClassLowering::lower_type(&mut typecast_expr.typecast_type);
+        self.visit_expr(&mut typecast_expr.expr);
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        ClassLowering::lower_type(&mut size_of_expr.sizeof_type);
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.visit_expr(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+    }
+
+    // `object.field` becomes `(*object).field`
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        let is_object = field_access_expr.expr.get_expr_type().as_class().is_some();
+        self.visit_expr(&mut field_access_expr.expr);
+        if is_object {
+            let object = std::mem::replace(&mut field_access_expr.expr, IntLiteral::new("0".to_owned()));
+            field_access_expr.expr = ValueAtExpr::new(object);
+        }
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        ClassLowering::lower_signature(&mut fun_call_expr.fun_decl);
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    // replaced by visit_expr
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) {
+        unreachable!()
+    }
+
+    fn visit_method_call_expr(&mut self, _method_call_expr: &mut MethodCallExpr) {
+        unreachable!()
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        ClassLowering::lower_signature(&mut fun_addr_expr.fun_decl);
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        ClassLowering::lower_signature(&mut indirect_call_expr.fun_decl);
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.visit_expr(&mut assign_expr.expr1);
+        self.visit_expr(&mut assign_expr.expr2);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.visit_expr(&mut inc_dec_expr.expr);
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.visit_expr(&mut conditional_expr.expr2);
+    }
+
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) {
+        ClassLowering::lower_type(a_type);
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        let is_grouped = expr.get_is_grouped();
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            *expr = call(&ClassLowering::constructor(&cast_expr.class_type.name), vec![]);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            *expr = self.lower_method_call(cast_expr);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+        expr.set_is_grouped(is_grouped);
+    }
+}
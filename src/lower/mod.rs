@@ -0,0 +1,2 @@
+pub mod class_lowering;
+pub mod storage_lowering;
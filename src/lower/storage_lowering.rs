@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Item, Program, StorageClass, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::lexer::position::Position;
+use crate::sem::stdlib;
+use crate::ASTNode;
+
+/// StorageLowering
+/// Leaves the code generators one declaration of each global, and no static local. A static local
+/// `x` of the function `f` becomes the static global `__static__f__x`, which its uses then refer
+/// to. The extern declarations of a global defined in the program are dropped, as are the
+/// prototypes of the functions it defines, so what is left declared extern, as a global or a
+/// prototype, is defined in another file.
+pub struct StorageLowering {
+    fun_name: String,
+    // the static locals of the function, by name and position, with the name of their global
+    renamed: Vec<(String, Position, String)>,
+    hoisted: Vec<VarDecl>,
+    // the names of the globals and functions, which those of the hoisted locals must differ from
+    taken: HashSet<String>,
+}
+
+// the new index of each declaration kept, once the others are removed
+fn reindex(kept: &[bool]) -> Vec<Option<usize>> {
+    let mut next = 0;
+    kept.iter().map(|&keep| keep.then(|| { next += 1; next - 1 })).collect()
+}
+
+fn retain<T>(decls: &mut Vec<T>, kept: &[bool]) {
+    let mut i = 0;
+    decls.retain(|_| { i += 1; kept[i - 1] });
+}
+
+impl StorageLowering {
+    /// Lowers the static locals and the extern declarations of an analysed program.
+    pub fn run(program: &mut Program) {
+        let taken = program.var_decls.iter().map(|var_decl| var_decl.var_name.to_owned())
+            .chain(program.fun_decls.iter().map(|fun_decl| fun_decl.name.to_owned()))
+            .chain(stdlib::fun_decls().iter().map(|fun_decl| fun_decl.name.to_owned()))
+            .collect();
+        program.accept(&mut StorageLowering { fun_name: String::new(), renamed: Vec::new(), hoisted: Vec::new(), taken });
+    }
+
+    /// Whether a function of the program has a static local.
+    pub fn has_static_locals(program: &Program) -> bool {
+        let mut lowering = StorageLowering { fun_name: String::new(), renamed: Vec::new(), hoisted: Vec::new(), taken: HashSet::new() };
+        for fun_decl in program.fun_decls.iter() {
+            fun_decl.clone().accept(&mut lowering);
+        }
+        !lowering.hoisted.is_empty()
+    }
+
+    // the global standing for a static local, numbered when the function has several of that name
+    fn global_name(&mut self, var_name: &str) -> String {
+        let mut name = format!("__static__{}__{}", self.fun_name, var_name);
+        let mut i = 1;
+        while self.taken.contains(&name) {
+            i += 1;
+            name = format!("__static__{}__{}__{}", self.fun_name, var_name, i);
+        }
+        self.taken.insert(name.to_owned());
+        name
+    }
+
+    // keeps the definition of each global, or its first extern declaration, and the prototypes of the
+    // functions the program does not define
+    fn drop_declarations(program: &mut Program) {
+        let defined: HashSet<String> = program.var_decls.iter()
+            .filter(|var_decl| var_decl.storage != StorageClass::Extern)
+            .map(|var_decl| var_decl.var_name.to_owned())
+            .collect();
+        let mut declared = HashSet::new();
+        let kept_vars: Vec<bool> = program.var_decls.iter()
+            .map(|var_decl| var_decl.storage != StorageClass::Extern || (!defined.contains(&var_decl.var_name) && declared.insert(var_decl.var_name.to_owned())))
+            .collect();
+
+        let mut declared: HashSet<String> = program.fun_decls.iter().map(|fun_decl| fun_decl.name.to_owned()).collect();
+        let kept_prototypes: Vec<bool> = program.prototypes.iter()
+            .map(|prototype| declared.insert(prototype.name.to_owned()))
+            .collect();
+
+        let (vars, prototypes) = (reindex(&kept_vars), reindex(&kept_prototypes));
+        program.items = program.items.iter().filter_map(|item| match *item {
+            Item::Var(i) => vars.get(i).copied().flatten().map(Item::Var),
+            Item::Prototype(i) => prototypes.get(i).copied().flatten().map(Item::Prototype),
+            item => Some(item),
+        }).collect();
+        retain(&mut program.var_decls, &kept_vars);
+        retain(&mut program.prototypes, &kept_prototypes);
+    }
+}
+
+impl ASTVisitor<()> for StorageLowering {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) { }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) { }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) { }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) { }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        StorageLowering::drop_declarations(program);
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+        program.var_decls.append(&mut self.hoisted);
+    }
+
+    fn visit_struct_type_decl(&mut self, _struct_type_decl: &mut StructTypeDecl) { }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) { }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        if let Some(init) = &mut var_decl.init {
+            for expr in init.exprs_mut() {
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.fun_name = fun_decl.name.to_owned();
+        self.renamed.clear();
+        fun_decl.block.accept(self);
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        for var_decl in block.var_decls.iter().filter(|var_decl| var_decl.storage == StorageClass::Static) {
+            let name = self.global_name(&var_decl.var_name);
+            self.renamed.push((var_decl.var_name.to_owned(), var_decl.position, name.to_owned()));
+            let mut global = var_decl.clone();
+            global.var_name = name;
+            self.hoisted.push(global);
+        }
+        block.var_decls.retain(|var_decl| var_decl.storage != StorageClass::Static);
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.visit_expr(&mut a_while.expr);
+        self.visit_stmt(&mut a_while.stmt);
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.visit_expr(&mut a_do_while.expr);
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.visit_stmt(&mut a_for.stmt);
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) { }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) { }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.visit_expr(&mut a_switch.expr);
+        for case in a_switch.cases.iter_mut() {
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, _int_literal: &mut IntLiteral) { }
+
+    fn visit_chr_literal(&mut self, _chr_literal: &mut ChrLiteral) { }
+
+    fn visit_str_literal(&mut self, _str_literal: &mut StrLiteral) { }
+
+    // the declaration a use was resolved to tells a static local from a variable of the same name
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        let var_decl = &var_expr.var_decl;
+        if let Some((_, _, name)) = self.renamed.iter().find(|(var_name, position, _)| *var_name == var_decl.var_name && *position == var_decl.position) {
+            var_expr.name = name.to_owned();
+            var_expr.var_decl.var_name = name.to_owned();
+        }
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.visit_expr(&mut typecast_expr.expr);
+    }
+
+    fn visit_size_of_expr(&mut self, _size_of_expr: &mut SizeOfExpr) { }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.visit_expr(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.visit_expr(&mut field_access_expr.expr);
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) { }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.visit_expr(&mut method_call_expr.expr);
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_fun_addr_expr(&mut self, _fun_addr_expr: &mut FunAddrExpr) { }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.visit_expr(&mut assign_expr.expr1);
+        self.visit_expr(&mut assign_expr.expr2);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.visit_expr(&mut inc_dec_expr.expr);
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.visit_expr(&mut conditional_expr.expr2);
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) { }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+    }
+}
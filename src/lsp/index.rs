@@ -0,0 +1,600 @@
+use std::collections::HashMap;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Item, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{declaration, ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::lexer::position::Position;
+use crate::ASTNode;
+
+/// Occurrence
+/// A name written in the source: a declaration or a use of a variable, a function, a struct type, a
+/// class, a field or a method, with the position of its declaration when the program declares it.
+pub struct Occurrence {
+    pub position: Position,
+    pub name: String,
+    pub definition: Option<Position>,
+    pub detail: String,
+}
+
+impl Occurrence {
+    pub fn contains(&self, position: Position) -> bool {
+        position.line() == self.position.line()
+            && position.column() >= self.position.column()
+            && position.column() < self.position.column() + self.name.len() as i32
+    }
+}
+
+/// SymbolIndex
+/// Records the occurrences of the names of an analysed program, answering the queries of the
+/// language server. Uses whose declaration was not resolved are skipped.
+pub struct SymbolIndex {
+    pub occurrences: Vec<Occurrence>,
+    struct_type_decls: HashMap<String, StructTypeDecl>,
+    class_decls: HashMap<String, ClassDecl>,
+    // variables with the function declaring them, none for the globals
+    var_decls: Vec<(VarDecl, Option<String>)>,
+    fun_decls: Vec<(Position, String)>,
+    function: Option<String>,
+}
+
+pub fn signature(fun_decl: &FunDecl) -> String {
+    let params: Vec<String> = fun_decl.params.iter().map(|param| param.declaration().trim_end().to_owned()).collect();
+    format!("{} {}({})", fun_decl.fun_type, fun_decl.name, params.join(", "))
+}
+
+// the position of a node built by the compiler, such as a stdlib function
+fn known(position: Position) -> Option<Position> {
+    if position == Position::default() { None } else { Some(position) }
+}
+
+// the length of the `.` or `->` ending `chars[..end]`, 0 if there is none
+fn separator(chars: &[char], end: usize) -> usize {
+    if end > 0 && chars[end - 1] == '.' {
+        1
+    } else if end > 1 && chars[end - 2] == '-' && chars[end - 1] == '>' {
+        2
+    } else {
+        0
+    }
+}
+
+impl SymbolIndex {
+    pub fn new(program: &mut Program) -> Self {
+        let mut index = SymbolIndex {
+            occurrences: Vec::new(),
+            struct_type_decls: HashMap::new(),
+            class_decls: HashMap::new(),
+            var_decls: Vec::new(),
+            fun_decls: Vec::new(),
+            function: None,
+        };
+        program.accept(&mut index);
+        index
+    }
+
+    pub fn find(&self, position: Position) -> Option<&Occurrence> {
+        self.occurrences.iter().find(|occurrence| occurrence.contains(position))
+    }
+
+    fn add(&mut self, position: Position, name: &str, definition: Option<Position>, detail: String) {
+        if known(position).is_some() && !name.is_empty() {
+            self.occurrences.push(Occurrence { position, name: name.to_owned(), definition, detail });
+        }
+    }
+
+    // the fields of a struct, or of a class and its ancestors
+    fn fields(&self, a_type: &dyn Type) -> Vec<&VarDecl> {
+        if let Some(struct_type) = a_type.as_struct() {
+            return self.struct_type_decls.get(&struct_type.name).map_or(vec![], |decl| decl.var_decls.iter().collect());
+        }
+        let mut fields = Vec::new();
+        let mut current = a_type.as_class().and_then(|class_type| self.class_decls.get(&class_type.name));
+        while let Some(class_decl) = current {
+            fields.extend(class_decl.var_decls.iter());
+            current = class_decl.parent.as_ref().and_then(|parent| self.class_decls.get(&parent.name));
+        }
+        fields
+    }
+
+    fn field(&self, a_type: &dyn Type, name: &str) -> Option<&VarDecl> {
+        self.fields(a_type).into_iter().find(|field| field.var_name == name)
+    }
+
+    // the variable a name refers to at a position: the last one declared before it in the enclosing
+    // function or among the globals, which is right as long as blocks do not shadow each other
+    fn variable(&self, name: &str, position: Position) -> Option<&VarDecl> {
+        let function = self.fun_decls.iter().rev()
+            .find(|(p, _)| (p.line(), p.column()) <= (position.line(), position.column()))
+            .map(|(_, name)| name);
+        self.var_decls.iter().rev()
+            .filter(|(var_decl, f)| var_decl.var_name == name && (f.is_none() || f.as_ref() == function))
+            .find(|(var_decl, _)| (var_decl.position.line(), var_decl.position.column()) < (position.line(), position.column()))
+            .map(|(var_decl, _)| var_decl)
+    }
+
+    /// The fields of the struct or the object accessed by the `.` or `->` ending `line`, a prefix of a source line,
+    /// found from the text since the line being edited rarely parses: `p.`, `a[i].q.` or `l->head->` for instance.
+    pub fn field_completions(&self, line: &str, position: Position) -> Vec<&VarDecl> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut end = chars.len();
+        // the beginning of the field name being typed
+        while end > 0 && (chars[end - 1].is_alphanumeric() || chars[end - 1] == '_') {
+            end -= 1;
+        }
+        let mut length = separator(&chars, end);
+        if length == 0 {
+            return vec![];
+        }
+
+        // walks back the chain of accesses, innermost last, -> dereferencing its pointer
+        let mut accesses = Vec::new();
+        loop {
+            if length == 2 {
+                accesses.push(None);
+            }
+            end -= length;
+            while end > 0 && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            while end > 0 && chars[end - 1] == ']' {
+                let mut depth = 0;
+                loop {
+                    if end == 0 {
+                        return vec![];
+                    }
+                    end -= 1;
+                    match chars[end] {
+                        ']' => depth += 1,
+                        '[' => depth -= 1,
+                        _ => {},
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                accesses.push(None);
+            }
+            let name_end = end;
+            while end > 0 && (chars[end - 1].is_alphanumeric() || chars[end - 1] == '_') {
+                end -= 1;
+            }
+            if end == name_end {
+                return vec![];
+            }
+            accesses.push(Some(chars[end..name_end].iter().collect::<String>()));
+            let mut before = end;
+            while before > 0 && chars[before - 1].is_whitespace() {
+                before -= 1;
+            }
+            length = separator(&chars, before);
+            if length == 0 {
+                break;
+            }
+            end = before;
+        }
+
+        let variable = match accesses.pop() {
+            Some(Some(name)) => name,
+            _ => return vec![],
+        };
+        let mut a_type = match self.variable(&variable, position) {
+            Some(var_decl) => var_decl.var_type.clone(),
+            None => return vec![],
+        };
+        while let Some(access) = accesses.pop() {
+            let next = match access {
+                None => match (a_type.as_array(), a_type.as_pointer()) {
+                    (Some(array_type), _) => Some(array_type.array_type.clone()),
+                    (_, Some(pointer_type)) => Some(pointer_type.pointer_type.clone()),
+                    _ => None,
+                },
+                Some(name) => self.field(a_type.as_ref(), &name).map(|field| field.var_type.clone()),
+            };
+            match next {
+                Some(next) => a_type = next,
+                None => return vec![],
+            }
+        }
+        self.fields(a_type.as_ref())
+    }
+}
+
+impl ASTVisitor<()> for SymbolIndex {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) {
+        self.visit_type(&mut pointer_type.pointer_type);
+    }
+
+    fn visit_struct_type(&mut self, struct_type: &mut StructType) {
+        let definition = self.struct_type_decls.get(&struct_type.name).and_then(|decl| known(decl.struct_type.position));
+        self.add(struct_type.position, &struct_type.name, definition, format!("struct {}", struct_type.name));
+    }
+
+    fn visit_array_type(&mut self, array_type: &mut ArrayType) {
+        self.visit_type(&mut array_type.array_type);
+    }
+
+    fn visit_class_type(&mut self, class_type: &mut ClassType) {
+        let definition = self.class_decls.get(&class_type.name).and_then(|decl| known(decl.class_type.position));
+        self.add(class_type.position, &class_type.name, definition, format!("class {}", class_type.name));
+    }
+
+    fn visit_fun_type(&mut self, fun_type: &mut FunType) {
+        self.visit_type(&mut fun_type.return_type);
+        for param_type in fun_type.param_types.iter_mut() {
+            self.visit_type(param_type);
+        }
+    }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        // struct types may be used before their declaration, by pointers
+        for struct_type_decl in program.struct_type_decls.iter() {
+            self.struct_type_decls.insert(struct_type_decl.struct_type.name.to_owned(), struct_type_decl.clone());
+        }
+        // and classes before theirs
+        for class_decl in program.class_decls.iter() {
+            self.class_decls.insert(class_decl.class_type.name.to_owned(), class_decl.clone());
+        }
+        // in source order, which finding the function enclosing a position relies on
+        for item in program.ordered_items() {
+            match item {
+                Item::Typedef(i) => {
+                    let typedef_decl = &mut program.typedef_decls[i];
+                    self.visit_type(&mut typedef_decl.typedef_type);
+                    let detail = format!("typedef {}", declaration(typedef_decl.typedef_type.as_ref(), &typedef_decl.name).trim_end());
+                    self.add(typedef_decl.position, &typedef_decl.name, known(typedef_decl.position), detail);
+                },
+                Item::Enum(i) => {
+                    let enum_decl = &mut program.enum_decls[i];
+                    self.add(enum_decl.position, &enum_decl.name, known(enum_decl.position), format!("enum {}", enum_decl.name));
+                    for enumerator in enum_decl.enumerators.iter_mut() {
+                        self.add(enumerator.position, &enumerator.name, known(enumerator.position), format!("enum {}", enum_decl.name).trim_end().to_owned());
+                        if let Some(expr) = &mut enumerator.expr {
+                            self.visit_expr(expr);
+                        }
+                    }
+                },
+                Item::Struct(i) => program.struct_type_decls[i].accept(self),
+                Item::Class(i) => program.class_decls[i].accept(self),
+                Item::Var(i) => program.var_decls[i].accept(self),
+                Item::Prototype(i) => {
+                    let prototype = &mut program.prototypes[i];
+                    self.visit_type(&mut prototype.fun_type);
+                    for param in prototype.params.iter_mut() {
+                        self.visit_type(&mut param.var_type);
+                    }
+                    self.add(prototype.position, &prototype.name, known(prototype.position), signature(prototype));
+                },
+                Item::Fun(i) => program.fun_decls[i].accept(self),
+            }
+        }
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) {
+        let struct_type = &struct_type_decl.struct_type;
+        self.add(struct_type.position, &struct_type.name.clone(), known(struct_type.position), format!("struct {}", struct_type.name));
+        for var_decl in struct_type_decl.var_decls.iter_mut() {
+            self.visit_type(&mut var_decl.var_type);
+            self.add(var_decl.position, &var_decl.var_name, known(var_decl.position), var_decl.declaration());
+        }
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &mut ClassDecl) {
+        class_decl.class_type.accept(self);
+        if let Some(parent) = &mut class_decl.parent {
+            parent.accept(self);
+        }
+        for var_decl in class_decl.var_decls.iter_mut() {
+            self.visit_type(&mut var_decl.var_type);
+            self.add(var_decl.position, &var_decl.var_name, known(var_decl.position), var_decl.declaration());
+        }
+        for fun_decl in class_decl.fun_decls.iter_mut() {
+            self.visit_type(&mut fun_decl.fun_type);
+            self.add(fun_decl.position, &fun_decl.name, known(fun_decl.position), signature(fun_decl));
+            // the variables of a method are told apart from the ones of a function with the same name
+            let function = format!("{}.{}", class_decl.class_type.name, fun_decl.name);
+            self.fun_decls.push((fun_decl.position, function.to_owned()));
+
+            self.function = Some(function);
+            for param in fun_decl.params.iter_mut() {
+                param.accept(self);
+            }
+            fun_decl.block.accept(self);
+            self.function = None;
+        }
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.visit_type(&mut var_decl.var_type);
+        self.add(var_decl.position, &var_decl.var_name, known(var_decl.position), var_decl.declaration());
+        self.var_decls.push((var_decl.clone(), self.function.clone()));
+        if let Some(init) = &mut var_decl.init {
+            for expr in init.exprs_mut() {
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.visit_type(&mut fun_decl.fun_type);
+        self.add(fun_decl.position, &fun_decl.name, known(fun_decl.position), signature(fun_decl));
+        self.fun_decls.push((fun_decl.position, fun_decl.name.to_owned()));
+
+        self.function = Some(fun_decl.name.to_owned());
+        for param in fun_decl.params.iter_mut() {
+            param.accept(self);
+        }
+        fun_decl.block.accept(self);
+        self.function = None;
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.visit_expr(&mut a_while.expr);
+        self.visit_stmt(&mut a_while.stmt);
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.visit_expr(&mut a_do_while.expr);
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.visit_stmt(&mut a_for.stmt);
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) { }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) { }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.visit_expr(&mut a_switch.expr);
+        for case in a_switch.cases.iter_mut() {
+            if let Some(expr) = &mut case.expr {
+                self.visit_expr(expr);
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, _int_literal: &mut IntLiteral) { }
+
+    fn visit_chr_literal(&mut self, _chr_literal: &mut ChrLiteral) { }
+
+    fn visit_str_literal(&mut self, _str_literal: &mut StrLiteral) { }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        let var_decl = &var_expr.var_decl;
+        if var_decl.var_name.is_empty() {
+            return;
+        }
+        let detail = var_decl.declaration();
+        self.add(var_expr.position, &var_expr.name, known(var_decl.position), detail);
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.visit_type(&mut typecast_expr.typecast_type);
+        self.visit_expr(&mut typecast_expr.expr);
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        self.visit_type(&mut size_of_expr.sizeof_type);
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.visit_expr(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.visit_expr(&mut field_access_expr.expr);
+        let field = self.field(field_access_expr.expr.get_expr_type(), &field_access_expr.name)
+            .map(|field| (known(field.position), field.declaration()));
+        if let Some((definition, detail)) = field {
+            self.add(field_access_expr.position, &field_access_expr.name, definition, detail);
+        }
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+        let fun_decl = &fun_call_expr.fun_decl;
+        if fun_decl.name.is_empty() {
+            return;
+        }
+        let (definition, detail) = (known(fun_decl.position), signature(fun_decl));
+        self.add(fun_call_expr.position, &fun_call_expr.name, definition, detail);
+    }
+
+    fn visit_new_expr(&mut self, new_expr: &mut NewExpr) {
+        new_expr.class_type.accept(self);
+    }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.visit_expr(&mut method_call_expr.expr);
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+        let fun_decl = &method_call_expr.fun_decl;
+        if fun_decl.name.is_empty() {
+            return;
+        }
+        let (definition, detail) = (known(fun_decl.position), signature(fun_decl));
+        self.add(method_call_expr.position, &method_call_expr.name, definition, detail);
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        let fun_decl = &fun_addr_expr.fun_decl;
+        if fun_decl.name.is_empty() {
+            return;
+        }
+        let (definition, detail) = (known(fun_decl.position), signature(fun_decl));
+        self.add(fun_addr_expr.position, &fun_addr_expr.name, definition, detail);
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.visit_expr(&mut assign_expr.expr1);
+        self.visit_expr(&mut assign_expr.expr2);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.visit_expr(&mut inc_dec_expr.expr);
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.visit_expr(&mut conditional_expr.expr2);
+    }
+
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) {
+        if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<BaseType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<PointerType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<StructType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ArrayType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ClassType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<FunType>() {
+            cast_type.accept(self);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+    }
+}
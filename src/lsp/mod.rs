@@ -0,0 +1,3 @@
+pub mod json;
+pub mod index;
+pub mod server;
@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::panic::{catch_unwind, set_hook, AssertUnwindSafe};
+use std::sync::Mutex;
+use crate::ast::decl::Program;
+use crate::lexer::position::{Diagnostic, Position};
+use crate::lexer::scanner::Scanner;
+use crate::lexer::tokenizer::Tokenizer;
+use crate::lsp::index::{signature, SymbolIndex};
+use crate::lsp::json::Json;
+use crate::parser::parser::Parser;
+use crate::sem::semantic_analyzer::SemanticAnalyzer;
+
+// error codes of json-rpc
+const PARSE_ERROR: i32 = -32700;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+// kinds of the protocol
+const SEVERITY_ERROR: i32 = 1;
+const MESSAGE_ERROR: i32 = 1;
+const SYMBOL_FIELD: i32 = 8;
+const SYMBOL_FUNCTION: i32 = 12;
+const SYMBOL_STRUCT: i32 = 23;
+const COMPLETION_FIELD: i32 = 5;
+
+struct Document {
+    text: String,
+    // from the last version which parsed, kept while the document is being edited, or a partially
+    // parsed version until one parses
+    program: Option<Program>,
+    index: Option<SymbolIndex>,
+    parsed: bool,
+}
+
+/// LanguageServer
+/// Speaks the language server protocol over the standard input and output: publishes the
+/// diagnostics of the open documents, and answers go to definition, hover, document symbol and
+/// field completion requests. Documents are synchronised in full on every change.
+pub struct LanguageServer {
+    documents: HashMap<String, Document>,
+    // the panics of the front end already logged to the client
+    panics: HashSet<String>,
+    shutdown: bool,
+}
+
+// the last panic of the front end, recorded by the hook of the server instead of being printed
+static PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+fn take_panic() -> String {
+    PANIC.lock().unwrap().take().unwrap_or_else(|| "panicked".to_owned())
+}
+
+// the text of a line of a document, lines counting from 0
+fn line_text(text: &str, line: i32) -> &str {
+    text.lines().nth(line.max(0) as usize).unwrap_or("")
+}
+
+// converts a column of a line between the utf-16 code units the protocol counts and the bytes the
+// scanner counts, both from 0, stopping at the end of the line
+fn convert_column(line: &str, column: usize, from_utf16: bool) -> usize {
+    let (mut bytes, mut units) = (0, 0);
+    for c in line.chars() {
+        if (if from_utf16 { units } else { bytes }) >= column {
+            break;
+        }
+        bytes += c.len_utf8();
+        units += c.len_utf16();
+    }
+    if from_utf16 { bytes } else { units }
+}
+
+// lines and characters count from 0 in the protocol; the length is in bytes
+fn range(text: &str, position: Position, length: usize) -> Json {
+    let line = position.line() - 1;
+    let column = (position.column() - 1).max(0) as usize;
+    let start = convert_column(line_text(text, line), column, false);
+    let end = convert_column(line_text(text, line), column + length.max(1), false).max(start + 1);
+    Json::object(vec![
+        ("start", Json::object(vec![("line", line.into()), ("character", (start as i32).into())])),
+        ("end", Json::object(vec![("line", line.into()), ("character", (end as i32).into())])),
+    ])
+}
+
+fn position_param(text: &str, params: &Json) -> Option<Position> {
+    let position = params.get("position");
+    let line = position.get("line").as_i64()? as i32;
+    let character = position.get("character").as_i64()?.max(0) as usize;
+    let column = convert_column(line_text(text, line), character, true);
+    Some(Position::new(line + 1, column as i32 + 1))
+}
+
+fn uri_param(params: &Json) -> String {
+    params.get("textDocument").get("uri").as_str().unwrap_or("").to_owned()
+}
+
+// the length in bytes of the word starting at a position, to underline a diagnostic
+fn word_length(text: &str, position: Position) -> usize {
+    let line = line_text(text, position.line() - 1);
+    line.bytes().skip((position.column() - 1).max(0) as usize)
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == b'_')
+        .count()
+}
+
+struct Analysis {
+    diagnostics: Vec<Diagnostic>,
+    analyzed: Option<(Program, SymbolIndex)>,
+    // false for a document which does not parse, analysed as far as it was parsed for a best-effort
+    // index whose semantic errors are not reported
+    parsed: bool,
+    // a panic of the front end, caught to be logged
+    panic: Option<String>,
+}
+
+// parses and analyses a document without printing anything, stdout being the channel to the client
+fn analyze(text: &str) -> Analysis {
+    let internal_error = || vec![Diagnostic::new(Position::new(1, 1), "internal compiler error, see the log".to_owned())];
+    let mut parser = Parser::new(Tokenizer::new(Scanner::from_string(text)).quiet()).quiet();
+    let result = catch_unwind(AssertUnwindSafe(|| parser.parse()));
+    let mut diagnostics = parser.get_diagnostics();
+    let mut program = match result {
+        Ok(program) => program,
+        Err(_) => return Analysis { diagnostics: internal_error(), analyzed: None, parsed: false, panic: Some(take_panic()) },
+    };
+    let parsed = diagnostics.is_empty();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut sem = SemanticAnalyzer::new().quiet();
+        sem.analyze(&mut program);
+        let index = SymbolIndex::new(&mut program);
+        (sem.get_diagnostics().to_vec(), index)
+    }));
+    match result {
+        Ok((errors, index)) => {
+            if parsed {
+                diagnostics.extend(errors);
+            }
+            Analysis { diagnostics, analyzed: Some((program, index)), parsed, panic: None }
+        },
+        Err(_) if parsed => Analysis { diagnostics: internal_error(), analyzed: None, parsed, panic: Some(take_panic()) },
+        Err(_) => Analysis { diagnostics, analyzed: None, parsed, panic: Some(take_panic()) },
+    }
+}
+
+impl LanguageServer {
+    pub fn new() -> Self {
+        LanguageServer {
+            documents: HashMap::new(),
+            panics: HashSet::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Serves requests until the client asks to exit, returning the exit code.
+    pub fn run(&mut self, input: &mut dyn BufRead, output: &mut dyn Write) -> i32 {
+        set_hook(Box::new(|info| *PANIC.lock().unwrap() = Some(info.to_string())));
+        loop {
+            let body = match read_message(input) {
+                Some(body) => body,
+                None => return 1,
+            };
+            let message = match Json::parse(&body) {
+                Some(message) => message,
+                None => {
+                    let error = Json::object(vec![("code", PARSE_ERROR.into()), ("message", "invalid json".into())]);
+                    send(output, Json::object(vec![("jsonrpc", "2.0".into()), ("id", Json::Null), ("error", error)]));
+                    continue;
+                },
+            };
+
+            let method = message.get("method").as_str().unwrap_or("").to_owned();
+            let params = message.get("params");
+            let id = message.get("id");
+            if method == "exit" {
+                return if self.shutdown { 0 } else { 1 };
+            }
+
+            let result = match method.as_str() {
+                "initialize" => Some(self.initialize()),
+                "shutdown" => {
+                    self.shutdown = true;
+                    Some(Json::Null)
+                },
+                "textDocument/didOpen" => {
+                    let document = params.get("textDocument");
+                    let text = document.get("text").as_str().unwrap_or("").to_owned();
+                    self.update(output, uri_param(params), text);
+                    None
+                },
+                "textDocument/didChange" => {
+                    // full synchronisation, the last change holds the whole text
+                    let text = params.get("contentChanges").as_array()
+                        .and_then(|changes| changes.last())
+                        .and_then(|change| change.get("text").as_str())
+                        .map(|text| text.to_owned());
+                    if let Some(text) = text {
+                        self.update(output, uri_param(params), text);
+                    }
+                    None
+                },
+                "textDocument/didClose" => {
+                    let uri = uri_param(params);
+                    self.documents.remove(&uri);
+                    publish(output, &uri, Vec::new());
+                    None
+                },
+                "textDocument/definition" => Some(self.definition(params)),
+                "textDocument/hover" => Some(self.hover(params)),
+                "textDocument/documentSymbol" => Some(self.document_symbols(params)),
+                "textDocument/completion" => Some(self.completion(params)),
+                _ => None,
+            };
+
+            // notifications have no id and get no response
+            if *id == Json::Null {
+                continue;
+            }
+            let response = match result {
+                Some(result) => Json::object(vec![("jsonrpc", "2.0".into()), ("id", id.clone()), ("result", result)]),
+                None => {
+                    let error = Json::object(vec![("code", METHOD_NOT_FOUND.into()), ("message", format!("unknown method {}", method).into())]);
+                    Json::object(vec![("jsonrpc", "2.0".into()), ("id", id.clone()), ("error", error)])
+                },
+            };
+            send(output, response);
+        }
+    }
+
+    fn initialize(&self) -> Json {
+        let capabilities = Json::object(vec![
+            ("textDocumentSync", 1.into()),
+            ("definitionProvider", true.into()),
+            ("hoverProvider", true.into()),
+            ("documentSymbolProvider", true.into()),
+            ("completionProvider", Json::object(vec![("triggerCharacters", vec![".".into(), ">".into()].into())])),
+        ]);
+        Json::object(vec![
+            ("capabilities", capabilities),
+            ("serverInfo", Json::object(vec![("name", "mini-compiler".into())])),
+        ])
+    }
+
+    fn update(&mut self, output: &mut dyn Write, uri: String, text: String) {
+        let Analysis { diagnostics, analyzed, parsed, panic } = analyze(&text);
+        if let Some(panic) = panic {
+            // once per panic rather than on every change of the document
+            if self.panics.insert(panic.clone()) {
+                let params = Json::object(vec![("type", MESSAGE_ERROR.into()), ("message", format!("internal compiler error: {}", panic).into())]);
+                send(output, Json::object(vec![("jsonrpc", "2.0".into()), ("method", "window/logMessage".into()), ("params", params)]));
+            }
+        }
+        let diagnostics = diagnostics.iter().map(|diagnostic| Json::object(vec![
+            ("range", range(&text, diagnostic.position, word_length(&text, diagnostic.position))),
+            ("severity", SEVERITY_ERROR.into()),
+            ("source", "minic".into()),
+            ("message", diagnostic.message.to_owned().into()),
+        ])).collect();
+        publish(output, &uri, diagnostics);
+
+        let document = self.documents.entry(uri).or_insert(Document { text: String::new(), program: None, index: None, parsed: false });
+        document.text = text;
+        if let (Some((program, index)), true) = (analyzed, parsed || !document.parsed) {
+            document.index = Some(index);
+            document.program = Some(program);
+            document.parsed = parsed;
+        }
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let uri = uri_param(params);
+        let document = match self.documents.get(&uri) {
+            Some(document) => document,
+            None => return Json::Null,
+        };
+        let occurrence = document.index.as_ref()
+            .zip(position_param(&document.text, params))
+            .and_then(|(index, position)| index.find(position));
+        match occurrence {
+            Some(occurrence) => match occurrence.definition {
+                Some(definition) => Json::object(vec![("uri", uri.into()), ("range", range(&document.text, definition, occurrence.name.len()))]),
+                None => Json::Null,
+            },
+            None => Json::Null,
+        }
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let document = match self.documents.get(&uri_param(params)) {
+            Some(document) => document,
+            None => return Json::Null,
+        };
+        let occurrence = document.index.as_ref()
+            .zip(position_param(&document.text, params))
+            .and_then(|(index, position)| index.find(position));
+        match occurrence {
+            Some(occurrence) => Json::object(vec![
+                ("contents", Json::object(vec![("kind", "markdown".into()), ("value", format!("```c\n{}\n```", occurrence.detail).into())])),
+                ("range", range(&document.text, occurrence.position, occurrence.name.len())),
+            ]),
+            None => Json::Null,
+        }
+    }
+
+    fn document_symbols(&self, params: &Json) -> Json {
+        let (text, program) = match self.documents.get(&uri_param(params)) {
+            Some(Document { text, program: Some(program), .. }) => (text, program),
+            _ => return Json::Array(vec![]),
+        };
+        let symbol = |name: &str, detail: String, kind: i32, position: Position, children: Vec<Json>| {
+            let range = range(text, position, name.len());
+            Json::object(vec![
+                ("name", name.into()),
+                ("detail", detail.into()),
+                ("kind", kind.into()),
+                ("range", range.clone()),
+                ("selectionRange", range),
+                ("children", children.into()),
+            ])
+        };
+
+        let mut symbols = Vec::new();
+        for struct_type_decl in program.struct_type_decls.iter() {
+            let fields = struct_type_decl.var_decls.iter()
+                .map(|field| symbol(&field.var_name, field.var_type.to_string(), SYMBOL_FIELD, field.position, vec![]))
+                .collect();
+            let struct_type = &struct_type_decl.struct_type;
+            symbols.push(symbol(&struct_type.name, format!("struct {}", struct_type.name), SYMBOL_STRUCT, struct_type.position, fields));
+        }
+        for fun_decl in program.fun_decls.iter() {
+            symbols.push(symbol(&fun_decl.name, signature(fun_decl), SYMBOL_FUNCTION, fun_decl.position, vec![]));
+        }
+        Json::Array(symbols)
+    }
+
+    fn completion(&self, params: &Json) -> Json {
+        let document = match self.documents.get(&uri_param(params)) {
+            Some(document) => document,
+            None => return Json::Array(vec![]),
+        };
+        let position = match position_param(&document.text, params) {
+            Some(position) => position,
+            None => return Json::Array(vec![]),
+        };
+        let index = match &document.index {
+            Some(index) => index,
+            None => return Json::Array(vec![]),
+        };
+        let line = line_text(&document.text, position.line() - 1);
+        let prefix = &line[..position.column() as usize - 1];
+        let items = index.field_completions(prefix, position).iter().map(|field| Json::object(vec![
+            ("label", field.var_name.to_owned().into()),
+            ("kind", COMPLETION_FIELD.into()),
+            ("detail", field.var_type.to_string().into()),
+        ])).collect();
+        Json::Array(items)
+    }
+}
+
+fn publish(output: &mut dyn Write, uri: &str, diagnostics: Vec<Json>) {
+    let params = Json::object(vec![("uri", uri.into()), ("diagnostics", diagnostics.into())]);
+    send(output, Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        ("params", params),
+    ]));
+}
+
+fn send(output: &mut dyn Write, message: Json) {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    output.flush().unwrap();
+}
+
+// reads the body of the next message, none at the end of the input
+fn read_message(input: &mut dyn BufRead) -> Option<String> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    Some(String::from_utf8_lossy(&body).into_owned())
+}
@@ -1,65 +1,244 @@
 #![allow(dead_code)]
 use std::env;
 use std::fs::File;
-use std::io::{BufWriter, stdout, Write};
+use std::io::{stdin, BufWriter, stdout, Write};
 use lexer::token::TokenClass;
 use crate::ast::ast_node::ASTNode;
 use crate::ast::ast_printer::ASTPrinter;
+use crate::ast::unparser::Unparser;
+use crate::ast::decl::{Program, StorageClass};
+use crate::cgen::c_emitter::CEmitter;
+use crate::fmt::formatter::SourceFormatter;
+use crate::gen::code_generator::{CodeGenerator, Target};
+use crate::llvm::llvm_generator::LlvmGenerator;
+use crate::lower::class_lowering::ClassLowering;
+use crate::lower::storage_lowering::StorageLowering;
+use crate::lsp::server::LanguageServer;
+use crate::repl::repl::Repl;
+use crate::opt::inliner::Inliner;
+use crate::sem::semantic_analyzer::SemanticAnalyzer;
 
 use crate::lexer::scanner::Scanner;
 use crate::lexer::tokenizer::Tokenizer;
 use crate::parser::parser::Parser;
+use crate::vm::bytecode::Module;
+use crate::vm::compiler::BytecodeCompiler;
+use crate::vm::vm::VM;
+use crate::wasm::wat_generator::WatGenerator;
 
 mod lexer;
 mod parser;
 mod ast;
+mod opt;
+mod lower;
+mod sem;
+mod gen;
+mod vm;
+mod wasm;
+mod cgen;
+mod llvm;
+mod fmt;
+mod lsp;
+mod repl;
 
 static FILE_NOT_FOUND: i32 = 2;
 static MODE_FAIL: i32      = 254;
 static LEXER_FAIL: i32     = 250;
 static PARSER_FAIL: i32    = 245;
 static SEM_FAIL: i32       = 240;
+static RUNTIME_FAIL: i32   = 235;
+static LINK_FAIL: i32      = 230;
+static FORMAT_FAIL: i32    = 1;
 static PASS: i32           = 0;
 
+#[allow(clippy::upper_case_acronyms)]
 enum Mode {
-    LEXER, PARSER, AST, SEMANTICANALYSIS, GEN
+    LEXER, PARSER, AST, UNPARSE, INLINE, SEMANTICANALYSIS, GEN, RISCV, BYTECODE, VM, DISASSEMBLE, WAT, EMITC, LLVM
 }
 
 fn usage() -> ! {
-    println!("Usage: rustc main.rs pass inputfile (outputfile)");
-    println!("where pass is either: -lexer, -parser, -ast, -sem or -gen");
+    println!("Usage: rustc main.rs pass (-O) inputfile (outputfile)");
+    println!("   or: rustc main.rs fmt (--check) inputfile...");
+    println!("   or: rustc main.rs lsp");
+    println!("   or: rustc main.rs repl");
+    println!("where pass is either: -lexer, -parser, -ast, -unparse, -inline, -sem, -gen, -riscv, -bc, -vm, -dis, -wat, -emit-c or -llvm,");
+    println!("and -O inlines the small functions before generating code");
     std::process::exit(-1);
 }
 
+// parses and checks the program, exiting if it is not valid
+fn parse_and_check(tokenizer: Tokenizer) -> Program {
+    let mut parser = Parser::new(tokenizer);
+    let mut program_ast = parser.parse();
+
+    if parser.get_error_count() != 0 {
+        println!("Parsing: failed ({} errors)", parser.get_error_count());
+        std::process::exit(PARSER_FAIL)
+    }
+    let mut sem = SemanticAnalyzer::new();
+    sem.analyze(&mut program_ast);
+    if sem.get_error_count() != 0 {
+        println!("Semantic analysis: failed ({} errors)", sem.get_error_count());
+        std::process::exit(SEM_FAIL)
+    }
+    program_ast
+}
+
+// parses and checks the program, exiting if it is not valid, inlines the small functions with -O and
+// lowers it for the code generators
+fn parse_and_analyze(tokenizer: Tokenizer, optimize: bool) -> Program {
+    let mut program_ast = parse_and_check(tokenizer);
+    if optimize {
+        Inliner::new().run(&mut program_ast);
+        // the copied bodies and the variables binding the arguments are analysed like the rest
+        let mut sem = SemanticAnalyzer::new().allow_reserved();
+        sem.analyze(&mut program_ast);
+        assert_eq!(sem.get_error_count(), 0, "the inlined program must be well typed");
+    }
+    ClassLowering::run(&mut program_ast);
+    StorageLowering::run(&mut program_ast);
+    program_ast
+}
+
+// exits if the program uses a global or a function declared extern, which the targets that cannot be
+// linked with other files have no definition of
+fn check_linked(program: &Program) {
+    let undefined: Vec<&str> = program.var_decls.iter()
+        .filter(|var_decl| var_decl.storage == StorageClass::Extern)
+        .map(|var_decl| var_decl.var_name.as_str())
+        .chain(program.prototypes.iter().map(|prototype| prototype.name.as_str()))
+        .collect();
+    if !undefined.is_empty() {
+        for name in undefined.iter() {
+            println!("undefined reference to {}", name);
+        }
+        println!("Linking: failed ({} errors)", undefined.len());
+        std::process::exit(LINK_FAIL)
+    }
+}
+
+fn create_output(path: &str) -> BufWriter<File> {
+    match File::create(path) {
+        Ok(f) => BufWriter::new(f),
+        Err(_) => {
+            println!("File {:?} cannot be created.", path);
+            std::process::exit(FILE_NOT_FOUND);
+        },
+    }
+}
+
+fn read_module(path: &str) -> Module {
+    let mut file = File::open(path).unwrap();
+    match Module::read(&mut file) {
+        Ok(module) => module,
+        Err(e) => {
+            println!("Invalid bytecode file: {}", e);
+            std::process::exit(MODE_FAIL);
+        },
+    }
+}
+
+fn open_input(path: &str) -> File {
+    match File::open(path) {
+        Ok(f) => f,
+        Err(_) => {
+            println!("File {:?} does not exist.", path);
+            std::process::exit(FILE_NOT_FOUND);
+        },
+    }
+}
+
+// rewrites the files in the canonical style, or with --check only lists the ones which are not
+fn format_files(args: &[String]) -> ! {
+    let check = args.first().is_some_and(|a| a == "--check");
+    let paths = if check { &args[1..] } else { args };
+    if paths.is_empty() {
+        usage();
+    }
+
+    let mut unformatted = 0;
+    for path in paths {
+        let mut tokenizer = Tokenizer::new(Scanner::new(open_input(path))).keep_trivia();
+        let mut tokens = Vec::new();
+        loop {
+            let token = tokenizer.next_token();
+            let eof = token.token_class == TokenClass::EOF;
+            tokens.push(token);
+            if eof {
+                break;
+            }
+        }
+        if tokenizer.get_error_count() != 0 {
+            println!("Lexing {}: failed ({} errors)", path, tokenizer.get_error_count());
+            std::process::exit(LEXER_FAIL);
+        }
+        let mut parser = Parser::new(Tokenizer::new(Scanner::new(open_input(path))));
+        parser.parse();
+        if parser.get_error_count() != 0 {
+            println!("Parsing {}: failed ({} errors)", path, parser.get_error_count());
+            std::process::exit(PARSER_FAIL);
+        }
+
+        // the scanner reads bytes as chars, write them back as they were
+        let formatted: Vec<u8> = SourceFormatter::format(tokens).chars().map(|c| c as u8).collect();
+        if std::fs::read(path).is_ok_and(|source| source == formatted) {
+            continue;
+        }
+        if check {
+            println!("{} is not formatted", path);
+            unformatted += 1;
+        } else {
+            let mut writer = create_output(path);
+            writer.write_all(&formatted).unwrap();
+            writer.flush().unwrap();
+        }
+    }
+    std::process::exit(if unformatted == 0 { PASS } else { FORMAT_FAIL })
+}
+
 #[allow(dead_code)]
 fn main() {
     let args: Vec<String> = env::args().collect();
 
+    if args.len() >= 2 && args[1] == "fmt" {
+        format_files(&args[2..]);
+    }
+    if args.len() == 2 && args[1] == "lsp" {
+        let code = LanguageServer::new().run(&mut stdin().lock(), &mut stdout().lock());
+        std::process::exit(code);
+    }
+    if args.len() == 2 && args[1] == "repl" {
+        Repl::new().run(&mut stdin().lock());
+        std::process::exit(PASS);
+    }
+
+    // -O may come anywhere, the other arguments are positional
+    let optimize = args.iter().skip(1).any(|arg| arg == "-O");
+    let args: Vec<String> = args.into_iter().filter(|arg| arg != "-O").collect();
     if args.len() != 4 && args.len() != 3 {
         usage();
     }
 
-    let mode;
     let flag = &args[1];
-    match &flag[..] {
-        "-lexer"  => mode = Mode::LEXER,
-        "-parser" => mode = Mode::PARSER,
-        "-ast"    => mode = Mode::AST,
-        "-sem"    => mode = Mode::SEMANTICANALYSIS,
-        "-gen"    => mode = Mode::GEN,
+    let mode = match &flag[..] {
+        "-lexer"  => Mode::LEXER,
+        "-parser" => Mode::PARSER,
+        "-ast"    => Mode::AST,
+        "-unparse" => Mode::UNPARSE,
+        "-inline" => Mode::INLINE,
+        "-sem"    => Mode::SEMANTICANALYSIS,
+        "-gen"    => Mode::GEN,
+        "-riscv"  => Mode::RISCV,
+        "-bc"     => Mode::BYTECODE,
+        "-vm"     => Mode::VM,
+        "-dis"    => Mode::DISASSEMBLE,
+        "-wat"    => Mode::WAT,
+        "-emit-c" => Mode::EMITC,
+        "-llvm"   => Mode::LLVM,
         _          => usage()
-    }
+    };
 
-    let input_file;
-    match File::open(&args[2]) {
-        Ok(f) => input_file = f,
-        Err(_) => {
-            println!("File {:?} does not exist.", args[2]);
-            std::process::exit(FILE_NOT_FOUND);
-        },
-    }
-
-    let scanner = Scanner::new(input_file);
+    let scanner = Scanner::new(open_input(&args[2]));
     let mut tokenizer = Tokenizer::new(scanner);
 
     match mode {
@@ -93,7 +272,7 @@ fn main() {
             let mut stream = BufWriter::new(Box::new(stdout()));
 
             for i in 0..10 {
-                stream.write(&[i+1]).unwrap();
+                stream.write_all(&[i+1]).unwrap();
             }
             stream.flush().unwrap();
 
@@ -113,13 +292,116 @@ fn main() {
                 std::process::exit(PARSER_FAIL)
             }
         },
+        Mode::UNPARSE => {
+            let mut parser = Parser::new(tokenizer);
+            let mut program_ast = parser.parse();
+
+            if parser.get_error_count() == 0 {
+                program_ast.accept(&mut Unparser::new(&mut BufWriter::new(stdout())));
+                std::process::exit(PASS)
+            } else {
+                println!("Parsing: failed ({} errors)", parser.get_error_count());
+                std::process::exit(PARSER_FAIL)
+            }
+        },
+        Mode::INLINE => {
+            let mut program_ast = parse_and_check(tokenizer);
+            let mut inliner = Inliner::new();
+            inliner.run(&mut program_ast);
+
+            let writer = BufWriter::new(stdout());
+            program_ast.accept(&mut ASTPrinter::new(writer));
+            println!();
+            println!("Inlining: {} call(s) inlined", inliner.get_inlined_count());
+            std::process::exit(PASS)
+        },
         Mode::SEMANTICANALYSIS => {
-            println!("Semantic analysis not implemented");
-            std::process::exit(MODE_FAIL)
+            let mut parser = Parser::new(tokenizer);
+            let mut program_ast = parser.parse();
+
+            if parser.get_error_count() == 0 {
+                let mut sem = SemanticAnalyzer::new();
+                sem.analyze(&mut program_ast);
+                if sem.get_error_count() == 0 {
+                    println!("Semantic analysis: pass");
+                    std::process::exit(PASS)
+                } else {
+                    println!("Semantic analysis: failed ({} errors)", sem.get_error_count());
+                    std::process::exit(SEM_FAIL)
+                }
+            } else {
+                println!("Parsing: failed ({} errors)", parser.get_error_count());
+                std::process::exit(PARSER_FAIL)
+            }
+        },
+        Mode::GEN => {
+            if args.len() != 4 {
+                usage();
+            }
+            let mut program_ast = parse_and_analyze(tokenizer, optimize);
+            let mut writer = create_output(&args[3]);
+            CodeGenerator::new(Target::MIPS).emit_program(&mut program_ast, &mut writer);
+            std::process::exit(PASS)
+        },
+        Mode::RISCV => {
+            if args.len() != 4 {
+                usage();
+            }
+            let mut program_ast = parse_and_analyze(tokenizer, optimize);
+            let mut writer = create_output(&args[3]);
+            CodeGenerator::new(Target::RISCV).emit_program(&mut program_ast, &mut writer);
+            std::process::exit(PASS)
+        },
+        Mode::BYTECODE => {
+            if args.len() != 4 {
+                usage();
+            }
+            let mut program_ast = parse_and_analyze(tokenizer, optimize);
+            check_linked(&program_ast);
+            let module = BytecodeCompiler::compile(&mut program_ast);
+            module.write(&mut create_output(&args[3])).unwrap();
+            std::process::exit(PASS)
+        },
+        Mode::WAT => {
+            if args.len() != 4 {
+                usage();
+            }
+            let mut program_ast = parse_and_analyze(tokenizer, optimize);
+            check_linked(&program_ast);
+            WatGenerator::emit_program(&mut program_ast, &mut create_output(&args[3]));
+            std::process::exit(PASS)
+        },
+        Mode::EMITC => {
+            let mut program_ast = parse_and_analyze(tokenizer, optimize);
+            if args.len() == 4 {
+                program_ast.accept(&mut CEmitter::new(&mut create_output(&args[3])));
+            } else {
+                program_ast.accept(&mut CEmitter::new(&mut BufWriter::new(stdout())));
+            }
+            std::process::exit(PASS)
+        },
+        Mode::LLVM => {
+            if args.len() != 4 {
+                usage();
+            }
+            let mut program_ast = parse_and_analyze(tokenizer, optimize);
+            LlvmGenerator::emit_program(&mut program_ast, &mut create_output(&args[3]));
+            std::process::exit(PASS)
+        },
+        Mode::VM => {
+            let module = read_module(&args[2]);
+            let mut vm = VM::new(&module, Box::new(stdin().lock()), Box::new(BufWriter::new(stdout())));
+            if let Err(e) = vm.run() {
+                println!();
+                println!("Runtime error: {}", e);
+                std::process::exit(RUNTIME_FAIL)
+            }
+            std::process::exit(PASS)
         },
-        Mode::GEN => { 
-            println!("Code generation not implemented");
-            std::process::exit(MODE_FAIL)
+        Mode::DISASSEMBLE => {
+            let module = read_module(&args[2]);
+            module.disassemble(&mut BufWriter::new(stdout())).unwrap();
+            std::process::exit(PASS)
         },
     }
 }
@@ -0,0 +1,338 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::ASTNode;
+
+/// CallGraph
+/// Maps every function declared in the program to the set of functions it calls.
+/// Calls to functions without a declaration (e.g. the minic stdlib) are not recorded.
+pub struct CallGraph {
+    pub edges: HashMap<String, HashSet<String>>,
+    current: String,
+}
+
+impl CallGraph {
+    pub fn new(program: &mut Program) -> Self {
+        let mut call_graph = CallGraph {
+            edges: HashMap::new(),
+            current: String::new(),
+        };
+        program.accept(&mut call_graph);
+
+        let declared: HashSet<String> = call_graph.edges.keys().cloned().collect();
+        for callees in call_graph.edges.values_mut() {
+            callees.retain(|callee| declared.contains(callee));
+        }
+        call_graph
+    }
+
+    /// Returns true if `name` can reach itself through one or more calls.
+    pub fn is_recursive(&self, name: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<&String> = match self.edges.get(name) {
+            Some(callees) => callees.iter().collect(),
+            None => return false,
+        };
+
+        while let Some(f) = stack.pop() {
+            if f == name {
+                return true;
+            }
+            if visited.insert(f) {
+                if let Some(callees) = self.edges.get(f) {
+                    stack.extend(callees.iter());
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the declared functions ordered so that callees come before their callers
+    /// (cycles are broken arbitrarily).
+    pub fn post_order(&self, roots: &[String]) -> Vec<String> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for root in roots {
+            self.post_order_from(root, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn post_order_from(&self, name: &String, visited: &mut HashSet<String>, order: &mut Vec<String>) {
+        if !visited.insert(name.to_owned()) {
+            return;
+        }
+        if let Some(callees) = self.edges.get(name) {
+            let mut callees: Vec<&String> = callees.iter().collect();
+            callees.sort();
+            for callee in callees {
+                self.post_order_from(callee, visited, order);
+            }
+        }
+        order.push(name.to_owned());
+    }
+}
+
+impl ASTVisitor<()> for CallGraph {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) { }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) { }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) { }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) { }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+    }
+
+    fn visit_struct_type_decl(&mut self, _struct_type_decl: &mut StructTypeDecl) { }
+
+    // methods are called through their vtable, so are never inlined
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) { }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        if let Some(init) = &mut var_decl.init {
+            for expr in init.exprs_mut() {
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.current = fun_decl.name.to_owned();
+        self.edges.entry(fun_decl.name.to_owned()).or_default();
+        fun_decl.block.accept(self);
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.visit_expr(&mut a_while.expr);
+        self.visit_stmt(&mut a_while.stmt);
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.visit_expr(&mut a_do_while.expr);
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.visit_stmt(&mut a_for.stmt);
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) { }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) { }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.visit_expr(&mut a_switch.expr);
+        for case in a_switch.cases.iter_mut() {
+            if let Some(expr) = &mut case.expr {
+                self.visit_expr(expr);
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, _int_literal: &mut IntLiteral) { }
+
+    fn visit_chr_literal(&mut self, _chr_literal: &mut ChrLiteral) { }
+
+    fn visit_str_literal(&mut self, _str_literal: &mut StrLiteral) { }
+
+    fn visit_var_expr(&mut self, _var_expr: &mut VarExpr) { }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.visit_expr(&mut typecast_expr.expr);
+    }
+
+    fn visit_size_of_expr(&mut self, _size_of_expr: &mut SizeOfExpr) { }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.visit_expr(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.visit_expr(&mut field_access_expr.expr);
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        self.edges.entry(self.current.to_owned()).or_default().insert(fun_call_expr.name.to_owned());
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) { }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.visit_expr(&mut method_call_expr.expr);
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    // the function may be called through its address
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        self.edges.entry(self.current.to_owned()).or_default().insert(fun_addr_expr.name.to_owned());
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.visit_expr(&mut assign_expr.expr1);
+        self.visit_expr(&mut assign_expr.expr2);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.visit_expr(&mut inc_dec_expr.expr);
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.visit_expr(&mut conditional_expr.expr2);
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) { }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+    }
+}
@@ -0,0 +1,829 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StorageClass, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::gen::ir_generator::collect_address_taken;
+use crate::opt::call_graph::CallGraph;
+use crate::ASTNode;
+
+// maximum number of statements and expressions in the body of a function to be inlined
+const INLINE_THRESHOLD: usize = 64;
+
+/// Inliner
+/// Replaces calls to small non-recursive functions by a copy of their body. A function with a
+/// static local is never inlined, as each copy of its body would have a variable of its own.
+/// Calls are expanded in statement position, i.e. `f(..);`, `x = f(..);` and `return f(..);`, to
+/// which the calls of a statement, an if or switch condition or a local initialiser are first moved:
+/// `print_i(f(x) + 1);` becomes `__tmp<N> = f(x); print_i(__tmp<N> + 1);`. They are moved in the
+/// order they are evaluated, up to the first effect which cannot be, e.g. a call of another function,
+/// and never out of the condition of a loop or an operand of `&&`, `||` or `?:` evaluated conditionally.
+/// Every parameter and local of the callee is renamed to `__inl<N>_<name>` (identifiers starting
+/// with a double underscore are reserved), the return value is stored in `__ret<N>` and, when the
+/// callee returns from anywhere but its tail, `__done<N>` guards the statements following a return.
+pub struct Inliner {
+    candidates: HashMap<String, FunDecl>,
+    scopes: Vec<HashSet<String>>,
+    // the locals of the caller which a call may modify, its statics and those whose address is taken
+    exposed: HashSet<String>,
+    fresh: i32,
+    inlined: i32,
+}
+
+impl Inliner {
+    pub fn new() -> Self {
+        Inliner {
+            candidates: HashMap::new(),
+            scopes: Vec::new(),
+            exposed: HashSet::new(),
+            fresh: 0,
+            inlined: 0,
+        }
+    }
+
+    pub fn get_inlined_count(&self) -> i32 {
+        self.inlined
+    }
+
+    pub fn run(&mut self, program: &mut Program) {
+        let call_graph = CallGraph::new(program);
+        let roots: Vec<String> = program.fun_decls.iter().map(|f| f.name.to_owned()).collect();
+
+        // callees are visited before their callers, so that candidates are already expanded
+        for name in call_graph.post_order(&roots) {
+            let fun_decl = match program.fun_decls.iter_mut().find(|f| f.name == name) {
+                Some(f) => f,
+                None => continue,
+            };
+            self.inline_fun_decl(fun_decl);
+
+            if name != "main" && !call_graph.is_recursive(&name) && Inliner::is_small(fun_decl) {
+                self.candidates.insert(name, fun_decl.clone());
+            }
+        }
+    }
+
+    // small enough to be inlined, and without a static local
+    fn is_small(fun_decl: &FunDecl) -> bool {
+        let mut renamer = Renamer::new(String::new());
+        fun_decl.clone().accept(&mut renamer);
+        renamer.statics.is_empty() && renamer.size <= INLINE_THRESHOLD
+    }
+
+    fn inline_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.exposed = HashSet::new();
+        collect_address_taken(&fun_decl.block, &mut self.exposed);
+        // static locals outlive the call, a call may modify them
+        let mut renamer = Renamer::new(String::new());
+        fun_decl.clone().accept(&mut renamer);
+        self.exposed.extend(renamer.statics);
+        self.scopes.push(fun_decl.params.iter().map(|p| p.var_name.to_owned()).collect());
+        self.inline_block(&mut fun_decl.block);
+        self.scopes.pop();
+    }
+
+    fn inline_block(&mut self, block: &mut Block) {
+        self.split_initialisers(block);
+        self.scopes.push(block.var_decls.iter().map(|v| v.var_name.to_owned()).collect());
+        for stmt in block.stmts.iter_mut() {
+            self.inline_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn inline_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        let mut expansion = None;
+        // the temporaries holding the calls moved out of the statement, and their assignments
+        let mut temps = Vec::new();
+        let mut calls = Vec::new();
+
+        if let Some(block) = stmt.as_mut_any().downcast_mut::<Block>() {
+            self.inline_block(block);
+        } else if let Some(a_while) = stmt.as_mut_any().downcast_mut::<While>() {
+            self.inline_stmt(&mut a_while.stmt);
+        } else if let Some(a_do_while) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            self.inline_stmt(&mut a_do_while.stmt);
+        } else if let Some(a_for) = stmt.as_mut_any().downcast_mut::<For>() {
+            // the init and step stay simple statements, only the body is expanded
+            self.inline_stmt(&mut a_for.stmt);
+        } else if let Some(a_switch) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            self.hoist(&mut a_switch.expr, &mut temps, &mut calls);
+            for case in a_switch.cases.iter_mut() {
+                for stmt in case.stmts.iter_mut() {
+                    self.inline_stmt(stmt);
+                }
+            }
+        } else if let Some(an_if) = stmt.as_mut_any().downcast_mut::<If>() {
+            self.hoist(&mut an_if.expr, &mut temps, &mut calls);
+            self.inline_stmt(&mut an_if.stmt1);
+            if let Some(stmt2) = &mut an_if.stmt2 {
+                self.inline_stmt(stmt2);
+            }
+        } else if let Some(expr_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            match expr_stmt.expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+                Some(call) => self.hoist_all(&mut call.exprs, &mut temps, &mut calls),
+                None => self.hoist(&mut expr_stmt.expr, &mut temps, &mut calls),
+            };
+            if let Some(call) = expr_stmt.expr.as_any().downcast_ref::<FunCallExpr>() {
+                expansion = self.expand(call, Target::Discard);
+            }
+        } else if let Some(assign) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            // the left hand side is evaluated after the body, so it must not depend on its side effects
+            let is_var = assign.expr1.as_any().downcast_ref::<VarExpr>().is_some();
+            match assign.expr2.as_mut_any().downcast_mut::<FunCallExpr>() {
+                Some(call) if is_var => self.hoist_all(&mut call.exprs, &mut temps, &mut calls),
+                _ => self.hoist_lhs(&mut assign.expr1, &mut temps, &mut calls) && self.hoist(&mut assign.expr2, &mut temps, &mut calls),
+            };
+            if let (true, Some(call)) = (is_var, assign.expr2.as_any().downcast_ref::<FunCallExpr>()) {
+                expansion = self.expand(call, Target::Assign(assign.expr1.clone()));
+            }
+        } else if let Some(a_return) = stmt.as_mut_any().downcast_mut::<Return>() {
+            if let Some(expr) = &mut a_return.expr {
+                match expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+                    Some(call) => self.hoist_all(&mut call.exprs, &mut temps, &mut calls),
+                    None => self.hoist(expr, &mut temps, &mut calls),
+                };
+            }
+            if let Some(call) = a_return.expr.as_ref().and_then(|e| e.as_any().downcast_ref::<FunCallExpr>()) {
+                expansion = self.expand(call, Target::Return);
+            }
+        }
+
+        if let Some(block) = expansion {
+            *stmt = Box::new(block);
+        }
+        if !temps.is_empty() {
+            // each call moved out is now in statement position
+            for call in calls.iter_mut() {
+                self.inline_stmt(call);
+            }
+            calls.push(std::mem::replace(stmt, Box::new(Block::new(vec![], vec![]))));
+            *stmt = Box::new(Block::new(temps, calls));
+        }
+    }
+
+    // the return type of the function called, if the call can be expanded
+    fn candidate(&self, call: &FunCallExpr) -> Option<Box<dyn Type>> {
+        if self.scopes.iter().any(|scope| scope.contains(&call.name)) {
+            return None;
+        }
+        let callee = self.candidates.get(&call.name)?;
+        let is_void = matches!(callee.fun_type.as_any().downcast_ref::<BaseType>(), Some(BaseType::VOID));
+        if is_void || callee.params.len() != call.exprs.len() {
+            return None;
+        }
+        Some(callee.fun_type.clone())
+    }
+
+    // moves the calls of candidates out of the expression, into temporaries assigned by `calls`, in the
+    // order they are evaluated; returns false once an effect which cannot be moved is reached
+    fn hoist(&mut self, expr: &mut Box<dyn Expr>, temps: &mut Vec<VarDecl>, calls: &mut Vec<Box<dyn Stmt>>) -> bool {
+        if let Some(call) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            if !self.hoist_all(&mut call.exprs, temps, calls) {
+                return false;
+            }
+            let fun_type = match self.candidate(call) {
+                Some(fun_type) => fun_type,
+                None => return false,
+            };
+            let temp = format!("__tmp{}", self.fresh);
+            self.fresh += 1;
+            temps.push(VarDecl::new(fun_type, temp.to_owned()));
+            let call = std::mem::replace(expr, VarExpr::new(temp.to_owned()));
+            calls.push(Assign::new(VarExpr::new(temp), call));
+            true
+        } else if let Some(bin_op) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            self.hoist(&mut bin_op.expr1, temps, calls) && !bin_op.op.is_short_circuit() && self.hoist(&mut bin_op.expr2, temps, calls)
+        } else if let Some(conditional_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            self.hoist(&mut conditional_expr.expr, temps, calls);
+            false
+        } else if let Some(assign_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            let _ = self.hoist_lhs(&mut assign_expr.expr1, temps, calls) && self.hoist(&mut assign_expr.expr2, temps, calls);
+            false
+        } else if let Some(inc_dec_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            self.hoist(&mut inc_dec_expr.expr, temps, calls);
+            false
+        } else if let Some(method_call_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            let _ = self.hoist(&mut method_call_expr.expr, temps, calls) && self.hoist_all(&mut method_call_expr.exprs, temps, calls);
+            false
+        } else if let Some(indirect_call_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            let _ = self.hoist(&mut indirect_call_expr.expr, temps, calls) && self.hoist_all(&mut indirect_call_expr.exprs, temps, calls);
+            false
+        } else if let Some(array_access_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            // a call may modify the memory read
+            let _ = self.hoist(&mut array_access_expr.expr1, temps, calls) && self.hoist(&mut array_access_expr.expr2, temps, calls);
+            false
+        } else if let Some(value_at_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            self.hoist(&mut value_at_expr.expr, temps, calls);
+            false
+        } else if let Some(field_access_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            self.hoist(&mut field_access_expr.expr, temps, calls) && self.is_private(field_access_expr.expr.as_ref())
+        } else if let Some(address_of_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            self.hoist_lhs(&mut address_of_expr.expr, temps, calls)
+        } else if let Some(typecast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            self.hoist(&mut typecast_expr.expr, temps, calls)
+        } else if let Some(var_expr) = expr.as_any().downcast_ref::<VarExpr>() {
+            self.is_private(var_expr)
+        } else {
+            expr.as_any().downcast_ref::<NewExpr>().is_none()
+        }
+    }
+
+    // as hoist, for an expression whose address is evaluated rather than its value
+    fn hoist_lhs(&mut self, expr: &mut Box<dyn Expr>, temps: &mut Vec<VarDecl>, calls: &mut Vec<Box<dyn Stmt>>) -> bool {
+        if expr.as_any().downcast_ref::<VarExpr>().is_some() {
+            true
+        } else if let Some(array_access_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            self.hoist_lhs(&mut array_access_expr.expr1, temps, calls) && self.hoist(&mut array_access_expr.expr2, temps, calls)
+        } else if let Some(field_access_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            self.hoist_lhs(&mut field_access_expr.expr, temps, calls)
+        } else if let Some(value_at_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            self.hoist(&mut value_at_expr.expr, temps, calls)
+        } else {
+            self.hoist(expr, temps, calls)
+        }
+    }
+
+    // true if the expression is a local no call can modify
+    fn is_private(&self, expr: &dyn Expr) -> bool {
+        match expr.as_any().downcast_ref::<VarExpr>() {
+            Some(var_expr) => !self.exposed.contains(&var_expr.name) && self.scopes.iter().any(|scope| scope.contains(&var_expr.name)),
+            None => false,
+        }
+    }
+
+    fn hoist_all(&mut self, exprs: &mut [Box<dyn Expr>], temps: &mut Vec<VarDecl>, calls: &mut Vec<Box<dyn Stmt>>) -> bool {
+        exprs.iter_mut().all(|expr| self.hoist(expr, temps, calls))
+    }
+
+    // turns the initialisers of the locals of a block, from the first calling a candidate, into
+    // assignments at the start of the block, if they are all plain values and none refers to a
+    // variable the block declares later
+    fn split_initialisers(&self, block: &mut Block) {
+        let first = block.var_decls.iter().position(|var_decl| {
+            var_decl.init.as_ref().is_some_and(|init| free_names(init).iter().any(|name| self.candidates.contains_key(name)))
+        });
+        let first = match first {
+            Some(first) => first,
+            None => return,
+        };
+        for (i, var_decl) in block.var_decls.iter().enumerate().skip(first) {
+            let init = match &var_decl.init {
+                Some(init) => init,
+                None => continue,
+            };
+            let is_value = matches!(init, Initializer::Expr(_)) && var_decl.var_type.as_array().is_none() && var_decl.var_type.as_struct().is_none();
+            let names = free_names(init);
+            if !is_value || var_decl.is_const || var_decl.storage != StorageClass::Auto || block.var_decls[i..].iter().any(|later| names.contains(&later.var_name)) {
+                return;
+            }
+        }
+
+        let mut assigns: Vec<Box<dyn Stmt>> = Vec::new();
+        for var_decl in block.var_decls.iter_mut().skip(first) {
+            if let Some(Initializer::Expr(expr)) = var_decl.init.take() {
+                assigns.push(Assign::new(VarExpr::new(var_decl.var_name.to_owned()), expr));
+            }
+        }
+        block.stmts.splice(0..0, assigns);
+    }
+
+    fn expand(&mut self, call: &FunCallExpr, target: Target) -> Option<Block> {
+        // a call of a local function pointer, rather than of the function of the same name
+        if self.scopes.iter().any(|scope| scope.contains(&call.name)) {
+            return None;
+        }
+        let mut callee = self.candidates.get(&call.name)?.clone();
+        if callee.params.len() != call.exprs.len() {
+            return None;
+        }
+
+        let is_void = matches!(callee.fun_type.as_any().downcast_ref::<BaseType>(), Some(BaseType::VOID));
+        if is_void && matches!(target, Target::Assign(_)) {
+            return None;
+        }
+
+        let id = self.fresh;
+        let mut renamer = Renamer::new(format!("__inl{}_", id));
+        callee.accept(&mut renamer);
+
+        // a global used by the callee must not be shadowed at the call site
+        if renamer.free.iter().any(|name| self.scopes.iter().any(|scope| scope.contains(name))) {
+            return None;
+        }
+        self.fresh += 1;
+        self.inlined += 1;
+
+        let returns = ReturnLowering {
+            result: if is_void { None } else { Some(format!("__ret{}", id)) },
+            done: if only_tail_returns(&callee.block.stmts) { None } else { Some(format!("__done{}", id)) },
+        };
+
+        let mut var_decls = Vec::new();
+        let mut stmts: Vec<Box<dyn Stmt>> = Vec::new();
+
+        for (param, arg) in callee.params.iter().zip(call.exprs.iter()) {
+            var_decls.push(param.clone());
+            // an array is passed as a pointer to its first element, which an assignment does not convert to
+            let arg = if arg.get_expr_type().as_array().is_some() { TypecastExpr::new(param.var_type.clone(), arg.clone()) } else { arg.clone() };
+            stmts.push(Assign::new(VarExpr::new(param.var_name.to_owned()), arg));
+        }
+        if let Some(result) = &returns.result {
+            var_decls.push(VarDecl::new(callee.fun_type.clone(), result.to_owned()));
+        }
+        if let Some(done) = &returns.done {
+            var_decls.push(VarDecl::new(Box::new(BaseType::INT), done.to_owned()));
+            stmts.push(Assign::new(VarExpr::new(done.to_owned()), IntLiteral::new("0".to_owned())));
+        }
+
+        let body = std::mem::take(&mut callee.block.stmts);
+        callee.block.stmts = returns.lower_stmts(body);
+        stmts.push(Box::new(callee.block));
+
+        let result = returns.result.map(|r| -> Box<dyn Expr> { VarExpr::new(r) });
+        match target {
+            Target::Discard => { },
+            Target::Assign(lhs) => stmts.push(Assign::new(lhs, result.unwrap())),
+            Target::Return => stmts.push(Return::new(result)),
+        }
+
+        Some(Block::new(var_decls, stmts))
+    }
+}
+
+/// What to do with the value of an inlined call.
+enum Target {
+    Discard,
+    Assign(Box<dyn Expr>),
+    Return,
+}
+
+/// Rewrites the `Return` statements of an inlined body into assignments to the result variable.
+struct ReturnLowering {
+    result: Option<String>,
+    done: Option<String>,
+}
+
+impl ReturnLowering {
+    fn lower_stmts(&self, stmts: Vec<Box<dyn Stmt>>) -> Vec<Box<dyn Stmt>> {
+        let mut lowered = Vec::new();
+        let mut iter = stmts.into_iter();
+
+        while let Some(mut stmt) = iter.next() {
+            let may_return = self.lower_stmt(&mut stmt);
+            lowered.push(stmt);
+
+            if may_return {
+                let rest: Vec<Box<dyn Stmt>> = iter.collect();
+                // without a flag every return is in tail position, so the rest is unreachable
+                if let (Some(done), false) = (&self.done, rest.is_empty()) {
+                    let rest = Box::new(Block::new(vec![], self.lower_stmts(rest)));
+                    lowered.push(If::new(not_done(done), rest, None));
+                }
+                break;
+            }
+        }
+        lowered
+    }
+
+    // returns true if the statement may return
+    fn lower_stmt(&self, stmt: &mut Box<dyn Stmt>) -> bool {
+        if let Some(block) = stmt.as_mut_any().downcast_mut::<Block>() {
+            let stmts = std::mem::take(&mut block.stmts);
+            let may_return = stmts.iter().any(|s| contains_return(s.as_ref()));
+            block.stmts = self.lower_stmts(stmts);
+            may_return
+        } else if let Some(a_while) = stmt.as_mut_any().downcast_mut::<While>() {
+            let may_return = self.lower_stmt(&mut a_while.stmt);
+            if let (Some(done), true) = (&self.done, may_return) {
+                let expr = std::mem::replace(&mut a_while.expr, IntLiteral::new("0".to_owned()));
+                a_while.expr = BinOp::new(not_done(done), Op::AND, expr);
+            }
+            may_return
+        } else if let Some(a_do_while) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            let may_return = self.lower_stmt(&mut a_do_while.stmt);
+            if let (Some(done), true) = (&self.done, may_return) {
+                let expr = std::mem::replace(&mut a_do_while.expr, IntLiteral::new("0".to_owned()));
+                a_do_while.expr = BinOp::new(not_done(done), Op::AND, expr);
+            }
+            may_return
+        } else if let Some(a_switch) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            let mut may_return = false;
+            for case in a_switch.cases.iter_mut() {
+                let stmts = std::mem::take(&mut case.stmts);
+                let case_returns = stmts.iter().any(|s| contains_return(s.as_ref()));
+                let mut stmts = self.lower_stmts(stmts);
+                // falling through from a case which returned must skip the following ones
+                if let (Some(done), true, false) = (&self.done, may_return, stmts.is_empty()) {
+                    stmts = vec![If::new(not_done(done), Box::new(Block::new(vec![], stmts)), None)];
+                }
+                may_return |= case_returns;
+                case.stmts = stmts;
+            }
+            may_return
+        } else if let Some(a_for) = stmt.as_mut_any().downcast_mut::<For>() {
+            let may_return = self.lower_stmt(&mut a_for.stmt);
+            if let (Some(done), true) = (&self.done, may_return) {
+                a_for.expr = Some(match a_for.expr.take() {
+                    Some(expr) => BinOp::new(not_done(done), Op::AND, expr),
+                    None => not_done(done),
+                });
+                // the step must not run once the body has returned
+                if let Some(step) = a_for.step.take() {
+                    a_for.step = Some(If::new(not_done(done), step, None));
+                }
+            }
+            may_return
+        } else if let Some(an_if) = stmt.as_mut_any().downcast_mut::<If>() {
+            let mut may_return = self.lower_stmt(&mut an_if.stmt1);
+            if let Some(stmt2) = &mut an_if.stmt2 {
+                may_return |= self.lower_stmt(stmt2);
+            }
+            may_return
+        } else if let Some(a_return) = stmt.as_mut_any().downcast_mut::<Return>() {
+            let mut stmts: Vec<Box<dyn Stmt>> = Vec::new();
+            match (a_return.expr.take(), &self.result) {
+                (Some(expr), Some(result)) => stmts.push(Assign::new(VarExpr::new(result.to_owned()), expr)),
+                (Some(expr), None) => stmts.push(ExprStmt::new(expr)),
+                (None, _) => { },
+            }
+            if let Some(done) = &self.done {
+                stmts.push(Assign::new(VarExpr::new(done.to_owned()), IntLiteral::new("1".to_owned())));
+            }
+            *stmt = Box::new(Block::new(vec![], stmts));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn not_done(done: &str) -> Box<dyn Expr> {
+    BinOp::new(VarExpr::new(done.to_owned()), Op::EQ, IntLiteral::new("0".to_owned()))
+}
+
+fn contains_return(stmt: &dyn Stmt) -> bool {
+    if let Some(block) = stmt.as_any().downcast_ref::<Block>() {
+        block.stmts.iter().any(|s| contains_return(s.as_ref()))
+    } else if let Some(a_while) = stmt.as_any().downcast_ref::<While>() {
+        contains_return(a_while.stmt.as_ref())
+    } else if let Some(a_do_while) = stmt.as_any().downcast_ref::<DoWhile>() {
+        contains_return(a_do_while.stmt.as_ref())
+    } else if let Some(a_for) = stmt.as_any().downcast_ref::<For>() {
+        contains_return(a_for.stmt.as_ref())
+    } else if let Some(a_switch) = stmt.as_any().downcast_ref::<Switch>() {
+        a_switch.cases.iter().any(|case| case.stmts.iter().any(|s| contains_return(s.as_ref())))
+    } else if let Some(an_if) = stmt.as_any().downcast_ref::<If>() {
+        contains_return(an_if.stmt1.as_ref()) || an_if.stmt2.as_ref().is_some_and(|s| contains_return(s.as_ref()))
+    } else {
+        stmt.as_any().downcast_ref::<Return>().is_some()
+    }
+}
+
+// the variables and functions an initialiser refers to
+fn free_names(init: &Initializer) -> HashSet<String> {
+    let mut renamer = Renamer::new(String::new());
+    let mut init = init.clone();
+    for expr in init.exprs_mut() {
+        renamer.visit_expr(expr);
+    }
+    renamer.free
+}
+
+// true if every return of the sequence is the last statement executed on its path
+fn only_tail_returns(stmts: &[Box<dyn Stmt>]) -> bool {
+    match stmts.split_last() {
+        Some((last, init)) => !init.iter().any(|s| contains_return(s.as_ref())) && only_tail_return(last.as_ref()),
+        None => true,
+    }
+}
+
+fn only_tail_return(stmt: &dyn Stmt) -> bool {
+    if let Some(block) = stmt.as_any().downcast_ref::<Block>() {
+        only_tail_returns(&block.stmts)
+    } else if let Some(an_if) = stmt.as_any().downcast_ref::<If>() {
+        only_tail_return(an_if.stmt1.as_ref()) && an_if.stmt2.as_ref().is_none_or(|s| only_tail_return(s.as_ref()))
+    } else {
+        stmt.as_any().downcast_ref::<Return>().is_some() || !contains_return(stmt)
+    }
+}
+
+/// Renamer
+/// Prefixes the parameters and locals of a function, collects the free variables and functions it
+/// refers to and its static locals, and counts its statements and expressions.
+struct Renamer {
+    prefix: String,
+    scopes: Vec<HashSet<String>>,
+    free: HashSet<String>,
+    size: usize,
+    statics: HashSet<String>,
+}
+
+impl Renamer {
+    fn new(prefix: String) -> Self {
+        Renamer {
+            prefix,
+            scopes: Vec::new(),
+            free: HashSet::new(),
+            size: 0,
+            statics: HashSet::new(),
+        }
+    }
+
+    fn declare(&mut self, var_decl: &mut VarDecl) {
+        self.scopes.last_mut().unwrap().insert(var_decl.var_name.to_owned());
+        var_decl.var_name = format!("{}{}", self.prefix, var_decl.var_name);
+    }
+}
+
+impl ASTVisitor<()> for Renamer {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) { }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) { }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) { }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) { }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+    }
+
+    fn visit_struct_type_decl(&mut self, _struct_type_decl: &mut StructTypeDecl) { }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) { }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        if var_decl.storage == StorageClass::Static {
+            self.statics.insert(var_decl.var_name.to_owned());
+        }
+        self.declare(var_decl);
+        if let Some(init) = &mut var_decl.init {
+            for expr in init.exprs_mut() {
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.scopes.push(HashSet::new());
+        for param in fun_decl.params.iter_mut() {
+            param.accept(self);
+        }
+        fun_decl.block.accept(self);
+        self.scopes.pop();
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        self.size += 1;
+        self.scopes.push(HashSet::new());
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.size += 1;
+        self.visit_expr(&mut a_while.expr);
+        self.visit_stmt(&mut a_while.stmt);
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.size += 1;
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.visit_expr(&mut a_do_while.expr);
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        self.size += 1;
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.visit_stmt(&mut a_for.stmt);
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        self.size += 1;
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        self.size += 1;
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.size += 1;
+        self.visit_expr(&mut an_if.expr);
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.size += 1;
+        self.visit_expr(&mut a_switch.expr);
+        for case in a_switch.cases.iter_mut() {
+            if let Some(expr) = &mut case.expr {
+                self.visit_expr(expr);
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        self.size += 1;
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.size += 1;
+        self.visit_expr(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.size += 1;
+        self.visit_expr(&mut expr_stmt.expr);
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, _int_literal: &mut IntLiteral) { }
+
+    fn visit_chr_literal(&mut self, _chr_literal: &mut ChrLiteral) { }
+
+    fn visit_str_literal(&mut self, _str_literal: &mut StrLiteral) { }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        if self.scopes.iter().any(|scope| scope.contains(&var_expr.name)) {
+            var_expr.name = format!("{}{}", self.prefix, var_expr.name);
+        } else {
+            self.free.insert(var_expr.name.to_owned());
+        }
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.visit_expr(&mut typecast_expr.expr);
+    }
+
+    fn visit_size_of_expr(&mut self, _size_of_expr: &mut SizeOfExpr) { }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.visit_expr(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.visit_expr(&mut field_access_expr.expr);
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+    }
+
+    // the callee is renamed when it is a function pointer parameter or local
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        if self.scopes.iter().any(|scope| scope.contains(&fun_call_expr.name)) {
+            fun_call_expr.name = format!("{}{}", self.prefix, fun_call_expr.name);
+        } else {
+            self.free.insert(fun_call_expr.name.to_owned());
+        }
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) { }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.visit_expr(&mut method_call_expr.expr);
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_fun_addr_expr(&mut self, _fun_addr_expr: &mut FunAddrExpr) { }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.visit_expr(&mut assign_expr.expr1);
+        self.visit_expr(&mut assign_expr.expr2);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.visit_expr(&mut inc_dec_expr.expr);
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.visit_expr(&mut conditional_expr.expr2);
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) { }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        self.size += 1;
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+    }
+}
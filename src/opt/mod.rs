@@ -0,0 +1,2 @@
+pub mod call_graph;
+pub mod inliner;
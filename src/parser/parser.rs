@@ -1,11 +1,12 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::Rc;
+use crate::lexer::position::{Diagnostic, Position};
 use crate::lexer::token::Token;
 use crate::{TokenClass, Tokenizer};
-use crate::ast::decl::{FunDecl, Program, StructTypeDecl, VarDecl};
-use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, BinOp, ChrLiteral, Expr, FieldAccessExpr, FunCallExpr, IntLiteral, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
-use crate::ast::stmt::{Assign, Block, ExprStmt, If, Return, Stmt, While};
-use crate::ast::types::{ArrayType, BaseType, PointerType, StructType, Type};
+use crate::ast::decl::{ClassDecl, EnumDecl, Enumerator, FunDecl, Initializer, Item, Program, StorageClass, StructTypeDecl, TypedefDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Case, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
 
 
 macro_rules! return_if {
@@ -21,6 +22,17 @@ pub struct Parser {
     tokenizer: Tokenizer,
     error: i32,
     last_error_token: Rc<Token>,
+    quiet: bool,
+    diagnostics: Vec<Diagnostic>,
+    // the names declared in each open scope, the outermost being the global one: a typedef with its
+    // type, or a variable shadowing any typedef of the same name. An identifier naming a type starts
+    // a declaration or a cast
+    typedefs: Vec<HashMap<String, Option<Box<dyn Type>>>>,
+    // the parameters of the function whose body is parsed next, in the scope of its block
+    params: Vec<String>,
+    // whether the fields of a struct or a class are parsed, which do not shadow the typedefs
+    fields: bool,
+    enums: HashSet<String>,
 }
 
 impl Parser {
@@ -30,20 +42,45 @@ impl Parser {
             buffer: VecDeque::new(),
             tokenizer,
             error: 0,
-            last_error_token: Rc::new(Token::new(TokenClass::INVALID, "", 0, 0))
+            last_error_token: Rc::new(Token::new(TokenClass::INVALID, "", 0, 0)),
+            quiet: false,
+            diagnostics: Vec::new(),
+            typedefs: vec![HashMap::new()],
+            params: Vec::new(),
+            fields: false,
+            enums: HashSet::new(),
         }
     }
 
+    /// Knows the typedefs and enums of a program parsed before, as the REPL parses each entry on its own.
+    pub fn with_types_of(mut self, program: &Program) -> Self {
+        for typedef_decl in &program.typedef_decls {
+            self.typedefs[0].insert(typedef_decl.name.to_owned(), Some(typedef_decl.typedef_type.clone()));
+        }
+        for enum_decl in &program.enum_decls {
+            self.enums.insert(enum_decl.name.to_owned());
+        }
+        self
+    }
+
+    /// Only collects the errors instead of also printing them, the tokenizer should be quiet as well.
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
     pub fn get_error_count(&self) -> i32 {
         self.error
     }
 
-    fn error(&mut self, expected: &[TokenClass]) {
-        if Rc::ptr_eq(&self.last_error_token, &self.token) {
-            // skip this error, same token causing trouble
-            return;
-        }
+    /// The lexing errors met while parsing followed by the parsing errors.
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.tokenizer.get_diagnostics().to_vec();
+        diagnostics.extend(self.diagnostics.iter().cloned());
+        diagnostics
+    }
 
+    fn error(&mut self, expected: &[TokenClass]) {
         let mut tokens = String::new();
         let mut sep = "";
 
@@ -53,7 +90,20 @@ impl Parser {
             sep = "|";
         }
 
-        println!("Parsing error: expected ({}) found ({}) at {}",tokens, self.token, self.token.position);
+        let message = format!("expected ({}) found ({})", tokens, self.token);
+        self.report(message);
+    }
+
+    fn report(&mut self, message: String) {
+        if Rc::ptr_eq(&self.last_error_token, &self.token) {
+            // skip this error, same token causing trouble
+            return;
+        }
+
+        if !self.quiet {
+            println!("Parsing error: {} at {}", message, self.token.position);
+        }
+        self.diagnostics.push(Diagnostic::new(self.token.position, message));
 
         self.error += 1;
         self.last_error_token = Rc::clone(&self.token);
@@ -70,17 +120,158 @@ impl Parser {
         }
         assert!(self.buffer.len() >= i as usize);
 
-        let mut cnt = 1;
-        for t in &self.buffer {
+        for (cnt, t) in (1..).zip(self.buffer.iter()) {
             if cnt == i {
                 return t;
             }
-            cnt += 1;
         }
 
         unreachable!();  // should never reach this
     }
 
+    // the class of the token following the type and the name of a declaration starting at the current token,
+    // `*` for a function pointer whose name is in parentheses
+    fn after_declarator(&mut self) -> TokenClass {
+        // the current token is the 0th one
+        let mut i = 0;
+        while matches!(self.class_at(i), TokenClass::STATIC | TokenClass::EXTERN | TokenClass::CONST) {
+            i += 1;
+        }
+        if matches!(self.class_at(i), TokenClass::STRUCT | TokenClass::CLASS | TokenClass::ENUM) {
+            i += 2;
+        } else if Parser::is_integer_specifier(&self.class_at(i)) {
+            while Parser::is_integer_specifier(&self.class_at(i)) {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+        while matches!(self.class_at(i), TokenClass::ASTERIX | TokenClass::CONST) {
+            i += 1;
+        }
+        if self.class_at(i) == TokenClass::LPAR && self.class_at(i + 1) == TokenClass::ASTERIX {
+            // `(*name)(..)` declares a function pointer, and `(*name(..))(..)` a function returning one
+            i += 1;
+            while self.class_at(i) == TokenClass::ASTERIX {
+                i += 1;
+            }
+            return if self.class_at(i + 1) == TokenClass::LPAR { TokenClass::LPAR } else { TokenClass::ASTERIX };
+        }
+        self.class_at(i + 1)
+    }
+
+    // the class of the i^th token from the current one, which is the 0th
+    fn class_at(&mut self, i: i32) -> TokenClass {
+        if i == 0 { self.token.token_class.clone() } else { self.look_a_head(i).token_class.clone() }
+    }
+
+    // the keywords which combine into an integer type, e.g. `unsigned long int`
+    fn is_integer_specifier(token_class: &TokenClass) -> bool {
+        matches!(token_class, TokenClass::INT | TokenClass::CHAR | TokenClass::SHORT | TokenClass::LONG | TokenClass::SIGNED | TokenClass::UNSIGNED)
+    }
+
+    // whether the token starts a type, a typedef name being one
+    fn is_type_start(&self, token: &Token) -> bool {
+        match token.token_class {
+            TokenClass::INT | TokenClass::CHAR | TokenClass::VOID | TokenClass::STRUCT | TokenClass::CLASS | TokenClass::ENUM
+                | TokenClass::SHORT | TokenClass::LONG | TokenClass::SIGNED | TokenClass::UNSIGNED | TokenClass::CONST => true,
+            TokenClass::IDENTIFIER => self.typedef(&token.data).is_some(),
+            _ => false,
+        }
+    }
+
+    // the type a name stands for in the innermost scope declaring it, if it is a typedef there
+    fn typedef(&self, name: &str) -> Option<&dyn Type> {
+        self.typedefs.iter().rev().find_map(|scope| scope.get(name)).and_then(|a_type| a_type.as_deref())
+    }
+
+    // declares a typedef, or a variable when there is no type, in the innermost scope; the names of a
+    // block are checked here as only the global typedefs are kept in the AST
+    fn declare(&mut self, name: &str, a_type: Option<Box<dyn Type>>) {
+        if self.fields {
+            return;
+        }
+        let scope = self.typedefs.last_mut().unwrap();
+        let redeclared = scope.get(name).is_some_and(|declared| declared.is_some() || a_type.is_some());
+        scope.insert(name.to_owned(), a_type);
+        if redeclared && self.typedefs.len() > 1 {
+            self.report(format!("{} is already declared", name));
+        }
+    }
+
+    fn at_type(&self) -> bool {
+        self.is_type_start(&self.token)
+    }
+
+    // a declaration may start with its storage class
+    fn at_declaration(&mut self) -> bool {
+        self.accept(&[TokenClass::STATIC, TokenClass::EXTERN]) || self.at_type()
+    }
+
+    // `enum {` or `enum name {`, unlike a variable of an enum type
+    fn at_enum_decl(&mut self) -> bool {
+        self.accept(&[TokenClass::ENUM]) && (self.look_a_head(1).token_class == TokenClass::LBRA || self.look_a_head(2).token_class == TokenClass::LBRA)
+    }
+
+    // whether the token after the current one starts a type, as in a cast
+    fn type_follows(&mut self) -> bool {
+        self.look_a_head(1);
+        self.is_type_start(&self.buffer[0])
+    }
+
+    // the dimensions following the name of an array, e.g. `[2][3]`, of which the first one may be left out
+    fn parse_dimensions(&mut self) -> Vec<Option<i32>> {
+        let mut dimensions = Vec::new();
+        while self.accept(&[TokenClass::LSBR]) {
+            self.next_token();
+            let mut size = None;
+            if self.accept(&[TokenClass::INTLITERAL]) {
+                size = Some(self.parse_int_literal().i as i32);
+            }
+            self.expect(&[TokenClass::RSBR]);
+            dimensions.push(size);
+        }
+        dimensions
+    }
+
+    // whether a function pointer declarator follows the type, as in `int (*cmp)(int, int)`
+    fn at_fun_pointer(&mut self) -> bool {
+        self.accept(&[TokenClass::LPAR]) && self.look_a_head(1).token_class == TokenClass::ASTERIX
+    }
+
+    // `(*name[2])(int, int)` after the return type, the name being left out in casts and sizeof. Returns the
+    // pointer to the function, with the name, its position and the dimensions of an array of such pointers
+    fn parse_fun_pointer(&mut self, return_type: Box<dyn Type>) -> (Box<dyn Type>, String, Position, Vec<Option<i32>>) {
+        self.next_token();
+        let mut pointers = 0;
+        while self.accept(&[TokenClass::ASTERIX]) {
+            self.next_token();
+            pointers += 1;
+        }
+        let mut name = String::from("");
+        let position = self.token.position;
+        if self.accept(&[TokenClass::IDENTIFIER]) {
+            name = self.token.data.to_owned();
+            self.next_token();
+        }
+        let dimensions = self.parse_dimensions();
+        self.expect(&[TokenClass::RPAR]);
+        self.expect(&[TokenClass::LPAR]);
+        let param_types = self.parse_params().into_iter().map(|param| param.var_type).collect();
+        self.expect(&[TokenClass::RPAR]);
+
+        let mut a_type: Box<dyn Type> = Box::new(FunType::new(return_type, param_types));
+        for _ in 0..pointers {
+            a_type = PointerType::new(a_type);
+        }
+        (a_type, name, position, dimensions)
+    }
+
+    // the array of the given dimensions, the first one being the outermost
+    fn array_type(element_type: Box<dyn Type>, dimensions: &[Option<i32>]) -> Box<dyn Type> {
+        dimensions.iter().rev().fold(element_type, |a_type, size| Box::new(ArrayType::of_size(a_type, *size)))
+    }
+
     /*
      * Consumes the next token from the tokeniser or the buffer if not empty.
      */
@@ -115,20 +306,69 @@ impl Parser {
         for e in expected {
             result |= e.clone() == self.token.token_class;
         }
-        return result;
+        result
     }
 
     pub fn parse(&mut self) -> Program {
         self.parse_program()
     }
 
+    /// Parses a chunk of code typed in the REPL, in which declarations of structs, classes, globals
+    /// and functions can be mixed with statements. Stops at the first error.
+    pub fn parse_entry(&mut self) -> (Program, Vec<Box<dyn Stmt>>) {
+        let mut program = Program::new(vec![], vec![], vec![]);
+        let mut stmts = Vec::new();
+        while !self.accept(&[TokenClass::EOF]) && self.error == 0 {
+            if !self.parse_decls(&mut program) {
+                stmts.push(self.parse_stmt());
+            }
+        }
+        (program, stmts)
+    }
+
     fn parse_program(&mut self) -> Program {
         self.parse_includes();
-        let struct_decls = self.parse_struct_decls();
-        let var_decls = self.parse_var_decls();
-        let fun_decls = self.parse_fun_decls();
+        let mut program = Program::new(vec![], vec![], vec![]);
+        while self.parse_decls(&mut program) {}
         self.expect(&[TokenClass::EOF]);
-        Program::new(struct_decls, var_decls,fun_decls)
+        program
+    }
+
+    // adds the next top-level declarations to the program in source order, false if there are none
+    fn parse_decls(&mut self, program: &mut Program) -> bool {
+        let count = program.items.len();
+        if let Some(typedef_decl) = self.parse_typedef_decl() {
+            program.items.push(Item::Typedef(program.typedef_decls.len()));
+            program.typedef_decls.push(typedef_decl);
+        }
+        if let Some(enum_decl) = self.parse_enum_decl() {
+            program.items.push(Item::Enum(program.enum_decls.len()));
+            program.enum_decls.push(enum_decl);
+        }
+        for struct_decl in self.parse_struct_decls() {
+            program.items.push(Item::Struct(program.struct_type_decls.len()));
+            program.struct_type_decls.push(struct_decl);
+        }
+        for class_decl in self.parse_class_decls() {
+            program.items.push(Item::Class(program.class_decls.len()));
+            program.class_decls.push(class_decl);
+        }
+        for var_decl in self.parse_var_decls() {
+            program.items.push(Item::Var(program.var_decls.len()));
+            program.var_decls.push(var_decl);
+        }
+        if let Some(mut fun_decl) = self.parse_fun_header() {
+            if self.accept(&[TokenClass::SC]) {
+                self.next_token();
+                program.items.push(Item::Prototype(program.prototypes.len()));
+                program.prototypes.push(fun_decl);
+            } else {
+                fun_decl.block = self.parse_block();
+                program.items.push(Item::Fun(program.fun_decls.len()));
+                program.fun_decls.push(fun_decl);
+            }
+        }
+        program.items.len() > count
     }
 
     // includes are ignored, so does not need to return an AST node
@@ -140,21 +380,111 @@ impl Parser {
         }
     }
 
+    // `typedef type name;`, the name standing for the type from then on
+    fn parse_typedef_decl(&mut self) -> Option<TypedefDecl> {
+        if !self.accept(&[TokenClass::TYPEDEF]) {
+            return None;
+        }
+
+        self.next_token();
+        let a_type = if self.at_type() {
+            self.parse_type()
+        } else {
+            self.expect(&[TokenClass::INT, TokenClass::CHAR, TokenClass::VOID, TokenClass::SHORT, TokenClass::LONG, TokenClass::SIGNED,
+                TokenClass::UNSIGNED, TokenClass::STRUCT, TokenClass::CLASS, TokenClass::ENUM]);
+            Box::new(BaseType::INT)
+        };
+        let (a_type, name, position, dimensions) = if self.at_fun_pointer() {
+            self.parse_fun_pointer(a_type)
+        } else {
+            let mut name = String::from("");
+            let position = self.token.position;
+            if self.accept(&[TokenClass::IDENTIFIER]) {
+                name = self.token.data.to_owned();
+                self.next_token();
+            } else {
+                self.expect(&[TokenClass::IDENTIFIER]);
+            }
+            (a_type, name, position, self.parse_dimensions())
+        };
+        self.expect(&[TokenClass::SC]);
+
+        let a_type = Parser::array_type(a_type, &dimensions);
+        self.declare(&name, Some(a_type.clone()));
+        let mut typedef_decl = TypedefDecl::new(a_type, name);
+        typedef_decl.position = position;
+        Some(typedef_decl)
+    }
+
+    // `enum name { A, B = 2 };` with an optional name and trailing comma, its type being int
+    fn parse_enum_decl(&mut self) -> Option<EnumDecl> {
+        if !self.at_enum_decl() {
+            return None;
+        }
+
+        let mut position = self.token.position;
+        self.next_token();
+        let mut name = String::from("");
+        if self.accept(&[TokenClass::IDENTIFIER]) {
+            name = self.token.data.to_owned();
+            position = self.token.position;
+            self.next_token();
+        }
+        self.expect(&[TokenClass::LBRA]);
+
+        let mut enumerators = Vec::new();
+        loop {
+            let mut enumerator_name = String::from("");
+            let enumerator_position = self.token.position;
+            if self.accept(&[TokenClass::IDENTIFIER]) {
+                enumerator_name = self.token.data.to_owned();
+                self.next_token();
+            } else {
+                self.expect(&[TokenClass::IDENTIFIER]);
+            }
+            let mut expr = None;
+            if self.accept(&[TokenClass::ASSIGN]) {
+                self.next_token();
+                expr = Some(self.parse_conditional());
+            }
+            let mut enumerator = Enumerator::new(enumerator_name, expr);
+            enumerator.position = enumerator_position;
+            enumerators.push(enumerator);
+
+            if !self.accept(&[TokenClass::COMMA]) { break; }
+            self.next_token();
+            if self.accept(&[TokenClass::RBRA]) { break; }
+        }
+        self.expect(&[TokenClass::RBRA]);
+        self.expect(&[TokenClass::SC]);
+
+        if !name.is_empty() {
+            self.enums.insert(name.to_owned());
+        }
+        let mut enum_decl = EnumDecl::new(name, enumerators);
+        enum_decl.position = position;
+        Some(enum_decl)
+    }
+
     fn parse_struct_decls(&mut self) -> Vec<StructTypeDecl> {
         let mut struct_decls = Vec::new();
         return_if!(!self.accept(&[TokenClass::STRUCT]) || !(self.look_a_head(2).token_class == TokenClass::LBRA); struct_decls);
 
         self.next_token();
         let mut struct_type_name = String::from("");
+        let position = self.token.position;
         if self.accept(&[TokenClass::IDENTIFIER]) {
             struct_type_name = self.token.data.to_owned();
             self.next_token();
         }
-        let struct_type = StructType::new(struct_type_name);
+        let mut struct_type = StructType::new(struct_type_name);
+        struct_type.position = position;
 
         self.expect(&[TokenClass::LBRA]);
 
+        self.fields = true;
         let var_decls = self.parse_var_decls();
+        self.fields = false;
 
         self.expect(&[TokenClass::RBRA]);
         self.expect(&[TokenClass::SC]);
@@ -164,59 +494,139 @@ impl Parser {
         struct_decls
     }
 
-    fn parse_var_decls(&mut self) -> Vec<VarDecl> {
-        let mut var_decls = Vec::new();
-        return_if!(!self.accept(&[TokenClass::INT, TokenClass::CHAR, TokenClass::VOID, TokenClass::STRUCT]); var_decls);
+    fn parse_class_decls(&mut self) -> Vec<ClassDecl> {
+        let mut class_decls = Vec::new();
+        return_if!(!self.accept(&[TokenClass::CLASS]) || !matches!(self.look_a_head(2).token_class, TokenClass::LBRA | TokenClass::EXTENDS); class_decls);
 
-        let tmp;
-        if self.accept(&[TokenClass::STRUCT]) {
-            tmp = if self.look_a_head(2).token_class == TokenClass::ASTERIX { self.look_a_head(4).token_class.clone() } else { self.look_a_head(3).token_class.clone() };
-        } else {
-            tmp = if self.look_a_head(1).token_class == TokenClass::ASTERIX { self.look_a_head(3).token_class.clone() } else { self.look_a_head(2).token_class.clone() };
+        self.next_token();
+        let class_type = self.parse_class_name();
+        let mut parent = None;
+        if self.accept(&[TokenClass::EXTENDS]) {
+            self.next_token();
+            parent = Some(self.parse_class_name());
         }
-        return_if!(tmp != TokenClass::SC && tmp != TokenClass::LSBR; var_decls);
 
-        let mut a_type = self.parse_type();
+        self.expect(&[TokenClass::LBRA]);
+        self.fields = true;
+        let var_decls = self.parse_var_decls();
+        self.fields = false;
+        let fun_decls = self.parse_fun_decls();
+        self.expect(&[TokenClass::RBRA]);
+
+        class_decls.push(ClassDecl::new(class_type, parent, var_decls, fun_decls));
+        class_decls.append(&mut self.parse_class_decls());
+        class_decls
+    }
 
-        let mut type_name= String::from("");
+    // the name following `class`, `extends` or `new class`
+    fn parse_class_name(&mut self) -> ClassType {
+        let mut class_type = ClassType::new(String::from(""));
+        class_type.position = self.token.position;
         if self.accept(&[TokenClass::IDENTIFIER]) {
-            type_name = self.token.data.to_owned();
+            class_type.name = self.token.data.to_owned();
             self.next_token();
+        } else {
+            self.expect(&[TokenClass::IDENTIFIER]);
         }
+        class_type
+    }
 
-        if self.accept(&[TokenClass::SC]) {
-            self.next_token();
+    fn parse_var_decls(&mut self) -> Vec<VarDecl> {
+        let mut var_decls = Vec::new();
+        return_if!(!self.at_declaration() || self.at_enum_decl(); var_decls);
+
+        let tmp = self.after_declarator();
+        return_if!(tmp != TokenClass::SC && tmp != TokenClass::LSBR && tmp != TokenClass::ASSIGN && tmp != TokenClass::ASTERIX; var_decls);
+
+        let storage = self.parse_storage();
+        let (a_type, is_const) = self.parse_qualified_type();
+        let (mut a_type, type_name, position, mut dimensions) = if self.at_fun_pointer() {
+            self.parse_fun_pointer(a_type)
         } else {
-            let mut i = 0;
-            self.expect(&[TokenClass::LSBR]);
-            if self.accept(&[TokenClass::INTLITERAL]) {
-                i = self.token.data.parse::<i32>().unwrap();
+            let mut type_name = String::from("");
+            let position = self.token.position;
+            if self.accept(&[TokenClass::IDENTIFIER]) {
+                type_name = self.token.data.to_owned();
                 self.next_token();
             }
-            self.expect(&[TokenClass::RSBR]);
-            self.expect(&[TokenClass::SC]);
-            a_type = Box::new(ArrayType::new(a_type, i));
+            (a_type, type_name, position, self.parse_dimensions())
+        };
+        // the name shadows a typedef from here on, its initialiser included
+        self.declare(&type_name, None);
+        let mut init = None;
+        if self.accept(&[TokenClass::ASSIGN]) {
+            self.next_token();
+            init = Some(self.parse_initializer());
         }
+        self.expect(&[TokenClass::SC]);
 
-        var_decls.push(VarDecl::new(a_type, type_name));
+        if let Some(size @ None) = dimensions.first_mut() {
+            // the size of an array left out is the one of its initialiser
+            *size = match &init {
+                Some(Initializer::List(items)) => Some(items.len() as i32),
+                Some(Initializer::Expr(expr)) => expr.as_any().downcast_ref::<StrLiteral>().map(|s| s.bytes().len() as i32 + 1),
+                None => None,
+            };
+        }
+        a_type = Parser::array_type(a_type, &dimensions);
+
+        let mut var_decl = VarDecl::new(a_type, type_name);
+        var_decl.init = init;
+        var_decl.is_const = is_const;
+        var_decl.storage = storage;
+        var_decl.position = position;
+        var_decls.push(var_decl);
         var_decls.append(&mut self.parse_var_decls());
         var_decls
     }
 
+    // an expression, or a list of initialisers in braces with an optional trailing comma
+    fn parse_initializer(&mut self) -> Initializer {
+        if !self.accept(&[TokenClass::LBRA]) {
+            return Initializer::Expr(self.parse_exp());
+        }
+        self.next_token();
+        let mut items = Vec::new();
+        while !self.accept(&[TokenClass::RBRA, TokenClass::EOF]) {
+            items.push(self.parse_initializer());
+            if !self.accept(&[TokenClass::COMMA]) {
+                break;
+            }
+            self.next_token();
+        }
+        self.expect(&[TokenClass::RBRA]);
+        Initializer::List(items)
+    }
+
     fn parse_fun_decls(&mut self) -> Vec<FunDecl> {
         let mut fun_decls = Vec::new();
-        return_if!(!self.accept(&[TokenClass::INT, TokenClass::CHAR, TokenClass::VOID, TokenClass::STRUCT]); fun_decls);
+        if let Some(mut fun_decl) = self.parse_fun_header() {
+            fun_decl.block = self.parse_block();
+            fun_decls.push(fun_decl);
+            fun_decls.append(&mut self.parse_fun_decls());
+        }
+        fun_decls
+    }
 
-        let tmp;
-        if self.accept(&[TokenClass::STRUCT]) {
-            tmp = if self.look_a_head(2).token_class == TokenClass::ASTERIX { self.look_a_head(4).token_class.clone() } else { self.look_a_head(3).token_class.clone() };
-        } else {
-            tmp = if self.look_a_head(1).token_class == TokenClass::ASTERIX { self.look_a_head(3).token_class.clone() } else { self.look_a_head(2).token_class.clone() };
+    // the type, name and parameters of a function, followed by its body or by `;` for a prototype
+    fn parse_fun_header(&mut self) -> Option<FunDecl> {
+        if !self.at_declaration() || self.after_declarator() != TokenClass::LPAR {
+            return None;
         }
-        return_if!(tmp != TokenClass::LPAR; fun_decls);
 
-        let a_type = self.parse_type();
+        let storage = self.parse_storage();
+        let mut a_type = self.parse_type();
+        // `int (*pick(int up))(int, int)` returns a pointer to a function taking two ints
+        let mut fun_pointers = 0;
+        if self.at_fun_pointer() {
+            self.next_token();
+            while self.accept(&[TokenClass::ASTERIX]) {
+                self.next_token();
+                fun_pointers += 1;
+            }
+        }
         let mut fun_name= String::from("");
+        let position = self.token.position;
         if self.accept(&[TokenClass::IDENTIFIER]){
             fun_name = self.token.data.to_owned();
             self.next_token();
@@ -224,28 +634,53 @@ impl Parser {
 
         self.expect(&[TokenClass::LPAR]);
         let params = self.parse_params();
-
+        self.params = params.iter().map(|param| param.var_name.to_owned()).collect();
         self.expect(&[TokenClass::RPAR]);
-        let block = self.parse_block();
+        if fun_pointers > 0 {
+            self.expect(&[TokenClass::RPAR]);
+            self.expect(&[TokenClass::LPAR]);
+            let param_types = self.parse_params().into_iter().map(|param| param.var_type).collect();
+            self.expect(&[TokenClass::RPAR]);
+            a_type = Box::new(FunType::new(a_type, param_types));
+            for _ in 0..fun_pointers {
+                a_type = PointerType::new(a_type);
+            }
+        }
 
-        fun_decls.push(FunDecl::new(a_type, fun_name, params, block));
-        fun_decls.append(&mut self.parse_fun_decls());
-        fun_decls
+        let mut fun_decl = FunDecl::new(a_type, fun_name, params, Block::new(vec![], vec![]));
+        fun_decl.storage = storage;
+        fun_decl.position = position;
+        Some(fun_decl)
     }
 
     fn parse_params(&mut self) -> Vec<VarDecl> {
         let mut params = Vec::new();
-        return_if!(!self.accept(&[TokenClass::INT, TokenClass::CHAR, TokenClass::VOID, TokenClass::STRUCT]); params);
+        return_if!(!self.at_type(); params);
 
         loop {
             if self.accept(&[TokenClass::COMMA]) { self.next_token(); }
-            let a_type = self.parse_type();
-            let mut var_name = String::from("");
-            if self.accept(&[TokenClass::IDENTIFIER]) {
-                var_name = self.token.data.to_owned();
-                self.next_token();
+            let (a_type, is_const) = self.parse_qualified_type();
+            let (mut a_type, var_name, position, dimensions) = if self.at_fun_pointer() {
+                self.parse_fun_pointer(a_type)
+            } else {
+                let mut var_name = String::from("");
+                let position = self.token.position;
+                if self.accept(&[TokenClass::IDENTIFIER]) {
+                    var_name = self.token.data.to_owned();
+                    self.next_token();
+                }
+                (a_type, var_name, position, self.parse_dimensions())
+            };
+            // an array parameter is a pointer to its first element, e.g. `int a[][3]` is a pointer to `int[3]`,
+            // and so is a parameter whose type is an array typedef
+            a_type = Parser::array_type(a_type, &dimensions);
+            if let Some(array_type) = a_type.as_array() {
+                a_type = PointerType::new(array_type.array_type.clone());
             }
-            params.push(VarDecl::new(a_type, var_name));
+            let mut param = VarDecl::new(a_type, var_name);
+            param.is_const = is_const;
+            param.position = position;
+            params.push(param);
 
             if !self.accept(&[TokenClass::COMMA]) { break; }
         }
@@ -255,17 +690,31 @@ impl Parser {
     fn parse_block(&mut self) -> Block {
 
         self.expect(&[TokenClass::LBRA]);
+        let scope = std::mem::take(&mut self.params).into_iter().map(|param| (param, None)).collect();
+        self.typedefs.push(scope);
 
-        let var_decls = self.parse_var_decls();
+        // a typedef of a block only names its type in the parser
+        let mut var_decls = Vec::new();
+        loop {
+            if self.parse_typedef_decl().is_some() {
+                continue;
+            }
+            let more = self.parse_var_decls();
+            if more.is_empty() {
+                break;
+            }
+            var_decls.extend(more);
+        }
         let mut stmts = Vec::new();
 
         while !self.accept(&[TokenClass::RBRA, TokenClass::EOF]) { stmts.push(self.parse_stmt()) };
         self.expect(&[TokenClass::RBRA]);
+        self.typedefs.pop();
         Block::new(var_decls, stmts)
     }
 
     fn parse_stmt(&mut self) -> Box<dyn Stmt> {
-        return if self.accept(&[TokenClass::LBRA]) {
+        if self.accept(&[TokenClass::LBRA]) {
             Box::new(self.parse_block())
         } else if self.accept(&[TokenClass::WHILE]) {
             self.next_token();
@@ -274,6 +723,15 @@ impl Parser {
             self.expect(&[TokenClass::RPAR]);
             let stmt = self.parse_stmt();
             While::new(expr, stmt)
+        } else if self.accept(&[TokenClass::DO]) {
+            self.next_token();
+            let stmt = self.parse_stmt();
+            self.expect(&[TokenClass::WHILE]);
+            self.expect(&[TokenClass::LPAR]);
+            let expr = self.parse_exp();
+            self.expect(&[TokenClass::RPAR]);
+            self.expect(&[TokenClass::SC]);
+            DoWhile::new(stmt, expr)
         } else if self.accept(&[TokenClass::IF]) {
             self.next_token();
             self.expect(&[TokenClass::LPAR]);
@@ -287,6 +745,34 @@ impl Parser {
                 stmt2 = Some(self.parse_stmt());
             }
             If::new(expr, stmt1, stmt2)
+        } else if self.accept(&[TokenClass::SWITCH]) {
+            self.next_token();
+            self.expect(&[TokenClass::LPAR]);
+            let expr = self.parse_exp();
+            self.expect(&[TokenClass::RPAR]);
+            self.expect(&[TokenClass::LBRA]);
+            let mut cases = Vec::new();
+
+            while self.accept(&[TokenClass::CASE, TokenClass::DEFAULT]) {
+                let position = self.token.position;
+                let label = if self.accept(&[TokenClass::CASE]) {
+                    self.next_token();
+                    Some(self.parse_exp())
+                } else {
+                    self.next_token();
+                    None
+                };
+                self.expect(&[TokenClass::COLON]);
+
+                let mut stmts = Vec::new();
+                while !self.accept(&[TokenClass::CASE, TokenClass::DEFAULT, TokenClass::RBRA, TokenClass::EOF]) { stmts.push(self.parse_stmt()) };
+                let mut case = Case::new(label, stmts);
+                case.position = position;
+                cases.push(case);
+            }
+            // a statement before the first label is reported as such
+            self.expect(&[TokenClass::CASE, TokenClass::DEFAULT, TokenClass::RBRA]);
+            Switch::new(expr, cases)
         } else if self.accept(&[TokenClass::RETURN]) {
             let mut expr = None;
             self.next_token();
@@ -298,206 +784,407 @@ impl Parser {
                 self.expect(&[TokenClass::SC]);
             }
             Return::new(expr)
+        } else if self.accept(&[TokenClass::FOR]) {
+            self.next_token();
+            self.expect(&[TokenClass::LPAR]);
+            let init = if self.accept(&[TokenClass::SC]) { None } else { Some(self.parse_simple_stmt()) };
+            self.expect(&[TokenClass::SC]);
+            let expr = if self.accept(&[TokenClass::SC]) { None } else { Some(self.parse_exp()) };
+            self.expect(&[TokenClass::SC]);
+            let step = if self.accept(&[TokenClass::RPAR]) { None } else { Some(self.parse_simple_stmt()) };
+            self.expect(&[TokenClass::RPAR]);
+            let stmt = self.parse_stmt();
+            For::new(init, expr, step, stmt)
+        } else if self.accept(&[TokenClass::BREAK]) {
+            self.next_token();
+            self.expect(&[TokenClass::SC]);
+            Break::new()
+        } else if self.accept(&[TokenClass::CONTINUE]) {
+            self.next_token();
+            self.expect(&[TokenClass::SC]);
+            Continue::new()
         } else {
-            let expr1 = self.parse_exp();
-            if self.accept(&[TokenClass::ASSIGN]) {
-                self.next_token();
-                let expr2 = self.parse_exp();
-                self.expect(&[TokenClass::SC]);
-                Assign::new(expr1, expr2)
-            } else {
-                self.expect(&[TokenClass::SC]);
-                ExprStmt::new(expr1)
-            }
+            let stmt = self.parse_simple_stmt();
+            self.expect(&[TokenClass::SC]);
+            stmt
+        }
+    }
+
+    // an assignment or an expression statement without its ';', as in the header of a for loop
+    fn parse_simple_stmt(&mut self) -> Box<dyn Stmt> {
+        let expr1 = self.parse_conditional();
+        if self.accept(&[TokenClass::ASSIGN]) {
+            self.next_token();
+            let expr2 = self.parse_exp();
+            Assign::new(expr1, expr2)
+        } else {
+            ExprStmt::new(self.parse_assignment(expr1))
         }
     }
 
     fn parse_exp(&mut self) -> Box<dyn Expr> {
-        let mut lhs = self.parse_term().unwrap();
-        while self.accept(&[TokenClass::DOT, TokenClass::LSBR, TokenClass::EQ, TokenClass::NE, TokenClass::LT, TokenClass::GT, TokenClass::LE, TokenClass::GE,
-            TokenClass::PLUS, TokenClass::MINUS, TokenClass::ASTERIX, TokenClass::DIV, TokenClass::REM, TokenClass::LOGAND, TokenClass::LOGOR]) {
-
-            // let mut pre = 0;
-            // let it = (&lhs).as_any();
-            // if let Some(lhs) = it.downcast_ref::<BinOp>() {
-            //     pre = Parser::parse_pre(lhs.op);
-            //     if let (4, Some(expr1)) = (pre, lhs.expr1.as_any().downcast_ref::<IntLiteral>()) {
-            //         if expr1.i == 0 { pre = 2; }
-            //     }
-            // } else if let Some(_) = it.downcast_ref::<TypecastExpr>() { pre = 2; }
-            // else if let Some(_) = it.downcast_ref::<ValueAtExpr>() { pre = 2; }
-            // else if let Some(_) = it.downcast_ref::<AddressOfExpr>() { pre = 2; }
-            //
-            // if lhs.get_is_grouped() { pre = 0; }
+        let lhs = self.parse_conditional();
+        self.parse_assignment(lhs)
+    }
+
+    // ?: binds looser than any binary operator and is right associative, its middle operand being
+    // any expression as it is delimited by the `:`
+    fn parse_conditional(&mut self) -> Box<dyn Expr> {
+        let expr = self.parse_bin_exp(Parser::parse_pre(Op::OR));
+        if !self.accept(&[TokenClass::QMARK]) {
+            return expr;
+        }
+        self.next_token();
+        let expr1 = self.parse_exp();
+        self.expect(&[TokenClass::COLON]);
+        let expr2 = self.parse_conditional();
+        ConditionalExpr::new(expr, expr1, expr2)
+    }
+
+    // assignments bind looser than ?: and are right associative
+    fn parse_assignment(&mut self, lhs: Box<dyn Expr>) -> Box<dyn Expr> {
+        let op = match self.token.token_class {
+            TokenClass::ASSIGN => None,
+            TokenClass::ADDASSIGN => Some(Op::ADD),
+            TokenClass::SUBASSIGN => Some(Op::SUB),
+            TokenClass::MULASSIGN => Some(Op::MUL),
+            TokenClass::DIVASSIGN => Some(Op::DIV),
+            TokenClass::REMASSIGN => Some(Op::MOD),
+            _ => return lhs,
+        };
+        self.next_token();
+        let rhs = self.parse_exp();
+        AssignExpr::new(lhs, op, rhs)
+    }
+
+    /*
+     * Parses a sequence of binary operators whose precedence is at most max_pre
+     * (lower values bind tighter, see parse_pre). All binary operators are left associative.
+     */
+    fn parse_bin_exp(&mut self, max_pre: i32) -> Box<dyn Expr> {
+        let mut lhs = self.parse_term().unwrap_or_else(|| IntLiteral::new("0".to_owned()));
+        while let Some(op) = self.parse_op() {
+            let pre = Parser::parse_pre(op);
+            if pre > max_pre {
+                break;
+            }
+            self.next_token();
+
+            let rhs = self.parse_bin_exp(pre - 1);
+            lhs = BinOp::new(lhs, op, rhs);
+        }
+        lhs
+    }
+
+    fn parse_term(&mut self) -> Option<Box<dyn Expr>> {
+        if self.accept(&[TokenClass::INC, TokenClass::DEC]) {
+            let op = if self.accept(&[TokenClass::INC]) { Op::ADD } else { Op::SUB };
+            self.next_token();
+            let expr = self.parse_term()?;
+            return Some(IncDecExpr::new(expr, op, true));
+        } else if self.accept(&[TokenClass::MINUS, TokenClass::PLUS, TokenClass::NOT, TokenClass::TILDE, TokenClass::ASTERIX, TokenClass::AND]) {
+            let t = self.token.token_class.clone();
+
+            self.next_token();
+            let expr = self.parse_term()?;
+
+            return if t == TokenClass::MINUS {
+                Some(BinOp::prefix(Op::SUB, expr))
+            } else if t == TokenClass::PLUS {
+                Some(BinOp::prefix(Op::ADD, expr))
+            } else if t == TokenClass::NOT {
+                // !e is 0 == e, and ~e flips every bit as -1 ^ e does
+                Some(BinOp::prefix(Op::EQ, expr))
+            } else if t == TokenClass::TILDE {
+                Some(BinOp::prefix(Op::BXOR, expr))
+            } else if t == TokenClass::ASTERIX {
+                Some(ValueAtExpr::new(expr))
+            } else {
+                Some(AddressOfExpr::new(expr))
+            }
+        } else if self.accept(&[TokenClass::LPAR]) && self.type_follows() {
+            self.next_token();
+            let mut a_type = self.parse_type();
+            if self.at_fun_pointer() {
+                let (fun_pointer, _, _, dimensions) = self.parse_fun_pointer(a_type);
+                a_type = Parser::array_type(fun_pointer, &dimensions);
+            }
+            self.expect(&[TokenClass::RPAR]);
+            let expr = self.parse_term()?;
+            return Some(TypecastExpr::new(a_type, expr));
+        }
+
+        let primary = self.parse_primary()?;
+        Some(self.parse_postfix(primary))
+    }
 
+    // calls, array accesses, field accesses and postfix increments bind tighter than any prefix operator
+    fn parse_postfix(&mut self, mut lhs: Box<dyn Expr>) -> Box<dyn Expr> {
+        loop {
             if self.accept(&[TokenClass::DOT]) {
                 self.next_token();
                 let mut name = String::from("");
+                let position = self.token.position;
                 if self.accept(&[TokenClass::IDENTIFIER]) {
                     name = self.token.data.to_owned();
                     self.next_token();
+                } else {
+                    self.expect(&[TokenClass::IDENTIFIER]);
                 }
-
-                // if pre != 0 {
-                //     if let Some(cast_lhs) = it.downcast_ref::<TypecastExpr>() {
-                //         lhs = TypecastExpr::new(cast_lhs.typecast_type.into(), FieldAccessExpr::new(cast_lhs.expr.into(), name));
-                //     } else if let Some(cast_lhs) = it.downcast_ref::<ValueAtExpr>() {
-                //         lhs = ValueAtExpr::new(FieldAccessExpr::new(cast_lhs.expr.into(), name));
-                //     } else if let Some(cast_lhs) = it.downcast_ref::<AddressOfExpr>() {
-                //         lhs = AddressOfExpr::new(FieldAccessExpr::new(cast_lhs.expr.into(), name));
-                //     } else if let Some(cast_lhs) = it.downcast_ref::<BinOp>() {
-                //         lhs = BinOp::new(cast_lhs.expr1.into(), cast_lhs.op, FieldAccessExpr::new(cast_lhs.expr2.into(), name));
-                //     }
-                // } else {
-                //     lhs = FieldAccessExpr::new( lhs, name);
-                // }
-                lhs = FieldAccessExpr::new( lhs, name);
+                if self.accept(&[TokenClass::LPAR]) {
+                    let exprs = self.parse_args();
+                    let mut method_call = MethodCallExpr::new(lhs, name, exprs);
+                    method_call.position = position;
+                    lhs = method_call;
+                    continue;
+                }
+                let mut field_access = FieldAccessExpr::new(lhs, name);
+                field_access.position = position;
+                lhs = field_access;
+            } else if self.accept(&[TokenClass::ARROW]) {
+                // p->f is (*p).f, the dereference being left ungrouped so that it can be told apart
+                self.next_token();
+                let mut name = String::from("");
+                let position = self.token.position;
+                if self.accept(&[TokenClass::IDENTIFIER]) {
+                    name = self.token.data.to_owned();
+                }
+                self.expect(&[TokenClass::IDENTIFIER]);
+                let mut field_access = FieldAccessExpr::new(ValueAtExpr::new(lhs), name);
+                field_access.position = position;
+                lhs = field_access;
+            } else if self.accept(&[TokenClass::LPAR]) {
+                // a call of the function a pointer points to, e.g. ops[i](x)
+                let position = self.token.position;
+                let exprs = self.parse_args();
+                let mut indirect_call = IndirectCallExpr::new(lhs, exprs);
+                indirect_call.position = position;
+                lhs = indirect_call;
             } else if self.accept(&[TokenClass::LSBR]) {
-
                 self.next_token();
                 let rhs = self.parse_exp();
                 self.expect(&[TokenClass::RSBR]);
-
-                // if pre != 0 {
-                //     if let Some(&cast_lhs) = it.downcast_ref::<TypecastExpr>() {
-                //         lhs = TypecastExpr::new(cast_lhs.typecast_type, ArrayAccessExpr::new(cast_lhs.expr, rhs));
-                //     } else if let Some(&cast_lhs) = it.downcast_ref::<ValueAtExpr>() {
-                //         lhs = ValueAtExpr::new(ArrayAccessExpr::new(cast_lhs.expr, rhs));
-                //     } else if let Some(&cast_lhs) = it.downcast_ref::<AddressOfExpr>() {
-                //         lhs = AddressOfExpr::new(ArrayAccessExpr::new(cast_lhs.expr, rhs));
-                //     } else if let Some(&cast_lhs) = it.downcast_ref::<BinOp>() {
-                //         if let Some(&expr2) = (&cast_lhs.expr2).as_any().downcast_ref::<BinOp>() {
-                //             let new_expr2 = BinOp::new(expr2.expr1, expr2.op, ArrayAccessExpr::new(expr2.expr2, rhs));
-                //             lhs = BinOp::new(cast_lhs.expr1, cast_lhs.op, new_expr2);
-                //         } else {
-                //             lhs = BinOp::new(cast_lhs.expr1, cast_lhs.op, ArrayAccessExpr::new(cast_lhs.expr2, rhs));
-                //         }
-                //     }
-                // } else {
-                //     lhs = ArrayAccessExpr::new(lhs.into(), rhs);
-                // }
                 lhs = ArrayAccessExpr::new(lhs, rhs);
-            } else {
-                let op = self.parse_op().unwrap();
+            } else if self.accept(&[TokenClass::INC, TokenClass::DEC]) {
+                let op = if self.accept(&[TokenClass::INC]) { Op::ADD } else { Op::SUB };
                 self.next_token();
+                lhs = IncDecExpr::new(lhs, op, false);
+            } else {
+                return lhs;
+            }
+        }
+    }
 
-                let rhs = self.parse_term().unwrap();
-
-                // if let (true, true, Some(&case_lhs)) = (pre != 0, pre > Parser::parse_pre(op), it.downcast_ref::<BinOp>()) {
-                //     lhs = BinOp::new(case_lhs.expr1, case_lhs.op, BinOp::new(case_lhs.expr2, op, rhs));
-                // } else {
-                //     lhs = BinOp::new(lhs.into(), op, rhs);
-                // }
-                lhs = BinOp::new(lhs, op, rhs);
+    // the arguments of a call in parentheses, the current token being the opening one
+    fn parse_args(&mut self) -> Vec<Box<dyn Expr>> {
+        let mut exprs = Vec::new();
+        if self.look_a_head(1).token_class != TokenClass::RPAR {
+            loop {
+                self.next_token();
+                exprs.push(self.parse_exp());
+                if !self.accept(&[TokenClass::COMMA]) { break; }
             }
+        } else {
+            self.next_token();
         }
-        lhs
+        self.expect(&[TokenClass::RPAR]);
+        exprs
     }
 
-    fn parse_term(&mut self) -> Option<Box<dyn Expr>> {
+    fn parse_primary(&mut self) -> Option<Box<dyn Expr>> {
         if self.accept(&[TokenClass::LPAR]) {
             self.next_token();
-            return if self.accept(&[TokenClass::INT, TokenClass::CHAR, TokenClass::VOID, TokenClass::STRUCT]) {
-                let a_type = self.parse_type();
-                self.expect(&[TokenClass::RPAR]);
-                let expr = self.parse_term().unwrap();
-                Some(TypecastExpr::new(a_type, expr))
-            } else {
-                let mut expr = self.parse_exp();
-                self.expect(&[TokenClass::RPAR]);
-                expr.set_is_grouped(true);
-                Some(expr)
-            }
+            let mut expr = self.parse_exp();
+            self.expect(&[TokenClass::RPAR]);
+            expr.set_is_grouped(true);
+            Some(expr)
         } else if self.accept(&[TokenClass::IDENTIFIER]) {
             let name = self.token.data.to_owned();
+            let position = self.token.position;
             self.next_token();
 
             if self.accept(&[TokenClass::LPAR]) {
-                let mut exprs = Vec::new();
-                if self.look_a_head(1).token_class != TokenClass::RPAR {
-                    loop {
-                        self.next_token();
-                        exprs.push(self.parse_exp());
-                        if !self.accept(&[TokenClass::COMMA]) { break; }
-                    }
-                } else {
-                    self.next_token();
-                }
-                self.expect(&[TokenClass::RPAR]);
-                return Some(FunCallExpr::new(name,exprs));
+                let exprs = self.parse_args();
+                let mut fun_call = FunCallExpr::new(name,exprs);
+                fun_call.position = position;
+                return Some(fun_call);
             }
-            return Some(VarExpr::new(name));
-        } else if self.accept(&[TokenClass::MINUS, TokenClass::PLUS, TokenClass::ASTERIX, TokenClass::AND]) {
-            let t = self.token.token_class.clone();
-
+            let mut var_expr = VarExpr::new(name);
+            var_expr.position = position;
+            Some(var_expr)
+        } else if self.accept(&[TokenClass::NEW]) {
             self.next_token();
-            let expr = self.parse_term().unwrap();
-
-            return if t == TokenClass::MINUS {
-                Some(BinOp::new(IntLiteral::new("0".to_owned()), Op::SUB, expr))
-            } else if t == TokenClass::PLUS {
-                Some(BinOp::new(IntLiteral::new("0".to_owned()), Op::ADD, expr))
-            } else if t == TokenClass::ASTERIX {
-                Some(ValueAtExpr::new(expr))
-            } else {
-                Some(AddressOfExpr::new(expr))
-            }
+            self.expect(&[TokenClass::CLASS]);
+            let class_type = self.parse_class_name();
+            self.expect(&[TokenClass::LPAR]);
+            self.expect(&[TokenClass::RPAR]);
+            Some(NewExpr::new(class_type))
         } else if self.accept(&[TokenClass::SIZEOF]) {
             self.next_token();
             self.expect(&[TokenClass::LPAR]);
 
+            // the size of an array type is written with its dimensions, e.g. sizeof(int[3])
             let a_type = self.parse_type();
+            let (a_type, dimensions) = if self.at_fun_pointer() {
+                let (fun_pointer, _, _, dimensions) = self.parse_fun_pointer(a_type);
+                (fun_pointer, dimensions)
+            } else {
+                let dimensions = self.parse_dimensions();
+                (a_type, dimensions)
+            };
 
             self.expect(&[TokenClass::RPAR]);
 
-            return Some(SizeOfExpr::new(a_type));
+            Some(SizeOfExpr::new(Parser::array_type(a_type, &dimensions)))
         } else if self.accept(&[TokenClass::INTLITERAL, TokenClass::CHARLITERAL, TokenClass::STRINGLITERAL]) {
-            let expr: Box<dyn Expr>;
             if self.token.token_class == TokenClass::INTLITERAL {
-                expr = IntLiteral::new(self.token.data.to_owned());
-            } else if self.token.token_class == TokenClass::CHARLITERAL {
-                expr = ChrLiteral::new(self.token.data.to_owned());
-            } else {
-                expr = StrLiteral::new(self.token.data.to_owned());
+                return Some(self.parse_int_literal());
             }
+            let expr: Box<dyn Expr> = if self.token.token_class == TokenClass::CHARLITERAL {
+                ChrLiteral::new(self.token.data.to_owned())
+            } else {
+                StrLiteral::new(self.token.data.to_owned())
+            };
 
             self.next_token();
-            return Some(expr);
+            Some(expr)
         } else {
             self.expect(&[TokenClass::LPAR, TokenClass::IDENTIFIER,
-                   TokenClass::MINUS, TokenClass::PLUS, TokenClass::ASTERIX, TokenClass::AND,
-                   TokenClass::SIZEOF, TokenClass::INTLITERAL, TokenClass::CHARLITERAL, TokenClass::STRINGLITERAL]);
+                   TokenClass::MINUS, TokenClass::PLUS, TokenClass::NOT, TokenClass::TILDE, TokenClass::ASTERIX, TokenClass::AND,
+                   TokenClass::INC, TokenClass::DEC,
+                   TokenClass::NEW, TokenClass::SIZEOF, TokenClass::INTLITERAL, TokenClass::CHARLITERAL, TokenClass::STRINGLITERAL]);
             self.next_token();
             None
         }
     }
 
+    // `static` or `extern`, if any
+    fn parse_storage(&mut self) -> StorageClass {
+        if self.accept(&[TokenClass::STATIC]) {
+            self.next_token();
+            StorageClass::Static
+        } else if self.accept(&[TokenClass::EXTERN]) {
+            self.next_token();
+            StorageClass::Extern
+        } else {
+            StorageClass::Auto
+        }
+    }
+
+    // whether a `const` follows, which may be repeated
+    fn parse_const(&mut self) -> bool {
+        let mut is_const = false;
+        while self.accept(&[TokenClass::CONST]) {
+            self.next_token();
+            is_const = true;
+        }
+        is_const
+    }
+
+    // a type outside of a declaration, where a `const` qualifying the value itself does not matter
     fn parse_type(&mut self) -> Box<dyn Type> {
+        self.parse_qualified_type().0
+    }
+
+    // a type with its `const` qualifiers, either before or after what they qualify, e.g. `const char* const`
+    // is a const pointer to const chars. Returns whether the declared value itself is const
+    fn parse_qualified_type(&mut self) -> (Box<dyn Type>, bool) {
+        let mut is_const = self.parse_const();
         let mut a_type: Option<Box<dyn Type>> = None;
         if self.accept(&[TokenClass::STRUCT]) {
             self.next_token();
             if self.accept(&[TokenClass::IDENTIFIER]) {
-                a_type = Some(Box::new(StructType::new(self.token.data.to_owned())));
+                let mut struct_type = StructType::new(self.token.data.to_owned());
+                struct_type.position = self.token.position;
+                a_type = Some(Box::new(struct_type));
                 self.next_token();
+            } else {
+                self.expect(&[TokenClass::IDENTIFIER]);
             }
-        } else {
-            if self.accept(&[TokenClass::INT]) {
-                a_type = Some(Box::new(BaseType::INT));
-                self.next_token();
-            } else if self.accept(&[TokenClass::VOID]) {
-                a_type = Some(Box::new(BaseType::VOID));
+        } else if self.accept(&[TokenClass::CLASS]) {
+            self.next_token();
+            if self.accept(&[TokenClass::IDENTIFIER]) {
+                let mut class_type = ClassType::new(self.token.data.to_owned());
+                class_type.position = self.token.position;
+                a_type = Some(Box::new(class_type));
                 self.next_token();
-            } else if self.accept(&[TokenClass::CHAR]) {
-                a_type = Some(Box::new(BaseType::CHAR));
+            } else {
+                self.expect(&[TokenClass::IDENTIFIER]);
+            }
+        } else if self.accept(&[TokenClass::ENUM]) {
+            // the constants of an enum are plain ints
+            self.next_token();
+            if self.accept(&[TokenClass::IDENTIFIER]) {
+                if !self.enums.contains(&self.token.data) {
+                    self.report(format!("enum {} is not declared", self.token.data));
+                }
                 self.next_token();
+            } else {
+                self.expect(&[TokenClass::IDENTIFIER]);
+            }
+            a_type = Some(Box::new(BaseType::INT));
+        } else if self.accept(&[TokenClass::IDENTIFIER]) {
+            a_type = self.typedef(&self.token.data).map(|a_type| a_type.clone_box());
+            if a_type.is_none() {
+                self.report(format!("{} is not a type", self.token.data));
             }
+            self.next_token();
+        } else if self.accept(&[TokenClass::VOID]) {
+            a_type = Some(Box::new(BaseType::VOID));
+            self.next_token();
+        } else {
+            a_type = Some(Box::new(self.parse_integer_type()));
+        }
+
+        // the error is reported, int standing for the missing type
+        let mut a_type = a_type.unwrap_or_else(|| Box::new(BaseType::INT));
+        is_const |= self.parse_const();
+        while self.accept(&[TokenClass::ASTERIX]) {
+            self.next_token();
+            a_type = if is_const { PointerType::to_const(a_type) } else { PointerType::new(a_type) };
+            is_const = self.parse_const();
         }
+        (a_type, is_const)
+    }
 
-        if self.accept(&[TokenClass::ASTERIX]) {
+    // a sequence of integer specifiers in any order, e.g. `unsigned char`, `long long` or `short int`.
+    // A plain `char` is neither `signed char` nor `unsigned char`
+    fn parse_integer_type(&mut self) -> BaseType {
+        let mut words = Vec::new();
+        while Parser::is_integer_specifier(&self.token.token_class) {
+            words.push(self.token.token_class.clone());
             self.next_token();
-            return PointerType::new(a_type.unwrap());
         }
-        a_type.unwrap()
+        let count = |word: TokenClass| words.iter().filter(|w| **w == word).count();
+        let (signed, unsigned) = (count(TokenClass::SIGNED), count(TokenClass::UNSIGNED));
+        let (char, short, long, int) = (count(TokenClass::CHAR), count(TokenClass::SHORT), count(TokenClass::LONG), count(TokenClass::INT));
+        let valid = signed + unsigned <= 1 && int <= 1 && long <= 2
+            && (char == 0 || short + long + int == 0) && (short == 0 || long == 0) && char <= 1 && short <= 1;
+        if !valid {
+            let words: Vec<String> = words.iter().map(|w| w.to_string().to_lowercase()).collect();
+            self.report(format!("invalid combination of type specifiers {}", words.join(" ")));
+        }
+        match (char, short, long, unsigned) {
+            (1.., _, _, _) if signed == 1 => BaseType::SCHAR,
+            (1.., _, _, 1) => BaseType::UCHAR,
+            (1.., _, _, _) => BaseType::CHAR,
+            (_, 1.., _, 0) => BaseType::SHORT,
+            (_, 1.., _, _) => BaseType::USHORT,
+            (_, _, 1.., 0) => BaseType::LONG,
+            (_, _, 1.., _) => BaseType::ULONG,
+            (_, _, _, 0) => BaseType::INT,
+            _ => BaseType::UINT,
+        }
+    }
+
+    // an integer literal, whose value must fit its type
+    fn parse_int_literal(&mut self) -> Box<IntLiteral> {
+        if IntLiteral::parse(&self.token.data).is_none() {
+            self.report(format!("invalid integer literal {}", self.token.data));
+        }
+        let int_literal = IntLiteral::new(self.token.data.to_owned());
+        self.next_token();
+        int_literal
     }
 
     fn parse_op(&self) -> Option<Op> {
@@ -513,20 +1200,29 @@ impl Parser {
             TokenClass::ASTERIX => Some(Op::MUL),
             TokenClass::DIV => Some(Op::DIV),
             TokenClass::REM => Some(Op::MOD),
+            TokenClass::SHL => Some(Op::SHL),
+            TokenClass::SHR => Some(Op::SHR),
+            TokenClass::AND => Some(Op::BAND),
+            TokenClass::XOR => Some(Op::BXOR),
+            TokenClass::OR => Some(Op::BOR),
             TokenClass::LOGAND => Some(Op::AND),
             TokenClass::LOGOR => Some(Op::OR),
             _ => None
         }
     }
 
-    fn parse_pre(op: Op) -> i32 {
+    pub fn parse_pre(op: Op) -> i32 {
         match op {
             Op::MUL | Op::DIV | Op::MOD => 3,
             Op::ADD | Op::SUB => 4,
-            Op::LT  | Op::GT | Op::LE | Op::GE => 5,
-            Op::EQ  | Op::NE => 6,
-            Op::AND => 7,
-            _ => 8
+            Op::SHL | Op::SHR => 5,
+            Op::LT  | Op::GT | Op::LE | Op::GE => 6,
+            Op::EQ  | Op::NE => 7,
+            Op::BAND => 8,
+            Op::BXOR => 9,
+            Op::BOR => 10,
+            Op::AND => 11,
+            Op::OR => 12
         }
     }
 }
\ No newline at end of file
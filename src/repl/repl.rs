@@ -0,0 +1,330 @@
+use std::io::{stdout, BufRead, BufWriter, Write};
+use crate::ast::ast_node::ASTNode;
+use crate::ast::ast_printer::ASTPrinter;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{FunDecl, Program, StorageClass};
+use crate::ast::expr::Expr;
+use crate::ast::stmt::{Block, ExprStmt, Return, Stmt};
+use crate::ast::types::{BaseType, Type};
+use crate::ast::unparser::escape_char;
+use crate::lexer::scanner::Scanner;
+use crate::lexer::tokenizer::Tokenizer;
+use crate::lower::storage_lowering::StorageLowering;
+use crate::parser::parser::Parser;
+use crate::sem::semantic_analyzer::SemanticAnalyzer;
+use crate::vm::compiler::BytecodeCompiler;
+use crate::vm::vm::VM;
+
+// the function wrapping the statements of an entry, named so that it cannot clash with the user's
+const ENTRY: &str = "$repl";
+// every entry runs with a data segment of this size, so that the stack and the heap stay in place
+const DATA_SIZE: u32 = 1 << 16;
+
+const HELP: &str = "Enter declarations, statements or expressions; a missing final ';' is added.
+  :ast <code>    print the AST of the code without running it
+  :type <expr>   print the type of an expression without evaluating it
+  :reset         forget every declaration and the values of the globals
+  :help          print this message
+  :quit          exit";
+
+/// Repl
+/// Reads minic code interactively. Declarations are added to a persistent session, statements run
+/// one by one on the bytecode VM, and for each expression statement the AST, the type and the value
+/// are printed. The memory of the VM is kept between entries so globals and the heap persist.
+pub struct Repl {
+    session: Program,
+    memory: Vec<u8>,
+    // size of the globals the memory holds the values of
+    kept: usize,
+}
+
+fn empty_program() -> Program {
+    Program::new(vec![], vec![], vec![])
+}
+
+// the depth of braces and parentheses left open, skipping literals and comments
+fn open_depth(text: &str) -> i32 {
+    let chars: Vec<char> = text.chars().collect();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth -= 1,
+            '"' | '\'' => {
+                let quote = chars[i];
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    // an unterminated comment waits for more lines as well
+                    return depth + 1;
+                }
+                i += 1;
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+    depth
+}
+
+fn is_scalar(a_type: &dyn Type) -> bool {
+    a_type.as_integer().is_some() || a_type.as_pointer().is_some()
+}
+
+fn show_value(a_type: &dyn Type, value: i64) -> String {
+    if a_type.is_base(BaseType::CHAR) {
+        format!("'{}'", escape_char(value as u8 as char))
+    } else if a_type.as_pointer().is_some() {
+        format!("0x{:x}", value)
+    } else if a_type.is_base(BaseType::ULONG) {
+        (value as u64).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn print_ast(node: &mut dyn FnMut(&mut ASTPrinter)) {
+    let mut printer = ASTPrinter::new(BufWriter::new(stdout()));
+    node(&mut printer);
+    drop(printer);
+    println!();
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            session: empty_program(),
+            memory: Vec::new(),
+            kept: 0,
+        }
+    }
+
+    /// Runs the loop until the end of the input or `:quit`. Programs read their input from the same
+    /// stream as the REPL.
+    pub fn run(&mut self, input: &mut dyn BufRead) {
+        println!("minic REPL, :help for the commands");
+        loop {
+            let mut text = String::new();
+            loop {
+                print!("{}", if text.is_empty() { "minic> " } else { "   ...> " });
+                stdout().flush().unwrap();
+                let mut line = String::new();
+                if input.read_line(&mut line).unwrap_or(0) == 0 {
+                    println!();
+                    return;
+                }
+                text.push_str(&line);
+                if open_depth(&text) <= 0 {
+                    break;
+                }
+            }
+
+            let text = text.trim();
+            if let Some(command) = text.strip_prefix(':') {
+                let (name, argument) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+                match name {
+                    "ast" => self.ast(argument),
+                    "type" => self.type_of(argument),
+                    "reset" => *self = Repl::new(),
+                    "help" => println!("{}", HELP),
+                    "quit" | "q" => return,
+                    _ => println!("unknown command :{}, :help for the list", name),
+                }
+            } else if !text.is_empty() {
+                self.evaluate(text, input);
+            }
+        }
+    }
+
+    // parses an entry, knowing the typedefs and enums of the session, reporting the errors
+    fn parse(&self, text: &str) -> Option<(Program, Vec<Box<dyn Stmt>>)> {
+        let mut source = text.to_owned();
+        if !source.ends_with(';') && !source.ends_with('}') {
+            source.push(';');
+        }
+        let mut parser = Parser::new(Tokenizer::new(Scanner::from_string(&source))).with_types_of(&self.session);
+        let entry = parser.parse_entry();
+        if parser.get_diagnostics().is_empty() { Some(entry) } else { None }
+    }
+
+    // the session extended with the declarations of an entry and the function running its statements
+    fn program(&self, decls: &Program, fun_type: Box<dyn Type>, stmts: Vec<Box<dyn Stmt>>) -> Program {
+        let mut program = self.session.clone();
+        program.typedef_decls.extend(decls.typedef_decls.iter().cloned());
+        program.enum_decls.extend(decls.enum_decls.iter().cloned());
+        program.struct_type_decls.extend(decls.struct_type_decls.iter().cloned());
+        program.var_decls.extend(decls.var_decls.iter().cloned());
+        program.prototypes.extend(decls.prototypes.iter().cloned());
+        program.fun_decls.extend(decls.fun_decls.iter().cloned());
+        program.fun_decls.push(FunDecl::new(fun_type, ENTRY.to_owned(), vec![], Block::new(vec![], stmts)));
+        program
+    }
+
+    fn analyze(program: &mut Program, quiet: bool) -> bool {
+        let mut sem = if quiet { SemanticAnalyzer::new().quiet() } else { SemanticAnalyzer::new() };
+        sem.analyze(program);
+        sem.get_error_count() == 0
+    }
+
+    fn ast(&mut self, text: &str) {
+        if let Some((mut decls, mut stmts)) = self.parse(text) {
+            for typedef_decl in decls.typedef_decls.iter_mut() {
+                print_ast(&mut |printer| printer.print_typedef_decl(typedef_decl));
+            }
+            for enum_decl in decls.enum_decls.iter_mut() {
+                print_ast(&mut |printer| printer.print_enum_decl(enum_decl));
+            }
+            for struct_type_decl in decls.struct_type_decls.iter_mut() {
+                print_ast(&mut |printer| struct_type_decl.accept(printer));
+            }
+            for class_decl in decls.class_decls.iter_mut() {
+                print_ast(&mut |printer| class_decl.accept(printer));
+            }
+            for var_decl in decls.var_decls.iter_mut() {
+                print_ast(&mut |printer| var_decl.accept(printer));
+            }
+            for prototype in decls.prototypes.iter_mut() {
+                print_ast(&mut |printer| printer.print_prototype(prototype));
+            }
+            for fun_decl in decls.fun_decls.iter_mut() {
+                print_ast(&mut |printer| fun_decl.accept(printer));
+            }
+            for stmt in stmts.iter_mut() {
+                print_ast(&mut |printer| printer.visit_stmt(stmt));
+            }
+        }
+    }
+
+    fn type_of(&mut self, text: &str) {
+        let (decls, stmts) = match self.parse(text) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let is_expr = stmts.len() == 1 && stmts[0].as_any().downcast_ref::<ExprStmt>().is_some();
+        if !is_expr || !decls.items.is_empty() {
+            println!(":type expects a single expression");
+            return;
+        }
+        let mut program = self.program(&decls, Box::new(BaseType::VOID), stmts);
+        if Repl::analyze(&mut program, false) {
+            let stmt = &program.fun_decls.last().unwrap().block.stmts[0];
+            println!("{}", stmt.as_any().downcast_ref::<ExprStmt>().unwrap().expr.get_expr_type());
+        }
+    }
+
+    fn evaluate(&mut self, text: &str, input: &mut dyn BufRead) {
+        let (decls, stmts) = match self.parse(text) {
+            Some(entry) => entry,
+            None => return,
+        };
+        if !decls.class_decls.is_empty() {
+            // the VM runs the session unlowered, so has no vtables to dispatch through
+            println!("classes are not supported in the REPL");
+            return;
+        }
+        if decls.var_decls.iter().any(|var_decl| var_decl.storage == StorageClass::Extern) {
+            // the session is a single program, with nothing to link an extern global to
+            println!("extern variables are not supported in the REPL");
+            return;
+        }
+        // the whole entry is checked before anything is declared or run
+        let mut program = self.program(&decls, Box::new(BaseType::VOID), stmts.clone());
+        if StorageLowering::has_static_locals(&program) {
+            // the VM runs the session unlowered, so would give each call a static local of its own
+            println!("static local variables are not supported in the REPL");
+            return;
+        }
+        if !Repl::analyze(&mut program, false) {
+            return;
+        }
+
+        for typedef_decl in decls.typedef_decls.iter() {
+            println!("defined type {}", typedef_decl.name);
+        }
+        for enum_decl in decls.enum_decls.iter() {
+            let names: Vec<&str> = enum_decl.enumerators.iter().map(|enumerator| enumerator.name.as_str()).collect();
+            println!("defined enum {}{{ {} }}", if enum_decl.name.is_empty() { String::new() } else { format!("{} ", enum_decl.name) }, names.join(", "));
+        }
+        for struct_type_decl in decls.struct_type_decls.iter() {
+            println!("defined struct {}", struct_type_decl.struct_type.name);
+        }
+        for var_decl in decls.var_decls.iter() {
+            println!("declared {} : {}", var_decl.var_name, var_decl.var_type);
+        }
+        for fun_decl in decls.fun_decls.iter() {
+            println!("defined {}", fun_decl.name);
+        }
+        self.session = self.program(&decls, Box::new(BaseType::VOID), vec![]);
+        self.session.fun_decls.pop();
+
+        for stmt in stmts {
+            if !self.execute(stmt, input) {
+                break;
+            }
+        }
+    }
+
+    // runs a statement, printing the AST, type and value of an expression; false on a runtime error
+    fn execute(&mut self, stmt: Box<dyn Stmt>, input: &mut dyn BufRead) -> bool {
+        let mut program = self.program(&empty_program(), Box::new(BaseType::VOID), vec![stmt]);
+        Repl::analyze(&mut program, true);
+        let stmt = program.fun_decls.last().unwrap().block.stmts[0].clone();
+
+        let mut expr: Option<Box<dyn Expr>> = stmt.as_any().downcast_ref::<ExprStmt>().map(|expr_stmt| expr_stmt.expr.clone());
+        if let Some(expr) = expr.as_mut() {
+            print_ast(&mut |printer| printer.visit_expr(expr));
+        }
+        // a scalar is returned by the entry function, leaving its value on the stack of the VM
+        let result_type = expr.as_ref().map(|expr| expr.get_expr_type().clone_box());
+        if let (Some(expr), Some(result_type)) = (expr, result_type.as_ref()) {
+            if is_scalar(result_type.as_ref()) {
+                program = self.program(&empty_program(), result_type.clone(), vec![Return::new(Some(expr))]);
+                Repl::analyze(&mut program, true);
+            }
+        }
+
+        let mut module = BytecodeCompiler::compile_with_entry(&mut program, ENTRY);
+        if module.data_size > DATA_SIZE {
+            println!("Runtime error: the globals and strings do not fit in the {} bytes of the REPL", DATA_SIZE);
+            return false;
+        }
+        module.data_size = DATA_SIZE;
+        let memory = std::mem::take(&mut self.memory);
+        let mut vm = VM::with_memory(&module, memory, self.kept, Box::new(&mut *input), Box::new(BufWriter::new(stdout())));
+        let outcome = vm.run();
+        let value = vm.result();
+        self.memory = vm.into_memory();
+        self.kept = BytecodeCompiler::globals_size(&self.session) as usize;
+
+        if let Err(e) = outcome {
+            println!();
+            println!("Runtime error: {}", e);
+            return false;
+        }
+        match result_type {
+            Some(result_type) if is_scalar(result_type.as_ref()) => {
+                println!(": {} = {}", result_type, show_value(result_type.as_ref(), value.unwrap_or(0)));
+            },
+            Some(result_type) if !result_type.is_base(BaseType::VOID) => println!(": {}", result_type),
+            _ => {},
+        }
+        true
+    }
+}
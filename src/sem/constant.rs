@@ -0,0 +1,65 @@
+use crate::ast::expr::{BinOp, ChrLiteral, ConditionalExpr, Expr, IntLiteral, Op, SizeOfExpr, TypecastExpr};
+use crate::ast::types::BaseType;
+
+/// The value of a constant expression, i.e. integer and char literals and the sizes of types
+/// combined by the binary operators, ?: and casts to integer types, computed as the backends do at run time. None if the
+/// expression is not constant or divides by zero; like at run time, the operands which are not
+/// evaluated do not matter. Once typed, each operator computes in the type of its operands, as
+/// converted by the type checker, and before that, e.g. for enumerators, in int.
+pub fn constant_value(expr: &dyn Expr) -> Option<i64> {
+    if let Some(int_literal) = expr.as_any().downcast_ref::<IntLiteral>() {
+        Some(int_literal.i)
+    } else if let Some(chr_literal) = expr.as_any().downcast_ref::<ChrLiteral>() {
+        Some(chr_literal.c as i64)
+    } else if let Some(size_of_expr) = expr.as_any().downcast_ref::<SizeOfExpr>() {
+        // known once typed
+        size_of_expr.size.map(i64::from)
+    } else if let Some(typecast_expr) = expr.as_any().downcast_ref::<TypecastExpr>() {
+        let int_type = typecast_expr.typecast_type.as_integer()?;
+        Some(int_type.wrap(constant_value(typecast_expr.expr.as_ref())?))
+    } else if let Some(bin_op) = expr.as_any().downcast_ref::<BinOp>() {
+        let v1 = constant_value(bin_op.expr1.as_ref())?;
+        match (bin_op.op, v1) {
+            (Op::AND, 0) => return Some(0),
+            (Op::OR, v1) if v1 != 0 => return Some(1),
+            _ => { },
+        }
+        let v2 = constant_value(bin_op.expr2.as_ref())?;
+        let t = bin_op.expr1.get_expr_type().as_integer().unwrap_or(BaseType::INT);
+        // the values are sign or zero extended, so that unsigned ones compare and divide as u64
+        let (u1, u2) = (v1 as u64, v2 as u64);
+        let signed = t.is_signed();
+        let amount = (v2 & if t.size() == 8 { 63 } else { 31 }) as u32;
+        let value = match bin_op.op {
+            Op::ADD => v1.wrapping_add(v2),
+            Op::SUB => v1.wrapping_sub(v2),
+            Op::MUL => v1.wrapping_mul(v2),
+            Op::DIV | Op::MOD if v2 == 0 => return None,
+            Op::DIV => if signed { v1.wrapping_div(v2) } else { (u1 / u2) as i64 },
+            Op::MOD => if signed { v1.wrapping_rem(v2) } else { (u1 % u2) as i64 },
+            Op::GT => return Some(if signed { v1 > v2 } else { u1 > u2 } as i64),
+            Op::LT => return Some(if signed { v1 < v2 } else { u1 < u2 } as i64),
+            Op::GE => return Some(if signed { v1 >= v2 } else { u1 >= u2 } as i64),
+            Op::LE => return Some(if signed { v1 <= v2 } else { u1 <= u2 } as i64),
+            Op::NE => return Some((v1 != v2) as i64),
+            Op::EQ => return Some((v1 == v2) as i64),
+            Op::OR => return Some((v1 != 0 || v2 != 0) as i64),
+            Op::AND => return Some((v1 != 0 && v2 != 0) as i64),
+            Op::BOR => v1 | v2,
+            Op::BAND => v1 & v2,
+            Op::BXOR => v1 ^ v2,
+            // the backends only use the low 5 bits of the amount, or 6 for 64 bit values
+            Op::SHL => v1 << amount,
+            Op::SHR => if signed { v1 >> amount } else { (u1 >> amount) as i64 },
+        };
+        Some(t.wrap(value))
+    } else if let Some(conditional_expr) = expr.as_any().downcast_ref::<ConditionalExpr>() {
+        if constant_value(conditional_expr.expr.as_ref())? != 0 {
+            constant_value(conditional_expr.expr1.as_ref())
+        } else {
+            constant_value(conditional_expr.expr2.as_ref())
+        }
+    } else {
+        None
+    }
+}
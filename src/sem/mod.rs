@@ -0,0 +1,6 @@
+pub mod scope;
+pub mod stdlib;
+pub mod constant;
+pub mod name_analyzer;
+pub mod type_analyzer;
+pub mod semantic_analyzer;
@@ -0,0 +1,859 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::lexer::position::{Diagnostic, Position};
+use crate::ast::decl::{ClassDecl, EnumDecl, FunDecl, Item, Program, StorageClass, StructTypeDecl, TypedefDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::sem::constant::constant_value;
+use crate::sem::scope::{Scope, Symbol};
+use crate::sem::stdlib;
+use crate::ASTNode;
+
+/// NameAnalyzer
+/// Resolves every variable and function use to its declaration (filling `VarExpr::var_decl` and
+/// `FunCallExpr::fun_decl`) and checks that struct types, classes, variables and functions are
+/// declared once. In a method, the fields and methods of its class used by their name alone are
+/// rewritten as accesses through the implicit parameter `this`, the enumerators are replaced by
+/// their value, and the functions used as values by their address. The identifiers starting with a
+/// double underscore are reserved for those introduced by the inliner and the lowerings.
+pub struct NameAnalyzer {
+    scope: Scope,
+    struct_names: HashSet<String>,
+    class_names: HashSet<String>,
+    // replaced by their type in the parser, so a variable or a function cannot have one of these names
+    typedef_names: HashSet<String>,
+    enum_names: HashSet<String>,
+    // the functions with a body, or provided by the runtime
+    defined: HashSet<String>,
+    // the classes declared so far, with the bodies of their methods left out
+    class_decls: HashMap<String, ClassDecl>,
+    // loops and switches enclosing the statement being visited
+    loops: i32,
+    switches: i32,
+    error: i32,
+    // of the last visited name, where errors are reported
+    position: Position,
+    quiet: bool,
+    allow_reserved: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl NameAnalyzer {
+    pub fn new() -> Self {
+        NameAnalyzer {
+            scope: Scope::new(),
+            struct_names: HashSet::new(),
+            class_names: HashSet::new(),
+            typedef_names: HashSet::new(),
+            enum_names: HashSet::new(),
+            defined: HashSet::new(),
+            class_decls: HashMap::new(),
+            loops: 0,
+            switches: 0,
+            error: 0,
+            position: Position::default(),
+            quiet: false,
+            allow_reserved: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    pub fn allow_reserved(mut self) -> Self {
+        self.allow_reserved = true;
+        self
+    }
+
+    pub fn get_error_count(&self) -> i32 {
+        self.error
+    }
+
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    // nodes built by the compiler itself have no position
+    fn at(&mut self, position: Position) {
+        if position != Position::default() {
+            self.position = position;
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        if !self.quiet {
+            println!("Semantic error: {}", message);
+        }
+        self.diagnostics.push(Diagnostic::new(self.position, message));
+        self.error += 1;
+    }
+
+    fn check_reserved(&mut self, name: &str) {
+        if !self.allow_reserved && name.starts_with("__") {
+            self.error(format!("{} is reserved, identifiers cannot start with __", name));
+        }
+    }
+
+    // declares a function by its definition or its prototype, which must agree with the previous ones.
+    // A function declared static stays so, whether or not the later declarations repeat it
+    fn declare_fun(&mut self, fun_decl: &mut FunDecl, definition: bool) {
+        self.check_reserved(&fun_decl.name);
+        if self.typedef_names.contains(&fun_decl.name) {
+            self.error(format!("{} is already declared as a type", fun_decl.name));
+            return;
+        }
+        match self.scope.lookup_current(&fun_decl.name) {
+            Some(Symbol::Fun(declared)) if !definition || !self.defined.contains(&fun_decl.name) => {
+                let (same_signature, storage) = (NameAnalyzer::same_signature(declared, fun_decl), declared.storage);
+                if !same_signature {
+                    self.error(format!("function {} does not match its previous declaration", fun_decl.name));
+                }
+                if storage == StorageClass::Static {
+                    fun_decl.storage = StorageClass::Static;
+                } else if fun_decl.storage == StorageClass::Static {
+                    self.error(format!("function {} is declared static after a declaration which is not", fun_decl.name));
+                }
+                if !definition {
+                    return;
+                }
+            },
+            Some(_) => {
+                self.error(format!("function {} is already declared", fun_decl.name));
+                return;
+            },
+            None => {},
+        }
+        if definition {
+            self.defined.insert(fun_decl.name.to_owned());
+        }
+        self.scope.put(fun_decl.name.to_owned(), Symbol::Fun(NameAnalyzer::header(fun_decl)));
+    }
+
+    // a global may be declared extern any number of times, and defined once, with the same type
+    fn declare_global(&mut self, var_decl: &VarDecl) -> bool {
+        let declared = match self.scope.lookup_current(&var_decl.var_name) {
+            Some(Symbol::Var(declared)) if declared.storage == StorageClass::Extern || var_decl.storage == StorageClass::Extern => declared,
+            Some(_) => {
+                self.error(format!("{} is already declared", var_decl.var_name));
+                return false;
+            },
+            None => return true,
+        };
+        if !declared.var_type.eq_type(var_decl.var_type.as_ref()) || declared.is_const != var_decl.is_const {
+            self.error(format!("global {} does not match its previous declaration", var_decl.var_name));
+            return false;
+        }
+        if var_decl.storage == StorageClass::Static {
+            self.error(format!("global {} is declared static after a declaration which is not", var_decl.var_name));
+            return false;
+        }
+        // the definition is the declaration that counts
+        declared.storage == StorageClass::Extern
+    }
+
+    // the body is not needed to resolve calls
+    fn header(fun_decl: &FunDecl) -> FunDecl {
+        let mut header = FunDecl::new(fun_decl.fun_type.clone(), fun_decl.name.to_owned(), fun_decl.params.clone(), Block::new(vec![], vec![]));
+        header.storage = fun_decl.storage;
+        header.position = fun_decl.position;
+        header
+    }
+
+    // the type is not visited, a typedef may name a struct declared after it
+    fn visit_typedef_decl(&mut self, typedef_decl: &mut TypedefDecl) {
+        self.at(typedef_decl.position);
+        self.check_reserved(&typedef_decl.name);
+        if self.scope.lookup_current(&typedef_decl.name).is_some() {
+            self.error(format!("{} is already declared", typedef_decl.name));
+        } else if !self.typedef_names.insert(typedef_decl.name.to_owned()) {
+            self.error(format!("type {} is already declared", typedef_decl.name));
+        }
+    }
+
+    // declares the enumerators with their value, the one of the previous enumerator plus one by default
+    fn visit_enum_decl(&mut self, enum_decl: &mut EnumDecl) {
+        self.at(enum_decl.position);
+        self.check_reserved(&enum_decl.name);
+        if !enum_decl.name.is_empty() && !self.enum_names.insert(enum_decl.name.to_owned()) {
+            self.error(format!("enum {} is already declared", enum_decl.name));
+        }
+        let mut value = 0;
+        for enumerator in enum_decl.enumerators.iter_mut() {
+            if let Some(expr) = &mut enumerator.expr {
+                self.visit_expr(expr);
+                self.at(enumerator.position);
+                match constant_value(expr.as_ref()) {
+                    Some(v) => value = v as i32,
+                    None => self.error(format!("value of enumerator {} must be a constant", enumerator.name)),
+                }
+            }
+            self.at(enumerator.position);
+            self.check_reserved(&enumerator.name);
+            if self.scope.lookup_current(&enumerator.name).is_some() || self.typedef_names.contains(&enumerator.name) {
+                self.error(format!("{} is already declared", enumerator.name));
+            } else {
+                self.scope.put(enumerator.name.to_owned(), Symbol::Constant(value));
+            }
+            value = value.wrapping_add(1);
+        }
+    }
+
+    // the names of the parameters of a prototype are only documentation, so are not declared
+    fn visit_prototype(&mut self, prototype: &mut FunDecl) {
+        self.visit_type(&mut prototype.fun_type);
+        for param in prototype.params.iter_mut() {
+            self.visit_type(&mut param.var_type);
+            if param.var_type.is_base(BaseType::VOID) {
+                self.at(param.position);
+                self.error(format!("a parameter of function {} cannot be of type void", prototype.name));
+            }
+        }
+        self.at(prototype.position);
+        self.declare_fun(prototype, false);
+    }
+
+    // the parameters and the body of a function or a method, which receives its object in `this`
+    fn visit_body(&mut self, fun_decl: &mut FunDecl, this: Option<VarDecl>) {
+        // parameters and the outermost locals share the same scope
+        self.scope.enter();
+        if let Some(this) = this {
+            self.scope.put(this.var_name.to_owned(), Symbol::Var(this));
+        }
+        for param in fun_decl.params.iter_mut() {
+            param.accept(self);
+        }
+        for var_decl in fun_decl.block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in fun_decl.block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scope.exit();
+    }
+
+    /// The fields and methods of a class, including the ones inherited, with the name of the class
+    /// declaring each of them. An overriding method replaces the one of the ancestor.
+    fn members(&self, class_name: &str) -> HashMap<String, (Symbol, String)> {
+        let mut ancestry = Vec::new();
+        let mut current = self.class_decls.get(class_name);
+        while let Some(class_decl) = current {
+            ancestry.push(class_decl);
+            current = class_decl.parent.as_ref().and_then(|parent| self.class_decls.get(&parent.name));
+        }
+
+        let mut members = HashMap::new();
+        for class_decl in ancestry.iter().rev() {
+            let owner = &class_decl.class_type.name;
+            for field in class_decl.var_decls.iter() {
+                members.insert(field.var_name.to_owned(), (Symbol::Field(field.clone()), owner.to_owned()));
+            }
+            for method in class_decl.fun_decls.iter() {
+                members.insert(method.name.to_owned(), (Symbol::Method(method.clone()), owner.to_owned()));
+            }
+        }
+        members
+    }
+
+    fn same_signature(fun_decl1: &FunDecl, fun_decl2: &FunDecl) -> bool {
+        fun_decl1.fun_type.eq_type(fun_decl2.fun_type.as_ref())
+            && fun_decl1.params.len() == fun_decl2.params.len()
+            && fun_decl1.params.iter().zip(fun_decl2.params.iter()).all(|(p1, p2)| p1.var_type.eq_type(p2.var_type.as_ref()))
+    }
+
+    // the bodies of the methods of a class, in a scope holding its members
+    fn visit_methods(&mut self, class_decl: &mut ClassDecl) {
+        self.scope.enter();
+        for (name, (symbol, _)) in self.members(&class_decl.class_type.name) {
+            self.scope.put(name, symbol);
+        }
+        for fun_decl in class_decl.fun_decls.iter_mut() {
+            self.at(fun_decl.position);
+            let this = VarDecl::new(Box::new(ClassType::new(class_decl.class_type.name.to_owned())), "this".to_owned());
+            self.visit_body(fun_decl, Some(this));
+        }
+        self.scope.exit();
+    }
+
+    // a field or a method of the class of the current method named alone, as a member of `this`
+    fn member_access(&self, expr: &mut Box<dyn Expr>) -> Option<Box<dyn Expr>> {
+        let is_grouped = expr.get_is_grouped();
+        let mut member: Box<dyn Expr> = if let Some(var_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            if !matches!(self.scope.lookup(&var_expr.name), Some(Symbol::Field(_))) {
+                return None;
+            }
+            let mut field_access = FieldAccessExpr::new(VarExpr::new("this".to_owned()), var_expr.name.to_owned());
+            field_access.position = var_expr.position;
+            field_access
+        } else if let Some(fun_call_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            if !matches!(self.scope.lookup(&fun_call_expr.name), Some(Symbol::Method(_))) {
+                return None;
+            }
+            let exprs = std::mem::take(&mut fun_call_expr.exprs);
+            let mut method_call = MethodCallExpr::new(VarExpr::new("this".to_owned()), fun_call_expr.name.to_owned(), exprs);
+            method_call.position = fun_call_expr.position;
+            method_call
+        } else {
+            return None;
+        };
+        member.set_is_grouped(is_grouped);
+        Some(member)
+    }
+
+    // the value of an enumerator named by a variable
+    fn enumerator(&self, expr: &mut Box<dyn Expr>) -> Option<Box<dyn Expr>> {
+        let var_expr = expr.as_any().downcast_ref::<VarExpr>()?;
+        let Some(Symbol::Constant(value)) = self.scope.lookup(&var_expr.name) else {
+            return None;
+        };
+        let mut int_literal = IntLiteral::new(value.to_string());
+        int_literal.is_grouped = var_expr.is_grouped;
+        Some(int_literal)
+    }
+
+    // a function named alone, or with `&`, is its address, and a call of a variable goes through the
+    // function pointer it holds
+    fn fun_pointer(&self, expr: &mut Box<dyn Expr>) -> Option<Box<dyn Expr>> {
+        let is_grouped = expr.get_is_grouped();
+        let mut fun_value: Box<dyn Expr> = if let Some(fun_call_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            if !matches!(self.scope.lookup(&fun_call_expr.name), Some(Symbol::Var(_)) | Some(Symbol::Field(_))) {
+                return None;
+            }
+            let mut var_expr = VarExpr::new(fun_call_expr.name.to_owned());
+            var_expr.position = fun_call_expr.position;
+            let mut indirect_call = IndirectCallExpr::new(var_expr, std::mem::take(&mut fun_call_expr.exprs));
+            indirect_call.position = fun_call_expr.position;
+            indirect_call
+        } else {
+            let operand = match expr.as_any().downcast_ref::<AddressOfExpr>() {
+                Some(address_of_expr) => address_of_expr.expr.as_ref(),
+                None => expr.as_ref(),
+            };
+            let var_expr = operand.as_any().downcast_ref::<VarExpr>()?;
+            if !matches!(self.scope.lookup(&var_expr.name), Some(Symbol::Fun(_))) {
+                return None;
+            }
+            let mut fun_addr = FunAddrExpr::new(var_expr.name.to_owned());
+            fun_addr.position = var_expr.position;
+            fun_addr
+        };
+        fun_value.set_is_grouped(is_grouped);
+        Some(fun_value)
+    }
+}
+
+impl ASTVisitor<()> for NameAnalyzer {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) {
+        self.visit_type(&mut pointer_type.pointer_type);
+    }
+
+    fn visit_struct_type(&mut self, struct_type: &mut StructType) {
+        self.at(struct_type.position);
+        if !self.struct_names.contains(&struct_type.name) {
+            self.error(format!("struct {} is not declared", struct_type.name));
+        }
+    }
+
+    fn visit_array_type(&mut self, array_type: &mut ArrayType) {
+        // the parser sized the arrays it could from their initialiser
+        if array_type.i.is_none() {
+            self.error("array size missing".to_owned());
+        }
+        self.visit_type(&mut array_type.array_type);
+    }
+
+    fn visit_class_type(&mut self, class_type: &mut ClassType) {
+        self.at(class_type.position);
+        if !self.class_names.contains(&class_type.name) {
+            self.error(format!("class {} is not declared", class_type.name));
+        }
+    }
+
+    fn visit_fun_type(&mut self, fun_type: &mut FunType) {
+        self.visit_type(&mut fun_type.return_type);
+        for param_type in fun_type.param_types.iter_mut() {
+            self.visit_type(param_type);
+        }
+    }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        for mut fun_decl in stdlib::fun_decls() {
+            self.declare_fun(&mut fun_decl, true);
+        }
+        // objects are references, so a class can be used before its declaration
+        for class_decl in program.class_decls.iter() {
+            self.class_names.insert(class_decl.class_type.name.to_owned());
+        }
+        // anything else is only visible after its declaration
+        for item in program.ordered_items() {
+            match item {
+                Item::Typedef(i) => self.visit_typedef_decl(&mut program.typedef_decls[i]),
+                Item::Enum(i) => self.visit_enum_decl(&mut program.enum_decls[i]),
+                Item::Struct(i) => program.struct_type_decls[i].accept(self),
+                Item::Class(i) => program.class_decls[i].accept(self),
+                Item::Var(i) => program.var_decls[i].accept(self),
+                Item::Prototype(i) => self.visit_prototype(&mut program.prototypes[i]),
+                Item::Fun(i) => program.fun_decls[i].accept(self),
+            }
+        }
+        // methods come last so that they can use every global and function
+        for class_decl in program.class_decls.iter_mut() {
+            self.visit_methods(class_decl);
+        }
+        // an extern function may be defined in another file
+        for prototype in program.prototypes.iter() {
+            if prototype.storage != StorageClass::Extern && !self.defined.contains(&prototype.name) {
+                self.at(prototype.position);
+                self.error(format!("function {} is declared but never defined", prototype.name));
+            }
+        }
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) {
+        let name = struct_type_decl.struct_type.name.to_owned();
+        self.at(struct_type_decl.struct_type.position);
+        self.check_reserved(&name);
+        // declared before its fields so that a struct can point to itself
+        if !self.struct_names.insert(name.to_owned()) {
+            self.error(format!("struct {} is already declared", name));
+        }
+
+        let mut fields = HashSet::new();
+        for var_decl in struct_type_decl.var_decls.iter_mut() {
+            self.at(var_decl.position);
+            self.visit_type(&mut var_decl.var_type);
+            self.at(var_decl.position);
+            if let Some(struct_type) = var_decl.var_type.as_struct() {
+                if struct_type.name == name {
+                    self.error(format!("struct {} cannot contain itself", name));
+                }
+            }
+            self.check_reserved(&var_decl.var_name);
+            if var_decl.init.is_some() {
+                self.error(format!("field {} cannot have an initialiser", var_decl.var_name));
+            }
+            if var_decl.storage != StorageClass::Auto {
+                self.error(format!("field {} cannot have a storage class", var_decl.var_name));
+            }
+            if !fields.insert(var_decl.var_name.to_owned()) {
+                self.error(format!("field {} is already declared in struct {}", var_decl.var_name, name));
+            }
+        }
+    }
+
+    // declares the class with its fields and the signatures of its methods, the bodies are visited
+    // once every global and function is declared
+    fn visit_class_decl(&mut self, class_decl: &mut ClassDecl) {
+        let name = class_decl.class_type.name.to_owned();
+        self.at(class_decl.class_type.position);
+        self.check_reserved(&name);
+        if self.class_decls.contains_key(&name) {
+            self.error(format!("class {} is already declared", name));
+            return;
+        }
+        let mut header = ClassDecl::new(class_decl.class_type.clone(), None, vec![], vec![]);
+        // the parent must be declared first, which rules out cycles
+        if let Some(parent) = &class_decl.parent {
+            self.at(parent.position);
+            if self.class_decls.contains_key(&parent.name) {
+                header.parent = Some(parent.clone());
+            } else {
+                self.error(format!("class {} is not declared before class {}", parent.name, name));
+            }
+        }
+        let inherited = header.parent.as_ref().map_or_else(HashMap::new, |parent| self.members(&parent.name));
+
+        let mut members = HashSet::new();
+        for var_decl in class_decl.var_decls.iter_mut() {
+            self.at(var_decl.position);
+            self.visit_type(&mut var_decl.var_type);
+            self.at(var_decl.position);
+            if var_decl.var_type.is_base(BaseType::VOID) {
+                self.error(format!("field {} cannot be of type void", var_decl.var_name));
+            }
+            self.check_reserved(&var_decl.var_name);
+            if var_decl.init.is_some() {
+                self.error(format!("field {} cannot have an initialiser", var_decl.var_name));
+            }
+            if var_decl.storage != StorageClass::Auto {
+                self.error(format!("field {} cannot have a storage class", var_decl.var_name));
+            }
+            if let Some((_, owner)) = inherited.get(&var_decl.var_name) {
+                self.error(format!("{} is already declared in class {}", var_decl.var_name, owner));
+            } else if !members.insert(var_decl.var_name.to_owned()) {
+                self.error(format!("{} is already declared in class {}", var_decl.var_name, name));
+            }
+            header.var_decls.push(var_decl.clone());
+        }
+        for fun_decl in class_decl.fun_decls.iter_mut() {
+            self.visit_type(&mut fun_decl.fun_type);
+            for param in fun_decl.params.iter_mut() {
+                self.visit_type(&mut param.var_type);
+            }
+            self.at(fun_decl.position);
+            if fun_decl.storage != StorageClass::Auto {
+                self.error(format!("method {} cannot have a storage class", fun_decl.name));
+            }
+            self.check_reserved(&fun_decl.name);
+            match inherited.get(&fun_decl.name) {
+                Some((Symbol::Method(overridden), owner)) if !NameAnalyzer::same_signature(fun_decl, overridden) => {
+                    self.error(format!("method {} of class {} must have the same signature as in class {}", fun_decl.name, name, owner));
+                },
+                Some((Symbol::Method(_), _)) => {},
+                Some((_, owner)) => self.error(format!("{} is already declared in class {}", fun_decl.name, owner)),
+                None => {},
+            }
+            if !members.insert(fun_decl.name.to_owned()) {
+                self.error(format!("{} is already declared in class {}", fun_decl.name, name));
+            }
+            header.fun_decls.push(NameAnalyzer::header(fun_decl));
+        }
+        self.class_decls.insert(name, header);
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.at(var_decl.position);
+        self.visit_type(&mut var_decl.var_type);
+        self.at(var_decl.position);
+        if var_decl.var_type.is_base(BaseType::VOID) {
+            self.error(format!("variable {} cannot be of type void", var_decl.var_name));
+        }
+        self.check_reserved(&var_decl.var_name);
+
+        if var_decl.storage == StorageClass::Extern {
+            if !self.scope.is_global() {
+                self.error(format!("local variable {} cannot be extern", var_decl.var_name));
+            } else if var_decl.init.is_some() {
+                self.error(format!("extern variable {} cannot have an initialiser", var_decl.var_name));
+            }
+        }
+
+        // a local may shadow a typedef, which is global
+        if self.scope.is_global() && self.typedef_names.contains(&var_decl.var_name) {
+            self.error(format!("{} is already declared", var_decl.var_name));
+            return;
+        }
+        if self.scope.is_global() {
+            if !self.declare_global(var_decl) {
+                return;
+            }
+        } else if self.scope.lookup_current(&var_decl.var_name).is_some() {
+            self.error(format!("{} is already declared", var_decl.var_name));
+            return;
+        }
+        self.scope.put(var_decl.var_name.to_owned(), Symbol::Var(var_decl.clone()));
+        // as in C, the variable is in scope in its own initialiser
+        if let Some(init) = &mut var_decl.init {
+            for expr in init.exprs_mut() {
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.visit_type(&mut fun_decl.fun_type);
+        self.at(fun_decl.position);
+        self.declare_fun(fun_decl, true);
+        self.visit_body(fun_decl, None);
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        self.scope.enter();
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scope.exit();
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        self.visit_expr(&mut a_while.expr);
+        self.loops += 1;
+        self.visit_stmt(&mut a_while.stmt);
+        self.loops -= 1;
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        self.loops += 1;
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.loops -= 1;
+        self.visit_expr(&mut a_do_while.expr);
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.loops += 1;
+        self.visit_stmt(&mut a_for.stmt);
+        self.loops -= 1;
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        if self.loops == 0 && self.switches == 0 {
+            self.error("break outside of a loop or switch".to_owned());
+        }
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        if self.loops == 0 {
+            self.error("continue outside of a loop".to_owned());
+        }
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        self.visit_expr(&mut a_switch.expr);
+        self.switches += 1;
+        for case in a_switch.cases.iter_mut() {
+            self.at(case.position);
+            if let Some(expr) = &mut case.expr {
+                self.visit_expr(expr);
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+        self.switches -= 1;
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, _int_literal: &mut IntLiteral) { }
+
+    fn visit_chr_literal(&mut self, _chr_literal: &mut ChrLiteral) { }
+
+    fn visit_str_literal(&mut self, _str_literal: &mut StrLiteral) { }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        self.at(var_expr.position);
+        match self.scope.lookup(&var_expr.name) {
+            Some(Symbol::Var(var_decl)) => var_expr.var_decl = var_decl.clone(),
+            // rewritten as its address by visit_expr
+            Some(Symbol::Fun(_)) => unreachable!(),
+            Some(Symbol::Method(_)) => self.error(format!("{} is a method, not a variable", var_expr.name)),
+            // rewritten as an access through `this`, or as its value, by visit_expr
+            Some(Symbol::Field(_)) | Some(Symbol::Constant(_)) => unreachable!(),
+            None => self.error(format!("variable {} is not declared", var_expr.name)),
+        }
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        self.visit_type(&mut typecast_expr.typecast_type);
+        self.visit_expr(&mut typecast_expr.expr);
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        self.visit_type(&mut size_of_expr.sizeof_type);
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.visit_expr(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.visit_expr(&mut field_access_expr.expr);
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        self.at(fun_call_expr.position);
+        match self.scope.lookup(&fun_call_expr.name) {
+            Some(Symbol::Fun(fun_decl)) => fun_call_expr.fun_decl = fun_decl.clone(),
+            Some(Symbol::Constant(_)) => self.error(format!("{} is a constant, not a function", fun_call_expr.name)),
+            // rewritten as a call through `this`, or through the function pointer, by visit_expr
+            Some(Symbol::Method(_)) | Some(Symbol::Var(_)) | Some(Symbol::Field(_)) => unreachable!(),
+            None => self.error(format!("function {} is not declared", fun_call_expr.name)),
+        }
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_new_expr(&mut self, new_expr: &mut NewExpr) {
+        new_expr.class_type.accept(self);
+    }
+
+    // the method is only known from the class of the object, by the type analyser
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) {
+        self.visit_expr(&mut method_call_expr.expr);
+        for expr in method_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    // the functions of the runtime are not compiled with the program, so have no address
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        self.at(fun_addr_expr.position);
+        match self.scope.lookup(&fun_addr_expr.name) {
+            Some(Symbol::Fun(fun_decl)) => fun_addr_expr.fun_decl = fun_decl.clone(),
+            _ => self.error(format!("function {} is not declared", fun_addr_expr.name)),
+        }
+        if stdlib::fun_decls().iter().any(|fun_decl| fun_decl.name == fun_addr_expr.name) {
+            self.error(format!("cannot take the address of library function {}", fun_addr_expr.name));
+        }
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        self.visit_expr(&mut indirect_call_expr.expr);
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        self.visit_expr(&mut assign_expr.expr1);
+        self.visit_expr(&mut assign_expr.expr2);
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        self.visit_expr(&mut inc_dec_expr.expr);
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.visit_expr(&mut conditional_expr.expr2);
+    }
+
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) {
+        if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<BaseType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<PointerType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<StructType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ArrayType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<ClassType>() {
+            cast_type.accept(self);
+        } else if let Some(cast_type) = a_type.as_mut_any().downcast_mut::<FunType>() {
+            cast_type.accept(self);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self);
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(member) = self.member_access(expr) {
+            *expr = member;
+        }
+        if let Some(value) = self.enumerator(expr) {
+            *expr = value;
+        }
+        if let Some(fun_value) = self.fun_pointer(expr) {
+            *expr = fun_value;
+        }
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self);
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self);
+        }
+    }
+}
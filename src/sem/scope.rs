@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use crate::ast::decl::{FunDecl, VarDecl};
+
+/// Symbol
+#[derive(Clone)]
+pub enum Symbol {
+    Var(VarDecl),
+    Fun(FunDecl),
+    // the members of the class of a method, accessed through `this`
+    Field(VarDecl),
+    Method(FunDecl),
+    // an enumerator, with its value
+    Constant(i32),
+}
+
+/// Scope
+/// A stack of nested scopes, the innermost being the last one.
+pub struct Scope {
+    scopes: Vec<HashMap<String, Symbol>>,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Scope {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn enter(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn exit(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Whether the current scope is the one of the globals and functions.
+    pub fn is_global(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    pub fn lookup_current(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.last().unwrap().get(name)
+    }
+
+    pub fn put(&mut self, name: String, symbol: Symbol) {
+        self.scopes.last_mut().unwrap().insert(name, symbol);
+    }
+}
@@ -0,0 +1,64 @@
+use crate::ast::decl::Program;
+use crate::lexer::position::Diagnostic;
+use crate::sem::name_analyzer::NameAnalyzer;
+use crate::sem::type_analyzer::TypeAnalyzer;
+use crate::ASTNode;
+
+/// SemanticAnalyzer
+/// Runs name analysis followed by type analysis; types are only checked once all names resolve.
+pub struct SemanticAnalyzer {
+    error: i32,
+    quiet: bool,
+    allow_reserved: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl SemanticAnalyzer {
+    pub fn new() -> Self {
+        SemanticAnalyzer {
+            error: 0,
+            quiet: false,
+            allow_reserved: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Only collects the errors instead of also printing them.
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Accepts the identifiers starting with a double underscore, to check a program transformed by
+    /// the compiler.
+    pub fn allow_reserved(mut self) -> Self {
+        self.allow_reserved = true;
+        self
+    }
+
+    pub fn get_error_count(&self) -> i32 {
+        self.error
+    }
+
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn analyze(&mut self, program: &mut Program) {
+        let mut name_analyzer = if self.quiet { NameAnalyzer::new().quiet() } else { NameAnalyzer::new() };
+        if self.allow_reserved {
+            name_analyzer = name_analyzer.allow_reserved();
+        }
+        program.accept(&mut name_analyzer);
+        self.error += name_analyzer.get_error_count();
+        self.diagnostics.extend(name_analyzer.get_diagnostics().iter().cloned());
+        if self.error != 0 {
+            return;
+        }
+
+        let mut type_analyzer = if self.quiet { TypeAnalyzer::new().quiet() } else { TypeAnalyzer::new() };
+        program.accept(&mut type_analyzer);
+        self.error += type_analyzer.get_error_count();
+        self.diagnostics.extend(type_analyzer.get_diagnostics().iter().cloned());
+    }
+}
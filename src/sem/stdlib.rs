@@ -0,0 +1,24 @@
+use crate::ast::decl::{FunDecl, VarDecl};
+use crate::ast::stmt::Block;
+use crate::ast::types::{BaseType, PointerType, Type};
+
+/// The functions declared in "minic-stdlib.h", provided by the runtime of every backend.
+pub fn fun_decls() -> Vec<FunDecl> {
+    let const_char_pointer = || -> Box<dyn Type> { PointerType::to_const(Box::new(BaseType::CHAR)) };
+    let fun_decl = |fun_type: Box<dyn Type>, name: &str, params: Vec<VarDecl>| {
+        FunDecl::new(fun_type, name.to_owned(), params, Block::new(vec![], vec![]))
+    };
+
+    vec![
+        fun_decl(Box::new(BaseType::VOID), "print_s", vec![VarDecl::new(const_char_pointer(), "s".to_owned())]),
+        fun_decl(Box::new(BaseType::VOID), "print_i", vec![VarDecl::new(Box::new(BaseType::INT), "i".to_owned())]),
+        fun_decl(Box::new(BaseType::VOID), "print_c", vec![VarDecl::new(Box::new(BaseType::CHAR), "c".to_owned())]),
+        fun_decl(Box::new(BaseType::CHAR), "read_c", vec![]),
+        fun_decl(Box::new(BaseType::INT), "read_i", vec![]),
+        fun_decl(PointerType::new(Box::new(BaseType::VOID)), "mcmalloc", vec![VarDecl::new(Box::new(BaseType::INT), "size".to_owned())]),
+    ]
+}
+
+pub fn is_stdlib(name: &str) -> bool {
+    matches!(name, "print_s" | "print_i" | "print_c" | "read_c" | "read_i" | "mcmalloc")
+}
@@ -0,0 +1,983 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::lexer::position::{Diagnostic, Position};
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StorageClass, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, ErrorType, FunType, PointerType, StructType, Type};
+use crate::ast::unparser::escape_char;
+use crate::gen::layout::Layout;
+use crate::sem::constant::constant_value;
+use crate::ASTNode;
+
+/// TypeAnalyzer
+/// Computes the type of every expression (stored in its `expr_type`) and checks the typing rules.
+/// Must run after the NameAnalyzer. Statements and declarations evaluate to `void`, and an expression
+/// which fails to check to the ErrorType, which causes no further errors.
+pub struct TypeAnalyzer {
+    struct_type_decls: HashMap<String, StructTypeDecl>,
+    class_decls: HashMap<String, ClassDecl>,
+    // the sizes of the types of sizeof, which are constants
+    layout: Layout,
+    fun_type: Box<dyn Type>,
+    error: i32,
+    // of the last visited name, where errors are reported
+    position: Position,
+    quiet: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl TypeAnalyzer {
+    pub fn new() -> Self {
+        TypeAnalyzer {
+            struct_type_decls: HashMap::new(),
+            class_decls: HashMap::new(),
+            layout: Layout::new(&[]),
+            fun_type: Box::new(BaseType::VOID),
+            error: 0,
+            position: Position::default(),
+            quiet: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    pub fn get_error_count(&self) -> i32 {
+        self.error
+    }
+
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    // nodes built by the compiler itself have no position
+    fn at(&mut self, position: Position) {
+        if position != Position::default() {
+            self.position = position;
+        }
+    }
+
+    fn error(&mut self, message: String) -> Box<dyn Type> {
+        if !self.quiet {
+            println!("Semantic error: {}", message);
+        }
+        self.diagnostics.push(Diagnostic::new(self.position, message));
+        self.error += 1;
+        Box::new(ErrorType)
+    }
+
+    // whether one of the types is that of an expression already reported
+    fn failed(types: &[&dyn Type]) -> bool {
+        types.iter().any(|t| t.is_error())
+    }
+
+    pub fn is_lvalue(expr: &dyn Expr) -> bool {
+        let any = expr.as_any();
+        any.is::<VarExpr>() || any.is::<FieldAccessExpr>() || any.is::<ArrayAccessExpr>() || any.is::<ValueAtExpr>()
+    }
+
+    /// What makes an lvalue const, if anything, e.g. `const variable x` for `x.f` or `x[2]`.
+    fn const_lvalue(&self, expr: &dyn Expr) -> Option<String> {
+        let any = expr.as_any();
+        let pointed_to = |pointer: &dyn Expr| pointer.get_expr_type().as_pointer()
+            .filter(|pointer_type| pointer_type.to_const)
+            .map(|_| format!("a value pointed to by {}", pointer.get_expr_type()));
+        if let Some(var_expr) = any.downcast_ref::<VarExpr>() {
+            var_expr.var_decl.is_const.then(|| format!("const variable {}", var_expr.name))
+        } else if let Some(field_access_expr) = any.downcast_ref::<FieldAccessExpr>() {
+            let expr_type = field_access_expr.expr.get_expr_type();
+            let is_const = if let Some(class_type) = expr_type.as_class() {
+                self.ancestry(&class_type.name).iter()
+                    .find_map(|class_decl| class_decl.var_decls.iter().find(|field| field.var_name == field_access_expr.name))
+                    .is_some_and(|field| field.is_const)
+            } else {
+                expr_type.as_struct()
+                    .and_then(|struct_type| self.struct_type_decls.get(&struct_type.name))
+                    .and_then(|decl| decl.var_decls.iter().find(|field| field.var_name == field_access_expr.name))
+                    .is_some_and(|field| field.is_const)
+            };
+            if is_const {
+                Some(format!("const field {}", field_access_expr.name))
+            } else if expr_type.as_struct().is_some() {
+                // the fields of a const struct are const
+                self.const_lvalue(field_access_expr.expr.as_ref())
+            } else {
+                None
+            }
+        } else if let Some(array_access_expr) = any.downcast_ref::<ArrayAccessExpr>() {
+            if array_access_expr.expr1.get_expr_type().as_array().is_some() {
+                self.const_lvalue(array_access_expr.expr1.as_ref())
+            } else {
+                pointed_to(array_access_expr.expr1.as_ref())
+            }
+        } else if let Some(value_at_expr) = any.downcast_ref::<ValueAtExpr>() {
+            pointed_to(value_at_expr.expr.as_ref())
+        } else {
+            None
+        }
+    }
+
+    // an array argument decays to a pointer to its first element, e.g. `int[2][3]` to `int(*)[3]`
+    fn accepts(param_type: &dyn Type, arg_type: &dyn Type) -> bool {
+        match (param_type.as_pointer(), arg_type.as_array()) {
+            (Some(pointer_type), Some(array_type)) => pointer_type.pointer_type.eq_type(array_type.array_type.as_ref()),
+            // any other argument is converted as in an assignment
+            _ => false,
+        }
+    }
+
+    // the class and its ancestors, starting with the class itself
+    fn ancestry(&self, class_name: &str) -> Vec<&ClassDecl> {
+        let mut ancestry = Vec::new();
+        let mut current = self.class_decls.get(class_name);
+        while let Some(class_decl) = current {
+            ancestry.push(class_decl);
+            current = class_decl.parent.as_ref().and_then(|parent| self.class_decls.get(&parent.name));
+        }
+        ancestry
+    }
+
+    /// Looks up a field of a class, which may be inherited.
+    fn field(&self, class_name: &str, name: &str) -> Option<Box<dyn Type>> {
+        self.ancestry(class_name).iter()
+            .find_map(|class_decl| class_decl.var_decls.iter().find(|field| field.var_name == name))
+            .map(|field| field.var_type.clone())
+    }
+
+    /// Looks up a method of a class, which may be inherited.
+    fn method(&self, class_name: &str, name: &str) -> Option<FunDecl> {
+        self.ancestry(class_name).iter()
+            .find_map(|class_decl| class_decl.fun_decls.iter().find(|method| method.name == name))
+            .cloned()
+    }
+
+    fn is_subclass(&self, class_name: &str, ancestor: &str) -> bool {
+        self.ancestry(class_name).iter().any(|class_decl| class_decl.class_type.name == ancestor)
+    }
+
+    /// Whether a value of type `expr_type` can be used where `target` is expected. An integer is
+    /// converted to any other integer type, and an object of a subclass to its ancestor, with an
+    /// explicit cast, so that later passes only see values of the exact type.
+    fn convert(&self, target: &dyn Type, expr: &mut Box<dyn Expr>, expr_type: &dyn Type) -> bool {
+        if let (Some(t1), Some(_)) = (target.as_integer(), expr_type.as_integer()) {
+            TypeAnalyzer::cast(expr, Box::new(t1));
+            return true;
+        }
+        match (target.as_class(), expr_type.as_class()) {
+            (Some(t1), Some(t2)) if t1.name != t2.name && self.is_subclass(&t2.name, &t1.name) => {
+                TypeAnalyzer::cast(expr, Box::new(t1.clone()));
+                true
+            },
+            _ => target.eq_type(expr_type) && !TypeAnalyzer::drops_const(target, expr_type),
+        }
+    }
+
+    // true if a pointer converted from `source` to `target` would allow assigning what is const,
+    // e.g. `const int*` to `int*`
+    fn drops_const(target: &dyn Type, source: &dyn Type) -> bool {
+        match (target.as_pointer(), source.as_pointer()) {
+            (Some(t1), Some(t2)) => (t2.to_const && !t1.to_const) || TypeAnalyzer::drops_const(t1.pointer_type.as_ref(), t2.pointer_type.as_ref()),
+            _ => false,
+        }
+    }
+
+    // wraps a checked expression in a typed cast, unless it already has the type
+    fn cast(expr: &mut Box<dyn Expr>, target: Box<dyn Type>) {
+        if expr.get_expr_type().eq_type(target.as_ref()) {
+            return;
+        }
+        let inner = std::mem::replace(expr, IntLiteral::new("0".to_owned()));
+        let mut typecast_expr = TypecastExpr::new(target.clone(), inner);
+        typecast_expr.expr_type = target;
+        *expr = typecast_expr;
+    }
+
+    // a condition may be of any integer type; one which is not an int is compared with zero, so that
+    // the backends only ever test ints
+    fn check_condition(&mut self, expr: &mut Box<dyn Expr>, expr_type: &dyn Type, what: &str) {
+        match expr_type.as_integer() {
+            Some(BaseType::INT) => { },
+            Some(int_type) => {
+                let compare_type = int_type.promote();
+                TypeAnalyzer::cast(expr, Box::new(compare_type));
+                let mut zero = IntLiteral::new("0".to_owned());
+                zero.int_type = compare_type;
+                zero.expr_type = Box::new(compare_type);
+                let inner = std::mem::replace(expr, IntLiteral::new("0".to_owned()));
+                let mut bin_op = BinOp::new(inner, Op::NE, zero);
+                bin_op.expr_type = Box::new(BaseType::INT);
+                *expr = bin_op;
+            },
+            None if expr_type.is_error() => { },
+            None => { self.error(format!("{} must be an integer, found {}", what, expr_type)); },
+        }
+    }
+
+    fn check_assign(&mut self, lhs: &dyn Expr, lhs_type: &dyn Type, rhs: &mut Box<dyn Expr>, rhs_type: &dyn Type) -> bool {
+        if !TypeAnalyzer::is_lvalue(lhs) {
+            self.error("left hand side of an assignment must be a variable, field, array element or dereference".to_owned());
+        } else if let Some(what) = self.const_lvalue(lhs) {
+            self.error(format!("cannot assign to {}", what));
+        } else if lhs_type.is_base(BaseType::VOID) || lhs_type.as_array().is_some() {
+            self.error(format!("cannot assign to a value of type {}", lhs_type));
+        } else if !self.convert(lhs_type, rhs, rhs_type) {
+            self.error(format!("cannot assign {} to {}", rhs_type, lhs_type));
+        } else {
+            return true;
+        }
+        false
+    }
+
+    // checks the arguments of a call of a function or a method, described by `callee` in errors
+    fn check_args(&mut self, callee: &str, position: Position, fun_decl: &FunDecl, exprs: &mut [Box<dyn Expr>]) -> bool {
+        let mut arg_types = Vec::new();
+        for expr in exprs.iter_mut() {
+            arg_types.push(self.visit_expr(expr));
+        }
+        self.at(position);
+        if fun_decl.params.len() != arg_types.len() {
+            self.error(format!("{} expects {} argument(s), found {}", callee, fun_decl.params.len(), arg_types.len()));
+            return false;
+        }
+        for (i, ((param, arg_type), expr)) in fun_decl.params.iter().zip(arg_types.iter()).zip(exprs.iter_mut()).enumerate() {
+            // a const array decays to a pointer to const
+            let decays_const = arg_type.as_array().is_some() && self.const_lvalue(expr.as_ref()).is_some()
+                && param.var_type.as_pointer().is_some_and(|pointer_type| !pointer_type.to_const);
+            let accepted = TypeAnalyzer::accepts(param.var_type.as_ref(), arg_type.as_ref()) && !decays_const;
+            if !accepted && !self.convert(param.var_type.as_ref(), expr, arg_type.as_ref()) {
+                // the parameters of a prototype may have no name
+                let name = if param.var_name.is_empty() { (i + 1).to_string() } else { param.var_name.to_owned() };
+                let found = if decays_const { format!("const {}", arg_type) } else { arg_type.to_string() };
+                self.error(format!("argument {} of {} expects {}, found {}", name, callee, param.var_type, found));
+                return false;
+            }
+        }
+        true
+    }
+
+    // the type of the field of a struct or an object whose type is `expr_type`
+    fn check_field(&mut self, field_access_expr: &mut FieldAccessExpr, expr_type: Box<dyn Type>) -> Box<dyn Type> {
+        self.at(field_access_expr.position);
+        if expr_type.is_error() {
+            return expr_type;
+        }
+        if let Some(class_type) = expr_type.as_class() {
+            match self.field(&class_type.name, &field_access_expr.name) {
+                Some(field_type) => field_access_expr.expr_type = field_type,
+                None => return self.error(format!("class {} has no field {}", class_type.name, field_access_expr.name)),
+            }
+            return field_access_expr.expr_type.clone();
+        }
+        let struct_type = match expr_type.as_struct() {
+            Some(struct_type) => struct_type,
+            None if expr_type.as_pointer().is_some_and(|p| p.pointer_type.as_struct().is_some()) => {
+                return self.error(format!("cannot access field {} of a value of type {}, did you mean `->`?", field_access_expr.name, expr_type))
+            },
+            None => return self.error(format!("cannot access field {} of a value of type {}", field_access_expr.name, expr_type)),
+        };
+
+        let field_type = self.struct_type_decls.get(&struct_type.name)
+            .and_then(|decl| decl.var_decls.iter().find(|field| field.var_name == field_access_expr.name))
+            .map(|field| field.var_type.clone());
+        match field_type {
+            Some(field_type) => field_access_expr.expr_type = field_type,
+            None => return self.error(format!("struct {} has no field {}", struct_type.name, field_access_expr.name)),
+        }
+        field_access_expr.expr_type.clone()
+    }
+
+    fn check_method_call(&mut self, method_call_expr: &mut MethodCallExpr, expr_type: Box<dyn Type>) -> Box<dyn Type> {
+        self.at(method_call_expr.position);
+        let class_type = match expr_type.as_class() {
+            Some(class_type) => class_type,
+            None if expr_type.is_error() => return expr_type,
+            None => return self.error(format!("cannot call method {} on a value of type {}", method_call_expr.name, expr_type)),
+        };
+        let fun_decl = match self.method(&class_type.name, &method_call_expr.name) {
+            Some(fun_decl) => fun_decl,
+            None => return self.error(format!("class {} has no method {}", class_type.name, method_call_expr.name)),
+        };
+
+        let callee = format!("method {}", method_call_expr.name);
+        if !self.check_args(&callee, method_call_expr.position, &fun_decl, &mut method_call_expr.exprs) {
+            return Box::new(ErrorType);
+        }
+        method_call_expr.expr_type = fun_decl.fun_type.clone();
+        method_call_expr.fun_decl = fun_decl;
+        method_call_expr.expr_type.clone()
+    }
+
+    // `s.f(x)` calls the function pointer in the field f of a struct, or of an object without a method f
+    fn field_call(&mut self, method_call_expr: &mut MethodCallExpr, expr_type: Box<dyn Type>) -> Box<IndirectCallExpr> {
+        let receiver = std::mem::replace(&mut method_call_expr.expr, IntLiteral::new("0".to_owned()));
+        let mut field_access_expr = FieldAccessExpr::new(receiver, method_call_expr.name.to_owned());
+        field_access_expr.position = method_call_expr.position;
+        self.check_field(&mut field_access_expr, expr_type);
+        let mut indirect_call_expr = IndirectCallExpr::new(field_access_expr, std::mem::take(&mut method_call_expr.exprs));
+        indirect_call_expr.position = method_call_expr.position;
+        indirect_call_expr.is_grouped = method_call_expr.is_grouped;
+        indirect_call_expr
+    }
+
+    // the callee is checked once it has its type, e.g. after the field holding it is checked
+    fn check_indirect_call(&mut self, indirect_call_expr: &mut IndirectCallExpr) -> Box<dyn Type> {
+        // a function stands for its address, so (*cmp)(a, b) is cmp(a, b)
+        while let Some(value_at_expr) = indirect_call_expr.expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            if value_at_expr.expr_type.as_fun().is_none() {
+                break;
+            }
+            indirect_call_expr.expr = std::mem::replace(&mut value_at_expr.expr, IntLiteral::new("0".to_owned()));
+        }
+        self.at(indirect_call_expr.position);
+        let expr_type = indirect_call_expr.expr.get_expr_type();
+        match expr_type.as_fun_pointer() {
+            Some(fun_type) => indirect_call_expr.fun_decl = FunDecl::of_signature(fun_type),
+            None if expr_type.is_error() => return Box::new(ErrorType),
+            None => return self.error(format!("cannot call a value of type {}", expr_type)),
+        }
+        if !self.check_args("function pointer", indirect_call_expr.position, &indirect_call_expr.fun_decl, &mut indirect_call_expr.exprs) {
+            return Box::new(ErrorType);
+        }
+        indirect_call_expr.expr_type = indirect_call_expr.fun_decl.fun_type.clone();
+        indirect_call_expr.expr_type.clone()
+    }
+
+    // a global is initialised before the program runs, so every value must be known at compile time;
+    // `constant` names the kind of such a variable in the error, "global" or "static"
+    fn check_init(&mut self, var_type: &dyn Type, init: &mut Initializer, constant: Option<&str>) {
+        match init {
+            Initializer::Expr(expr) => {
+                let expr_type = self.visit_expr(expr);
+                if let Some(array_type) = var_type.as_array() {
+                    match expr.as_any().downcast_ref::<StrLiteral>() {
+                        Some(str_literal) if array_type.array_type.is_base(BaseType::CHAR) => {
+                            // the terminating '\0' is dropped when it does not fit
+                            if array_type.i.is_some_and(|i| str_literal.bytes().len() as i32 > i) {
+                                self.error(format!("string literal is too long for {}", var_type));
+                            }
+                        },
+                        _ => { self.error("array initialiser must be a brace enclosed list".to_owned()); }
+                    }
+                } else if !self.convert(var_type, expr, expr_type.as_ref()) {
+                    self.error(format!("cannot initialise {} with {}", var_type, expr_type));
+                } else if let Some(kind) = constant.filter(|_| !expr_type.is_error() && constant_value(expr.as_ref()).is_none()) {
+                    self.error(format!("{} initialiser must be a constant", kind));
+                }
+            },
+            Initializer::List(items) => {
+                if let Some(array_type) = var_type.as_array() {
+                    if array_type.i.is_some_and(|i| items.len() as i32 > i) {
+                        self.error(format!("too many initialisers for {}", var_type));
+                    }
+                    for item in items.iter_mut() {
+                        self.check_init(array_type.array_type.as_ref(), item, constant);
+                    }
+                } else if let Some(struct_type) = var_type.as_struct() {
+                    let fields: Vec<Box<dyn Type>> = self.struct_type_decls.get(&struct_type.name)
+                        .map_or(vec![], |decl| decl.var_decls.iter().map(|field| field.var_type.clone()).collect());
+                    if items.len() > fields.len() {
+                        self.error(format!("too many initialisers for {}", var_type));
+                    }
+                    for (field_type, item) in fields.iter().zip(items.iter_mut()) {
+                        self.check_init(field_type.as_ref(), item, constant);
+                    }
+                } else {
+                    self.error(format!("cannot initialise {} with a brace enclosed list", var_type));
+                }
+            }
+        }
+    }
+}
+
+impl ASTVisitor<Box<dyn Type>> for TypeAnalyzer {
+    fn visit_base_type(&mut self, base_type: &mut BaseType) -> Box<dyn Type> {
+        Box::new(*base_type)
+    }
+
+    fn visit_pointer_type(&mut self, pointer_type: &mut PointerType) -> Box<dyn Type> {
+        Box::new(pointer_type.clone())
+    }
+
+    fn visit_struct_type(&mut self, struct_type: &mut StructType) -> Box<dyn Type> {
+        Box::new(struct_type.clone())
+    }
+
+    fn visit_array_type(&mut self, array_type: &mut ArrayType) -> Box<dyn Type> {
+        Box::new(array_type.clone())
+    }
+
+    fn visit_class_type(&mut self, class_type: &mut ClassType) -> Box<dyn Type> {
+        Box::new(class_type.clone())
+    }
+
+    fn visit_fun_type(&mut self, fun_type: &mut FunType) -> Box<dyn Type> {
+        Box::new(fun_type.clone())
+    }
+
+    fn visit_program(&mut self, program: &mut Program) -> Box<dyn Type> {
+        for struct_type_decl in program.struct_type_decls.iter_mut() {
+            struct_type_decl.accept(self);
+        }
+        self.layout = Layout::new(&program.struct_type_decls);
+        // methods can use every class, so they are all known before any body is checked
+        for class_decl in program.class_decls.iter() {
+            self.class_decls.insert(class_decl.class_type.name.to_owned(), class_decl.clone());
+        }
+        for var_decl in program.var_decls.iter_mut() {
+            if let Some(init) = &mut var_decl.init {
+                self.at(var_decl.position);
+                self.check_init(var_decl.var_type.as_ref(), init, Some("global"));
+            }
+        }
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+        for class_decl in program.class_decls.iter_mut() {
+            class_decl.accept(self);
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &mut ClassDecl) -> Box<dyn Type> {
+        for fun_decl in class_decl.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_struct_type_decl(&mut self, struct_type_decl: &mut StructTypeDecl) -> Box<dyn Type> {
+        self.struct_type_decls.insert(struct_type_decl.struct_type.name.to_owned(), struct_type_decl.clone());
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) -> Box<dyn Type> {
+        if let Some(init) = &mut var_decl.init {
+            self.at(var_decl.position);
+            // a static local is initialised once, as a global
+            let constant = (var_decl.storage == StorageClass::Static).then_some("static");
+            self.check_init(var_decl.var_type.as_ref(), init, constant);
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) -> Box<dyn Type> {
+        self.fun_type = fun_decl.fun_type.clone();
+        self.at(fun_decl.position);
+        fun_decl.block.accept(self);
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_block(&mut self, block: &mut Block) -> Box<dyn Type> {
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut a_while.expr);
+        self.check_condition(&mut a_while.expr, expr_type.as_ref(), "while condition");
+        self.visit_stmt(&mut a_while.stmt);
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) -> Box<dyn Type> {
+        self.visit_stmt(&mut a_do_while.stmt);
+        let expr_type = self.visit_expr(&mut a_do_while.expr);
+        self.check_condition(&mut a_do_while.expr, expr_type.as_ref(), "do while condition");
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) -> Box<dyn Type> {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        if let Some(expr) = &mut a_for.expr {
+            let expr_type = self.visit_expr(expr);
+            self.check_condition(expr, expr_type.as_ref(), "for condition");
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.visit_stmt(&mut a_for.stmt);
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) -> Box<dyn Type> {
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) -> Box<dyn Type> {
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut an_if.expr);
+        self.check_condition(&mut an_if.expr, expr_type.as_ref(), "if condition");
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.visit_stmt(stmt2);
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    // the expression is promoted and the labels converted to its type, two labels being duplicates when
+    // they are equal once converted
+    fn visit_switch(&mut self, a_switch: &mut Switch) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut a_switch.expr);
+        let is_char = expr_type.is_base(BaseType::CHAR);
+        let switch_type = expr_type.as_integer().map(|t| t.promote());
+        match switch_type {
+            Some(t) => TypeAnalyzer::cast(&mut a_switch.expr, Box::new(t)),
+            None if expr_type.is_error() => { },
+            None => { self.error(format!("switch expression must be an integer, found {}", expr_type)); },
+        }
+
+        let mut labels = HashSet::new();
+        let mut has_default = false;
+        for case in a_switch.cases.iter_mut() {
+            self.at(case.position);
+            match &mut case.expr {
+                Some(expr) => {
+                    let label_type = self.visit_expr(expr);
+                    match (switch_type, label_type.as_integer()) {
+                        (Some(t), Some(_)) => TypeAnalyzer::cast(expr, Box::new(t)),
+                        (Some(_), None) if label_type.is_error() => { },
+                        (Some(_), None) => { self.error(format!("case label must be an integer, found {}", label_type)); },
+                        (None, _) => { },
+                    }
+                    match constant_value(expr.as_ref()) {
+                        None if label_type.is_error() => { },
+                        Some(value) if !labels.insert(value) => {
+                            let label = if is_char { format!("'{}'", escape_char(value as u8 as char)) } else { value.to_string() };
+                            self.error(format!("duplicate case label {}", label));
+                        },
+                        Some(_) => { },
+                        None => { self.error("case label must be a constant".to_owned()); },
+                    }
+                },
+                None if has_default => { self.error("duplicate default label".to_owned()); },
+                None => has_default = true,
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) -> Box<dyn Type> {
+        let fun_type = self.fun_type.clone();
+        match &mut a_return.expr {
+            Some(expr) => {
+                let expr_type = self.visit_expr(expr);
+                if !self.convert(fun_type.as_ref(), expr, expr_type.as_ref()) {
+                    self.error(format!("cannot return {} from a function returning {}", expr_type, fun_type));
+                }
+            },
+            None => {
+                if !fun_type.is_base(BaseType::VOID) {
+                    self.error(format!("missing return value in a function returning {}", fun_type));
+                }
+            }
+        }
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) -> Box<dyn Type> {
+        let lhs_type = self.visit_expr(&mut assign.expr1);
+        let rhs_type = self.visit_expr(&mut assign.expr2);
+        self.check_assign(assign.expr1.as_ref(), lhs_type.as_ref(), &mut assign.expr2, rhs_type.as_ref());
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) -> Box<dyn Type> {
+        self.visit_expr(&mut expr_stmt.expr);
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) -> Box<dyn Type> {
+        let lhs_type = self.visit_expr(&mut bin_op.expr1);
+        let rhs_type = self.visit_expr(&mut bin_op.expr2);
+        if TypeAnalyzer::failed(&[lhs_type.as_ref(), rhs_type.as_ref()]) {
+            bin_op.expr_type = Box::new(ErrorType);
+            return bin_op.expr_type.clone();
+        }
+
+        let int_types = lhs_type.as_integer().zip(rhs_type.as_integer());
+        let result = match bin_op.op {
+            // the operand of a prefix operator, which is reported as written
+            _ if bin_op.prefix && int_types.is_none() => {
+                self.error(format!("operator {} expects an integer operand, found {}", bin_op.symbol(), rhs_type))
+            },
+            Op::AND | Op::OR if int_types.is_some() => {
+                self.check_condition(&mut bin_op.expr1, lhs_type.as_ref(), "operand");
+                self.check_condition(&mut bin_op.expr2, rhs_type.as_ref(), "operand");
+                Box::new(BaseType::INT)
+            },
+            // the amount of a shift is an int, whatever the type of the shifted value
+            Op::SHL | Op::SHR if int_types.is_some() => {
+                let (t1, _) = int_types.unwrap();
+                TypeAnalyzer::cast(&mut bin_op.expr1, Box::new(t1.promote()));
+                TypeAnalyzer::cast(&mut bin_op.expr2, Box::new(BaseType::INT));
+                Box::new(t1.promote())
+            },
+            // the usual arithmetic conversions, comparisons giving an int
+            _ if int_types.is_some() => {
+                let (t1, t2) = int_types.unwrap();
+                let common = t1.common(t2);
+                TypeAnalyzer::cast(&mut bin_op.expr1, Box::new(common));
+                TypeAnalyzer::cast(&mut bin_op.expr2, Box::new(common));
+                match bin_op.op {
+                    Op::EQ | Op::NE | Op::LT | Op::GT | Op::LE | Op::GE => Box::new(BaseType::INT),
+                    _ => Box::new(common),
+                }
+            },
+            Op::EQ | Op::NE => {
+                if lhs_type.as_struct().is_some() || lhs_type.as_array().is_some() || lhs_type.is_base(BaseType::VOID) {
+                    self.error(format!("cannot compare values of type {}", lhs_type))
+                } else if !self.convert(lhs_type.as_ref(), &mut bin_op.expr2, rhs_type.as_ref())
+                    && !self.convert(rhs_type.as_ref(), &mut bin_op.expr1, lhs_type.as_ref()) {
+                    self.error(format!("cannot compare {} with {}", lhs_type, rhs_type))
+                } else {
+                    Box::new(BaseType::INT)
+                }
+            },
+            _ => self.error(format!("operator {} expects integer operands, found {} and {}", bin_op.symbol(), lhs_type, rhs_type)),
+        };
+        bin_op.expr_type = result.clone();
+        result
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) -> Box<dyn Type> {
+        Box::new(BaseType::VOID)
+    }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) -> Box<dyn Type> {
+        int_literal.expr_type = Box::new(int_literal.int_type);
+        int_literal.expr_type.clone()
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) -> Box<dyn Type> {
+        chr_literal.expr_type = Box::new(BaseType::CHAR);
+        chr_literal.expr_type.clone()
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) -> Box<dyn Type> {
+        // one more character for the terminating '\0'
+        str_literal.expr_type = Box::new(ArrayType::new(Box::new(BaseType::CHAR), str_literal.bytes().len() as i32 + 1));
+        str_literal.expr_type.clone()
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) -> Box<dyn Type> {
+        self.at(var_expr.position);
+        var_expr.expr_type = var_expr.var_decl.var_type.clone();
+        var_expr.expr_type.clone()
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut typecast_expr.expr);
+        let cast_type = typecast_expr.typecast_type.as_ref();
+
+        let valid = if expr_type.is_error() {
+            true
+        } else if cast_type.as_integer().is_some() {
+            expr_type.as_integer().is_some()
+        } else if let Some(pointer_type) = cast_type.as_pointer() {
+            match expr_type.as_array() {
+                Some(array_type) => array_type.array_type.eq_type(pointer_type.pointer_type.as_ref()),
+                None => expr_type.as_pointer().is_some(),
+            }
+        } else if let (Some(t1), Some(t2)) = (cast_type.as_class(), expr_type.as_class()) {
+            // a downcast is not checked at run time
+            self.is_subclass(&t1.name, &t2.name) || self.is_subclass(&t2.name, &t1.name)
+        } else {
+            false
+        };
+
+        if !valid {
+            return self.error(format!("cannot cast {} to {}", expr_type, cast_type));
+        }
+        typecast_expr.expr_type = typecast_expr.typecast_type.clone();
+        typecast_expr.expr_type.clone()
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) -> Box<dyn Type> {
+        size_of_expr.size = Some(self.layout.size_of(size_of_expr.sizeof_type.as_ref()));
+        size_of_expr.expr_type = Box::new(BaseType::INT);
+        size_of_expr.expr_type.clone()
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut address_of_expr.expr);
+        if !TypeAnalyzer::is_lvalue(address_of_expr.expr.as_ref()) {
+            return self.error("cannot take the address of a value that is not stored in memory".to_owned());
+        }
+        // the address of a const value points to const
+        address_of_expr.expr_type = match self.const_lvalue(address_of_expr.expr.as_ref()) {
+            Some(_) => PointerType::to_const(expr_type),
+            None => PointerType::new(expr_type),
+        };
+        address_of_expr.expr_type.clone()
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut value_at_expr.expr);
+        match expr_type.as_pointer() {
+            Some(pointer_type) => value_at_expr.expr_type = pointer_type.pointer_type.clone(),
+            // a function stands for its address, so dereferencing it gives the function again
+            None if expr_type.as_fun().is_some() || expr_type.is_error() => value_at_expr.expr_type = expr_type,
+            None => return self.error(format!("cannot dereference a value of type {}", expr_type)),
+        }
+        value_at_expr.expr_type.clone()
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) -> Box<dyn Type> {
+        let expr_type = if field_access_expr.is_arrow() {
+            // the dereference of p->f is checked here to report the misuse of -> itself
+            let value_at_expr = field_access_expr.expr.as_mut_any().downcast_mut::<ValueAtExpr>().unwrap();
+            let pointer_type = self.visit_expr(&mut value_at_expr.expr);
+            self.at(field_access_expr.position);
+            match pointer_type.as_pointer().map(|p| p.pointer_type.clone()) {
+                Some(struct_type) if struct_type.as_struct().is_some() => value_at_expr.expr_type = struct_type,
+                _ if pointer_type.is_error() => return pointer_type,
+                _ if pointer_type.as_struct().is_some() || pointer_type.as_class().is_some() => {
+                    return self.error(format!("operator -> expects a pointer to a struct, found {}, did you mean `.`?", pointer_type))
+                },
+                _ => return self.error(format!("operator -> expects a pointer to a struct, found {}", pointer_type)),
+            }
+            value_at_expr.expr_type.clone()
+        } else {
+            self.visit_expr(&mut field_access_expr.expr)
+        };
+        self.check_field(field_access_expr, expr_type)
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) -> Box<dyn Type> {
+        let array_type = self.visit_expr(&mut array_access_expr.expr1);
+        let index_type = self.visit_expr(&mut array_access_expr.expr2);
+
+        if TypeAnalyzer::failed(&[array_type.as_ref(), index_type.as_ref()]) {
+            array_access_expr.expr_type = Box::new(ErrorType);
+            return array_access_expr.expr_type.clone();
+        } else if index_type.as_integer().is_none() {
+            return self.error(format!("array index must be an integer, found {}", index_type));
+        }
+        TypeAnalyzer::cast(&mut array_access_expr.expr2, Box::new(BaseType::INT));
+        if let Some(t) = array_type.as_array() {
+            array_access_expr.expr_type = t.array_type.clone();
+        } else if let Some(t) = array_type.as_pointer() {
+            array_access_expr.expr_type = t.pointer_type.clone();
+        } else {
+            return self.error(format!("cannot index a value of type {}", array_type));
+        }
+        array_access_expr.expr_type.clone()
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) -> Box<dyn Type> {
+        let callee = format!("function {}", fun_call_expr.name);
+        if !self.check_args(&callee, fun_call_expr.position, &fun_call_expr.fun_decl, &mut fun_call_expr.exprs) {
+            return Box::new(ErrorType);
+        }
+        fun_call_expr.expr_type = fun_call_expr.fun_decl.fun_type.clone();
+        fun_call_expr.expr_type.clone()
+    }
+
+    fn visit_new_expr(&mut self, new_expr: &mut NewExpr) -> Box<dyn Type> {
+        new_expr.expr_type = Box::new(new_expr.class_type.clone());
+        new_expr.expr_type.clone()
+    }
+
+    fn visit_method_call_expr(&mut self, method_call_expr: &mut MethodCallExpr) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut method_call_expr.expr);
+        self.check_method_call(method_call_expr, expr_type)
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) -> Box<dyn Type> {
+        fun_addr_expr.expr_type = PointerType::new(Box::new(fun_addr_expr.fun_decl.signature()));
+        fun_addr_expr.expr_type.clone()
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) -> Box<dyn Type> {
+        self.visit_expr(&mut indirect_call_expr.expr);
+        self.check_indirect_call(indirect_call_expr)
+    }
+
+    // the value of an assignment is the one stored, of the type of the left hand side. The right hand
+    // side of a compound assignment is converted to the type the operation is computed in, by the usual
+    // arithmetic conversions, the result being converted back to the type of the left hand side
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) -> Box<dyn Type> {
+        let lhs_type = self.visit_expr(&mut assign_expr.expr1);
+        let rhs_type = self.visit_expr(&mut assign_expr.expr2);
+
+        let valid = match assign_expr.op {
+            None => self.check_assign(assign_expr.expr1.as_ref(), lhs_type.as_ref(), &mut assign_expr.expr2, rhs_type.as_ref()),
+            Some(_) if !TypeAnalyzer::is_lvalue(assign_expr.expr1.as_ref()) => {
+                self.error("left hand side of an assignment must be a variable, field, array element or dereference".to_owned());
+                false
+            },
+            Some(_) if self.const_lvalue(assign_expr.expr1.as_ref()).is_some() => {
+                let what = self.const_lvalue(assign_expr.expr1.as_ref()).unwrap();
+                self.error(format!("cannot assign to {}", what));
+                false
+            },
+            Some(_) if TypeAnalyzer::failed(&[lhs_type.as_ref(), rhs_type.as_ref()]) => false,
+            Some(op) => match lhs_type.as_integer().zip(rhs_type.as_integer()) {
+                Some((t1, t2)) => {
+                    TypeAnalyzer::cast(&mut assign_expr.expr2, Box::new(t1.common(t2)));
+                    true
+                },
+                None => {
+                    self.error(format!("operator {}= expects integer operands, found {} and {}", op.symbol(), lhs_type, rhs_type));
+                    false
+                },
+            },
+        };
+        if !valid {
+            return Box::new(ErrorType);
+        }
+        assign_expr.expr_type = lhs_type;
+        assign_expr.expr_type.clone()
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut inc_dec_expr.expr);
+        let symbol = if inc_dec_expr.op == Op::ADD { "++" } else { "--" };
+
+        if !TypeAnalyzer::is_lvalue(inc_dec_expr.expr.as_ref()) {
+            return self.error(format!("operand of {} must be a variable, field, array element or dereference", symbol));
+        } else if let Some(what) = self.const_lvalue(inc_dec_expr.expr.as_ref()) {
+            return self.error(format!("operand of {} cannot be {}", symbol, what));
+        } else if expr_type.as_integer().is_none() && !expr_type.is_error() {
+            return self.error(format!("operator {} expects an integer operand, found {}", symbol, expr_type));
+        }
+        inc_dec_expr.expr_type = expr_type;
+        inc_dec_expr.expr_type.clone()
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) -> Box<dyn Type> {
+        let expr_type = self.visit_expr(&mut conditional_expr.expr);
+        let type1 = self.visit_expr(&mut conditional_expr.expr1);
+        let type2 = self.visit_expr(&mut conditional_expr.expr2);
+
+        if TypeAnalyzer::failed(&[expr_type.as_ref(), type1.as_ref(), type2.as_ref()]) {
+            conditional_expr.expr_type = Box::new(ErrorType);
+            return conditional_expr.expr_type.clone();
+        } else if expr_type.as_integer().is_none() {
+            return self.error(format!("condition of ?: must be an integer, found {}", expr_type));
+        } else if type1.as_array().is_some() {
+            return self.error(format!("branches of ?: cannot be of type {}", type1));
+        }
+        self.check_condition(&mut conditional_expr.expr, expr_type.as_ref(), "condition of ?:");
+        if let (Some(t1), Some(t2)) = (type1.as_integer(), type2.as_integer()) {
+            let common = t1.common(t2);
+            TypeAnalyzer::cast(&mut conditional_expr.expr1, Box::new(common));
+            TypeAnalyzer::cast(&mut conditional_expr.expr2, Box::new(common));
+            conditional_expr.expr_type = Box::new(common);
+            return conditional_expr.expr_type.clone();
+        }
+        // a class branch is converted to the class of the other one when it is a subclass of it
+        if self.convert(type1.as_ref(), &mut conditional_expr.expr2, type2.as_ref()) {
+            conditional_expr.expr_type = type1;
+        } else if self.convert(type2.as_ref(), &mut conditional_expr.expr1, type1.as_ref()) {
+            conditional_expr.expr_type = type2;
+        } else {
+            return self.error(format!("branches of ?: have incompatible types {} and {}", type1, type2));
+        }
+        conditional_expr.expr_type.clone()
+    }
+
+    fn visit_type(&mut self, a_type: &mut Box<dyn Type>) -> Box<dyn Type> {
+        a_type.clone()
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) -> Box<dyn Type> {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self)
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) -> Box<dyn Type> {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            let expr_type = self.visit_expr(&mut cast_expr.expr);
+            let is_field = match expr_type.as_class() {
+                Some(class_type) => self.method(&class_type.name, &cast_expr.name).is_none() && self.field(&class_type.name, &cast_expr.name).is_some(),
+                None => expr_type.as_struct().is_some(),
+            };
+            if !is_field {
+                return self.check_method_call(cast_expr, expr_type);
+            }
+            let mut indirect_call_expr = self.field_call(cast_expr, expr_type);
+            let call_type = self.check_indirect_call(&mut indirect_call_expr);
+            *expr = indirect_call_expr;
+            call_type
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self)
+        } else {
+            unreachable!()
+        }
+    }
+}
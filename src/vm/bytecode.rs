@@ -0,0 +1,312 @@
+use std::fmt::{Display, Formatter};
+use std::io::{self, Read, Write};
+use crate::vm::vm::{MAX_MEMORY, STACK_SIZE};
+
+/// Bytecode file format
+///
+/// All integers are little endian.
+///
+/// ```text
+/// magic       "MCBC"
+/// version     u8
+/// data_size   u32     bytes of the data segment: globals followed by string literals
+/// data_len    u32     number of initialised bytes at the start of data_init
+/// data_init   [u8; data_len]
+/// fun_count   u32
+/// functions   fun_count times:
+///     name_len u32, name [u8; name_len], entry u32, frame_size u32
+/// main        u32     index of the function called on start
+/// code_len    u32
+/// code        [u8; code_len]
+/// ```
+///
+/// The data segment is loaded at DATA_BASE and the string literals are placed after the globals,
+/// so only data_init contains anything but zeros. Each instruction is a one byte opcode followed
+/// by a four byte signed operand for the opcodes listed in `Opcode::has_operand`.
+///
+/// Values are 64 bits wide. Those of narrower types are kept sign or zero extended according to the
+/// signedness of their type, so that the compiler only needs a Wrap after the operations which can
+/// leave their range, and the signed and unsigned variants of the operators can work on all 64 bits.
+pub const MAGIC: &[u8; 4] = b"MCBC";
+pub const VERSION: u8 = 2;
+pub const DATA_BASE: i32 = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Opcode {
+    // push the operand
+    Push = 0x01,
+    // push fp + operand, the address of a local
+    Local = 0x02,
+    Pop = 0x03,
+    // pop an address and push the sign extended word, zero extended byte or half word, or the double
+    // word it points to
+    LoadW = 0x10,
+    LoadB = 0x11,
+    // pop an address then a value and store the low bytes of the value
+    StoreW = 0x12,
+    StoreB = 0x13,
+    // pop a destination then a source address and copy operand bytes
+    Copy = 0x14,
+    LoadH = 0x15,
+    LoadD = 0x16,
+    StoreH = 0x17,
+    StoreD = 0x18,
+    Add = 0x20,
+    Sub = 0x21,
+    Mul = 0x22,
+    Div = 0x23,
+    Rem = 0x24,
+    Lt = 0x25,
+    Gt = 0x26,
+    Le = 0x27,
+    Ge = 0x28,
+    Eq = 0x29,
+    Ne = 0x2a,
+    And = 0x2b,
+    Or = 0x2c,
+    Xor = 0x2d,
+    // the amount of the shifts is taken modulo 64, Shr keeps the sign
+    Shl = 0x2e,
+    Shr = 0x2f,
+    // jumps take an absolute code offset, the conditional ones pop the condition
+    Jmp = 0x30,
+    Jz = 0x31,
+    Jnz = 0x32,
+    // call the function of the given index, arguments are left on the stack in order
+    Call = 0x40,
+    Ret = 0x41,
+    // call the host implementation of a stdlib function, see Syscall
+    Sys = 0x42,
+    // pop a function index, pushed as the address of the function, and call it like Call
+    CallInd = 0x43,
+    // truncate the value on top to operand bits, then sign extend it if the operand is negative or
+    // zero extend it otherwise, e.g. -8 for a signed char
+    Wrap = 0x50,
+    // the operators treating their operands as unsigned, Shr not keeping the sign
+    DivU = 0x51,
+    RemU = 0x52,
+    LtU = 0x53,
+    GtU = 0x54,
+    LeU = 0x55,
+    GeU = 0x56,
+    ShrU = 0x57,
+}
+
+impl Opcode {
+    const ALL: [Opcode; 43] = [
+        Opcode::Push, Opcode::Local, Opcode::Pop, Opcode::LoadW, Opcode::LoadB, Opcode::StoreW, Opcode::StoreB,
+        Opcode::Copy, Opcode::LoadH, Opcode::LoadD, Opcode::StoreH, Opcode::StoreD, Opcode::Add, Opcode::Sub, Opcode::Mul, Opcode::Div, Opcode::Rem, Opcode::Lt, Opcode::Gt,
+        Opcode::Le, Opcode::Ge, Opcode::Eq, Opcode::Ne, Opcode::And, Opcode::Or, Opcode::Xor, Opcode::Shl,
+        Opcode::Shr, Opcode::Jmp, Opcode::Jz, Opcode::Jnz, Opcode::Call,
+        Opcode::Ret, Opcode::Sys, Opcode::CallInd, Opcode::Wrap, Opcode::DivU, Opcode::RemU, Opcode::LtU,
+        Opcode::GtU, Opcode::LeU, Opcode::GeU, Opcode::ShrU,
+    ];
+
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        Opcode::ALL.iter().find(|op| **op as u8 == byte).copied()
+    }
+
+    pub fn has_operand(&self) -> bool {
+        matches!(self, Opcode::Push | Opcode::Local | Opcode::Copy | Opcode::Jmp | Opcode::Jz | Opcode::Jnz | Opcode::Call | Opcode::Sys
+            | Opcode::Wrap)
+    }
+
+    pub fn size(&self) -> usize {
+        if self.has_operand() { 5 } else { 1 }
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Host functions, the operand of Sys.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Syscall {
+    PrintS = 0,
+    PrintI = 1,
+    PrintC = 2,
+    ReadC = 3,
+    ReadI = 4,
+    Mcmalloc = 5,
+}
+
+impl Syscall {
+    pub fn from_name(name: &str) -> Option<Syscall> {
+        match name {
+            "print_s" => Some(Syscall::PrintS),
+            "print_i" => Some(Syscall::PrintI),
+            "print_c" => Some(Syscall::PrintC),
+            "read_c" => Some(Syscall::ReadC),
+            "read_i" => Some(Syscall::ReadI),
+            "mcmalloc" => Some(Syscall::Mcmalloc),
+            _ => None,
+        }
+    }
+
+    pub fn from_operand(operand: i32) -> Option<Syscall> {
+        [Syscall::PrintS, Syscall::PrintI, Syscall::PrintC, Syscall::ReadC, Syscall::ReadI, Syscall::Mcmalloc]
+            .iter().find(|s| **s as i32 == operand).copied()
+    }
+}
+
+#[derive(Clone)]
+pub struct FunctionEntry {
+    pub name: String,
+    pub entry: u32,
+    pub frame_size: u32,
+}
+
+/// Module
+/// A compiled program, as written to and read from a bytecode file.
+pub struct Module {
+    pub data_size: u32,
+    pub data_init: Vec<u8>,
+    pub functions: Vec<FunctionEntry>,
+    pub main: u32,
+    pub code: Vec<u8>,
+}
+
+fn write_u32(writer: &mut dyn Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(reader: &mut dyn Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_bytes(reader: &mut dyn Read, len: u32) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+impl Module {
+    pub fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_u32(writer, self.data_size)?;
+        write_u32(writer, self.data_init.len() as u32)?;
+        writer.write_all(&self.data_init)?;
+        write_u32(writer, self.functions.len() as u32)?;
+        for function in self.functions.iter() {
+            write_u32(writer, function.name.len() as u32)?;
+            writer.write_all(function.name.as_bytes())?;
+            write_u32(writer, function.entry)?;
+            write_u32(writer, function.frame_size)?;
+        }
+        write_u32(writer, self.main)?;
+        write_u32(writer, self.code.len() as u32)?;
+        writer.write_all(&self.code)?;
+        writer.flush()
+    }
+
+    pub fn read(reader: &mut dyn Read) -> io::Result<Module> {
+        let mut magic = [0; 5];
+        reader.read_exact(&mut magic)?;
+        if &magic[0..4] != MAGIC {
+            return Err(invalid("not a minic bytecode file"));
+        }
+        if magic[4] != VERSION {
+            return Err(invalid("unsupported bytecode version"));
+        }
+
+        let data_size = read_u32(reader)?;
+        let data_len = read_u32(reader)?;
+        let data_init = read_bytes(reader, data_len)?;
+        let fun_count = read_u32(reader)?;
+        let mut functions = Vec::new();
+        for _ in 0..fun_count {
+            let name_len = read_u32(reader)?;
+            let name = String::from_utf8(read_bytes(reader, name_len)?).map_err(|_| invalid("invalid function name"))?;
+            let entry = read_u32(reader)?;
+            let frame_size = read_u32(reader)?;
+            functions.push(FunctionEntry { name, entry, frame_size });
+        }
+        let main = read_u32(reader)?;
+        let code_len = read_u32(reader)?;
+        let code = read_bytes(reader, code_len)?;
+
+        if data_init.len() > data_size as usize || data_size as usize > MAX_MEMORY || main >= fun_count {
+            return Err(invalid("malformed bytecode file"));
+        }
+        // the VM trusts the functions, whose frames must fit in the stack, and the calls to them
+        if functions.iter().any(|f| f.entry as usize >= code.len() || f.frame_size > STACK_SIZE as u32) {
+            return Err(invalid("invalid function entry"));
+        }
+        let module = Module { data_size, data_init, functions, main, code };
+        if module.calls().any(|index| index as u32 >= fun_count) {
+            return Err(invalid("call to an undefined function"));
+        }
+        Ok(module)
+    }
+
+    // the operands of the Call instructions, the bytes which are no instruction being skipped as the
+    // disassembler does
+    fn calls(&self) -> impl Iterator<Item = i32> + '_ {
+        let mut pc = 0;
+        std::iter::from_fn(move || {
+            while pc < self.code.len() {
+                let at = pc;
+                match Opcode::from_byte(self.code[at]) {
+                    Some(opcode) if at + opcode.size() <= self.code.len() => {
+                        pc += opcode.size();
+                        if opcode == Opcode::Call {
+                            return Some(self.operand(at));
+                        }
+                    },
+                    _ => pc += 1,
+                }
+            }
+            None
+        })
+    }
+
+    pub fn operand(&self, pc: usize) -> i32 {
+        i32::from_le_bytes([self.code[pc + 1], self.code[pc + 2], self.code[pc + 3], self.code[pc + 4]])
+    }
+
+    /// Prints the functions and instructions of the module in a readable form.
+    pub fn disassemble(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "data: {} bytes, {} initialised", self.data_size, self.data_init.len())?;
+        let mut pc = 0;
+        while pc < self.code.len() {
+            for (i, function) in self.functions.iter().enumerate() {
+                if function.entry as usize == pc {
+                    writeln!(writer, "\nfunction {} {} (frame {}){}:", i, function.name, function.frame_size,
+                             if i as u32 == self.main { " main" } else { "" })?;
+                }
+            }
+
+            let opcode = match Opcode::from_byte(self.code[pc]) {
+                Some(opcode) if pc + opcode.size() <= self.code.len() => opcode,
+                _ => {
+                    writeln!(writer, "{:6}  <invalid {:#04x}>", pc, self.code[pc])?;
+                    pc += 1;
+                    continue;
+                },
+            };
+            if !opcode.has_operand() {
+                writeln!(writer, "{:6}  {}", pc, opcode)?;
+            } else {
+                let operand = self.operand(pc);
+                let comment = match opcode {
+                    Opcode::Call => self.functions.get(operand as usize).map(|f| format!("  ; {}", f.name)),
+                    Opcode::Sys => Syscall::from_operand(operand).map(|s| format!("  ; {:?}", s)),
+                    _ => None,
+                };
+                writeln!(writer, "{:6}  {} {}{}", pc, opcode, operand, comment.unwrap_or_default())?;
+            }
+            pc += opcode.size();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,820 @@
+use std::collections::HashMap;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::gen::layout::{align_to, InitValue, Layout, WORD_SIZE};
+use crate::vm::bytecode::{FunctionEntry, Module, Opcode, Syscall, DATA_BASE};
+use crate::ASTNode;
+
+#[derive(Clone, Copy)]
+enum Location {
+    // offset from the frame pointer
+    Local(i32),
+    // absolute address in the data segment
+    Global(i32),
+}
+
+/// BytecodeCompiler
+/// Compiles a checked Program to a bytecode Module. Every variable lives in memory, globals in the
+/// data segment and locals in the frame of their function, so pointers behave as in C. Expressions
+/// push their value on the operand stack, except structs and arrays which push their address. The
+/// values of the integer types narrower than 64 bits are kept sign or zero extended to 64 bits.
+pub struct BytecodeCompiler {
+    layout: Layout,
+    code: Vec<u8>,
+    data: Vec<u8>,
+    globals_size: i32,
+    // the initial contents of the globals
+    globals_init: Vec<u8>,
+    functions: HashMap<String, u32>,
+    entries: Vec<FunctionEntry>,
+    scopes: Vec<HashMap<String, Location>>,
+    frame_size: i32,
+    ret_ptr: Option<i32>,
+    // the break and continue jumps of the enclosing loops and switches to patch, the innermost last
+    loops: Vec<(Vec<usize>, Vec<usize>)>,
+}
+
+impl BytecodeCompiler {
+    pub fn new(struct_type_decls: &[StructTypeDecl]) -> Self {
+        BytecodeCompiler {
+            layout: Layout::new(struct_type_decls),
+            code: Vec::new(),
+            data: Vec::new(),
+            globals_size: 0,
+            globals_init: Vec::new(),
+            functions: HashMap::new(),
+            entries: Vec::new(),
+            scopes: vec![HashMap::new()],
+            frame_size: 0,
+            ret_ptr: None,
+            loops: Vec::new(),
+        }
+    }
+
+    pub fn compile(program: &mut Program) -> Module {
+        BytecodeCompiler::compile_with_entry(program, "main")
+    }
+
+    /// Compiles a program whose execution starts with the given function instead of main.
+    pub fn compile_with_entry(program: &mut Program, entry: &str) -> Module {
+        let mut compiler = BytecodeCompiler::new(&program.struct_type_decls);
+        program.accept(&mut compiler);
+
+        let main = compiler.functions[entry];
+        Module {
+            data_size: compiler.globals_size as u32 + compiler.data.len() as u32,
+            data_init: [compiler.globals_init, compiler.data].concat(),
+            functions: compiler.entries,
+            main,
+            code: compiler.code,
+        }
+    }
+
+    fn emit(&mut self, opcode: Opcode) {
+        self.code.push(opcode as u8);
+    }
+
+    fn emit_with(&mut self, opcode: Opcode, operand: i32) -> usize {
+        self.code.push(opcode as u8);
+        self.code.extend(operand.to_le_bytes());
+        self.code.len() - 4
+    }
+
+    // emits a jump whose target is set later by patch
+    fn emit_jump(&mut self, opcode: Opcode) -> usize {
+        self.emit_with(opcode, 0)
+    }
+
+    fn patch(&mut self, at: usize) {
+        self.patch_to(at, self.code.len());
+    }
+
+    fn patch_to(&mut self, at: usize, target: usize) {
+        self.code[at..at + 4].copy_from_slice(&(target as i32).to_le_bytes());
+    }
+
+    // compiles the body of a loop, returning its break and continue jumps
+    fn loop_body(&mut self, stmt: &mut Box<dyn Stmt>) -> (Vec<usize>, Vec<usize>) {
+        self.loops.push((Vec::new(), Vec::new()));
+        self.visit_stmt(stmt);
+        self.loops.pop().unwrap()
+    }
+
+    /// The size of the globals of a program, which come first in the data segment in their order of
+    /// declaration.
+    pub fn globals_size(program: &Program) -> i32 {
+        let mut compiler = BytecodeCompiler::new(&program.struct_type_decls);
+        compiler.declare_globals(&program.var_decls);
+        compiler.globals_size
+    }
+
+    fn declare_globals(&mut self, var_decls: &[VarDecl]) {
+        for var_decl in var_decls.iter() {
+            let var_type = var_decl.var_type.as_ref();
+            let offset = align_to(self.globals_size, self.layout.align_of(var_type).max(1));
+            self.globals_size = offset + self.layout.size_of(var_type);
+            self.globals_init.resize(self.globals_size as usize, 0);
+            if let Some(init) = &var_decl.init {
+                let image = self.layout.image(var_type, init);
+                self.globals_init[offset as usize..self.globals_size as usize].copy_from_slice(&image);
+            }
+            self.scopes[0].insert(var_decl.var_name.to_owned(), Location::Global(DATA_BASE + offset));
+        }
+        self.globals_size = align_to(self.globals_size, WORD_SIZE);
+        self.globals_init.resize(self.globals_size as usize, 0);
+    }
+
+    fn new_local(&mut self, a_type: &dyn Type) -> i32 {
+        let offset = align_to(self.frame_size, self.layout.align_of(a_type).max(1));
+        self.frame_size = offset + self.layout.size_of(a_type);
+        offset
+    }
+
+    fn declare(&mut self, var_decl: &VarDecl) -> i32 {
+        let offset = self.new_local(var_decl.var_type.as_ref());
+        self.scopes.last_mut().unwrap().insert(var_decl.var_name.to_owned(), Location::Local(offset));
+        if let Some(init) = &var_decl.init {
+            self.initialize(var_decl.var_type.as_ref(), init, offset);
+        }
+        offset
+    }
+
+    fn initialize(&mut self, var_type: &dyn Type, init: &Initializer, offset: i32) {
+        for (value_offset, value_type, value) in self.layout.flatten(var_type, init) {
+            match value {
+                InitValue::Expr(mut expr) => self.visit_expr(&mut expr),
+                InitValue::Const(c) => { self.emit_with(Opcode::Push, c); },
+            }
+            self.emit_with(Opcode::Local, offset + value_offset);
+            self.emit_store(value_type.as_ref());
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Location {
+        *self.scopes.iter().rev().find_map(|scope| scope.get(name)).unwrap()
+    }
+
+    fn emit_location(&mut self, location: Location) {
+        match location {
+            Location::Local(offset) => self.emit_with(Opcode::Local, offset),
+            Location::Global(address) => self.emit_with(Opcode::Push, address),
+        };
+    }
+
+    // replaces the address on top of the stack by the value it points to, unless it is a struct or an array
+    fn emit_load(&mut self, a_type: &dyn Type) {
+        if Layout::is_aggregate(a_type) {
+            return;
+        }
+        let int_type = a_type.as_integer().unwrap_or(BaseType::INT);
+        self.emit(match int_type.size() {
+            1 => Opcode::LoadB,
+            2 => Opcode::LoadH,
+            8 => Opcode::LoadD,
+            _ => Opcode::LoadW,
+        });
+        // bytes and half words are zero extended and words sign extended
+        if matches!(int_type, BaseType::SCHAR | BaseType::SHORT | BaseType::UINT) {
+            self.emit_wrap(int_type);
+        }
+    }
+
+    // stores or copies the value below the address on top of the stack
+    fn emit_store(&mut self, a_type: &dyn Type) {
+        if a_type.as_struct().is_some() {
+            let size = self.layout.size_of(a_type);
+            self.emit_with(Opcode::Copy, size);
+        } else {
+            self.emit(match self.layout.size_of(a_type) {
+                1 => Opcode::StoreB,
+                2 => Opcode::StoreH,
+                8 => Opcode::StoreD,
+                _ => Opcode::StoreW,
+            });
+        }
+    }
+
+    // brings the value on top of the stack back to the range of the integer type
+    fn emit_wrap(&mut self, int_type: BaseType) {
+        let bits = int_type.size() * 8;
+        if bits < 64 {
+            self.emit_with(Opcode::Wrap, if int_type.is_signed() { -bits } else { bits });
+        }
+    }
+
+    // converts the integer on top of the stack, which only changes its value if it does not fit the
+    // new type
+    fn emit_convert(&mut self, from: BaseType, to: BaseType) {
+        let widens = to.size() > from.size() && (to.is_signed() || !from.is_signed());
+        if from != to && !widens {
+            self.emit_wrap(to);
+        }
+    }
+
+    // pushes a constant, a 64 bit one in two halves
+    fn emit_const(&mut self, value: i64) {
+        if value as i32 as i64 == value {
+            self.emit_with(Opcode::Push, value as i32);
+            return;
+        }
+        self.emit_with(Opcode::Push, (value >> 32) as i32);
+        self.emit_with(Opcode::Push, 32);
+        self.emit(Opcode::Shl);
+        self.emit_with(Opcode::Push, value as i32);
+        self.emit_with(Opcode::Wrap, 32);
+        self.emit(Opcode::Or);
+    }
+
+    /// Pushes the address of an lvalue, or of a struct or array valued expression.
+    fn address(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(var_expr) = expr.as_any().downcast_ref::<VarExpr>() {
+            let location = self.lookup(&var_expr.name);
+            self.emit_location(location);
+        } else if let Some(field_access_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            self.field_address(field_access_expr);
+        } else if let Some(array_access_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            self.element_address(array_access_expr);
+        } else if let Some(value_at_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            self.visit_expr(&mut value_at_expr.expr);
+        } else {
+            self.visit_expr(expr);
+        }
+    }
+
+    // evaluates the address of an lvalue once into a new local, returning the offset of the local
+    fn save_address(&mut self, expr: &mut Box<dyn Expr>) -> i32 {
+        let offset = self.new_local(&BaseType::INT);
+        self.address(expr);
+        self.emit_with(Opcode::Local, offset);
+        self.emit(Opcode::StoreW);
+        offset
+    }
+
+    // pushes the address saved by save_address
+    fn saved_address(&mut self, offset: i32) {
+        self.emit_with(Opcode::Local, offset);
+        self.emit(Opcode::LoadW);
+    }
+
+    // the operators which are not short circuit, on operands of the given type. The amount of a
+    // shift of a 32 bit value is taken modulo 32, and the results which can leave the range of the
+    // type are wrapped
+    fn emit_op(&mut self, op: Op, int_type: BaseType) {
+        let signed = int_type.is_signed();
+        if matches!(op, Op::SHL | Op::SHR) && int_type.size() < 8 {
+            self.emit_with(Opcode::Push, 31);
+            self.emit(Opcode::And);
+        }
+        self.emit(match op {
+            Op::ADD => Opcode::Add,
+            Op::SUB => Opcode::Sub,
+            Op::MUL => Opcode::Mul,
+            Op::DIV => if signed { Opcode::Div } else { Opcode::DivU },
+            Op::MOD => if signed { Opcode::Rem } else { Opcode::RemU },
+            Op::GT => if signed { Opcode::Gt } else { Opcode::GtU },
+            Op::LT => if signed { Opcode::Lt } else { Opcode::LtU },
+            Op::GE => if signed { Opcode::Ge } else { Opcode::GeU },
+            Op::LE => if signed { Opcode::Le } else { Opcode::LeU },
+            Op::NE => Opcode::Ne,
+            Op::EQ => Opcode::Eq,
+            Op::BAND => Opcode::And,
+            Op::BOR => Opcode::Or,
+            Op::BXOR => Opcode::Xor,
+            Op::SHL => Opcode::Shl,
+            Op::SHR => if signed { Opcode::Shr } else { Opcode::ShrU },
+            Op::OR | Op::AND => unreachable!(),
+        });
+        if matches!(op, Op::ADD | Op::SUB | Op::MUL | Op::DIV | Op::SHL) {
+            self.emit_wrap(int_type);
+        }
+    }
+
+    fn field_address(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        let struct_name = field_access_expr.expr.get_expr_type().as_struct().unwrap().name.to_owned();
+        let (offset, _) = self.layout.field(&struct_name, &field_access_expr.name);
+        self.address(&mut field_access_expr.expr);
+        if offset != 0 {
+            self.emit_with(Opcode::Push, offset);
+            self.emit(Opcode::Add);
+        }
+    }
+
+    fn element_address(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        let array_type = array_access_expr.expr1.get_expr_type();
+        let element_size = match (array_type.as_array(), array_type.as_pointer()) {
+            (Some(t), _) => self.layout.size_of(t.array_type.as_ref()),
+            (_, Some(t)) => self.layout.size_of(t.pointer_type.as_ref()),
+            _ => unreachable!(),
+        };
+        // arrays evaluate to their address
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+        if element_size != 1 {
+            self.emit_with(Opcode::Push, element_size);
+            self.emit(Opcode::Mul);
+        }
+        self.emit(Opcode::Add);
+    }
+}
+
+impl ASTVisitor<()> for BytecodeCompiler {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) { }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) { }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) { }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) { }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        self.declare_globals(&program.var_decls);
+
+        // functions can call those declared after them
+        for (i, fun_decl) in program.fun_decls.iter().enumerate() {
+            self.functions.insert(fun_decl.name.to_owned(), i as u32);
+        }
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+        }
+    }
+
+    fn visit_struct_type_decl(&mut self, _struct_type_decl: &mut StructTypeDecl) { }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.declare(var_decl);
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        let entry = self.code.len() as u32;
+        self.frame_size = 0;
+        self.scopes.push(HashMap::new());
+
+        // the arguments are on the stack, the last one on top
+        let mut params = Vec::new();
+        self.ret_ptr = None;
+        if fun_decl.fun_type.as_struct().is_some() {
+            let offset = self.new_local(&BaseType::INT);
+            self.ret_ptr = Some(offset);
+            params.push((offset, Box::new(BaseType::INT) as Box<dyn Type>));
+        }
+        for param in fun_decl.params.iter() {
+            let offset = self.declare(param);
+            params.push((offset, param.var_type.clone()));
+        }
+        for (offset, param_type) in params.iter().rev() {
+            // struct arguments are passed by address and copied by the callee
+            self.emit_with(Opcode::Local, *offset);
+            self.emit_store(param_type.as_ref());
+        }
+
+        for var_decl in fun_decl.block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in fun_decl.block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.emit(Opcode::Ret);
+
+        self.scopes.pop();
+        self.entries.push(FunctionEntry {
+            name: fun_decl.name.to_owned(),
+            entry,
+            frame_size: align_to(self.frame_size, WORD_SIZE) as u32,
+        });
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        self.scopes.push(HashMap::new());
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        let start = self.code.len() as i32;
+        self.visit_expr(&mut a_while.expr);
+        let end = self.emit_jump(Opcode::Jz);
+        let (breaks, continues) = self.loop_body(&mut a_while.stmt);
+        self.emit_with(Opcode::Jmp, start);
+        self.patch(end);
+        for at in breaks {
+            self.patch(at);
+        }
+        for at in continues {
+            self.patch_to(at, start as usize);
+        }
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        let start = self.code.len();
+        let (breaks, continues) = self.loop_body(&mut a_do_while.stmt);
+        for at in continues {
+            self.patch(at);
+        }
+        self.visit_expr(&mut a_do_while.expr);
+        self.emit_with(Opcode::Jnz, start as i32);
+        for at in breaks {
+            self.patch(at);
+        }
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        let start = self.code.len() as i32;
+        let end = a_for.expr.as_mut().map(|expr| {
+            self.visit_expr(expr);
+            self.emit_jump(Opcode::Jz)
+        });
+        let (breaks, continues) = self.loop_body(&mut a_for.stmt);
+        for at in continues {
+            self.patch(at);
+        }
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.emit_with(Opcode::Jmp, start);
+        for at in end.into_iter().chain(breaks) {
+            self.patch(at);
+        }
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        let at = self.emit_jump(Opcode::Jmp);
+        self.loops.last_mut().unwrap().0.push(at);
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        let at = self.emit_jump(Opcode::Jmp);
+        self.loops.last_mut().unwrap().1.push(at);
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        let else_jump = self.emit_jump(Opcode::Jz);
+        self.visit_stmt(&mut an_if.stmt1);
+        match &mut an_if.stmt2 {
+            Some(stmt2) => {
+                let end = self.emit_jump(Opcode::Jmp);
+                self.patch(else_jump);
+                self.visit_stmt(stmt2);
+                self.patch(end);
+            },
+            None => self.patch(else_jump),
+        }
+    }
+
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        // the value is kept in a slot of the frame while it is compared with each label
+        let switch_type = a_switch.expr.get_expr_type().clone_box();
+        let slot = self.new_local(switch_type.as_ref());
+        self.visit_expr(&mut a_switch.expr);
+        self.emit_with(Opcode::Local, slot);
+        self.emit_store(switch_type.as_ref());
+
+        let mut jumps = Vec::new();
+        for case in a_switch.cases.iter_mut() {
+            if let Some(expr) = &mut case.expr {
+                self.emit_with(Opcode::Local, slot);
+                self.emit_load(switch_type.as_ref());
+                self.visit_expr(expr);
+                self.emit(Opcode::Eq);
+                jumps.push(Some(self.emit_jump(Opcode::Jnz)));
+            } else {
+                jumps.push(None);
+            }
+        }
+        let default = self.emit_jump(Opcode::Jmp);
+
+        self.loops.push((Vec::new(), Vec::new()));
+        let mut has_default = false;
+        for (case, jump) in a_switch.cases.iter_mut().zip(jumps) {
+            match jump {
+                Some(at) => self.patch(at),
+                None => {
+                    self.patch(default);
+                    has_default = true;
+                },
+            }
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+        let (breaks, continues) = self.loops.pop().unwrap();
+        if !has_default {
+            self.patch(default);
+        }
+        for at in breaks {
+            self.patch(at);
+        }
+        // continue goes on with the enclosing loop
+        if let Some(enclosing) = self.loops.last_mut() {
+            enclosing.1.extend(continues);
+        }
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            self.visit_expr(expr);
+            if let Some(ret_ptr) = self.ret_ptr {
+                let size = self.layout.size_of(expr.get_expr_type());
+                self.emit_with(Opcode::Local, ret_ptr);
+                self.emit(Opcode::LoadW);
+                self.emit_with(Opcode::Copy, size);
+            }
+        }
+        self.emit(Opcode::Ret);
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.visit_expr(&mut assign.expr2);
+        self.address(&mut assign.expr1);
+        let a_type = assign.expr1.get_expr_type().clone_box();
+        self.emit_store(a_type.as_ref());
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+        if !expr_stmt.expr.get_expr_type().is_base(BaseType::VOID) {
+            self.emit(Opcode::Pop);
+        }
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        if bin_op.op.is_short_circuit() {
+            // the right operand is only evaluated if the left one does not decide
+            let decided = if bin_op.op == Op::AND { Opcode::Jz } else { Opcode::Jnz };
+            self.visit_expr(&mut bin_op.expr1);
+            let left = self.emit_jump(decided);
+            self.visit_expr(&mut bin_op.expr2);
+            let right = self.emit_jump(decided);
+            self.emit_with(Opcode::Push, if bin_op.op == Op::AND { 1 } else { 0 });
+            let end = self.emit_jump(Opcode::Jmp);
+            self.patch(left);
+            self.patch(right);
+            self.emit_with(Opcode::Push, if bin_op.op == Op::AND { 0 } else { 1 });
+            self.patch(end);
+            return;
+        }
+
+        let int_type = bin_op.expr1.get_expr_type().as_integer().unwrap_or(BaseType::INT);
+        self.visit_expr(&mut bin_op.expr1);
+        self.visit_expr(&mut bin_op.expr2);
+        self.emit_op(bin_op.op, int_type);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) {
+        self.emit_const(int_literal.i);
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) {
+        self.emit_with(Opcode::Push, chr_literal.c as i32);
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) {
+        // string literals follow the globals in the data segment
+        let address = DATA_BASE + self.globals_size + self.data.len() as i32;
+        self.data.extend(str_literal.bytes());
+        self.data.push(0);
+        self.emit_with(Opcode::Push, address);
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        let location = self.lookup(&var_expr.name);
+        self.emit_location(location);
+        self.emit_load(var_expr.expr_type.as_ref());
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        // arrays already evaluate to their address
+        self.visit_expr(&mut typecast_expr.expr);
+        if let (Some(from), Some(to)) = (typecast_expr.expr.get_expr_type().as_integer(), typecast_expr.typecast_type.as_integer()) {
+            self.emit_convert(from, to);
+        }
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        let size = self.layout.size_of(size_of_expr.sizeof_type.as_ref());
+        self.emit_with(Opcode::Push, size);
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.address(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+        self.emit_load(value_at_expr.expr_type.as_ref());
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.field_address(field_access_expr);
+        self.emit_load(field_access_expr.expr_type.as_ref());
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.element_address(array_access_expr);
+        self.emit_load(array_access_expr.expr_type.as_ref());
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        // a struct result is written by the callee to a temporary of the caller, passed first
+        let fun_type = fun_call_expr.fun_decl.fun_type.clone();
+        let result = fun_type.as_struct().map(|_| self.new_local(fun_type.as_ref()));
+        if let Some(offset) = result {
+            self.emit_with(Opcode::Local, offset);
+        }
+
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+
+        match Syscall::from_name(&fun_call_expr.name) {
+            Some(syscall) => self.emit_with(Opcode::Sys, syscall as i32),
+            None => {
+                let index = self.functions[&fun_call_expr.name];
+                self.emit_with(Opcode::Call, index as i32)
+            },
+        };
+
+        if let Some(offset) = result {
+            self.emit_with(Opcode::Local, offset);
+        }
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_method_call_expr(&mut self, _method_call_expr: &mut MethodCallExpr) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    // the address of a function is its index
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        let index = self.functions[&fun_addr_expr.name];
+        self.emit_with(Opcode::Push, index as i32);
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        let fun_type = indirect_call_expr.fun_decl.fun_type.clone();
+        let result = fun_type.as_struct().map(|_| self.new_local(fun_type.as_ref()));
+        if let Some(offset) = result {
+            self.emit_with(Opcode::Local, offset);
+        }
+
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+        self.visit_expr(&mut indirect_call_expr.expr);
+        self.emit(Opcode::CallInd);
+
+        if let Some(offset) = result {
+            self.emit_with(Opcode::Local, offset);
+        }
+    }
+
+    // the left hand side is evaluated first, then the right hand side, and the value stored is read back.
+    // A compound assignment computes in the type of the right hand side, converted by the type checker
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        let a_type = assign_expr.expr_type.clone();
+        let address = self.save_address(&mut assign_expr.expr1);
+        if let Some(op) = assign_expr.op {
+            let lhs_type = a_type.as_integer().unwrap();
+            let op_type = assign_expr.expr2.get_expr_type().as_integer().unwrap();
+            self.saved_address(address);
+            self.emit_load(a_type.as_ref());
+            self.emit_convert(lhs_type, op_type);
+            self.visit_expr(&mut assign_expr.expr2);
+            self.emit_op(op, op_type);
+            self.emit_convert(op_type, lhs_type);
+        } else {
+            self.visit_expr(&mut assign_expr.expr2);
+        }
+        self.saved_address(address);
+        self.emit_store(a_type.as_ref());
+        self.saved_address(address);
+        self.emit_load(a_type.as_ref());
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        let a_type = inc_dec_expr.expr_type.clone();
+        let int_type = a_type.as_integer().unwrap();
+        let address = self.save_address(&mut inc_dec_expr.expr);
+        if !inc_dec_expr.prefix {
+            // the old value stays below the new one
+            self.saved_address(address);
+            self.emit_load(a_type.as_ref());
+        }
+        self.saved_address(address);
+        self.emit_load(a_type.as_ref());
+        self.emit_with(Opcode::Push, 1);
+        self.emit_op(inc_dec_expr.op, int_type.promote());
+        self.emit_convert(int_type.promote(), int_type);
+        self.saved_address(address);
+        self.emit_store(a_type.as_ref());
+        if inc_dec_expr.prefix {
+            self.saved_address(address);
+            self.emit_load(a_type.as_ref());
+        }
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        // only the selected branch runs, leaving its value on the stack
+        self.visit_expr(&mut conditional_expr.expr);
+        let else_jump = self.emit_jump(Opcode::Jz);
+        self.visit_expr(&mut conditional_expr.expr1);
+        let end = self.emit_jump(Opcode::Jmp);
+        self.patch(else_jump);
+        self.visit_expr(&mut conditional_expr.expr2);
+        self.patch(end);
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) { }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self)
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self)
+        }
+    }
+}
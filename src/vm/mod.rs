@@ -0,0 +1,4 @@
+pub mod bytecode;
+pub mod compiler;
+#[allow(clippy::module_inception)]
+pub mod vm;
@@ -0,0 +1,305 @@
+use std::io::{BufRead, Write};
+use crate::vm::bytecode::{Module, Opcode, Syscall, DATA_BASE};
+
+pub const STACK_SIZE: i32 = 1 << 20;
+pub const MAX_MEMORY: usize = 1 << 28;
+
+/// VM
+/// Executes a bytecode Module. Memory is a single byte array: addresses below DATA_BASE are invalid
+/// so that null pointers fault, then come the data segment, the stack of frames and the heap grown
+/// by mcmalloc. Values are 64 bit integers kept on a separate operand stack.
+pub struct VM<'a> {
+    module: &'a Module,
+    memory: Vec<u8>,
+    stack: Vec<i64>,
+    // return address and frame pointer of the callers
+    frames: Vec<(usize, i32)>,
+    pc: usize,
+    fp: i32,
+    sp: i32,
+    stack_limit: i32,
+    input: Box<dyn BufRead + 'a>,
+    output: Box<dyn Write + 'a>,
+}
+
+impl<'a> VM<'a> {
+    pub fn new(module: &'a Module, input: Box<dyn BufRead + 'a>, output: Box<dyn Write + 'a>) -> Self {
+        VM::with_memory(module, Vec::new(), 0, input, output)
+    }
+
+    /// Runs in the memory left by a previous run of a module with a data segment of the same size,
+    /// as the REPL does for each entry: the first `kept` bytes of the data segment, the globals
+    /// already defined, and the heap keep their contents while the rest is initialised again.
+    pub fn with_memory(module: &'a Module, mut memory: Vec<u8>, kept: usize, input: Box<dyn BufRead + 'a>, output: Box<dyn Write + 'a>) -> Self {
+        let data_end = DATA_BASE as usize + module.data_size as usize;
+        let stack_base = data_end.next_multiple_of(8);
+        if memory.len() < stack_base + STACK_SIZE as usize {
+            memory.resize(stack_base + STACK_SIZE as usize, 0);
+        }
+        let kept = kept.min(module.data_init.len());
+        memory[DATA_BASE as usize + kept..data_end].fill(0);
+        memory[DATA_BASE as usize + kept..DATA_BASE as usize + module.data_init.len()].copy_from_slice(&module.data_init[kept..]);
+        let stack_base = stack_base as i32;
+
+        VM {
+            module,
+            memory,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            pc: 0,
+            fp: stack_base,
+            sp: stack_base,
+            stack_limit: stack_base + STACK_SIZE,
+            input,
+            output,
+        }
+    }
+
+    pub fn into_memory(self) -> Vec<u8> {
+        self.memory
+    }
+
+    /// The value left by the last run, the result of its main function unless it is void.
+    pub fn result(&self) -> Option<i64> {
+        self.stack.last().copied()
+    }
+
+    /// Runs the main function of the module to completion.
+    pub fn run(&mut self) -> Result<(), String> {
+        self.call(self.module.main as usize, usize::MAX)?;
+        let result = self.execute();
+        self.output.flush().unwrap();
+        result
+    }
+
+    fn pop(&mut self) -> Result<i64, String> {
+        self.stack.pop().ok_or_else(|| "operand stack underflow".to_owned())
+    }
+
+    fn push(&mut self, value: i64) {
+        self.stack.push(value);
+    }
+
+    fn check(&self, address: i64, size: i32) -> Result<usize, String> {
+        if address < DATA_BASE as i64 || address + size as i64 > self.memory.len() as i64 {
+            return Err(format!("invalid memory access at address {}", address));
+        }
+        Ok(address as usize)
+    }
+
+    fn pop_address(&mut self, size: i32) -> Result<usize, String> {
+        let address = self.pop()?;
+        self.check(address, size)
+    }
+
+    // pops an address and pushes the little endian value of the given size it points to
+    fn load(&mut self, size: usize) -> Result<u64, String> {
+        let a = self.pop_address(size as i32)?;
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(&self.memory[a..a + size]);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    // pops an address then a value and stores the low bytes of the value
+    fn store(&mut self, size: usize) -> Result<(), String> {
+        let address = self.pop_address(size as i32)?;
+        let value = self.pop()?;
+        let bytes = self.memory.get_mut(address..address + size).ok_or_else(|| format!("invalid memory access at address {}", address))?;
+        bytes.copy_from_slice(&value.to_le_bytes()[..size]);
+        Ok(())
+    }
+
+    fn call(&mut self, index: usize, return_pc: usize) -> Result<(), String> {
+        let function = self.module.functions.get(index).ok_or_else(|| format!("invalid function {}", index))?;
+        self.frames.push((return_pc, self.fp));
+        self.fp = self.sp;
+        self.sp = self.sp.checked_add_unsigned(function.frame_size).filter(|sp| *sp <= self.stack_limit)
+            .ok_or_else(|| "stack overflow".to_owned())?;
+        let frame = self.memory.get_mut(self.fp as usize..self.sp as usize).ok_or_else(|| "stack overflow".to_owned())?;
+        frame.fill(0);
+        self.pc = function.entry as usize;
+        Ok(())
+    }
+
+    fn execute(&mut self) -> Result<(), String> {
+        loop {
+            let opcode = match self.module.code.get(self.pc).and_then(|b| Opcode::from_byte(*b)) {
+                Some(opcode) if self.pc + opcode.size() <= self.module.code.len() => opcode,
+                _ => return Err(format!("invalid instruction at {}", self.pc)),
+            };
+            let operand = if opcode.has_operand() { self.module.operand(self.pc) } else { 0 };
+            self.pc += opcode.size();
+
+            match opcode {
+                Opcode::Push => self.push(operand as i64),
+                Opcode::Local => self.push((self.fp + operand) as i64),
+                Opcode::Pop => {
+                    self.pop()?;
+                },
+                Opcode::LoadW => {
+                    let value = self.load(4)?;
+                    self.push(value as i32 as i64);
+                },
+                Opcode::LoadB => {
+                    let value = self.load(1)?;
+                    self.push(value as i64);
+                },
+                Opcode::LoadH => {
+                    let value = self.load(2)?;
+                    self.push(value as i64);
+                },
+                Opcode::LoadD => {
+                    let value = self.load(8)?;
+                    self.push(value as i64);
+                },
+                Opcode::StoreW => self.store(4)?,
+                Opcode::StoreB => self.store(1)?,
+                Opcode::StoreH => self.store(2)?,
+                Opcode::StoreD => self.store(8)?,
+                Opcode::Copy => {
+                    let dst = self.pop_address(operand)?;
+                    let src = self.pop_address(operand)?;
+                    if operand < 0 {
+                        return Err(format!("invalid instruction at {}", self.pc - opcode.size()));
+                    }
+                    self.memory.copy_within(src..src + operand as usize, dst);
+                },
+                Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Rem | Opcode::Lt | Opcode::Gt
+                | Opcode::Le | Opcode::Ge | Opcode::Eq | Opcode::Ne | Opcode::And | Opcode::Or | Opcode::Xor
+                | Opcode::Shl | Opcode::Shr | Opcode::DivU | Opcode::RemU | Opcode::LtU | Opcode::GtU | Opcode::LeU
+                | Opcode::GeU | Opcode::ShrU => {
+                    let rhs = self.pop()?;
+                    let lhs = self.pop()?;
+                    if rhs == 0 && matches!(opcode, Opcode::Div | Opcode::Rem | Opcode::DivU | Opcode::RemU) {
+                        return Err("division by zero".to_owned());
+                    }
+                    let (ulhs, urhs) = (lhs as u64, rhs as u64);
+                    self.push(match opcode {
+                        Opcode::Add => lhs.wrapping_add(rhs),
+                        Opcode::Sub => lhs.wrapping_sub(rhs),
+                        Opcode::Mul => lhs.wrapping_mul(rhs),
+                        Opcode::Div => lhs.wrapping_div(rhs),
+                        Opcode::Rem => lhs.wrapping_rem(rhs),
+                        Opcode::DivU => (ulhs / urhs) as i64,
+                        Opcode::RemU => (ulhs % urhs) as i64,
+                        Opcode::Lt => (lhs < rhs) as i64,
+                        Opcode::Gt => (lhs > rhs) as i64,
+                        Opcode::Le => (lhs <= rhs) as i64,
+                        Opcode::Ge => (lhs >= rhs) as i64,
+                        Opcode::LtU => (ulhs < urhs) as i64,
+                        Opcode::GtU => (ulhs > urhs) as i64,
+                        Opcode::LeU => (ulhs <= urhs) as i64,
+                        Opcode::GeU => (ulhs >= urhs) as i64,
+                        Opcode::Eq => (lhs == rhs) as i64,
+                        Opcode::And => lhs & rhs,
+                        Opcode::Or => lhs | rhs,
+                        Opcode::Xor => lhs ^ rhs,
+                        Opcode::Shl => lhs.wrapping_shl(rhs as u32),
+                        Opcode::Shr => lhs.wrapping_shr(rhs as u32),
+                        Opcode::ShrU => ulhs.wrapping_shr(rhs as u32) as i64,
+                        _ => (lhs != rhs) as i64,
+                    });
+                },
+                Opcode::Wrap => {
+                    let value = self.pop()?;
+                    let bits = operand.unsigned_abs();
+                    let shift = 64 - bits.clamp(1, 64);
+                    self.push(if operand < 0 { value << shift >> shift } else { ((value as u64) << shift >> shift) as i64 });
+                },
+                Opcode::Jmp => self.pc = operand as usize,
+                Opcode::Jz => if self.pop()? == 0 {
+                    self.pc = operand as usize;
+                },
+                Opcode::Jnz => if self.pop()? != 0 {
+                    self.pc = operand as usize;
+                },
+                Opcode::Call => self.call(operand as usize, self.pc)?,
+                Opcode::CallInd => {
+                    let index = self.pop()?;
+                    self.call(index as usize, self.pc)?;
+                },
+                Opcode::Ret => {
+                    let (pc, fp) = self.frames.pop().ok_or_else(|| "return without caller".to_owned())?;
+                    self.sp = self.fp;
+                    self.fp = fp;
+                    if pc == usize::MAX {
+                        return Ok(());
+                    }
+                    self.pc = pc;
+                },
+                Opcode::Sys => {
+                    let syscall = Syscall::from_operand(operand).ok_or_else(|| format!("invalid syscall {}", operand))?;
+                    self.syscall(syscall)?;
+                },
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let byte = *self.input.fill_buf().ok()?.first()?;
+        self.input.consume(1);
+        Some(byte)
+    }
+
+    fn peek_byte(&mut self) -> Option<u8> {
+        self.input.fill_buf().ok()?.first().copied()
+    }
+
+    fn syscall(&mut self, syscall: Syscall) -> Result<(), String> {
+        match syscall {
+            Syscall::PrintS => {
+                let mut address = self.pop()?;
+                let mut bytes = Vec::new();
+                loop {
+                    let a = self.check(address, 1)?;
+                    if self.memory[a] == 0 {
+                        break;
+                    }
+                    bytes.push(self.memory[a]);
+                    address += 1;
+                }
+                self.output.write_all(&bytes).unwrap();
+            },
+            Syscall::PrintI => {
+                let i = self.pop()? as i32;
+                write!(self.output, "{}", i).unwrap();
+            },
+            Syscall::PrintC => {
+                let c = self.pop()?;
+                self.output.write_all(&[c as u8]).unwrap();
+            },
+            Syscall::ReadC => {
+                self.output.flush().unwrap();
+                // -1 at the end of the input
+                let c = self.read_byte().map_or(-1, |c| c as i64);
+                self.push(c);
+            },
+            Syscall::ReadI => {
+                self.output.flush().unwrap();
+                while self.peek_byte().is_some_and(|c| c.is_ascii_whitespace()) {
+                    self.read_byte();
+                }
+                let negative = self.peek_byte() == Some(b'-');
+                if negative {
+                    self.read_byte();
+                }
+                let mut i: i32 = 0;
+                while let Some(c) = self.peek_byte().filter(|c| c.is_ascii_digit()) {
+                    i = i.wrapping_mul(10).wrapping_add((c - b'0') as i32);
+                    self.read_byte();
+                }
+                self.push(if negative { i.wrapping_neg() } else { i } as i64);
+            },
+            Syscall::Mcmalloc => {
+                let size = self.pop()?;
+                let address = self.memory.len().next_multiple_of(8);
+                if size < 0 || address + size as usize > MAX_MEMORY {
+                    return Err("out of memory".to_owned());
+                }
+                self.memory.resize(address + size as usize, 0);
+                self.push(address as i64);
+            },
+        }
+        Ok(())
+    }
+}
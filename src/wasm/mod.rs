@@ -0,0 +1 @@
+pub mod wat_generator;
@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+use crate::ast::ast_visitor::ASTVisitor;
+use crate::ast::decl::{ClassDecl, FunDecl, Initializer, Program, StructTypeDecl, VarDecl};
+use crate::ast::expr::{AddressOfExpr, ArrayAccessExpr, AssignExpr, BinOp, ChrLiteral, ConditionalExpr, Expr, FieldAccessExpr, FunAddrExpr, FunCallExpr, IncDecExpr, IndirectCallExpr, IntLiteral, MethodCallExpr, NewExpr, Op, SizeOfExpr, StrLiteral, TypecastExpr, ValueAtExpr, VarExpr};
+use crate::ast::stmt::{Assign, Block, Break, Continue, DoWhile, ExprStmt, For, If, Return, Stmt, Switch, While};
+use crate::ast::types::{ArrayType, BaseType, ClassType, FunType, PointerType, StructType, Type};
+use crate::gen::layout::{align_to, InitValue, Layout, WORD_SIZE};
+use crate::sem::stdlib;
+use crate::ASTNode;
+
+const DATA_BASE: i32 = 16;
+const STACK_SIZE: i32 = 1 << 20;
+const PAGE_SIZE: i32 = 1 << 16;
+// replaced by the frame size once the whole function is generated
+const FRAME_SIZE: &str = "__frame_size__";
+
+#[derive(Clone, Copy)]
+enum Location {
+    // offset from the frame pointer
+    Local(i32),
+    Global(i32),
+}
+
+/// WatGenerator
+/// Compiles a checked Program to a WebAssembly module in text format. All variables live in linear
+/// memory, globals from address 16 and locals in frames of a stack growing down from `__heap_base`,
+/// so that pointers, arrays and structs behave as in C. Expressions leave their value on the wasm
+/// stack, except structs and arrays which leave their address. Longs are i64 values, the narrower
+/// integers i32 values sign or zero extended from their width.
+///
+/// The stdlib functions are imported from the "env" module:
+/// `print_s(i32)`, `print_i(i32)`, `print_c(i32)`, `read_c() -> i32`, `read_i() -> i32` and
+/// `mcmalloc(i32) -> i32`, which should allocate above the exported `__heap_base`, growing the
+/// exported `memory` as needed. `main` is exported. The address of a function is its index in the
+/// table of the functions whose address is taken, starting at 1 so that 0 is never a function.
+pub struct WatGenerator {
+    layout: Layout,
+    out: String,
+    data: Vec<u8>,
+    globals_size: i32,
+    scopes: Vec<HashMap<String, Location>>,
+    frame_size: i32,
+    ret_ptr: Option<i32>,
+    is_void: bool,
+    labels: i32,
+    // the labels of the blocks break and continue go to for the enclosing loops and switches, the
+    // innermost last
+    loops: Vec<(i32, i32)>,
+    // the functions whose address is taken, in the order of their index in the table
+    table: Vec<String>,
+}
+
+impl WatGenerator {
+    pub fn new(struct_type_decls: &[StructTypeDecl]) -> Self {
+        WatGenerator {
+            layout: Layout::new(struct_type_decls),
+            out: String::new(),
+            data: Vec::new(),
+            globals_size: 0,
+            scopes: vec![HashMap::new()],
+            frame_size: 0,
+            ret_ptr: None,
+            is_void: true,
+            labels: 0,
+            loops: Vec::new(),
+            table: Vec::new(),
+        }
+    }
+
+    pub fn emit_program(program: &mut Program, writer: &mut dyn Write) {
+        let mut generator = WatGenerator::new(&program.struct_type_decls);
+        program.accept(&mut generator);
+        writer.write_all(generator.out.as_bytes()).unwrap();
+        writer.flush().unwrap();
+    }
+
+    fn emit(&mut self, inst: &str) {
+        writeln!(self.out, "    {}", inst).unwrap();
+    }
+
+    fn new_label(&mut self) -> i32 {
+        self.labels += 1;
+        self.labels
+    }
+
+    fn new_local(&mut self, a_type: &dyn Type) -> i32 {
+        let offset = align_to(self.frame_size, self.layout.align_of(a_type).max(1));
+        self.frame_size = offset + self.layout.size_of(a_type);
+        offset
+    }
+
+    fn declare(&mut self, var_decl: &VarDecl) -> i32 {
+        let offset = self.new_local(var_decl.var_type.as_ref());
+        self.scopes.last_mut().unwrap().insert(var_decl.var_name.to_owned(), Location::Local(offset));
+        if let Some(init) = &var_decl.init {
+            self.initialize(var_decl.var_type.as_ref(), init, offset);
+        }
+        offset
+    }
+
+    fn initialize(&mut self, var_type: &dyn Type, init: &Initializer, offset: i32) {
+        for (value_offset, value_type, value) in self.layout.flatten(var_type, init) {
+            self.emit_location(Location::Local(offset + value_offset));
+            match value {
+                InitValue::Expr(mut expr) => self.visit_expr(&mut expr),
+                InitValue::Const(c) => self.emit(&format!("{}.const {}", value_type_of(value_type.as_ref()), c)),
+            }
+            self.emit_store(value_type.as_ref());
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Location {
+        *self.scopes.iter().rev().find_map(|scope| scope.get(name)).unwrap()
+    }
+
+    fn emit_location(&mut self, location: Location) {
+        match location {
+            Location::Local(offset) => {
+                self.emit("local.get $fp");
+                if offset != 0 {
+                    self.emit(&format!("i32.const {}", offset));
+                    self.emit("i32.add");
+                }
+            },
+            Location::Global(address) => self.emit(&format!("i32.const {}", address)),
+        }
+    }
+
+    fn emit_load(&mut self, a_type: &dyn Type) {
+        if Layout::is_aggregate(a_type) {
+            return;
+        }
+        self.emit(match a_type.as_integer() {
+            Some(BaseType::SCHAR) => "i32.load8_s",
+            Some(t) if t.size() == 1 => "i32.load8_u",
+            Some(BaseType::SHORT) => "i32.load16_s",
+            Some(BaseType::USHORT) => "i32.load16_u",
+            Some(t) if t.size() == 8 => "i64.load",
+            _ => "i32.load",
+        });
+    }
+
+    // stores or copies the value on top of the stack to the address below it
+    fn emit_store(&mut self, a_type: &dyn Type) {
+        if a_type.as_struct().is_some() {
+            let size = self.layout.size_of(a_type);
+            self.emit(&format!("i32.const {}", size));
+            self.emit("memory.copy");
+        } else {
+            self.emit(match a_type.as_integer().map(|t| t.size()) {
+                Some(1) => "i32.store8",
+                Some(2) => "i32.store16",
+                Some(8) => "i64.store",
+                _ => "i32.store",
+            });
+        }
+    }
+
+    // pops the frame, leaving the value being returned if any on the stack
+    fn emit_epilogue(&mut self) {
+        self.emit("local.get $fp");
+        self.emit(&format!("i32.const {}", FRAME_SIZE));
+        self.emit("i32.add");
+        self.emit("global.set $sp");
+    }
+
+    /// Pushes the address of an lvalue, or of a struct or array valued expression.
+    fn address(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(var_expr) = expr.as_any().downcast_ref::<VarExpr>() {
+            let location = self.lookup(&var_expr.name);
+            self.emit_location(location);
+        } else if let Some(field_access_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            self.field_address(field_access_expr);
+        } else if let Some(array_access_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            self.element_address(array_access_expr);
+        } else if let Some(value_at_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            self.visit_expr(&mut value_at_expr.expr);
+        } else {
+            self.visit_expr(expr);
+        }
+    }
+
+    // the operators which are not short circuit, applied to operands of an int, unsigned int or long type
+    fn emit_op(&mut self, op: Op, int_type: BaseType) {
+        let inst = match op {
+            Op::ADD => "add",
+            Op::SUB => "sub",
+            Op::MUL => "mul",
+            Op::DIV => "div",
+            Op::MOD => "rem",
+            Op::GT => "gt",
+            Op::LT => "lt",
+            Op::GE => "ge",
+            Op::LE => "le",
+            Op::NE => "ne",
+            Op::EQ => "eq",
+            Op::BAND => "and",
+            Op::BOR => "or",
+            Op::BXOR => "xor",
+            Op::SHL => "shl",
+            Op::SHR => "shr",
+            Op::OR | Op::AND => unreachable!(),
+        };
+        let ty = value_type_of(&int_type);
+        match op {
+            Op::DIV | Op::MOD | Op::GT | Op::LT | Op::GE | Op::LE | Op::SHR => {
+                let sign = if int_type.is_signed() { "s" } else { "u" };
+                self.emit(&format!("{}.{}_{}", ty, inst, sign));
+            },
+            _ => self.emit(&format!("{}.{}", ty, inst)),
+        }
+    }
+
+    // converts the integer on top of the stack to another integer type
+    fn emit_convert(&mut self, from: BaseType, to: BaseType) {
+        if to.size() == 8 {
+            if from.size() < 8 {
+                self.emit(if from.is_signed() { "i64.extend_i32_s" } else { "i64.extend_i32_u" });
+            }
+            return;
+        }
+        if from.size() == 8 {
+            self.emit("i32.wrap_i64");
+        }
+        // a wider type, or one as wide, holds every value of the type converted from
+        let widening = to.size() > from.size() && (to.is_signed() || !from.is_signed());
+        if to.size() < 4 && !widening && from != to {
+            let bits = 32 - to.size() * 8;
+            if to.is_signed() {
+                self.emit(&format!("i32.const {}", bits));
+                self.emit("i32.shl");
+                self.emit(&format!("i32.const {}", bits));
+                self.emit("i32.shr_s");
+            } else {
+                self.emit(&format!("i32.const {}", (1i64 << (to.size() * 8)) - 1));
+                self.emit("i32.and");
+            }
+        }
+    }
+
+    // evaluates the address of an lvalue once into a new local, returning the offset of the local
+    fn save_address(&mut self, expr: &mut Box<dyn Expr>) -> i32 {
+        let offset = self.new_local(&BaseType::INT);
+        self.emit_location(Location::Local(offset));
+        self.address(expr);
+        self.emit("i32.store");
+        offset
+    }
+
+    // pushes the address saved by save_address
+    fn saved_address(&mut self, offset: i32) {
+        self.emit_location(Location::Local(offset));
+        self.emit("i32.load");
+    }
+
+    fn field_address(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        let struct_name = field_access_expr.expr.get_expr_type().as_struct().unwrap().name.to_owned();
+        let (offset, _) = self.layout.field(&struct_name, &field_access_expr.name);
+        self.address(&mut field_access_expr.expr);
+        if offset != 0 {
+            self.emit(&format!("i32.const {}", offset));
+            self.emit("i32.add");
+        }
+    }
+
+    fn element_address(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        let array_type = array_access_expr.expr1.get_expr_type();
+        let element_size = match (array_type.as_array(), array_type.as_pointer()) {
+            (Some(t), _) => self.layout.size_of(t.array_type.as_ref()),
+            (_, Some(t)) => self.layout.size_of(t.pointer_type.as_ref()),
+            _ => unreachable!(),
+        };
+        // arrays evaluate to their address
+        self.visit_expr(&mut array_access_expr.expr1);
+        self.visit_expr(&mut array_access_expr.expr2);
+        if element_size != 1 {
+            self.emit(&format!("i32.const {}", element_size));
+            self.emit("i32.mul");
+        }
+        self.emit("i32.add");
+    }
+
+    fn signature(fun_decl: &FunDecl) -> String {
+        let mut signature = String::new();
+        if fun_decl.fun_type.as_struct().is_some() {
+            signature.push_str(" (param i32)");
+        }
+        for param in fun_decl.params.iter() {
+            write!(signature, " (param {})", value_type_of(param.var_type.as_ref())).unwrap();
+        }
+        if !fun_decl.fun_type.is_base(BaseType::VOID) && fun_decl.fun_type.as_struct().is_none() {
+            write!(signature, " (result {})", value_type_of(fun_decl.fun_type.as_ref())).unwrap();
+        }
+        signature
+    }
+}
+
+// the wasm type of a value of a type, structs and arrays being represented by their address
+fn value_type_of(a_type: &dyn Type) -> &'static str {
+    if a_type.as_integer().is_some_and(|t| t.size() == 8) { "i64" } else { "i32" }
+}
+
+// a wat string literal with every byte that is not printable escaped
+fn wat_string(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    for b in bytes {
+        if b.is_ascii_graphic() && *b != b'"' && *b != b'\\' || *b == b' ' {
+            s.push(*b as char);
+        } else {
+            write!(s, "\\{:02x}", b).unwrap();
+        }
+    }
+    s
+}
+
+impl ASTVisitor<()> for WatGenerator {
+    fn visit_base_type(&mut self, _base_type: &mut BaseType) { }
+
+    fn visit_pointer_type(&mut self, _pointer_type: &mut PointerType) { }
+
+    fn visit_struct_type(&mut self, _struct_type: &mut StructType) { }
+
+    fn visit_array_type(&mut self, _array_type: &mut ArrayType) { }
+
+    fn visit_class_type(&mut self, _class_type: &mut ClassType) { }
+
+    fn visit_fun_type(&mut self, _fun_type: &mut FunType) { }
+
+    fn visit_program(&mut self, program: &mut Program) {
+        let mut globals_init = Vec::new();
+        for var_decl in program.var_decls.iter() {
+            let var_type = var_decl.var_type.as_ref();
+            let offset = align_to(self.globals_size, self.layout.align_of(var_type).max(1));
+            self.globals_size = offset + self.layout.size_of(var_type);
+            if let Some(init) = &var_decl.init {
+                globals_init.push((DATA_BASE + offset, self.layout.image(var_type, init)));
+            }
+            self.scopes[0].insert(var_decl.var_name.to_owned(), Location::Global(DATA_BASE + offset));
+        }
+        self.globals_size = align_to(self.globals_size, WORD_SIZE);
+
+        // the functions are generated first to collect the string literals
+        let mut functions = String::new();
+        for fun_decl in program.fun_decls.iter_mut() {
+            fun_decl.accept(self);
+            functions.push_str(&std::mem::take(&mut self.out));
+        }
+
+        let heap_base = align_to(DATA_BASE + self.globals_size + self.data.len() as i32, 16) + STACK_SIZE;
+        let pages = (heap_base + PAGE_SIZE - 1) / PAGE_SIZE + 1;
+
+        writeln!(self.out, "(module").unwrap();
+        for fun_decl in stdlib::fun_decls() {
+            writeln!(self.out, "  (import \"env\" \"{}\" (func ${}{}))", fun_decl.name, fun_decl.name, WatGenerator::signature(&fun_decl)).unwrap();
+        }
+        writeln!(self.out, "  (memory (export \"memory\") {})", pages).unwrap();
+        writeln!(self.out, "  (global $sp (mut i32) (i32.const {}))", heap_base).unwrap();
+        writeln!(self.out, "  (global (export \"__heap_base\") i32 (i32.const {}))", heap_base).unwrap();
+        for (address, bytes) in globals_init.iter() {
+            writeln!(self.out, "  (data (i32.const {}) \"{}\")", address, wat_string(bytes)).unwrap();
+        }
+        if !self.data.is_empty() {
+            writeln!(self.out, "  (data (i32.const {}) \"{}\")", DATA_BASE + self.globals_size, wat_string(&self.data)).unwrap();
+        }
+        if !self.table.is_empty() {
+            let elems: Vec<String> = self.table.iter().map(|name| format!("${}", name)).collect();
+            writeln!(self.out, "  (table {} funcref)", self.table.len() + 1).unwrap();
+            writeln!(self.out, "  (elem (i32.const 1) {})", elems.join(" ")).unwrap();
+        }
+        self.out.push_str(&functions);
+        writeln!(self.out, "  (export \"main\" (func $main))").unwrap();
+        writeln!(self.out, ")").unwrap();
+    }
+
+    fn visit_struct_type_decl(&mut self, _struct_type_decl: &mut StructTypeDecl) { }
+
+    fn visit_class_decl(&mut self, _class_decl: &mut ClassDecl) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_var_decl(&mut self, var_decl: &mut VarDecl) {
+        self.declare(var_decl);
+    }
+
+    fn visit_fun_decl(&mut self, fun_decl: &mut FunDecl) {
+        self.frame_size = 0;
+        self.scopes.push(HashMap::new());
+        self.is_void = fun_decl.fun_type.is_base(BaseType::VOID) || fun_decl.fun_type.as_struct().is_some();
+
+        writeln!(self.out, "  (func ${}{}", fun_decl.name, WatGenerator::signature(fun_decl)).unwrap();
+        writeln!(self.out, "    (local $fp i32)").unwrap();
+        self.emit("global.get $sp");
+        self.emit(&format!("i32.const {}", FRAME_SIZE));
+        self.emit("i32.sub");
+        self.emit("local.tee $fp");
+        self.emit("global.set $sp");
+
+        let mut params = Vec::new();
+        self.ret_ptr = None;
+        if fun_decl.fun_type.as_struct().is_some() {
+            let offset = self.new_local(&BaseType::INT);
+            self.ret_ptr = Some(offset);
+            params.push((offset, Box::new(BaseType::INT) as Box<dyn Type>));
+        }
+        for param in fun_decl.params.iter() {
+            let offset = self.declare(param);
+            params.push((offset, param.var_type.clone()));
+        }
+        for (i, (offset, param_type)) in params.iter().enumerate() {
+            // struct arguments are passed by address and copied by the callee
+            self.emit_location(Location::Local(*offset));
+            self.emit(&format!("local.get {}", i));
+            self.emit_store(param_type.as_ref());
+        }
+
+        for var_decl in fun_decl.block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in fun_decl.block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.emit_epilogue();
+        if !self.is_void {
+            // falling off the end of a non void function is undefined, return 0
+            self.emit(&format!("{}.const 0", value_type_of(fun_decl.fun_type.as_ref())));
+        }
+        writeln!(self.out, "  )").unwrap();
+
+        self.scopes.pop();
+        let frame_size = align_to(self.frame_size, 16).to_string();
+        self.out = self.out.replace(FRAME_SIZE, &frame_size);
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        self.scopes.push(HashMap::new());
+        for var_decl in block.var_decls.iter_mut() {
+            var_decl.accept(self);
+        }
+        for stmt in block.stmts.iter_mut() {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_while(&mut self, a_while: &mut While) {
+        let label = self.new_label();
+        self.emit(&format!("block $break{}", label));
+        self.emit(&format!("loop $continue{}", label));
+        self.visit_expr(&mut a_while.expr);
+        self.emit("i32.eqz");
+        self.emit(&format!("br_if $break{}", label));
+        self.loops.push((label, label));
+        self.visit_stmt(&mut a_while.stmt);
+        self.loops.pop();
+        self.emit(&format!("br $continue{}", label));
+        self.emit("end");
+        self.emit("end");
+    }
+
+    fn visit_do_while(&mut self, a_do_while: &mut DoWhile) {
+        let label = self.new_label();
+        self.emit(&format!("block $break{}", label));
+        self.emit(&format!("loop $loop{}", label));
+        // continue leaves the block of the body and evaluates the condition
+        self.emit(&format!("block $continue{}", label));
+        self.loops.push((label, label));
+        self.visit_stmt(&mut a_do_while.stmt);
+        self.loops.pop();
+        self.emit("end");
+        self.visit_expr(&mut a_do_while.expr);
+        self.emit(&format!("br_if $loop{}", label));
+        self.emit("end");
+        self.emit("end");
+    }
+
+    fn visit_for(&mut self, a_for: &mut For) {
+        let label = self.new_label();
+        if let Some(init) = &mut a_for.init {
+            self.visit_stmt(init);
+        }
+        self.emit(&format!("block $break{}", label));
+        self.emit(&format!("loop $loop{}", label));
+        if let Some(expr) = &mut a_for.expr {
+            self.visit_expr(expr);
+            self.emit("i32.eqz");
+            self.emit(&format!("br_if $break{}", label));
+        }
+        // continue leaves the block of the body and runs the step
+        self.emit(&format!("block $continue{}", label));
+        self.loops.push((label, label));
+        self.visit_stmt(&mut a_for.stmt);
+        self.loops.pop();
+        self.emit("end");
+        if let Some(step) = &mut a_for.step {
+            self.visit_stmt(step);
+        }
+        self.emit(&format!("br $loop{}", label));
+        self.emit("end");
+        self.emit("end");
+    }
+
+    fn visit_break(&mut self, _a_break: &mut Break) {
+        let (label, _) = *self.loops.last().unwrap();
+        self.emit(&format!("br $break{}", label));
+    }
+
+    fn visit_continue(&mut self, _a_continue: &mut Continue) {
+        let (_, label) = *self.loops.last().unwrap();
+        self.emit(&format!("br $continue{}", label));
+    }
+
+    fn visit_if(&mut self, an_if: &mut If) {
+        self.visit_expr(&mut an_if.expr);
+        self.emit("if");
+        self.visit_stmt(&mut an_if.stmt1);
+        if let Some(stmt2) = &mut an_if.stmt2 {
+            self.emit("else");
+            self.visit_stmt(stmt2);
+        }
+        self.emit("end");
+    }
+
+    // every case starts after the end of a block, the first one being the innermost; branching out
+    // of a block enters its case, and the statements fall through to the next case
+    fn visit_switch(&mut self, a_switch: &mut Switch) {
+        let label = self.new_label();
+        let int_type = a_switch.expr.get_expr_type().as_integer().unwrap();
+        let slot = self.new_local(&int_type);
+        self.emit_location(Location::Local(slot));
+        self.visit_expr(&mut a_switch.expr);
+        self.emit_store(&int_type);
+
+        self.emit(&format!("block $break{}", label));
+        for i in (0..a_switch.cases.len()).rev() {
+            self.emit(&format!("block $case{}_{}", label, i));
+        }
+        let mut default = format!("$break{}", label);
+        for (i, case) in a_switch.cases.iter_mut().enumerate() {
+            match &mut case.expr {
+                Some(expr) => {
+                    self.emit_location(Location::Local(slot));
+                    self.emit_load(&int_type);
+                    self.visit_expr(expr);
+                    self.emit_op(Op::EQ, int_type);
+                    self.emit(&format!("br_if $case{}_{}", label, i));
+                },
+                None => default = format!("$case{}_{}", label, i),
+            }
+        }
+        self.emit(&format!("br {}", default));
+
+        let next = self.loops.last().map_or(0, |(_, next)| *next);
+        self.loops.push((label, next));
+        for case in a_switch.cases.iter_mut() {
+            self.emit("end");
+            for stmt in case.stmts.iter_mut() {
+                self.visit_stmt(stmt);
+            }
+        }
+        self.loops.pop();
+        self.emit("end");
+    }
+
+    fn visit_return(&mut self, a_return: &mut Return) {
+        if let Some(expr) = &mut a_return.expr {
+            if let Some(ret_ptr) = self.ret_ptr {
+                let a_type = expr.get_expr_type().clone_box();
+                self.emit_location(Location::Local(ret_ptr));
+                self.emit("i32.load");
+                self.visit_expr(expr);
+                self.emit_store(a_type.as_ref());
+            } else {
+                self.visit_expr(expr);
+            }
+        }
+        self.emit_epilogue();
+        self.emit("return");
+    }
+
+    fn visit_assign(&mut self, assign: &mut Assign) {
+        self.address(&mut assign.expr1);
+        self.visit_expr(&mut assign.expr2);
+        let a_type = assign.expr1.get_expr_type().clone_box();
+        self.emit_store(a_type.as_ref());
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &mut ExprStmt) {
+        self.visit_expr(&mut expr_stmt.expr);
+        if !expr_stmt.expr.get_expr_type().is_base(BaseType::VOID) {
+            self.emit("drop");
+        }
+    }
+
+    fn visit_bin_op(&mut self, bin_op: &mut BinOp) {
+        self.visit_expr(&mut bin_op.expr1);
+
+        if bin_op.op.is_short_circuit() {
+            // the right operand is only evaluated if the left one does not decide
+            self.emit("if (result i32)");
+            if bin_op.op == Op::OR {
+                self.emit("i32.const 1");
+                self.emit("else");
+            }
+            self.visit_expr(&mut bin_op.expr2);
+            self.emit("i32.const 0");
+            self.emit("i32.ne");
+            if bin_op.op == Op::AND {
+                self.emit("else");
+                self.emit("i32.const 0");
+            }
+            self.emit("end");
+            return;
+        }
+
+        // the operands were converted to a common type, or the left one is shifted by an int
+        let int_type = bin_op.expr1.get_expr_type().as_integer().unwrap_or(BaseType::INT);
+        self.visit_expr(&mut bin_op.expr2);
+        if (bin_op.op == Op::SHL || bin_op.op == Op::SHR) && int_type.size() == 8 {
+            self.emit("i64.extend_i32_u");
+        }
+        self.emit_op(bin_op.op, int_type);
+    }
+
+    fn visit_op(&mut self, _op: &mut Op) { }
+
+    fn visit_int_literal(&mut self, int_literal: &mut IntLiteral) {
+        if int_literal.int_type.size() == 8 {
+            self.emit(&format!("i64.const {}", int_literal.i));
+        } else {
+            self.emit(&format!("i32.const {}", int_literal.i as i32));
+        }
+    }
+
+    fn visit_chr_literal(&mut self, chr_literal: &mut ChrLiteral) {
+        self.emit(&format!("i32.const {}", chr_literal.c as i32));
+    }
+
+    fn visit_str_literal(&mut self, str_literal: &mut StrLiteral) {
+        // string literals follow the globals in memory
+        let address = DATA_BASE + self.globals_size + self.data.len() as i32;
+        self.data.extend(str_literal.bytes());
+        self.data.push(0);
+        self.emit(&format!("i32.const {}", address));
+    }
+
+    fn visit_var_expr(&mut self, var_expr: &mut VarExpr) {
+        let location = self.lookup(&var_expr.name);
+        self.emit_location(location);
+        self.emit_load(var_expr.expr_type.as_ref());
+    }
+
+    fn visit_typecast_expr(&mut self, typecast_expr: &mut TypecastExpr) {
+        // arrays already evaluate to their address
+        self.visit_expr(&mut typecast_expr.expr);
+        if let (Some(from), Some(to)) = (typecast_expr.expr.get_expr_type().as_integer(), typecast_expr.typecast_type.as_integer()) {
+            self.emit_convert(from, to);
+        }
+    }
+
+    fn visit_size_of_expr(&mut self, size_of_expr: &mut SizeOfExpr) {
+        let size = self.layout.size_of(size_of_expr.sizeof_type.as_ref());
+        self.emit(&format!("i32.const {}", size));
+    }
+
+    fn visit_address_of_exp(&mut self, address_of_expr: &mut AddressOfExpr) {
+        self.address(&mut address_of_expr.expr);
+    }
+
+    fn visit_value_at_expr(&mut self, value_at_expr: &mut ValueAtExpr) {
+        self.visit_expr(&mut value_at_expr.expr);
+        self.emit_load(value_at_expr.expr_type.as_ref());
+    }
+
+    fn visit_field_access_exp(&mut self, field_access_expr: &mut FieldAccessExpr) {
+        self.field_address(field_access_expr);
+        self.emit_load(field_access_expr.expr_type.as_ref());
+    }
+
+    fn visit_array_access_expr(&mut self, array_access_expr: &mut ArrayAccessExpr) {
+        self.element_address(array_access_expr);
+        self.emit_load(array_access_expr.expr_type.as_ref());
+    }
+
+    fn visit_fun_call_expr(&mut self, fun_call_expr: &mut FunCallExpr) {
+        // a struct result is written by the callee to a temporary of the caller, passed first
+        let fun_type = fun_call_expr.fun_decl.fun_type.clone();
+        let result = fun_type.as_struct().map(|_| self.new_local(fun_type.as_ref()));
+        if let Some(offset) = result {
+            self.emit_location(Location::Local(offset));
+        }
+        for expr in fun_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+        self.emit(&format!("call ${}", fun_call_expr.name));
+        if let Some(offset) = result {
+            self.emit_location(Location::Local(offset));
+        }
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &mut NewExpr) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_method_call_expr(&mut self, _method_call_expr: &mut MethodCallExpr) {
+        unreachable!("classes are lowered before code generation")
+    }
+
+    fn visit_fun_addr_expr(&mut self, fun_addr_expr: &mut FunAddrExpr) {
+        let index = match self.table.iter().position(|name| *name == fun_addr_expr.name) {
+            Some(index) => index,
+            None => {
+                self.table.push(fun_addr_expr.name.to_owned());
+                self.table.len() - 1
+            },
+        };
+        self.emit(&format!("i32.const {}", index + 1));
+    }
+
+    fn visit_indirect_call_expr(&mut self, indirect_call_expr: &mut IndirectCallExpr) {
+        let fun_type = indirect_call_expr.fun_decl.fun_type.clone();
+        let result = fun_type.as_struct().map(|_| self.new_local(fun_type.as_ref()));
+        if let Some(offset) = result {
+            self.emit_location(Location::Local(offset));
+        }
+        for expr in indirect_call_expr.exprs.iter_mut() {
+            self.visit_expr(expr);
+        }
+        self.visit_expr(&mut indirect_call_expr.expr);
+        self.emit(&format!("call_indirect{}", WatGenerator::signature(&indirect_call_expr.fun_decl)));
+        if let Some(offset) = result {
+            self.emit_location(Location::Local(offset));
+        }
+    }
+
+    // the left hand side is evaluated first, then the right hand side, and the value stored is read back
+    fn visit_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+        let a_type = assign_expr.expr_type.clone();
+        let address = self.save_address(&mut assign_expr.expr1);
+        self.saved_address(address);
+        if let Some(op) = assign_expr.op {
+            // the right hand side was converted to the type the operation is done in
+            let lhs_type = a_type.as_integer().unwrap();
+            let rhs_type = assign_expr.expr2.get_expr_type().as_integer().unwrap();
+            self.saved_address(address);
+            self.emit_load(a_type.as_ref());
+            self.emit_convert(lhs_type, rhs_type);
+            self.visit_expr(&mut assign_expr.expr2);
+            self.emit_op(op, rhs_type);
+            self.emit_convert(rhs_type, lhs_type);
+        } else {
+            self.visit_expr(&mut assign_expr.expr2);
+        }
+        self.emit_store(a_type.as_ref());
+        self.saved_address(address);
+        self.emit_load(a_type.as_ref());
+    }
+
+    fn visit_inc_dec_expr(&mut self, inc_dec_expr: &mut IncDecExpr) {
+        let int_type = inc_dec_expr.expr.get_expr_type().as_integer().unwrap();
+        let promoted = int_type.promote();
+        let address = self.save_address(&mut inc_dec_expr.expr);
+        if !inc_dec_expr.prefix {
+            // the old value stays below the store
+            self.saved_address(address);
+            self.emit_load(&int_type);
+        }
+        self.saved_address(address);
+        self.saved_address(address);
+        self.emit_load(&int_type);
+        self.emit_convert(int_type, promoted);
+        self.emit(&format!("{}.const 1", value_type_of(&promoted)));
+        self.emit_op(inc_dec_expr.op, promoted);
+        self.emit_convert(promoted, int_type);
+        self.emit_store(&int_type);
+        if inc_dec_expr.prefix {
+            self.saved_address(address);
+            self.emit_load(&int_type);
+        }
+    }
+
+    fn visit_conditional_expr(&mut self, conditional_expr: &mut ConditionalExpr) {
+        self.visit_expr(&mut conditional_expr.expr);
+        if conditional_expr.expr_type.is_base(BaseType::VOID) {
+            self.emit("if");
+        } else {
+            let result = format!("if (result {})", value_type_of(conditional_expr.expr_type.as_ref()));
+            self.emit(&result);
+        }
+        self.visit_expr(&mut conditional_expr.expr1);
+        self.emit("else");
+        self.visit_expr(&mut conditional_expr.expr2);
+        self.emit("end");
+    }
+
+    fn visit_type(&mut self, _a_type: &mut Box<dyn Type>) { }
+
+    fn visit_stmt(&mut self, stmt: &mut Box<dyn Stmt>) {
+        if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Block>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<While>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<DoWhile>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<For>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Break>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Continue>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<If>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Switch>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Assign>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<Return>() {
+            cast_stmt.accept(self)
+        } else if let Some(cast_stmt) = stmt.as_mut_any().downcast_mut::<ExprStmt>() {
+            cast_stmt.accept(self)
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Box<dyn Expr>) {
+        if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IntLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<StrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ChrLiteral>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<VarExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<BinOp>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ArrayAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FieldAccessExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ValueAtExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AddressOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<SizeOfExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<TypecastExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<NewExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<MethodCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<FunAddrExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IndirectCallExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<AssignExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<IncDecExpr>() {
+            cast_expr.accept(self)
+        } else if let Some(cast_expr) = expr.as_mut_any().downcast_mut::<ConditionalExpr>() {
+            cast_expr.accept(self)
+        }
+    }
+}
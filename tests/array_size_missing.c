@@ -0,0 +1,16 @@
+#include "minic-stdlib.h"
+
+// the size of an array may only be left out when its initialiser gives it
+
+int table[];
+char name[] = "minic";
+
+void main() {
+    int a[];
+    int b[] = {1, 2, 3};
+    int grid[2][];
+    a[0] = 1;
+    print_i(a[0]);
+    print_i(b[2]);
+    print_s((char*) name);
+}
@@ -0,0 +1,80 @@
+#include "minic-stdlib.h"
+
+// compound assignment, prefix and postfix increment and decrement, and assignment as an expression
+
+struct counter {
+    int hits;
+    int misses;
+};
+
+int calls = 0;
+
+void show(int n, char end) {
+    print_i(n);
+    print_c(end);
+}
+
+int next() {
+    return ++calls;
+}
+
+void main() {
+    int a[5] = {1, 2, 3, 4, 5};
+    struct counter c = {0, 0};
+    int* p = &a[0];
+    int i;
+    int j;
+    int x;
+    int y;
+    int sum;
+
+    x = y = 7;
+    show(x, ' ');
+    show(y, '\n');
+
+    sum = 0;
+    for (i = 0; i < 5; i++) {
+        sum += a[i];
+    }
+    show(sum, ' ');
+    sum -= 3;
+    show(sum, ' ');
+    sum *= 4;
+    show(sum, ' ');
+    sum /= 5;
+    show(sum, ' ');
+    sum %= 5;
+    show(sum, '\n');
+
+    i = 3;
+    show(i++, ' ');
+    show(i, ' ');
+    show(++i, ' ');
+    show(i--, ' ');
+    show(--i, '\n');
+
+    j = 0;
+    a[j++] += 10;
+    a[++j] *= 3;
+    show(j, ' ');
+    show(a[0], ' ');
+    show(a[1], ' ');
+    show(a[2], ' ');
+    c.hits++;
+    c.misses += 2;
+    ++c.hits;
+    *p -= 1;
+    show(c.hits, ' ');
+    show(c.misses, ' ');
+    show(a[0], '\n');
+
+    x = (y = next()) + 10;
+    show(x, ' ');
+    show(y, ' ');
+    show(calls, ' ');
+    while ((x -= 4) > 0) {
+        y++;
+    }
+    show(x, ' ');
+    show(y, '\n');
+}
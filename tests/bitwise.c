@@ -0,0 +1,75 @@
+#include "minic-stdlib.h"
+
+// bitwise operators, shifts and logical not with the precedence of C, e.g. a tic-tac-toe board as
+// a mask of nine bits
+
+int wins[8] = {7, 56, 448, 73, 146, 292, 273, 84};
+
+int has_won(int board) {
+    int i;
+    for (i = 0; i < 8; i++) {
+        if ((board & wins[i]) == wins[i]) {
+            return 1;
+        }
+    }
+    return 0;
+}
+
+int bit(int row, int col) {
+    return 1 << (row * 3 + col);
+}
+
+int popcount(int x) {
+    int n = 0;
+    while (x) {
+        n = n + (x & 1);
+        x = x >> 1;
+    }
+    return n;
+}
+
+void main() {
+    int x = 0;
+    int o = 0;
+
+    print_i(12 | 3);
+    print_c(' ');
+    print_i(12 & 10);
+    print_c(' ');
+    print_i(12 ^ 10);
+    print_c(' ');
+    print_i(~0);
+    print_c(' ');
+    print_i(~5);
+    print_c(' ');
+    print_i(1 << 10);
+    print_c(' ');
+    print_i(-16 >> 2);
+    print_c(' ');
+    print_i(!0);
+    print_i(!7);
+    print_i(!!7);
+    print_c('\n');
+
+    // & binds tighter than ^ which binds tighter than |, all below ==, and shifts below +
+    print_i(1 | 2 ^ 3 & 6);
+    print_c(' ');
+    print_i(1 << 2 + 1);
+    print_c(' ');
+    print_i((5 & 4) == 4);
+    print_c(' ');
+    print_i(!(1 < 2) | 4);
+    print_c('\n');
+
+    x = x | bit(0, 0) | bit(1, 1);
+    o = o | bit(0, 2) | bit(2, 0);
+    print_i(has_won(x));
+    x = x | bit(2, 2);
+    print_i(has_won(x));
+    print_i(has_won(o));
+    print_c(' ');
+    print_i(popcount(x | o));
+    print_c(' ');
+    print_i(511 & ~(x | o));
+    print_c('\n');
+}
@@ -0,0 +1,84 @@
+#include "minic-stdlib.h"
+
+// classes with fields and methods, single inheritance and methods dispatched on the class of the
+// object at run time
+
+class Counter {
+    int count;
+
+    void add(int n) {
+        count = count + n;
+    }
+
+    int get() {
+        return count;
+    }
+}
+
+class Animal {
+    int legs;
+
+    char sound() {
+        return '?';
+    }
+
+    void speak() {
+        // calls the method of the class of the object
+        print_c(sound());
+        print_i(legs);
+    }
+}
+
+class Dog extends Animal {
+    char sound() {
+        return 'w';
+    }
+}
+
+class Bird extends Animal {
+    int wings;
+
+    char sound() {
+        return 't';
+    }
+
+    int limbs() {
+        return legs + wings;
+    }
+}
+
+void all_speak(class Animal a, class Animal b, class Animal c) {
+    a.speak();
+    b.speak();
+    c.speak();
+}
+
+void main() {
+    class Counter c;
+    class Counter same;
+    class Animal a;
+    class Dog d;
+    class Bird b;
+
+    c = new class Counter();
+    c.add(2);
+    same = c;
+    same.add(3);
+    print_i(c.get());
+    print_c(' ');
+
+    a = new class Animal();
+    a.legs = 0;
+    d = new class Dog();
+    d.legs = 4;
+    b = new class Bird();
+    b.legs = 2;
+    b.wings = 2;
+    all_speak(a, d, b);
+    print_c(' ');
+    print_i(b.limbs());
+    print_c(' ');
+    a = b;
+    print_c(a.sound());
+    print_c('\n');
+}
@@ -0,0 +1,314 @@
+// Loads the assembly of the MIPS and RV32IM backends into a small machine, which the interpreters of
+// mips.rs and riscv.rs run. Only what the backends emit is known: the .data and .text sections,
+// labels, the .align, .asciiz, .asciz, .byte and .space directives, and instructions whose operands
+// are registers, integers, labels or `offset(register)`. The memory follows MARS and RARS: the text
+// at TEXT_BASE, the data then the heap grown by sbrk at DATA_BASE, and the stack below STACK_TOP.
+use std::collections::HashMap;
+
+pub const TEXT_BASE: u32 = 0x0040_0000;
+pub const DATA_BASE: u32 = 0x1001_0000;
+pub const STACK_TOP: u32 = 0x7fff_effc;
+const STACK_SIZE: u32 = 1 << 20;
+const MAX_HEAP: usize = 1 << 28;
+// a program still running after this many instructions is taken to loop forever
+const MAX_STEPS: u64 = 200_000_000;
+
+pub enum Operand {
+    Reg(usize),
+    // an integer, or the address of a label
+    Imm(i32),
+    // offset(register)
+    Mem(i32, usize),
+}
+
+pub struct Instruction {
+    pub op: String,
+    pub operands: Vec<Operand>,
+    line: String,
+}
+
+impl Instruction {
+    // the operands, which must be `count`
+    pub fn args(&self, count: usize) -> Result<&[Operand], String> {
+        if self.operands.len() != count {
+            return Err(format!("{} expects {} operands", self.op, count));
+        }
+        Ok(&self.operands)
+    }
+
+    pub fn reg(&self, index: usize) -> Result<usize, String> {
+        match self.operands.get(index) {
+            Some(Operand::Reg(r)) => Ok(*r),
+            _ => Err(format!("operand {} of {} must be a register", index + 1, self.op)),
+        }
+    }
+
+    pub fn imm(&self, index: usize) -> Result<i32, String> {
+        match self.operands.get(index) {
+            Some(Operand::Imm(i)) => Ok(*i),
+            _ => Err(format!("operand {} of {} must be an integer or a label", index + 1, self.op)),
+        }
+    }
+
+    pub fn mem(&self, index: usize) -> Result<(i32, usize), String> {
+        match self.operands.get(index) {
+            Some(Operand::Mem(offset, r)) => Ok((*offset, *r)),
+            _ => Err(format!("operand {} of {} must be an address", index + 1, self.op)),
+        }
+    }
+}
+
+pub struct Machine<'a> {
+    text: Vec<Instruction>,
+    pub regs: [u32; 32],
+    // the registers of the MIPS multiplications and divisions
+    pub hi: u32,
+    pub lo: u32,
+    pub pc: u32,
+    data: Vec<u8>,
+    stack: Vec<u8>,
+    input: &'a [u8],
+    pub output: Vec<u8>,
+    exited: bool,
+}
+
+// the bytes of a string literal, whose escapes are those of minic
+fn unescape(literal: &str) -> Result<Vec<u8>, String> {
+    let contents = literal.strip_prefix('"').and_then(|l| l.strip_suffix('"'))
+        .ok_or_else(|| format!("invalid string {}", literal))?;
+    let mut bytes = Vec::new();
+    let mut chars = contents.chars();
+    while let Some(c) = chars.next() {
+        let c = if c != '\\' {
+            c
+        } else {
+            match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some(c) => c,
+                None => return Err(format!("invalid string {}", literal)),
+            }
+        };
+        let mut buf = [0; 4];
+        bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+    }
+    bytes.push(0);
+    Ok(bytes)
+}
+
+fn integer(text: &str) -> Option<i32> {
+    // the immediates of lui and ori are written unsigned
+    text.parse::<i64>().ok().filter(|i| (i32::MIN as i64..=u32::MAX as i64).contains(i)).map(|i| i as i32)
+}
+
+// the operands of an instruction, the labels being replaced by their address
+fn operand(text: &str, labels: &HashMap<String, u32>, register: fn(&str) -> Option<usize>) -> Result<Operand, String> {
+    if let Some(r) = register(text) {
+        Ok(Operand::Reg(r))
+    } else if let Some((offset, base)) = text.strip_suffix(')').and_then(|t| t.split_once('(')) {
+        let offset = if offset.is_empty() { Some(0) } else { integer(offset) };
+        match (offset, register(base)) {
+            (Some(offset), Some(r)) => Ok(Operand::Mem(offset, r)),
+            _ => Err(format!("invalid address {}", text)),
+        }
+    } else if let Some(i) = integer(text) {
+        Ok(Operand::Imm(i))
+    } else {
+        labels.get(text).map(|address| Operand::Imm(*address as i32)).ok_or_else(|| format!("undefined label {}", text))
+    }
+}
+
+impl<'a> Machine<'a> {
+    // assembles the program, whose registers are named as `register` reads them, and starts it at main
+    pub fn assemble(assembly: &str, input: &'a [u8], register: fn(&str) -> Option<usize>) -> Result<Machine<'a>, String> {
+        let mut labels = HashMap::new();
+        let mut data = Vec::new();
+        let mut lines = Vec::new();
+        let mut in_text = false;
+        for line in assembly.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let mut statement = line;
+            // a label, unless the colon is in a string
+            if let Some((label, rest)) = line.split_once(':') {
+                if label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    let address = if in_text { TEXT_BASE + 4 * lines.len() as u32 } else { DATA_BASE + data.len() as u32 };
+                    if labels.insert(label.to_owned(), address).is_some() {
+                        return Err(format!("label {} is defined twice", label));
+                    }
+                    statement = rest.trim();
+                }
+            }
+            let (directive, arguments) = statement.split_once(' ').unwrap_or((statement, ""));
+            match directive {
+                "" | ".globl" => {},
+                ".data" => in_text = false,
+                ".text" => in_text = true,
+                ".align" if in_text => {},
+                ".align" => {
+                    let alignment = 1 << arguments.parse::<u32>().map_err(|_| format!("invalid alignment in {}", line))?;
+                    data.resize(data.len().next_multiple_of(alignment), 0);
+                },
+                ".asciiz" | ".asciz" => data.extend(unescape(arguments)?),
+                ".byte" => {
+                    for byte in arguments.split(',') {
+                        data.push(integer(byte.trim()).ok_or_else(|| format!("invalid byte in {}", line))? as u8);
+                    }
+                },
+                ".space" => {
+                    let size = arguments.parse::<usize>().map_err(|_| format!("invalid size in {}", line))?;
+                    data.resize(data.len() + size, 0);
+                },
+                _ if directive.starts_with('.') => return Err(format!("unknown directive {}", directive)),
+                _ if !in_text => return Err(format!("instruction {} outside of .text", line)),
+                _ => lines.push((directive, arguments, line)),
+            }
+        }
+
+        let mut text = Vec::new();
+        for (op, arguments, line) in lines {
+            let operands = arguments.split(',').map(str::trim).filter(|a| !a.is_empty())
+                .map(|a| operand(a, &labels, register))
+                .collect::<Result<Vec<Operand>, String>>()
+                .map_err(|e| format!("{} in `{}`", e, line))?;
+            text.push(Instruction { op: op.to_owned(), operands, line: line.to_owned() });
+        }
+        let pc = *labels.get("main").ok_or_else(|| "no main label".to_owned())?;
+
+        Ok(Machine {
+            text,
+            regs: [0; 32],
+            hi: 0,
+            lo: 0,
+            pc,
+            data,
+            stack: vec![0; STACK_SIZE as usize],
+            input,
+            output: Vec::new(),
+            exited: false,
+        })
+    }
+
+    // runs the instructions with `step` until the program exits, the pc being that of the next
+    // instruction when `step` runs
+    pub fn run(&mut self, step: fn(&mut Machine, &Instruction) -> Result<(), String>) -> Result<(), String> {
+        let text = std::mem::take(&mut self.text);
+        let mut steps = 0;
+        let result = loop {
+            if self.exited {
+                break Ok(());
+            }
+            steps += 1;
+            if steps > MAX_STEPS {
+                break Err(format!("still running after {} instructions", MAX_STEPS));
+            }
+            let index = self.pc.wrapping_sub(TEXT_BASE) / 4;
+            let Some(instruction) = text.get(index as usize).filter(|_| self.pc.is_multiple_of(4)) else {
+                break Err(format!("invalid pc {:#x}", self.pc));
+            };
+            self.pc += 4;
+            if let Err(e) = step(self, instruction) {
+                break Err(format!("{} in `{}`", e, instruction.line));
+            }
+            self.regs[0] = 0;
+        };
+        self.text = text;
+        result
+    }
+
+    // the value of a register, or an integer
+    pub fn value(&self, operand: &Operand) -> Result<u32, String> {
+        match operand {
+            Operand::Reg(r) => Ok(self.regs[*r]),
+            Operand::Imm(i) => Ok(*i as u32),
+            Operand::Mem(..) => Err("an address is not a value".to_owned()),
+        }
+    }
+
+    pub fn set(&mut self, r: usize, value: u32) {
+        self.regs[r] = value;
+    }
+
+    pub fn address(&self, (offset, r): (i32, usize)) -> u32 {
+        self.regs[r].wrapping_add(offset as u32)
+    }
+
+    fn bytes(&mut self, address: u32, size: u32) -> Result<&mut [u8], String> {
+        if !address.is_multiple_of(size) {
+            return Err(format!("unaligned access at {:#x}", address));
+        }
+        let stack_base = STACK_TOP + 4 - STACK_SIZE;
+        let (memory, start) = if address >= stack_base {
+            (&mut self.stack, address - stack_base)
+        } else {
+            (&mut self.data, address.wrapping_sub(DATA_BASE))
+        };
+        memory.get_mut(start as usize..start as usize + size as usize).ok_or_else(|| format!("invalid access at {:#x}", address))
+    }
+
+    // the value of `size` bytes, sign extended when `signed`
+    pub fn load(&mut self, address: u32, size: u32, signed: bool) -> Result<u32, String> {
+        let mut value = [0; 4];
+        value[..size as usize].copy_from_slice(self.bytes(address, size)?);
+        let shift = 32 - 8 * size;
+        Ok(if signed { ((u32::from_le_bytes(value) << shift) as i32 >> shift) as u32 } else { u32::from_le_bytes(value) })
+    }
+
+    pub fn store(&mut self, address: u32, size: u32, value: u32) -> Result<(), String> {
+        self.bytes(address, size)?.copy_from_slice(&value.to_le_bytes()[..size as usize]);
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let (byte, rest) = self.input.split_first()?;
+        self.input = rest;
+        Some(*byte)
+    }
+
+    // the system calls of MARS and RARS which the backends use, returning the result if there is one
+    pub fn syscall(&mut self, code: u32, argument: u32) -> Result<Option<u32>, String> {
+        match code {
+            1 => self.output.extend_from_slice((argument as i32).to_string().as_bytes()),
+            4 => {
+                let mut address = argument;
+                loop {
+                    let byte = self.load(address, 1, false)? as u8;
+                    if byte == 0 {
+                        break;
+                    }
+                    self.output.push(byte);
+                    address += 1;
+                }
+            },
+            5 => {
+                while self.input.first().is_some_and(|c| c.is_ascii_whitespace()) {
+                    self.read_byte();
+                }
+                let negative = self.input.first() == Some(&b'-');
+                if negative {
+                    self.read_byte();
+                }
+                let mut i: i32 = 0;
+                while let Some(c) = self.input.first().copied().filter(|c| c.is_ascii_digit()) {
+                    i = i.wrapping_mul(10).wrapping_add((c - b'0') as i32);
+                    self.read_byte();
+                }
+                return Ok(Some(if negative { i.wrapping_neg() } else { i } as u32));
+            },
+            9 => {
+                let start = self.data.len().next_multiple_of(8);
+                if start + argument as usize > MAX_HEAP {
+                    return Err("out of memory".to_owned());
+                }
+                self.data.resize(start + argument as usize, 0);
+                return Ok(Some(DATA_BASE + start as u32));
+            },
+            10 => self.exited = true,
+            11 => self.output.push(argument as u8),
+            // -1 at the end of the input
+            12 => return Ok(Some(self.read_byte().map_or(u32::MAX, |c| c as u32))),
+            _ => return Err(format!("unknown system call {}", code)),
+        }
+        Ok(None)
+    }
+}
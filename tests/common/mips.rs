@@ -0,0 +1,142 @@
+// An interpreter of the MIPS assembly of `-gen`, which runs it as MARS would: the instructions are
+// those the backend emits, the pseudo instructions of MARS included, and the system calls are read
+// from $v0.
+use super::asm::{Instruction, Machine, STACK_TOP};
+
+const V0: usize = 2;
+const A0: usize = 4;
+const SP: usize = 29;
+const RA: usize = 31;
+
+const NAMES: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7",
+    "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp", "ra",
+];
+
+fn register(name: &str) -> Option<usize> {
+    let name = name.strip_prefix('$')?;
+    NAMES.iter().position(|n| *n == name).or_else(|| name.parse().ok().filter(|r| *r < 32))
+}
+
+// the operators of the instructions `op rd, rs, rt` and `op rt, rs, immediate`
+fn alu(op: &str, a: u32, b: u32) -> Option<u32> {
+    let (sa, sb) = (a as i32, b as i32);
+    Some(match op {
+        "addu" | "addiu" => a.wrapping_add(b),
+        "subu" => a.wrapping_sub(b),
+        "mul" => a.wrapping_mul(b),
+        "and" | "andi" => a & b,
+        "or" | "ori" => a | b,
+        "xor" | "xori" => a ^ b,
+        "sllv" => a << (b & 31),
+        "srlv" => a >> (b & 31),
+        "srav" => (sa >> (b & 31)) as u32,
+        "slt" | "slti" => (sa < sb) as u32,
+        "sltu" | "sltiu" => (a < b) as u32,
+        "seq" => (a == b) as u32,
+        "sne" => (a != b) as u32,
+        "sgt" => (sa > sb) as u32,
+        "sle" => (sa <= sb) as u32,
+        "sge" => (sa >= sb) as u32,
+        _ => return None,
+    })
+}
+
+fn step(m: &mut Machine, i: &Instruction) -> Result<(), String> {
+    let op = i.op.as_str();
+    if let [_, a, b] = &i.operands[..] {
+        if let Some(value) = alu(op, m.value(a)?, m.value(b)?) {
+            m.set(i.reg(0)?, value);
+            return Ok(());
+        }
+    }
+    match op {
+        "li" | "la" => {
+            i.args(2)?;
+            m.set(i.reg(0)?, i.imm(1)? as u32);
+        },
+        "move" => {
+            i.args(2)?;
+            m.set(i.reg(0)?, m.regs[i.reg(1)?]);
+        },
+        "lui" => {
+            i.args(2)?;
+            m.set(i.reg(0)?, (i.imm(1)? as u32) << 16);
+        },
+        "lw" | "lh" | "lhu" | "lb" | "lbu" => {
+            i.args(2)?;
+            let size = match op { "lw" => 4, "lh" | "lhu" => 2, _ => 1 };
+            let value = m.load(m.address(i.mem(1)?), size, !op.ends_with('u'))?;
+            m.set(i.reg(0)?, value);
+        },
+        "sw" | "sh" | "sb" => {
+            i.args(2)?;
+            let size = match op { "sw" => 4, "sh" => 2, _ => 1 };
+            m.store(m.address(i.mem(1)?), size, m.regs[i.reg(0)?])?;
+        },
+        "div" | "divu" | "multu" => {
+            i.args(2)?;
+            let (a, b) = (m.regs[i.reg(0)?], m.regs[i.reg(1)?]);
+            if b == 0 && op != "multu" {
+                return Err("division by zero".to_owned());
+            }
+            (m.lo, m.hi) = match op {
+                "div" => ((a as i32).wrapping_div(b as i32) as u32, (a as i32).wrapping_rem(b as i32) as u32),
+                "divu" => (a / b, a % b),
+                _ => {
+                    let product = a as u64 * b as u64;
+                    (product as u32, (product >> 32) as u32)
+                },
+            };
+        },
+        "mflo" | "mfhi" => {
+            i.args(1)?;
+            m.set(i.reg(0)?, if op == "mflo" { m.lo } else { m.hi });
+        },
+        "j" => {
+            i.args(1)?;
+            m.pc = i.imm(0)? as u32;
+        },
+        "jal" => {
+            i.args(1)?;
+            m.set(RA, m.pc);
+            m.pc = i.imm(0)? as u32;
+        },
+        "jr" => {
+            i.args(1)?;
+            m.pc = m.regs[i.reg(0)?];
+        },
+        "jalr" => {
+            i.args(1)?;
+            let target = m.regs[i.reg(0)?];
+            m.set(RA, m.pc);
+            m.pc = target;
+        },
+        "beqz" | "bnez" => {
+            i.args(2)?;
+            if (m.regs[i.reg(0)?] == 0) == (op == "beqz") {
+                m.pc = i.imm(1)? as u32;
+            }
+        },
+        "syscall" => {
+            i.args(0)?;
+            if let Some(result) = m.syscall(m.regs[V0], m.regs[A0])? {
+                m.set(V0, result);
+            }
+        },
+        _ => return Err(format!("unknown instruction {}", op)),
+    }
+    Ok(())
+}
+
+// the output of the program, or the reason it failed with the output it printed until then
+pub fn run(assembly: &str, input: &[u8]) -> Result<String, String> {
+    let mut machine = Machine::assemble(assembly, input, register)?;
+    machine.set(SP, STACK_TOP);
+    let result = machine.run(step);
+    let output = String::from_utf8_lossy(&machine.output).into_owned();
+    match result {
+        Ok(()) => Ok(output),
+        Err(e) => Err(format!("{}\n{}", e, output)),
+    }
+}
@@ -0,0 +1,57 @@
+// Helpers shared by the tests which compile the samples of this directory: running the compiler and
+// the tools, finding the samples, and the interpreters of the MIPS and RV32IM backends.
+#![allow(dead_code)]
+
+pub mod asm;
+pub mod mips;
+pub mod riscv;
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+pub const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+pub fn run(command: &mut Command, input: &[u8]) -> Output {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+pub fn has_tool(tool: &str) -> bool {
+    Command::new(tool).arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok_and(|status| status.success())
+}
+
+// tells that a test does not do part of its checks, written to the standard error directly since the
+// test harness captures eprintln!
+#[allow(clippy::explicit_write)]
+pub fn skip(test: &str, tool: &str, what: &str) {
+    writeln!(std::io::stderr(), "note: {} skips {}: {} is not installed", test, what, tool).unwrap();
+}
+
+pub fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// the samples which have an expected output `<name>.out`, in order
+pub fn sources() -> Vec<PathBuf> {
+    let mut sources: Vec<PathBuf> = fs::read_dir(tests_dir()).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "c") && path.with_extension("out").exists())
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty());
+    sources
+}
+
+// the expected output of a sample, and the input it reads
+pub fn expectation(source: &Path) -> (String, Vec<u8>) {
+    let expected = fs::read_to_string(source.with_extension("out")).unwrap();
+    let input = fs::read(source.with_extension("in")).unwrap_or_default();
+    (expected, input)
+}
+
+pub fn sample_name(source: &Path) -> String {
+    source.file_name().unwrap().to_string_lossy().into_owned()
+}
@@ -0,0 +1,130 @@
+// An interpreter of the RV32IM assembly of `-riscv`, which runs it as RARS would: the instructions are
+// those the backend emits, the pseudo instructions included, and the system calls are read from a7.
+use super::asm::{Instruction, Machine, STACK_TOP};
+
+const RA: usize = 1;
+const SP: usize = 2;
+const A0: usize = 10;
+const A7: usize = 17;
+
+const NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+fn register(name: &str) -> Option<usize> {
+    match name {
+        "fp" => Some(8),
+        _ => NAMES.iter().position(|n| *n == name)
+            .or_else(|| name.strip_prefix('x').and_then(|r| r.parse().ok()).filter(|r| *r < 32)),
+    }
+}
+
+// the operators of the instructions `op rd, rs1, rs2` and `op rd, rs1, immediate`, the divisions by
+// zero giving what the M extension specifies
+fn alu(op: &str, a: u32, b: u32) -> Option<u32> {
+    let (sa, sb) = (a as i32, b as i32);
+    Some(match op {
+        "add" | "addi" => a.wrapping_add(b),
+        "sub" => a.wrapping_sub(b),
+        "mul" => a.wrapping_mul(b),
+        "mulhu" => ((a as u64 * b as u64) >> 32) as u32,
+        "div" if b == 0 => u32::MAX,
+        "div" => sa.wrapping_div(sb) as u32,
+        "divu" => a.checked_div(b).unwrap_or(u32::MAX),
+        "rem" if b == 0 => a,
+        "rem" => sa.wrapping_rem(sb) as u32,
+        "remu" => a.checked_rem(b).unwrap_or(a),
+        "and" | "andi" => a & b,
+        "or" | "ori" => a | b,
+        "xor" | "xori" => a ^ b,
+        "sll" | "slli" => a << (b & 31),
+        "srl" | "srli" => a >> (b & 31),
+        "sra" | "srai" => (sa >> (b & 31)) as u32,
+        "slt" | "slti" => (sa < sb) as u32,
+        "sltu" | "sltiu" => (a < b) as u32,
+        "sgt" => (sa > sb) as u32,
+        _ => return None,
+    })
+}
+
+fn step(m: &mut Machine, i: &Instruction) -> Result<(), String> {
+    let op = i.op.as_str();
+    if let [_, a, b] = &i.operands[..] {
+        if let Some(value) = alu(op, m.value(a)?, m.value(b)?) {
+            m.set(i.reg(0)?, value);
+            return Ok(());
+        }
+    }
+    match op {
+        "li" | "la" => {
+            i.args(2)?;
+            m.set(i.reg(0)?, i.imm(1)? as u32);
+        },
+        "mv" | "seqz" | "snez" => {
+            i.args(2)?;
+            let value = m.regs[i.reg(1)?];
+            m.set(i.reg(0)?, match op { "mv" => value, "seqz" => (value == 0) as u32, _ => (value != 0) as u32 });
+        },
+        "lw" | "lh" | "lhu" | "lb" | "lbu" => {
+            i.args(2)?;
+            let size = match op { "lw" => 4, "lh" | "lhu" => 2, _ => 1 };
+            let value = m.load(m.address(i.mem(1)?), size, !op.ends_with('u'))?;
+            m.set(i.reg(0)?, value);
+        },
+        "sw" | "sh" | "sb" => {
+            i.args(2)?;
+            let size = match op { "sw" => 4, "sh" => 2, _ => 1 };
+            m.store(m.address(i.mem(1)?), size, m.regs[i.reg(0)?])?;
+        },
+        "j" => {
+            i.args(1)?;
+            m.pc = i.imm(0)? as u32;
+        },
+        "call" => {
+            i.args(1)?;
+            m.set(RA, m.pc);
+            m.pc = i.imm(0)? as u32;
+        },
+        "ret" => {
+            i.args(0)?;
+            m.pc = m.regs[RA];
+        },
+        "jr" => {
+            i.args(1)?;
+            m.pc = m.regs[i.reg(0)?];
+        },
+        "jalr" => {
+            i.args(1)?;
+            let target = m.regs[i.reg(0)?];
+            m.set(RA, m.pc);
+            m.pc = target;
+        },
+        "beqz" | "bnez" => {
+            i.args(2)?;
+            if (m.regs[i.reg(0)?] == 0) == (op == "beqz") {
+                m.pc = i.imm(1)? as u32;
+            }
+        },
+        "ecall" => {
+            i.args(0)?;
+            if let Some(result) = m.syscall(m.regs[A7], m.regs[A0])? {
+                m.set(A0, result);
+            }
+        },
+        _ => return Err(format!("unknown instruction {}", op)),
+    }
+    Ok(())
+}
+
+// the output of the program, or the reason it failed with the output it printed until then
+pub fn run(assembly: &str, input: &[u8]) -> Result<String, String> {
+    let mut machine = Machine::assemble(assembly, input, register)?;
+    machine.set(SP, STACK_TOP);
+    let result = machine.run(step);
+    let output = String::from_utf8_lossy(&machine.output).into_owned();
+    match result {
+        Ok(()) => Ok(output),
+        Err(e) => Err(format!("{}\n{}", e, output)),
+    }
+}
@@ -0,0 +1,80 @@
+#include "minic-stdlib.h"
+
+// prints 0 1 1 2 0 4 0 6 / 5 1 6 2 / -1 0 1 720 / 9 100 / 1 2 3 4 / 30 2 / eoeoe
+
+struct P { int x; int y; };
+class A { int v; int get() { return v; } }
+class B extends A { int get() { return v * 10; } }
+int calls;
+
+int hit(int v) {
+  calls = calls + 1;
+  return v;
+}
+
+void show(int v) {
+  print_i(v);
+  print_c(' ');
+}
+
+int sign(int v) {
+  return v < 0 ? -1 : v > 0 ? 1 : 0;
+}
+
+int fact(int n) {
+  return n <= 1 ? 1 : n * fact(n - 1);
+}
+
+void main() {
+  int i;
+  int x;
+  int y;
+  int *p;
+  struct P s;
+  struct P t;
+  struct P u;
+  class A a;
+  class B b;
+  class A r;
+
+  // the right operand of && and || is only evaluated when the left one does not decide
+  calls = 0;
+  show(hit(0) && hit(1)); show(calls);
+  show(hit(1) || hit(2)); show(calls);
+  show(hit(1) && hit(0)); show(calls);
+  show(hit(0) || hit(0)); show(calls);
+  print_c('\n');
+
+  // only the selected branch is evaluated
+  calls = 0;
+  show(1 ? hit(5) : hit(6)); show(calls);
+  show(0 ? hit(5) : hit(6)); show(calls);
+  print_c('\n');
+
+  show(sign(-5)); show(sign(0)); show(sign(9)); show(fact(6));
+  print_c('\n');
+
+  // a conditional pointer can be dereferenced and assigned through
+  x = 9;
+  y = 9;
+  p = &y;
+  *(x < y ? &x : p) = 100;
+  show(x); show(y);
+  print_c('\n');
+
+  s.x = 1; s.y = 2; t.x = 3; t.y = 4;
+  u = x > 0 ? s : t; show(u.x); show(u.y);
+  u = x < 0 ? s : t; show(u.x); show(u.y);
+  print_c('\n');
+
+  // a subclass branch is converted to the class of the other one
+  a = new class A(); a.v = 2;
+  b = new class B(); b.v = 3;
+  r = x > 0 ? b : a; show(r.get());
+  r = x < 0 ? b : a; show(r.get());
+  print_c('\n');
+
+  for (i = 0; i < 5; i++)
+    print_c(i % 2 ? 'o' : 'e');
+  print_c('\n');
+}
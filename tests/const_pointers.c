@@ -0,0 +1,28 @@
+#include "minic-stdlib.h"
+
+// a pointer to const does not convert to a plain pointer, which would allow assigning through it
+
+const int limits[2] = {1, 2};
+
+int* unconst(const int* p) {
+    return p;
+}
+
+void set(int* p) {
+    *p = 9;
+}
+
+void main() {
+    const int c = 3;
+    int* p;
+    const int* q = &c;
+    int* r = &c;
+    p = &c;
+    p = q;
+    set(q);
+    set(limits);
+    // the const can still be cast away, and a plain pointer converts to a pointer to const
+    p = (int*) q;
+    q = p;
+    print_s("ok\n");
+}
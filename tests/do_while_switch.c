@@ -0,0 +1,119 @@
+#include "minic-stdlib.h"
+
+// do/while runs its body at least once, and the cases of a switch fall through to the next one
+// until a break
+
+struct pair {
+    char c;
+    int i;
+};
+
+// sizeof of a type is a constant, in a global initialiser as in a case label
+int pair_words = sizeof(struct pair) / sizeof(int);
+
+char name(int d) {
+    switch (d) {
+        case 0:
+            return 'z';
+        case 1:
+        case 2:
+            return 's';
+        default:
+            return 'b';
+    }
+}
+
+char width(int size) {
+    switch (size) {
+        case sizeof(char):
+            return 'c';
+        case sizeof(short):
+            return 's';
+        case sizeof(int):
+            return 'i';
+        case sizeof(long):
+            return 'l';
+        default:
+            return '?';
+    }
+}
+
+int fall(int n) {
+    int r = 0;
+    switch (n) {
+        case 1:
+            r = r + 1;
+        case 2:
+            r = r + 10;
+            break;
+        case 3:
+            r = r + 100;
+        default:
+            r = r + 1000;
+    }
+    return r;
+}
+
+void main() {
+    int i;
+    int digits;
+    int n;
+
+    i = 10;
+    do {
+        i++;
+    } while (i < 5);
+    print_i(i);
+    print_c(' ');
+
+    // the number of digits, 1 for 0
+    n = 0;
+    digits = 0;
+    do {
+        digits++;
+        n = n / 10;
+    } while (n > 0);
+    print_i(digits);
+    n = 12345;
+    digits = 0;
+    do {
+        digits++;
+        n = n / 10;
+    } while (n > 0);
+    print_i(digits);
+    print_c('\n');
+
+    for (i = 0; i < 5; i++) {
+        print_c(name(i));
+    }
+    print_c(' ');
+    print_i(fall(1));
+    print_c(' ');
+    print_i(fall(2));
+    print_c(' ');
+    print_i(fall(3));
+    print_c(' ');
+    print_i(fall(4));
+    print_c('\n');
+
+    // break leaves the switch, continue the loop around it
+    i = 0;
+    do {
+        i++;
+        switch (i % 3) {
+            case 0:
+                continue;
+            default:
+                break;
+        }
+        print_i(i);
+    } while (i < 7);
+    print_c('\n');
+
+    for (i = 1; i <= 8; i++) {
+        print_c(width(i));
+    }
+    print_c(' ');
+    print_i(pair_words);
+    print_c('\n');
+}
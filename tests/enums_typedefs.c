@@ -0,0 +1,69 @@
+#include "minic-stdlib.h"
+
+// enums are named int constants, and typedefs name a type
+
+enum Color { RED, GREEN = 5, BLUE, };
+
+enum { SIZE = 3 };
+
+struct point {
+    int x;
+    int y;
+};
+
+typedef struct point Point;
+typedef Point* PointRef;
+typedef int Row[3];
+typedef enum Color Color;
+
+char initial(Color c) {
+    switch (c) {
+        case RED:
+            return 'r';
+        case GREEN:
+            return 'g';
+        case BLUE:
+            return 'b';
+    }
+    return '?';
+}
+
+void move(PointRef p, int dx) {
+    p->x = p->x + dx;
+}
+
+int sum(Row row) {
+    int s = 0;
+    int i;
+    for (i = 0; i < SIZE; i++) {
+        s += row[i];
+    }
+    return s;
+}
+
+void main() {
+    Point p;
+    Row row = {1, 2, 3};
+    Color c = BLUE;
+    enum Color d;
+    d = GREEN;
+    print_i(RED);
+    print_c(' ');
+    print_i(GREEN);
+    print_c(' ');
+    print_i(c);
+    print_c(' ');
+    print_c(initial(c));
+    print_c(initial(d));
+    print_c(initial(RED));
+    print_c(' ');
+    p.x = 1;
+    p.y = 2;
+    move(&p, 4);
+    print_i(p.x);
+    print_c(' ');
+    print_i(sum(row));
+    print_c(' ');
+    print_i(sizeof(Row) / sizeof(int));
+    print_c('\n');
+}
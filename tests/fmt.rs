@@ -0,0 +1,120 @@
+// Runs `fmt` on copies of sources in a directory of their own, which it formats in place.
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+// a copy of a source to be formatted, in a directory named after the test
+fn source(test: &str, text: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("minic-fmt-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("source.c");
+    fs::write(&path, text).unwrap();
+    path
+}
+
+fn fmt(args: &[&str], path: &PathBuf) -> Output {
+    Command::new(COMPILER).arg("fmt").args(args).arg(path).output().unwrap()
+}
+
+// the comments of a source in their order
+fn comments(text: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(['/', '"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap();
+        let after = &rest[start + 1..];
+        let end = if quote != '/' {
+            // skips a literal, with its escapes
+            let mut escaped = false;
+            start + 2 + after.find(|c| {
+                let end = !escaped && c == quote;
+                escaped = !escaped && c == '\\';
+                end
+            }).unwrap_or(after.len() - 1)
+        } else if after.starts_with('/') {
+            // up to the end of the line, excluded
+            let end = start + after.find('\n').map_or(rest.len() - start, |end| end + 1);
+            comments.push(&rest[start..end]);
+            end
+        } else if after.starts_with('*') {
+            let end = start + 1 + after.find("*/").unwrap() + 2;
+            comments.push(&rest[start..end]);
+            end
+        } else {
+            start + 1
+        };
+        rest = &rest[end..];
+    }
+    comments
+}
+
+fn ast(path: &PathBuf) -> Vec<u8> {
+    Command::new(COMPILER).arg("-ast").arg(path).output().unwrap().stdout
+}
+
+#[test]
+fn check() {
+    let unformatted = b"int  x ;\n// the entry\nvoid main(){x=1; /* set */\n  if(x) { print_i(x); } // printed\n}\n";
+    let path = source("check", unformatted);
+
+    // --check lists the file and leaves it as it is
+    let output = fmt(&["--check"], &path);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{} is not formatted\n", path.display()));
+    assert_eq!(fs::read(&path).unwrap(), unformatted);
+
+    assert!(fmt(&[], &path).status.success());
+    let formatted = fs::read_to_string(&path).unwrap();
+    assert_ne!(formatted.as_bytes(), unformatted);
+    let output = fmt(&["--check"], &path);
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(comments(&formatted), ["// the entry", "/* set */", "// printed"]);
+}
+
+// every sample formats to a program with the same AST and the same comments, which is formatted
+#[test]
+fn samples() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let mut sources: Vec<PathBuf> = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "c"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty());
+
+    let mut failures = Vec::new();
+    for original in sources.iter() {
+        let name = original.file_stem().unwrap().to_string_lossy();
+        let text = fs::read_to_string(original).unwrap();
+        let path = source(&format!("sample-{}", name), text.as_bytes());
+        if !fmt(&[], &path).status.success() {
+            failures.push(format!("{} does not format", name));
+            continue;
+        }
+        let formatted = fs::read_to_string(&path).unwrap();
+        if comments(&formatted) != comments(&text) {
+            failures.push(format!("{} loses or moves comments:\n{}", name, formatted));
+        }
+        if ast(&path) != ast(original) {
+            failures.push(format!("{} has another AST once formatted:\n{}", name, formatted));
+        }
+        if fmt(&["--check"], &path).status.code() != Some(0) {
+            failures.push(format!("{} is not stable:\n{}", name, formatted));
+        }
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn crlf_comments() {
+    let path = source("crlf", b"int x;\r\n// c\r\n/* a\r\n   b */\r\nvoid main(){\r\n}\r\n");
+    assert!(fmt(&[], &path).status.success());
+    let formatted = fs::read(&path).unwrap();
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+    assert_eq!(String::from_utf8(formatted).unwrap(), "int x;\n// c\n/* a\n   b */\nvoid main() {\n}\n");
+}
@@ -0,0 +1,75 @@
+#include "minic-stdlib.h"
+
+// for loops, and break and continue in every kind of loop
+
+int first_multiple(int n, int k) {
+    int i;
+    for (i = 1;; i++) {
+        if (i % k == 0 && i >= n) {
+            break;
+        }
+    }
+    return i;
+}
+
+void main() {
+    int i;
+    int j;
+    int sum;
+
+    // the odd numbers below 10
+    for (i = 0; i < 10; i++) {
+        if (i % 2 == 0) {
+            continue;
+        }
+        print_i(i);
+    }
+    print_c('\n');
+
+    // break only leaves the innermost loop
+    sum = 0;
+    for (i = 0; i < 4; i++) {
+        for (j = 0; j < 10; j++) {
+            if (j > i) {
+                break;
+            }
+            sum = sum + j;
+        }
+    }
+    print_i(sum);
+    print_c(' ');
+    print_i(first_multiple(10, 7));
+    print_c('\n');
+
+    // the init and step may be left out, continue still running the step
+    i = 0;
+    for (; i < 5;) {
+        i++;
+    }
+    print_i(i);
+    print_c(' ');
+    sum = 0;
+    for (i = 0; i < 10; i = i + 1) {
+        if (i == 3) {
+            continue;
+        }
+        if (i == 6) {
+            break;
+        }
+        sum = sum + i;
+    }
+    print_i(sum);
+    print_c(' ');
+
+    // and in a while loop
+    i = 0;
+    while (1) {
+        i++;
+        if (i < 3) {
+            continue;
+        }
+        break;
+    }
+    print_i(i);
+    print_c('\n');
+}
@@ -0,0 +1,105 @@
+#include "minic-stdlib.h"
+
+// prints 1 1 3 4 5 / 5 4 3 1 1 / 10 4 21 / +7 *10 9 / 1024 11 42 10 1 1 1 / 5 6
+
+typedef int (*Cmp)(int, int);
+
+struct op {
+  char symbol;
+  int (*apply)(int, int);
+};
+
+class Counter {
+  int n;
+  int (*step)(int);
+  int next() { n = step(n); return n; }
+}
+
+int ascending(int a, int b) { return a - b; }
+int descending(int a, int b) { return b - a; }
+int add(int a, int b) { return a + b; }
+int sub(int a, int b) { return a - b; }
+int mul(int a, int b) { return a * b; }
+int twice(int x) { return x * 2; }
+int succ(int x) { return x + 1; }
+
+void sort(int *a, int n, Cmp cmp) {
+  int i;
+  int j;
+  int t;
+  for (i = 0; i < n; i++)
+    for (j = i + 1; j < n; j++)
+      if ((*cmp)(a[i], a[j]) > 0) {
+        t = a[i];
+        a[i] = a[j];
+        a[j] = t;
+      }
+}
+
+void show(int *a, int n) {
+  int i;
+  for (i = 0; i < n; i++) {
+    print_i(a[i]);
+    print_c(' ');
+  }
+  print_c('\n');
+}
+
+int apply(int (*f)(int), int x, int times) {
+  while (times-- > 0)
+    x = f(x);
+  return x;
+}
+
+Cmp pick(int up) {
+  return up ? ascending : &descending;
+}
+
+void main() {
+  int a[5];
+  int (*ops[3])(int, int);
+  struct op table[2];
+  struct op *p;
+  Cmp cmp;
+  int (*f)(int);
+  class Counter c;
+  int i;
+
+  a[0] = 3; a[1] = 1; a[2] = 4; a[3] = 1; a[4] = 5;
+  sort(a, 5, ascending);
+  show(a, 5);
+  sort(a, 5, pick(0));
+  show(a, 5);
+
+  ops[0] = add; ops[1] = &sub; ops[2] = mul;
+  for (i = 0; i < 3; i++) {
+    print_i(ops[i](7, 3));
+    print_c(' ');
+  }
+  print_c('\n');
+
+  table[0].symbol = '+'; table[0].apply = add;
+  table[1].symbol = '*'; table[1].apply = mul;
+  p = &table[1];
+  print_c(table[0].symbol); print_i(table[0].apply(2, 5)); print_c(' ');
+  print_c(p->symbol); print_i(p->apply(2, 5)); print_c(' ');
+  print_i((*p).apply(3, 3)); print_c('\n');
+
+  f = twice;
+  print_i(apply(f, 1, 10)); print_c(' ');
+  print_i(apply(succ, 1, 10)); print_c(' ');
+  print_i((**f)(21)); print_c(' ');
+  print_i(f == twice); print_i(f == succ); print_c(' ');
+  cmp = pick(1);
+  print_i(cmp(1, 2) < 0); print_c(' ');
+  print_i(sizeof(int (*)(int)) == sizeof(void*)); print_c(' ');
+  print_i(sizeof(int (*[3])(int, int)) == 3 * sizeof(Cmp)); print_c('\n');
+
+  c = new class Counter();
+  c.n = 1;
+  c.step = twice;
+  c.next(); c.next();
+  c.step = succ;
+  print_i(c.next()); print_c(' ');
+  print_i(c.step(c.n)); print_c('\n');
+}
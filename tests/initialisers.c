@@ -0,0 +1,62 @@
+#include "minic-stdlib.h"
+
+// variables initialised where they are declared, the elements and fields left out being zero
+
+struct point {
+    int x;
+    int y;
+    char tag;
+};
+
+int count = 3;
+char greeting[] = "hi";
+int primes[5] = {2, 3, 5};
+struct point origin = {0, 0, 'o'};
+struct point corners[2] = {{1, 2, 'a'}, {3}};
+int grid[2][3] = {{1, 2, 3}, {4}};
+
+void show(struct point p) {
+    print_c(p.tag);
+    print_i(p.x);
+    print_i(p.y);
+}
+
+void main() {
+    int total = count * 2;
+    char word[6] = "minic";
+    int squares[4] = {0, 1, 4, 9};
+    struct point p = {count, total, 'p'};
+    int* q = &squares[2];
+    int i;
+    int sum = 0;
+
+    print_i(count);
+    print_c(' ');
+    print_i(total);
+    print_c(' ');
+    print_s((char*) greeting);
+    print_c(' ');
+    print_s((char*) word);
+    print_c('\n');
+    for (i = 0; i < 5; i++) {
+        print_i(primes[i]);
+    }
+    print_c(' ');
+    print_i(*q);
+    print_c(' ');
+    for (i = 0; i < 3; i++) {
+        sum = sum + grid[0][i] * 10 + grid[1][i];
+    }
+    print_i(sum);
+    print_c('\n');
+    show(origin);
+    print_c(' ');
+    show(corners[0]);
+    print_c(' ');
+    print_i(corners[1].x);
+    print_i(corners[1].y);
+    print_i(corners[1].tag);
+    print_c(' ');
+    show(p);
+    print_c('\n');
+}
@@ -0,0 +1,103 @@
+#include "minic-stdlib.h"
+
+// compiled with -O, the calls of the small functions below are replaced by their bodies
+
+int total;
+
+void add(int v) {
+    total = total + v;
+}
+
+int sign(int v) {
+    if (v < 0) {
+        return -1;
+    }
+    if (v == 0) {
+        return 0;
+    }
+    return 1;
+}
+
+int sum_to(int n) {
+    int s = 0;
+    int i;
+    for (i = 1; i <= n; i++) {
+        s += i;
+    }
+    return s;
+}
+
+// an array argument is bound as a pointer to its first element
+int sum(int* a, int n) {
+    int s = 0;
+    int i;
+    for (i = 0; i < n; i++) {
+        s += a[i];
+    }
+    return s;
+}
+
+int corner(int m[][3]) {
+    return m[1][2];
+}
+
+int twice_sign(int v) {
+    return sign(v);
+}
+
+int sq(int v) {
+    return v * v;
+}
+
+int bump() {
+    static int count;
+    count++;
+    return total + count;
+}
+
+// calls in an expression are moved out of it, in the order they are evaluated
+void expressions(int x) {
+    int t = 3;
+    int y = sq(x) + 1;
+    print_i(sq(x) + t);
+    print_c(' ');
+    print_i(y);
+    print_c(' ');
+    if (sq(x) > 10 && sign(x) > 0) {
+        print_i(sq(sq(2)));
+    }
+    print_c(' ');
+    // a call of a function which is not inlined stays in place, and so do the calls following it
+    print_i(bump() + sq(x));
+    print_c(' ');
+    print_i(x > 0 ? sq(x) : -sq(x));
+    print_c(' ');
+    while (sq(x) > 0) {
+        x = x - 1;
+    }
+    print_i(x);
+    print_c('\n');
+}
+
+void main() {
+    int x;
+    int a[4] = {1, 2, 3, 4};
+    int m[2][3] = {{1, 2, 3}, {4, 5, 6}};
+    add(5);
+    add(7);
+    x = sign(-3);
+    print_i(x);
+    print_c(' ');
+    x = sum_to(10);
+    print_i(x);
+    print_c(' ');
+    print_i(total);
+    print_c(' ');
+    x = sum(a, 4);
+    print_i(x);
+    print_c(' ');
+    x = corner(m);
+    print_i(x);
+    print_c('\n');
+    expressions(4);
+}
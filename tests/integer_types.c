@@ -0,0 +1,307 @@
+#include "minic-stdlib.h"
+
+struct sample {
+    char tag;
+    unsigned short port;
+    long stamp;
+    unsigned int crc;
+};
+
+unsigned int table[4] = {1u, 2u, 4294967295u, 7};
+long big = 9000000000;
+unsigned long mask = 18446744073709551615UL;
+short neg = -2;
+signed char sc = -5;
+unsigned char uc = 250;
+
+void print_ul(unsigned long v) {
+    if (v >= 10) {
+        print_ul(v / 10);
+    }
+    print_c((char) ('0' + v % 10));
+}
+
+void print_l(long v) {
+    if (v < 0) {
+        print_c('-');
+        print_ul(-(unsigned long) v);
+    } else {
+        print_ul(v);
+    }
+}
+
+void line() {
+    print_c('\n');
+}
+
+unsigned int crc32(char* data, int n) {
+    unsigned int crc;
+    int i;
+    int k;
+    crc = 4294967295u;
+    i = 0;
+    while (i < n) {
+        crc = crc ^ data[i];
+        for (k = 0; k < 8; k++) {
+            if (crc & 1) {
+                crc = (crc >> 1) ^ 3988292384u;
+            } else {
+                crc = crc >> 1;
+            }
+        }
+        i++;
+    }
+    return ~crc;
+}
+
+long mul_add(long a, int b, unsigned short c) {
+    return a * b + c;
+}
+
+unsigned long fnv(char* s) {
+    unsigned long h;
+    int i;
+    h = 14695981039346656037UL;
+    i = 0;
+    while (s[i]) {
+        h = h ^ (unsigned char) s[i];
+        h = h * 1099511628211UL;
+        i++;
+    }
+    return h;
+}
+
+struct sample make(long stamp) {
+    struct sample s;
+    s.tag = 'x';
+    s.port = 65535;
+    s.stamp = stamp;
+    s.crc = 0;
+    return s;
+}
+
+void main() {
+    unsigned int u;
+    int i;
+    long l;
+    unsigned long ul;
+    short s;
+    unsigned short us;
+    signed char c;
+    unsigned char b;
+    char pc;
+    struct sample smp;
+    long arr[3];
+    long* p;
+
+    // wraparound
+    u = 0;
+    u = u - 1;
+    print_ul(u);
+    line();
+    i = 2147483647;
+    i = i + 1;
+    print_l(i);
+    line();
+    s = 32767;
+    s++;
+    print_l(s);
+    line();
+    us = 0;
+    us--;
+    print_l(us);
+    line();
+    c = 127;
+    c += 1;
+    print_l(c);
+    line();
+    b = 255;
+    b += 2;
+    print_l(b);
+    line();
+    // a plain char is unsigned
+    pc = 200;
+    print_l(pc);
+    print_c(' ');
+    print_l(pc == 200);
+    print_c(' ');
+    print_l(pc > 100);
+    line();
+    print_l(sc);
+    print_c(' ');
+    print_l(uc);
+    print_c(' ');
+    print_l(neg);
+    line();
+
+    // signed and unsigned division and shifts
+    i = -7;
+    u = 4294967289u;
+    print_l(i / 2);
+    print_c(' ');
+    print_l(i % 2);
+    print_c(' ');
+    print_ul(u / 2);
+    print_c(' ');
+    print_ul(u % 2);
+    line();
+    print_l(i >> 1);
+    print_c(' ');
+    print_ul(u >> 1);
+    print_c(' ');
+    print_ul(u << 4);
+    line();
+    l = -9000000000;
+    ul = 18000000000000000000UL;
+    print_l(l / 7);
+    print_c(' ');
+    print_l(l % 7);
+    print_c(' ');
+    print_ul(ul / 7);
+    print_c(' ');
+    print_ul(ul % 7);
+    line();
+    print_l(l >> 3);
+    print_c(' ');
+    print_ul(ul >> 3);
+    print_c(' ');
+    print_l(l << 5);
+    print_c(' ');
+    print_ul(ul << 33);
+    line();
+    i = 40;
+    print_l(l >> i);
+    print_c(' ');
+    print_ul(ul >> i);
+    print_c(' ');
+    print_ul(1UL << i);
+    line();
+
+    // the usual arithmetic conversions
+    i = -1;
+    u = 1;
+    print_l(i < u);
+    print_c(' ');
+    print_l(i < (int) u);
+    print_c(' ');
+    print_l(i < 1L);
+    line();
+    print_ul(i + u);
+    print_c(' ');
+    print_l(i + 1L);
+    print_c(' ');
+    print_ul(i + 0UL);
+    line();
+    print_l(sizeof(long));
+    print_c(' ');
+    print_l(sizeof(short));
+    print_c(' ');
+    print_l(sizeof(struct sample));
+    line();
+    i = 100000;
+    l = i * i;
+    print_l(l);
+    print_c(' ');
+    l = (long) i * i;
+    print_l(l);
+    line();
+    u = 3000000000u;
+    ul = u * 2;
+    print_ul(ul);
+    print_c(' ');
+    ul = u * 2UL;
+    print_ul(ul);
+    line();
+    i = -1;
+    i /= 2u;
+    print_l(i);
+    line();
+    us = 65535;
+    print_l(us * us);
+    print_c(' ');
+    print_l(~us);
+    print_c(' ');
+    print_l(-us);
+    line();
+
+    // conversions
+    l = 4294967296 + 5;
+    print_l((int) l);
+    print_c(' ');
+    print_l((short) 70000);
+    print_c(' ');
+    print_l((unsigned char) 300);
+    print_c(' ');
+    print_l((signed char) 200);
+    print_c(' ');
+    print_ul((unsigned long) -1);
+    line();
+    print_l(mul_add(5000000000, -3, 65535));
+    line();
+    print_ul(crc32("123456789", 9));
+    line();
+    print_ul(fnv("hello"));
+    line();
+    print_ul(mask);
+    print_c(' ');
+    print_l(big);
+    print_c(' ');
+    print_ul(table[2]);
+    print_c(' ');
+    print_ul(table[3]);
+    line();
+
+    // longs in memory
+    smp = make(-123456789012);
+    print_c(smp.tag);
+    print_c(' ');
+    print_l(smp.port);
+    print_c(' ');
+    print_l(smp.stamp);
+    line();
+    arr[0] = 1;
+    arr[1] = 1;
+    for (i = 2; i < 3; i++) {
+        arr[i] = arr[i - 1] + arr[i - 2];
+    }
+    p = &arr[2];
+    *p = *p * 4000000000;
+    print_l(arr[2]);
+    line();
+    l = 0;
+    while (l < 3000000000) {
+        l += 1000000000;
+    }
+    if (l) {
+        print_l(l);
+        line();
+    }
+    ul = 5;
+    switch (ul) {
+        case 5UL:
+            print_c('5');
+            break;
+        default:
+            print_c('?');
+    }
+    switch (sc) {
+        case -5:
+            print_c('n');
+            break;
+        default:
+            print_c('?');
+    }
+    line();
+    l = 1;
+    l = l ? l + 1 : 0;
+    print_l(l);
+    print_c(' ');
+    print_l(l && mask);
+    print_c(' ');
+    print_l(!ul);
+    line();
+    l = 9223372036854775807;
+    l++;
+    print_l(l);
+    line();
+}
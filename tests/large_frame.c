@@ -0,0 +1,30 @@
+#include "minic-stdlib.h"
+
+// a frame larger than the immediates of a load or a store can reach: prints 49995000 9999
+
+int sum(int n) {
+  int a[10000];
+  int i;
+  int total;
+  i = 0;
+  while (i < n) {
+    a[i] = i;
+    i = i + 1;
+  }
+  total = 0;
+  i = 0;
+  while (i < n) {
+    total = total + a[i];
+    i = i + 1;
+  }
+  return total;
+}
+
+void main() {
+  int last[10000];
+  last[9999] = 9999;
+  print_i(sum(10000));
+  print_c(' ');
+  print_i(last[9999]);
+  print_c('\n');
+}
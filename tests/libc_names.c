@@ -0,0 +1,27 @@
+#include "minic-stdlib.h"
+
+// functions and globals named after those of libc are the program's own: prints 7 4 2 / 5 -1
+
+int div = 2;
+int rand;
+
+int abs(int x) { return x + 4; }
+
+int ldiv(int a, int b) { return a / b / div; }
+
+int time() { rand = rand - 1; return rand; }
+
+void main() {
+  int labs;
+  labs = 5;
+  print_i(abs(3));
+  print_c(' ');
+  print_i(ldiv(16, 2));
+  print_c(' ');
+  print_i(div);
+  print_c('\n');
+  print_i(labs);
+  print_c(' ');
+  print_i(time());
+  print_c('\n');
+}
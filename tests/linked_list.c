@@ -0,0 +1,77 @@
+#include "minic-stdlib.h"
+
+// a linked list of nodes allocated on the heap and ended by a sentinel, walked and updated through
+// pointers with ->
+
+struct node {
+    int value;
+    struct node* next;
+};
+
+struct node end;
+
+struct list {
+    struct node* head;
+    int length;
+};
+
+void push(struct list* l, int value) {
+    struct node* n = (struct node*) mcmalloc(sizeof(struct node));
+    n->value = value;
+    n->next = l->head;
+    l->head = n;
+    l->length = l->length + 1;
+}
+
+int sum(struct list* l) {
+    struct node* n;
+    int total = 0;
+    for (n = l->head; n != &end; n = n->next) {
+        total = total + n->value;
+    }
+    return total;
+}
+
+void reverse(struct list* l) {
+    struct node* previous = &end;
+    struct node* n = l->head;
+    struct node* next;
+    while (n != &end) {
+        next = n->next;
+        n->next = previous;
+        previous = n;
+        n = next;
+    }
+    l->head = previous;
+}
+
+void show(struct list* l) {
+    struct node* n = l->head;
+    while (n != &end) {
+        print_i(n->value);
+        if (n->next != &end) {
+            print_c(' ');
+        }
+        n = n->next;
+    }
+    print_c('\n');
+}
+
+void main() {
+    struct list l = {&end, 0};
+    struct list* p = &l;
+    int i;
+    for (i = 1; i <= 5; i++) {
+        push(p, i * i);
+    }
+    show(p);
+    print_i(p->length);
+    print_c(' ');
+    print_i(sum(p));
+    print_c('\n');
+    reverse(p);
+    p->head->next->value = 0;
+    show(&l);
+    print_i(l.head->next->next->value);
+    print_c('\n');
+}
@@ -0,0 +1,88 @@
+// Generates LLVM IR for the samples of this directory which have an expected output. When `llvm-as`
+// is installed the IR must assemble; the program is then linked with runtime/minic_stdlib.c by
+// `clang`, or by `llc` and `cc` without it, and run. A program which does not compile is expected to
+// print its errors. A tool which is missing is reported on the standard error.
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use common::{expectation, has_tool, run, sample_name, skip, sources, COMPILER};
+
+enum Linker {
+    Clang,
+    LlcAndCc,
+}
+
+// links the IR with the runtime, returning the errors of the tools on failure
+fn link(linker: &Linker, ir: &Path, runtime: &Path, out_dir: &Path) -> Result<PathBuf, String> {
+    let exe = out_dir.join("program");
+    let object = out_dir.join("program.o");
+    let steps = match linker {
+        Linker::Clang => vec![run(Command::new("clang").arg("-w").arg("-o").arg(&exe).arg(ir).arg(runtime), &[])],
+        Linker::LlcAndCc => vec![
+            run(Command::new("llc").args(["-filetype=obj", "-relocation-model=pic", "-o"]).arg(&object).arg(ir), &[]),
+            run(Command::new("cc").arg("-o").arg(&exe).arg(&object).arg(runtime), &[]),
+        ],
+    };
+    match steps.iter().find(|output| !output.status.success()) {
+        Some(output) => Err(String::from_utf8_lossy(&output.stderr).into_owned()),
+        None => Ok(exe),
+    }
+}
+
+#[test]
+fn samples() {
+    let runtime = Path::new(env!("CARGO_MANIFEST_DIR")).join("runtime").join("minic_stdlib.c");
+    if !has_tool("llvm-as") {
+        skip("llvm::samples", "llvm-as", "assembling and running the IR");
+        return;
+    }
+    let out_dir = std::env::temp_dir().join(format!("minic-llvm-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let linker = if has_tool("clang") {
+        Some(Linker::Clang)
+    } else if has_tool("llc") && has_tool("cc") {
+        Some(Linker::LlcAndCc)
+    } else {
+        skip("llvm::samples", "clang or llc with cc", "linking and running the IR");
+        None
+    };
+
+    let mut failures = Vec::new();
+    for source in sources().iter() {
+        let (expected, input) = expectation(source);
+        let name = sample_name(source);
+        let ir = out_dir.join("program.ll");
+        fs::remove_file(&ir).ok();
+
+        let compiled = run(Command::new(COMPILER).arg("-llvm").arg(source).arg(&ir), &[]);
+        if !compiled.status.success() {
+            let output = String::from_utf8_lossy(&compiled.stdout);
+            if output != expected {
+                failures.push(format!("{} does not compile:\n{}", name, output));
+            }
+            continue;
+        }
+        let assembled = run(Command::new("llvm-as").arg("-o").arg(out_dir.join("program.bc")).arg(&ir), &[]);
+        if !assembled.status.success() {
+            failures.push(format!("{} does not assemble:\n{}", name, String::from_utf8_lossy(&assembled.stderr)));
+            continue;
+        }
+        let Some(linker) = linker.as_ref() else {
+            continue;
+        };
+        match link(linker, &ir, &runtime, &out_dir) {
+            Ok(exe) => {
+                let output = run(&mut Command::new(&exe), &input);
+                let output = String::from_utf8_lossy(&output.stdout);
+                if output != expected {
+                    failures.push(format!("{} linked with the runtime:\n{}", name, output));
+                }
+            },
+            Err(errors) => failures.push(format!("{} does not link:\n{}", name, errors)),
+        }
+    }
+    fs::remove_dir_all(&out_dir).ok();
+    assert!(failures.is_empty(), "unexpected LLVM IR of\n{}", failures.join("\n"));
+}
@@ -0,0 +1,133 @@
+// Talks to the language server over its standard input and output: the messages of a session are
+// written in one go, and the responses and notifications read back in the order they were sent.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+const URI: &str = "file:///test.c";
+
+fn frame(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+// the json of a string, escaping what a source can hold
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn did_open(text: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"minic","version":1,"text":{}}}}}}}"#, URI, quote(text))
+}
+
+fn did_change(text: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didChange","params":{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"text":{}}}]}}}}"#, URI, quote(text))
+}
+
+fn request(id: i32, method: &str, line: i32, character: i32) -> String {
+    format!(r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}}}"#, id, method, URI, line, character)
+}
+
+// runs a session, ending it with shutdown and exit, and returns the bodies of the messages of the
+// server before the response to shutdown
+fn session(messages: &[String]) -> Vec<String> {
+    let mut input: String = messages.iter().map(|message| frame(message)).collect();
+    input.push_str(&frame(r#"{"jsonrpc":"2.0","id":999,"method":"shutdown"}"#));
+    input.push_str(&frame(r#"{"jsonrpc":"2.0","method":"exit"}"#));
+
+    let mut child = Command::new(COMPILER).arg("lsp")
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "the server did not exit cleanly");
+    assert!(output.stderr.is_empty(), "the server wrote to stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let mut bodies = Vec::new();
+    let mut rest = &output.stdout[..];
+    while !rest.is_empty() {
+        let header_end = rest.windows(4).position(|w| w == b"\r\n\r\n").expect("a header");
+        let header = std::str::from_utf8(&rest[..header_end]).unwrap();
+        let length: usize = header.strip_prefix("Content-Length: ").expect("a content length").parse().unwrap();
+        let body = &rest[header_end + 4..header_end + 4 + length];
+        bodies.push(String::from_utf8(body.to_vec()).unwrap());
+        rest = &rest[header_end + 4 + length..];
+    }
+    assert_eq!(bodies.pop().as_deref(), Some(r#"{"jsonrpc":"2.0","id":999,"result":null}"#));
+    bodies
+}
+
+fn range(line: i32, start: i32, end: i32) -> String {
+    format!(r#""range":{{"start":{{"line":{},"character":{}}},"end":{{"line":{},"character":{}}}}}"#, line, start, line, end)
+}
+
+#[test]
+fn non_ascii_positions_count_utf16_code_units() {
+    // é takes two bytes and one utf-16 code unit, 𝄞 four bytes and two code units
+    let text = "/* éé */ int g;\nvoid main() { /* é𝄞 */ g = 1; x = 2; }\n";
+    let bodies = session(&[
+        did_open(text),
+        request(1, "textDocument/definition", 1, 24),
+        request(2, "textDocument/hover", 1, 24),
+        request(3, "textDocument/hover", 1, 23),
+    ]);
+    assert_eq!(bodies.len(), 4);
+    assert!(bodies[0].contains(&format!(r#"{{{},"severity":1,"source":"minic","message":"variable x is not declared"}}"#, range(1, 31, 32))), "{}", bodies[0]);
+    assert_eq!(bodies[1], format!(r#"{{"jsonrpc":"2.0","id":1,"result":{{"uri":"{}",{}}}}}"#, URI, range(0, 13, 14)));
+    assert!(bodies[2].contains(&range(1, 24, 25)), "{}", bodies[2]);
+    assert_eq!(bodies[3], r#"{"jsonrpc":"2.0","id":3,"result":null}"#);
+}
+
+#[test]
+fn session_answers_requests() {
+    let text = "struct point { int x; int y; };\nint sum(struct point p) {\n  return p.x + p.y;\n}\nvoid main() {\n  struct point q;\n  q.x = 1;\n}\n";
+    let edited = text.replace("  q.x = 1;\n", "  q.x = 1;\n  q.\n");
+    let bodies = session(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#.to_owned(),
+        r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#.to_owned(),
+        did_open(text),
+        request(2, "textDocument/definition", 6, 2),
+        request(3, "textDocument/hover", 2, 9),
+        format!(r#"{{"jsonrpc":"2.0","id":4,"method":"textDocument/documentSymbol","params":{{"textDocument":{{"uri":"{}"}}}}}}"#, URI),
+        did_change(&edited),
+        request(5, "textDocument/completion", 7, 4),
+        r#"{"jsonrpc":"2.0","id":6,"method":"unknown/method","params":{}}"#.to_owned(),
+    ]);
+    assert_eq!(bodies.len(), 8);
+
+    assert!(bodies[0].starts_with(r#"{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"#), "{}", bodies[0]);
+    for capability in [r#""definitionProvider":true"#, r#""hoverProvider":true"#, r#""documentSymbolProvider":true"#, r#""triggerCharacters":[".",">"]"#] {
+        assert!(bodies[0].contains(capability), "{}", bodies[0]);
+    }
+
+    assert_eq!(bodies[1], format!(r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"{}","diagnostics":[]}}}}"#, URI));
+
+    // q in q.x = 1 is declared by struct point q
+    assert_eq!(bodies[2], format!(r#"{{"jsonrpc":"2.0","id":2,"result":{{"uri":"{}",{}}}}}"#, URI, range(5, 15, 16)));
+
+    // p in p.x is the parameter
+    assert_eq!(bodies[3], format!(r#"{{"jsonrpc":"2.0","id":3,"result":{{"contents":{{"kind":"markdown","value":"```c\nstruct point p\n```"}},{}}}}}"#, range(2, 9, 10)));
+
+    let symbols = &bodies[4];
+    assert!(symbols.starts_with(r#"{"jsonrpc":"2.0","id":4,"result":[{"name":"point","detail":"struct point","kind":23,"#), "{}", symbols);
+    assert!(symbols.contains(&format!(r#"{{"name":"x","detail":"int","kind":8,{}"#, range(0, 19, 20))), "{}", symbols);
+    assert!(symbols.contains(&format!(r#"{{"name":"sum","detail":"int sum(struct point p)","kind":12,{}"#, range(1, 4, 7))), "{}", symbols);
+    assert!(symbols.contains(r#"{"name":"main","detail":"void main()","kind":12,"#), "{}", symbols);
+
+    // the edited document does not parse, its error is published and the fields still complete
+    assert!(bodies[5].contains(r#""message":"expected (IDENTIFIER) found (RBRA)""#), "{}", bodies[5]);
+    assert_eq!(bodies[6], r#"{"jsonrpc":"2.0","id":5,"result":[{"label":"x","kind":5,"detail":"int"},{"label":"y","kind":5,"detail":"int"}]}"#);
+
+    assert_eq!(bodies[7], r#"{"jsonrpc":"2.0","id":6,"error":{"code":-32601,"message":"unknown method unknown/method"}}"#);
+}
@@ -0,0 +1,115 @@
+// Generates MIPS assembly for the samples of this directory which have an expected output. The
+// assembly is run on the interpreter of tests/common/mips.rs, checked by the MIPS assembler of LLVM
+// when `llvm-mc` is installed, and run on MARS too when `MARS_JAR` names its jar; a program which
+// does not compile is expected to print its errors.
+mod common;
+
+use std::fs;
+use std::process::Command;
+use common::{expectation, has_tool, mips, run, sample_name, skip, sources, tests_dir, COMPILER};
+
+// the lines of a function, from its label to its exit label
+fn body<'a>(assembly: &'a str, function: &str) -> Vec<&'a str> {
+    assembly.lines()
+        .skip_while(|line| *line != format!("f_{}:", function))
+        .take_while(|line| *line != format!("f_{}_exit:", function))
+        .collect()
+}
+
+#[test]
+fn samples() {
+    let out_dir = std::env::temp_dir().join(format!("minic-mips-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let with_assembler = has_tool("llvm-mc");
+    if !with_assembler {
+        skip("mips::samples", "llvm-mc", "assembling the samples");
+    }
+    let mars = std::env::var_os("MARS_JAR");
+
+    let sources = sources();
+    assert!(sources.iter().any(|source| source.ends_with("array_complex_reduction_8.c")));
+
+    let mut failures = Vec::new();
+    for source in sources.iter() {
+        let (expected, input) = expectation(source);
+        let name = sample_name(source);
+        let assembly = out_dir.join("program.s");
+        fs::remove_file(&assembly).ok();
+
+        let compiled = run(Command::new(COMPILER).arg("-gen").arg(source).arg(&assembly), &[]);
+        if !compiled.status.success() {
+            let output = String::from_utf8_lossy(&compiled.stdout);
+            if output != expected {
+                failures.push(format!("{} does not compile:\n{}", name, output));
+            }
+            continue;
+        }
+        let text = fs::read_to_string(&assembly).unwrap();
+        if !text.lines().any(|line| line == "f_main:") {
+            failures.push(format!("{} has no main function:\n{}", name, text));
+            continue;
+        }
+        if with_assembler {
+            let object = out_dir.join("program.o");
+            let assembled = run(Command::new("llvm-mc").args(["-triple=mipsel", "-mcpu=mips32r2", "-filetype=obj", "-o"]).arg(&object).arg(&assembly), &[]);
+            if !assembled.status.success() {
+                failures.push(format!("{} does not assemble:\n{}", name, String::from_utf8_lossy(&assembled.stderr)));
+                continue;
+            }
+        }
+        match mips::run(&text, &input) {
+            Ok(output) if output == expected => {},
+            Ok(output) => failures.push(format!("{} on the interpreter:\n{}", name, output)),
+            Err(error) => failures.push(format!("{} fails on the interpreter: {}", name, error)),
+        }
+        if let Some(mars) = mars.as_ref() {
+            let output = run(Command::new("java").arg("-jar").arg(mars).arg("nc").arg(&assembly), &input);
+            let output = String::from_utf8_lossy(&output.stdout);
+            if output != expected {
+                failures.push(format!("{} on MARS:\n{}", name, output));
+            }
+        }
+    }
+    fs::remove_dir_all(&out_dir).ok();
+    assert!(failures.is_empty(), "unexpected MIPS of\n{}", failures.join("\n"));
+}
+
+// more values are live at once than there are registers, some of them are reloaded from the frame
+#[test]
+fn spills() {
+    let source = tests_dir().join("register_pressure.c");
+    let assembly = std::env::temp_dir().join(format!("minic-spills-{}.s", std::process::id()));
+    assert!(run(Command::new(COMPILER).arg("-gen").arg(&source).arg(&assembly), &[]).status.success());
+    let text = fs::read_to_string(&assembly).unwrap();
+    fs::remove_file(&assembly).ok();
+
+    let body = body(&text, "pressure");
+    assert!(!body.is_empty(), "{}", text);
+    assert!(body.iter().any(|line| line.trim_start().starts_with("lw ") && line.ends_with("($sp)")), "{}", text);
+}
+
+// the frames of tests/large_frame.c are larger than a 16-bit immediate, their offsets are added
+// through a register
+#[test]
+fn large_frame() {
+    let source = tests_dir().join("large_frame.c");
+    let assembly = std::env::temp_dir().join(format!("minic-large-frame-{}.s", std::process::id()));
+    assert!(run(Command::new(COMPILER).arg("-gen").arg(&source).arg(&assembly), &[]).status.success());
+    let text = fs::read_to_string(&assembly).unwrap();
+    fs::remove_file(&assembly).ok();
+
+    for line in text.lines() {
+        let Some((op, operands)) = line.trim_start().split_once(' ') else {
+            continue;
+        };
+        let immediate = match op {
+            "addiu" => operands.rsplit(", ").next(),
+            "lw" | "sw" | "lb" | "lbu" | "sb" | "lh" | "lhu" | "sh" => operands.split(", ").nth(1).and_then(|address| address.split('(').next()),
+            _ => None,
+        };
+        if let Some(immediate) = immediate.and_then(|i| i.parse::<i32>().ok()) {
+            assert!((-32768..32768).contains(&immediate), "{}", line);
+        }
+    }
+    assert!(body(&text, "sum").iter().any(|line| line.trim_start().starts_with("lui ")), "{}", text);
+}
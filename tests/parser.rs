@@ -0,0 +1,53 @@
+// Checks the precedence and associativity of the operators in the AST printed by `-ast`.
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+fn ast(path: &Path) -> String {
+    let output = Command::new(COMPILER).arg("-ast").arg(path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn precedence_and_associativity() {
+    let source = "void main() {
+  int a; int b; int c; int d[3];
+  a = 1 - 2 - 3;
+  a = b + c * d[1];
+  a = b * c + d[0] * d[1];
+  a = (b + c) * d[2];
+  a = b < c == c > b;
+  a = b || c && b;
+  a = -b * *d;
+}
+";
+    let path = std::env::temp_dir().join(format!("minic-parser-{}.c", std::process::id()));
+    fs::write(&path, source).unwrap();
+    let ast = ast(&path);
+    fs::remove_file(&path).ok();
+
+    for expected in [
+        "BinOp(BinOp(IntLiteral(1),SUB,IntLiteral(2)),SUB,IntLiteral(3))",
+        "BinOp(VarExpr(b),ADD,BinOp(VarExpr(c),MUL,ArrayAccessExpr(VarExpr(d),IntLiteral(1))))",
+        "BinOp(BinOp(VarExpr(b),MUL,VarExpr(c)),ADD,BinOp(ArrayAccessExpr(VarExpr(d),IntLiteral(0)),MUL,ArrayAccessExpr(VarExpr(d),IntLiteral(1))))",
+        "BinOp(BinOp(VarExpr(b),ADD,VarExpr(c)),MUL,ArrayAccessExpr(VarExpr(d),IntLiteral(2)))",
+        "BinOp(BinOp(VarExpr(b),LT,VarExpr(c)),EQ,BinOp(VarExpr(c),GT,VarExpr(b)))",
+        "BinOp(VarExpr(b),OR,BinOp(VarExpr(c),AND,VarExpr(b)))",
+        "BinOp(BinOp(IntLiteral(0),SUB,VarExpr(b)),MUL,ValueAtExpr(VarExpr(d)))",
+    ] {
+        assert!(ast.contains(expected), "{} not in\n{}", expected, ast);
+    }
+}
+
+// the array accesses of t + b[..] * b[..] + u - v bind tighter than the product, which binds tighter
+// than the sums, evaluated from the left
+#[test]
+fn sample() {
+    let ast = ast(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("array_complex_reduction_8.c"));
+    let index = |op: &str| format!("BinOp(BinOp(BinOp(VarExpr(q),SUB,IntLiteral(158)),DIV,IntLiteral(3)),{},IntLiteral(15))", op);
+    let expected = format!("BinOp(BinOp(BinOp(VarExpr(t),ADD,BinOp(ArrayAccessExpr(VarExpr(b),{}),MUL,ArrayAccessExpr(VarExpr(b),{}))),ADD,VarExpr(u)),SUB,VarExpr(v))", index("DIV"), index("MOD"));
+    assert!(ast.contains(&expected), "{} not in\n{}", expected, ast);
+}
@@ -0,0 +1,90 @@
+#include "minic-stdlib.h"
+
+// pointers to pointers, arrays of arrays stored row after row, and array parameters which are
+// pointers to their first element
+
+struct node {
+    int value;
+    struct node* next;
+};
+
+void swap(int** a, int** b) {
+    int* t = *a;
+    *a = *b;
+    *b = t;
+}
+
+int trace(int m[][3], int n) {
+    int i;
+    int s = 0;
+    for (i = 0; i < n; i++) {
+        s = s + m[i][i];
+    }
+    return s;
+}
+
+int sum(int v[], int n) {
+    int i;
+    int s = 0;
+    for (i = 0; i < n; i++) {
+        s = s + v[i];
+    }
+    return s;
+}
+
+void main() {
+    int x = 1;
+    int y = 2;
+    int* px = &x;
+    int* py = &y;
+    int** ppx = &px;
+    int*** pppx = &ppx;
+    char grid[3][3];
+    int m[3][3];
+    int cube[2][2][2];
+    struct node n1;
+    struct node n2;
+    struct node* pn = &n1;
+    struct node** ppn = &pn;
+    int i;
+    int j;
+    int* flat;
+
+    swap(&px, &py);
+    print_i(*px);
+    print_i(*py);
+    ***pppx = 7;
+    print_i(y);
+    print_c(' ');
+
+    for (i = 0; i < 3; i++) {
+        for (j = 0; j < 3; j++) {
+            grid[i][j] = '.';
+            m[i][j] = i * 3 + j;
+        }
+    }
+    grid[1][1] = 'x';
+    for (i = 0; i < 3; i++) {
+        print_c(grid[1][i]);
+    }
+    print_c(' ');
+    print_i(trace(m, 3));
+    print_c(' ');
+    // the rows follow each other in memory
+    flat = (int*) m[0];
+    print_i(flat[5]);
+    print_c(' ');
+    print_i(sum(m[2], 3));
+    print_c(' ');
+    cube[1][0][1] = 5;
+    print_i(cube[1][0][1]);
+    print_i(sizeof(int[2][2][2]) / sizeof(int[2][2]));
+    print_c(' ');
+
+    n1.value = 1;
+    n1.next = &n2;
+    n2.value = 2;
+    (**ppn).next->value = 20;
+    print_i(n2.value);
+    print_c('\n');
+}
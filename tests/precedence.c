@@ -0,0 +1,78 @@
+#include "minic-stdlib.h"
+
+// the parentheses the source has and the ones precedence requires, which -unparse keeps
+
+struct point { int x; int y; };
+
+void show(int v) {
+    print_i(v);
+    print_c(' ');
+}
+
+void main() {
+    int a = 7;
+    int b = 3;
+    int c = 2;
+    int n[3] = {4, 5, 6};
+    int* p = (int*) n;
+    struct point s;
+    struct point* q = &s;
+
+    // grouped where precedence does not need it, and where it does
+    show(a - (b - c));
+    show((a - b) - c);
+    show(a - b - c);
+    show((a * b) + c);
+    show(a * (b + c));
+    show(((a)));
+    show(a / b * c);
+    show(a / (b * c));
+    show(a % (b + c) << 1);
+    show((a << b) >> c);
+    show(a << (b >> 1));
+    print_c('\n');
+
+    // prefix operators, signs which must not read back as -- or ++
+    show(-a);
+    show(- -a);
+    show(-(-a));
+    show(-(a + b));
+    show(+ +b);
+    show(!(a < b));
+    show(!!c);
+    show(~(a & b));
+    show(~-c);
+    show(0 - -c);
+    show((-1) ^ a);
+    print_c('\n');
+
+    // comparisons, bitwise and logical operators of different precedence
+    show(a < b == (c < b));
+    show((a < b) < c);
+    show((a & b) == 3);
+    show(a & (b == 3));
+    show(a | b ^ c & 1);
+    show((a | b) ^ (c & 1));
+    show(a && b || 0 && c);
+    show(a && (b || 0) && c);
+    print_c('\n');
+
+    // conditionals, assignments and postfix expressions
+    show(a > b ? a : b > c ? b : c);
+    show((a > b ? 0 : 1) ? 10 : 20);
+    show(a > b ? (c = 9) : (c = 8));
+    show(c);
+    a = b = c = 1;
+    show(a + b + c);
+    s.x = 4;
+    q->y = 5;
+    show((*q).x + q->y);
+    show(*p + p[2] * 2);
+    show((*p)++);
+    show(*p);
+    show(++*p);
+    show(n[1]);
+    show(-n[0] * (int) (char) 300);
+    show((int) sizeof(int[3]) + sizeof(struct point));
+    print_c('\n');
+}
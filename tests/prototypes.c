@@ -0,0 +1,56 @@
+#include "minic-stdlib.h"
+
+// declarations in any order, and prototypes declaring a function before its definition
+
+int is_even(int);
+int is_odd(int n);
+
+struct pair {
+    int a;
+    int b;
+};
+
+int sum(struct pair p) {
+    return p.a + p.b;
+}
+
+// a global and a struct declared after a function
+int calls;
+
+struct triple {
+    int a;
+    int b;
+    int c;
+};
+
+void report(int n) {
+    print_i(n);
+    print_c(is_even(n) ? 'e' : 'o');
+    print_c(' ');
+    calls++;
+}
+
+int is_even(int n) {
+    if (n == 0) {
+        return 1;
+    }
+    return is_odd(n - 1);
+}
+
+int is_odd(int n) {
+    if (n == 0) {
+        return 0;
+    }
+    return is_even(n - 1);
+}
+
+void main() {
+    struct pair p = {3, 4};
+    struct triple t = {1, 2, 3};
+    report(4);
+    report(7);
+    report(sum(p));
+    report(t.a + t.b + t.c);
+    print_i(calls);
+    print_c('\n');
+}
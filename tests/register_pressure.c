@@ -0,0 +1,115 @@
+#include "minic-stdlib.h"
+
+// every value is live until the sum at the end, more than there are registers to hold them
+int pressure(int n) {
+  int v0;
+  int v1;
+  int v2;
+  int v3;
+  int v4;
+  int v5;
+  int v6;
+  int v7;
+  int v8;
+  int v9;
+  int v10;
+  int v11;
+  int v12;
+  int v13;
+  int v14;
+  int v15;
+  int v16;
+  int v17;
+  int v18;
+  int v19;
+  int v20;
+  int v21;
+  int v22;
+  int v23;
+  int v24;
+  int v25;
+  int v26;
+  int v27;
+  int v28;
+  int v29;
+  int v30;
+  int v31;
+  int total;
+
+  v0 = n * 1 + 0;
+  v1 = n * 2 + 1;
+  v2 = n * 3 + 2;
+  v3 = n * 4 + 3;
+  v4 = n * 5 + 4;
+  v5 = n * 6 + 5;
+  v6 = n * 7 + 6;
+  v7 = n * 8 + 7;
+  v8 = n * 9 + 8;
+  v9 = n * 10 + 9;
+  v10 = n * 11 + 10;
+  v11 = n * 12 + 11;
+  v12 = n * 13 + 12;
+  v13 = n * 14 + 13;
+  v14 = n * 15 + 14;
+  v15 = n * 16 + 15;
+  v16 = n * 17 + 16;
+  v17 = n * 18 + 17;
+  v18 = n * 19 + 18;
+  v19 = n * 20 + 19;
+  v20 = n * 21 + 20;
+  v21 = n * 22 + 21;
+  v22 = n * 23 + 22;
+  v23 = n * 24 + 23;
+  v24 = n * 25 + 24;
+  v25 = n * 26 + 25;
+  v26 = n * 27 + 26;
+  v27 = n * 28 + 27;
+  v28 = n * 29 + 28;
+  v29 = n * 30 + 29;
+  v30 = n * 31 + 30;
+  v31 = n * 32 + 31;
+  total = 0;
+  total = total + v31 * 32;
+  total = total + v30 * 31;
+  total = total + v29 * 30;
+  total = total + v28 * 29;
+  total = total + v27 * 28;
+  total = total + v26 * 27;
+  total = total + v25 * 26;
+  total = total + v24 * 25;
+  total = total + v23 * 24;
+  total = total + v22 * 23;
+  total = total + v21 * 22;
+  total = total + v20 * 21;
+  total = total + v19 * 20;
+  total = total + v18 * 19;
+  total = total + v17 * 18;
+  total = total + v16 * 17;
+  total = total + v15 * 16;
+  total = total + v14 * 15;
+  total = total + v13 * 14;
+  total = total + v12 * 13;
+  total = total + v11 * 12;
+  total = total + v10 * 11;
+  total = total + v9 * 10;
+  total = total + v8 * 9;
+  total = total + v7 * 8;
+  total = total + v6 * 7;
+  total = total + v5 * 6;
+  total = total + v4 * 5;
+  total = total + v3 * 4;
+  total = total + v2 * 3;
+  total = total + v1 * 2;
+  total = total + v0 * 1;
+  return total;
+}
+
+void main() {
+  int n;
+  n = 0;
+  while (n < 4) {
+    print_i(pressure(n));
+    print_c('\n');
+    n = n + 1;
+  }
+}
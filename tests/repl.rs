@@ -0,0 +1,67 @@
+// Feeds a script to the REPL on its standard input and compares what it prints, prompts included.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+fn repl(script: &str) -> String {
+    let mut child = Command::new(COMPILER).arg("repl")
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "the REPL did not exit cleanly");
+    assert!(output.stderr.is_empty(), "the REPL wrote to stderr: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn session() {
+    let script = "int g;
+int add(int a,
+  int b) {
+  return a + b;
+}
+g = add(2, 3);
+g + 1
+:type g * 2
+:ast g + 1
+int q; q = 10 / (g - 5);
+g
+:reset
+g
+";
+    // the function spans four lines, read until its braces and parentheses are closed; the division
+    // by zero stops the entry after q is declared, and g keeps its value
+    let expected = "minic REPL, :help for the commands
+minic> declared g : int
+minic>    ...>    ...>    ...> defined add
+minic> minic> BinOp(VarExpr(g),ADD,IntLiteral(1))
+: int = 6
+minic> int
+minic> ExprStmt(BinOp(VarExpr(g),ADD,IntLiteral(1)))
+minic> declared q : int
+
+Runtime error: division by zero
+minic> VarExpr(g)
+: int = 5
+minic> minic> Semantic error: variable g is not declared
+minic> 
+";
+    assert_eq!(repl(script), expected);
+}
+
+#[test]
+fn quit_and_errors() {
+    // an entry which does not type check declares nothing, not even its valid declarations
+    let script = "int x; y = 1;\nint x;\nx = 3;\n:type x; x\n:nope\nx * 2\n:quit\nx\n";
+    let expected = "minic REPL, :help for the commands
+minic> Semantic error: variable y is not declared
+minic> declared x : int
+minic> minic> :type expects a single expression
+minic> unknown command :nope, :help for the list
+minic> BinOp(VarExpr(x),MUL,IntLiteral(2))
+: int = 6
+minic> ";
+    assert_eq!(repl(script), expected);
+}
@@ -0,0 +1,109 @@
+// Generates RV32IM assembly for the samples of this directory which have an expected output. The
+// assembly is run on the interpreter of tests/common/riscv.rs, checked by a riscv32 GNU `as`, or the
+// RISC-V assembler of LLVM, when one is installed, and run on RARS too when `RARS_JAR` names its jar;
+// a program which does not compile is expected to print its errors. The assembly of tests/fibonacci.c
+// is compared to tests/snapshots/, which `UPDATE_SNAPSHOTS=1` rewrites.
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use common::{expectation, has_tool, riscv, run, sample_name, skip, sources, tests_dir, COMPILER};
+
+// the assemblers tried in order, with the flags selecting RV32IM
+const ASSEMBLERS: [(&str, &[&str]); 4] = [
+    ("riscv32-unknown-elf-as", &["-march=rv32im", "-mabi=ilp32"]),
+    ("riscv64-unknown-elf-as", &["-march=rv32im", "-mabi=ilp32"]),
+    ("riscv64-linux-gnu-as", &["-march=rv32im", "-mabi=ilp32"]),
+    ("llvm-mc", &["-triple=riscv32", "-mattr=+m", "-filetype=obj"]),
+];
+
+fn riscv(source: &Path, assembly: &Path) -> Output {
+    run(Command::new(COMPILER).arg("-riscv").arg(source).arg(assembly), &[])
+}
+
+#[test]
+fn samples() {
+    let out_dir = std::env::temp_dir().join(format!("minic-riscv-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let assembler = ASSEMBLERS.iter().find(|(tool, _)| has_tool(tool));
+    if assembler.is_none() {
+        skip("riscv::samples", "a RISC-V assembler", "assembling the samples");
+    }
+    let rars = std::env::var_os("RARS_JAR");
+
+    let mut failures = Vec::new();
+    for source in sources().iter() {
+        let (expected, input) = expectation(source);
+        let name = sample_name(source);
+        let assembly = out_dir.join("program.s");
+        fs::remove_file(&assembly).ok();
+
+        let compiled = riscv(source, &assembly);
+        if !compiled.status.success() {
+            let output = String::from_utf8_lossy(&compiled.stdout);
+            if output != expected {
+                failures.push(format!("{} does not compile:\n{}", name, output));
+            }
+            continue;
+        }
+        let text = fs::read_to_string(&assembly).unwrap();
+        if !text.lines().any(|line| line == "f_main:") {
+            failures.push(format!("{} has no main function:\n{}", name, text));
+            continue;
+        }
+        if let Some((tool, flags)) = assembler {
+            let assembled = run(Command::new(tool).args(*flags).arg("-o").arg(out_dir.join("program.o")).arg(&assembly), &[]);
+            if !assembled.status.success() {
+                failures.push(format!("{} does not assemble with {}:\n{}", name, tool, String::from_utf8_lossy(&assembled.stderr)));
+                continue;
+            }
+        }
+        match riscv::run(&text, &input) {
+            Ok(output) if output == expected => {},
+            Ok(output) => failures.push(format!("{} on the interpreter:\n{}", name, output)),
+            Err(error) => failures.push(format!("{} fails on the interpreter: {}", name, error)),
+        }
+        if let Some(rars) = rars.as_ref() {
+            let output = run(Command::new("java").arg("-jar").arg(rars).arg("nc").arg(&assembly), &input);
+            let output = String::from_utf8_lossy(&output.stdout);
+            if output != expected {
+                failures.push(format!("{} on RARS:\n{}", name, output));
+            }
+        }
+    }
+    fs::remove_dir_all(&out_dir).ok();
+    assert!(failures.is_empty(), "unexpected RISC-V of\n{}", failures.join("\n"));
+}
+
+#[test]
+fn snapshot() {
+    let dir = tests_dir();
+    let snapshot = dir.join("snapshots").join("fibonacci.riscv.s");
+    let assembly = std::env::temp_dir().join(format!("minic-riscv-snapshot-{}.s", std::process::id()));
+    assert!(riscv(&dir.join("fibonacci.c"), &assembly).status.success());
+    let text = fs::read_to_string(&assembly).unwrap();
+    fs::remove_file(&assembly).ok();
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&snapshot, &text).unwrap();
+    }
+    assert_eq!(text, fs::read_to_string(&snapshot).unwrap());
+}
+
+// more values are live at once than there are registers, some of them are reloaded from the frame
+#[test]
+fn spills() {
+    let source = tests_dir().join("register_pressure.c");
+    let assembly = std::env::temp_dir().join(format!("minic-riscv-spills-{}.s", std::process::id()));
+    assert!(riscv(&source, &assembly).status.success());
+    let text = fs::read_to_string(&assembly).unwrap();
+    fs::remove_file(&assembly).ok();
+
+    let body: Vec<&str> = text.lines()
+        .skip_while(|line| *line != "f_pressure:")
+        .take_while(|line| *line != "f_pressure_exit:")
+        .collect();
+    assert!(!body.is_empty(), "{}", text);
+    assert!(body.iter().any(|line| line.trim_start().starts_with("lw ") && line.ends_with("(sp)")), "{}", text);
+}
@@ -0,0 +1,18 @@
+#include "minic-stdlib.h"
+
+// functions and globals named after those the runtime calls are the program's own: prints 2 7 / 3
+
+int stdout = 3;
+
+int putchar(int c) { return c + 5; }
+
+int abs(int x) { return x; }
+
+void main() {
+  print_i(abs(2));
+  print_c(' ');
+  print_i(putchar(abs(2)));
+  print_c('\n');
+  print_i(stdout);
+  print_c('\n');
+}
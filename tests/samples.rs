@@ -0,0 +1,68 @@
+// Runs the sample programs of this directory which have an expected output `<name>.out`, reading
+// `<name>.in` when there is one. A program is run on the VM and, when a C compiler is found, through
+// the C emitter, with and without -O; a program which does not compile is expected to print its
+// errors instead.
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{expectation, has_tool, run, sample_name, skip, sources, tests_dir, COMPILER};
+
+// the output of the program on the VM, or the errors of the compiler
+fn run_vm(source: &Path, flags: &[&str], out_dir: &Path, input: &[u8]) -> (bool, String) {
+    let module = out_dir.join("module.mcbc");
+    let compiled = run(Command::new(COMPILER).arg("-bc").args(flags).arg(source).arg(&module), &[]);
+    if !compiled.status.success() {
+        return (false, String::from_utf8_lossy(&compiled.stdout).into_owned());
+    }
+    let output = run(Command::new(COMPILER).arg("-vm").arg(&module), input);
+    (true, String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn run_c(source: &Path, flags: &[&str], out_dir: &Path, input: &[u8]) -> String {
+    let c_file = out_dir.join("program.c");
+    let exe = out_dir.join("program");
+    assert!(run(Command::new(COMPILER).arg("-emit-c").args(flags).arg(source).arg(&c_file), &[]).status.success());
+    assert!(run(Command::new("cc").arg("-w").arg("-o").arg(&exe).arg(&c_file), &[]).status.success(), "cc failed on {}", c_file.display());
+    String::from_utf8_lossy(&run(&mut Command::new(&exe), input).stdout).into_owned()
+}
+
+#[test]
+fn samples() {
+    let out_dir = std::env::temp_dir().join(format!("minic-samples-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let with_c = has_tool("cc");
+    if !with_c {
+        skip("samples::samples", "cc", "running the emitted C");
+    }
+
+    let mut failures = Vec::new();
+    for source in sources().iter() {
+        let (expected, input) = expectation(source);
+        let name = sample_name(source);
+
+        for flags in [&[][..], &["-O"][..]] {
+            let (compiled, output) = run_vm(source, flags, &out_dir, &input);
+            if output != expected {
+                failures.push(format!("{} {:?} on the VM:\n{}", name, flags, output));
+            }
+            if compiled && with_c {
+                let output = run_c(source, flags, &out_dir, &input);
+                if output != expected {
+                    failures.push(format!("{} {:?} through C:\n{}", name, flags, output));
+                }
+            }
+        }
+    }
+    fs::remove_dir_all(&out_dir).ok();
+    assert!(failures.is_empty(), "unexpected output of\n{}", failures.join("\n"));
+}
+
+#[test]
+fn inlining() {
+    let source = tests_dir().join("inlining.c");
+    let output = run(Command::new(COMPILER).arg("-inline").arg(&source), &[]);
+    let output = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.lines().last(), Some("Inlining: 12 call(s) inlined"));
+}
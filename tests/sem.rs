@@ -0,0 +1,127 @@
+// Checks that `-sem` types the operators by their precedence: the postfix operators bind tighter than
+// the prefix ones, which bind tighter than the binary ones.
+use std::fs;
+use std::process::{Command, Output};
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+fn sem(name: &str, source: &str) -> Output {
+    let path = std::env::temp_dir().join(format!("minic-sem-{}-{}.c", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(COMPILER).arg("-sem").arg(&path).output().unwrap();
+    fs::remove_file(&path).ok();
+    output
+}
+
+#[test]
+fn precedence() {
+    let output = sem("precedence", "struct s { int* p; int v; };
+void main() {
+  struct s x; struct s* ps; int a[2]; int* q;
+  q = &x.v;
+  *x.p = 1;
+  x.v = -a[0] * a[1] + *q - 2 - 1;
+  (*ps).v = *&a[0];
+}
+");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Semantic analysis: pass\n");
+}
+
+// *ps.v accesses a field of the pointer before dereferencing it
+#[test]
+fn field_of_pointer() {
+    let output = sem("field", "struct s { int* p; int v; };
+void main() {
+  struct s* ps; int v;
+  v = *ps.v;
+}
+");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("cannot access field v of a value of type struct s*"), "{}", stdout);
+}
+
+// identifiers starting with __ are left to the inliner and the lowerings
+#[test]
+fn reserved_names() {
+    let output = sem("reserved", "struct __class__C { int __vtable; };
+int __static__f__x;
+int __f() { return 1; }
+void main() {
+  int __tmp0;
+  __tmp0 = 7;
+  print_i(__f() + __tmp0);
+}
+");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for name in ["__class__C", "__vtable", "__static__f__x", "__f", "__tmp0"] {
+        assert!(stdout.contains(&format!("{} is reserved", name)), "{}", stdout);
+    }
+    assert!(stdout.contains("Semantic analysis: failed (5 errors)"), "{}", stdout);
+}
+
+// the prefix operators the parser desugars, and the binary ones, are reported as written
+#[test]
+fn operators_as_written() {
+    let output = sem("operators", "struct s { int a; };
+void main() {
+  struct s v; int* p; int i;
+  i = !v;
+  i = ~p;
+  i = p | 1;
+  i += p;
+  i = !!i + ~~i + - -i;
+}
+");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "operator ! expects an integer operand, found struct s",
+        "operator ~ expects an integer operand, found int*",
+        "operator | expects integer operands, found int* and int",
+        "operator += expects integer operands, found int and int*",
+    ] {
+        assert!(stdout.contains(expected), "{} not in\n{}", expected, stdout);
+    }
+}
+
+// an expression which fails to check is reported once, not again by the expressions using it
+#[test]
+fn no_follow_on_errors() {
+    let output = sem("follow_on", "struct s { int a; };
+int f(int x) { return x; }
+void main() {
+  struct s v; int i; int* p;
+  i = v + 1;
+  i = f(v.b) + 2;
+  i = (v + 1) ? *p : 2;
+  if (v.b) { i = 1; }
+  i = p[v.b];
+  v.b++;
+}
+");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("void"), "{}", stdout);
+    assert!(stdout.contains("Semantic analysis: failed (6 errors)"), "{}", stdout);
+}
+
+// a static local is initialised once, before the program runs, as a global
+#[test]
+fn static_initialiser() {
+    let output = sem("static", "int g;
+int h = g;
+void main() {
+  int y;
+  static int x = y;
+  static int z = 3;
+}
+");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("global initialiser must be a constant"), "{}", stdout);
+    assert!(stdout.contains("static initialiser must be a constant"), "{}", stdout);
+    assert!(stdout.contains("Semantic analysis: failed (2 errors)"), "{}", stdout);
+}
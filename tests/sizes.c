@@ -0,0 +1,41 @@
+#include "minic-stdlib.h"
+
+// sizes are those of minic on every backend, folded or not: prints 8 8 / 4 p / 24
+
+struct node {
+  int value;
+  struct node* next;
+};
+
+int folded = sizeof(struct node);
+
+char kind(int size) {
+  switch (size) {
+    case sizeof(char): return 'c';
+    case sizeof(char*): return 'p';
+    case sizeof(struct node): return 's';
+  }
+  return '?';
+}
+
+void main() {
+  struct node* nodes;
+  int i;
+  print_i(folded);
+  print_c(' ');
+  print_i(sizeof(struct node));
+  print_c('\n');
+  print_i(sizeof(char*));
+  print_c(' ');
+  print_c(kind(sizeof(int*)));
+  print_c('\n');
+  nodes = (struct node*) mcmalloc(3 * sizeof(struct node));
+  i = 0;
+  while (i < 3) {
+    nodes[i].value = i * 8;
+    nodes[i].next = nodes;
+    i = i + 1;
+  }
+  print_i(nodes[0].value + nodes[1].value + nodes[2].value);
+  print_c('\n');
+}
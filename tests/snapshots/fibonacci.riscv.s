@@ -0,0 +1,63 @@
+.data
+str0: .asciz "First "
+str1: .asciz " terms of Fibonacci series are : "
+str2: .asciz " "
+
+.text
+.globl main
+main:
+    call f_main
+    li a7, 10
+    ecall
+
+.globl f_main
+f_main:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw s0, 8(sp)
+    addi s0, sp, 16
+    li a7, 5
+    ecall
+    mv t3, a0
+    li t2, 0
+    li t1, 1
+    la a0, str0
+    li a7, 4
+    ecall
+    mv a0, t3
+    li a7, 1
+    ecall
+    la a0, str1
+    li a7, 4
+    ecall
+    li t0, 0
+L1:
+    slt t4, t0, t3
+    beqz t4, L2
+    li t4, 1
+    slt t4, t4, t0
+    xori t4, t4, 1
+    beqz t4, L3
+    mv a0, t0
+    j L4
+L3:
+    add a0, t2, t1
+    mv t2, t1
+    mv t1, a0
+L4:
+    li a7, 1
+    ecall
+    la a0, str2
+    li a7, 4
+    ecall
+    li t4, 1
+    add t0, t0, t4
+    j L1
+L2:
+    j f_main_exit
+f_main_exit:
+    addi sp, s0, -16
+    lw ra, 12(sp)
+    lw s0, 8(sp)
+    addi sp, sp, 16
+    ret
@@ -0,0 +1,61 @@
+#include "minic-stdlib.h"
+
+extern int total;
+extern int total;
+extern const int limit;
+
+const int primes[5] = {2, 3, 5, 7, 11};
+const int limit = 4;
+static int calls;
+int total;
+
+static int next_id();
+
+int next_id() {
+    static int id = 100;
+    id++;
+    return id;
+}
+
+static int count() {
+    static int n;
+    calls++;
+    n = n + 1;
+    return n;
+}
+
+int sum_primes(const int* p, int n) {
+    int s = 0;
+    int i;
+    for (i = 0; i < n; i++) {
+        s += p[i];
+    }
+    return s;
+}
+
+void report(const char* label, int value) {
+    print_s(label);
+    print_i(value);
+    print_c('\n');
+}
+
+int main() {
+    int i;
+    int n = 0;
+    const int* p = (const int*) primes;
+    int* const q = &n;
+    for (i = 0; i < limit; i++) {
+        static int seen = 0;
+        seen += primes[i];
+        total = seen;
+        n = count();
+    }
+    *q = *q + 1;
+    report((char*) "count: ", n);
+    report((char*) "calls: ", calls);
+    report((char*) "total: ", total);
+    report((char*) "sum: ", sum_primes(p, 5));
+    report((char*) "id: ", next_id());
+    report((char*) "id: ", next_id());
+    return 0;
+}
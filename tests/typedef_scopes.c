@@ -0,0 +1,38 @@
+#include "minic-stdlib.h"
+
+// a typedef name is shadowed by a variable or a parameter of the same name, and a block may declare
+// a typedef of its own
+
+typedef int T;
+
+struct Pair {
+    T T;
+    T other;
+};
+
+T next(T T) {
+    return T + 1;
+}
+
+T twice(T v) {
+    typedef T* P;
+    P p = &v;
+    return *p * 2;
+}
+
+void main() {
+    struct Pair pair;
+    int T;
+    T = 3;
+    pair.T = next(T);
+    print_i(pair.T);
+    print_c(' ');
+    print_i(twice(T));
+    print_c(' ');
+    {
+        typedef char T;
+        T c = 'a';
+        print_c(c);
+    }
+    print_c('\n');
+}
@@ -0,0 +1,68 @@
+// Parses every sample of this directory, unparses it and parses the output again, which must give the
+// same AST. Unparsing that output must give it back unchanged, the parentheses of the source
+// (`is_grouped`) included; tests/precedence.c holds the cases of grouping and precedence.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+fn run(flag: &str, source: &Path) -> Output {
+    Command::new(COMPILER).arg(flag).arg(source).output().unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let out_dir = std::env::temp_dir().join(format!("minic-unparse-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+
+    let mut sources: Vec<PathBuf> = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "c"))
+        .collect();
+    sources.sort();
+    assert!(sources.iter().any(|source| source.ends_with("precedence.c")));
+
+    let mut failures = Vec::new();
+    for source in sources.iter() {
+        let name = source.file_name().unwrap().to_string_lossy();
+        let ast = run("-ast", source);
+        let unparsed = run("-unparse", source);
+        if !ast.status.success() {
+            // a sample which does not parse is not unparsed either
+            if unparsed.status.success() {
+                failures.push(format!("{} is unparsed but does not parse", name));
+            }
+            continue;
+        }
+        if !unparsed.status.success() {
+            failures.push(format!("{} does not unparse:\n{}", name, String::from_utf8_lossy(&unparsed.stdout)));
+            continue;
+        }
+
+        let copy = out_dir.join(&*name);
+        fs::write(&copy, &unparsed.stdout).unwrap();
+        let unparsed_text = String::from_utf8_lossy(&unparsed.stdout);
+        if run("-ast", &copy).stdout != ast.stdout {
+            failures.push(format!("{} has another AST once unparsed:\n{}", name, unparsed_text));
+        }
+        if run("-unparse", &copy).stdout != unparsed.stdout {
+            failures.push(format!("{} unparses differently the second time:\n{}", name, unparsed_text));
+        }
+    }
+    fs::remove_dir_all(&out_dir).ok();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// only the prefix operators which would read back as `--` or `++` are separated from their operand
+#[test]
+fn prefix_operators() {
+    let source = std::env::temp_dir().join(format!("minic-unparse-prefix-{}.c", std::process::id()));
+    fs::write(&source, "void main() {\n  int a;\n  a = !!a + ~~a + - -a + - --a + -(-a) + !~-a;\n}\n").unwrap();
+    let unparsed = run("-unparse", &source);
+    fs::remove_file(&source).ok();
+    assert!(unparsed.status.success());
+    let text = String::from_utf8_lossy(&unparsed.stdout);
+    assert!(text.contains("a = !!a + ~~a + - -a + - --a + -(-a) + !~-a;"), "{}", text);
+}
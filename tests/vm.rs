@@ -0,0 +1,67 @@
+// Runs hand written bytecode files on `-vm`: a malformed file is rejected when it is read and an
+// invalid instruction is a runtime error, neither making the VM panic.
+use std::fs;
+use std::process::{Command, Output};
+
+const COMPILER: &str = env!("CARGO_BIN_EXE_mini-compiler");
+
+const PUSH: u8 = 0x01;
+const COPY: u8 = 0x14;
+const CALL: u8 = 0x40;
+const RET: u8 = 0x41;
+
+// a module with an 8 byte data segment and a single function main
+fn module(entry: u32, frame_size: u32, code: &[u8]) -> Vec<u8> {
+    let mut bytes = b"MCBC\x02".to_vec();
+    for value in [8, 0, 1, 4] {
+        bytes.extend_from_slice(&u32::to_le_bytes(value));
+    }
+    bytes.extend_from_slice(b"main");
+    for value in [entry, frame_size, 0, code.len() as u32] {
+        bytes.extend_from_slice(&u32::to_le_bytes(value));
+    }
+    bytes.extend_from_slice(code);
+    bytes
+}
+
+fn instruction(opcode: u8, operand: i32) -> Vec<u8> {
+    [&[opcode], &operand.to_le_bytes()[..]].concat()
+}
+
+fn vm(name: &str, bytes: &[u8]) -> Output {
+    let path = std::env::temp_dir().join(format!("minic-vm-{}-{}.mcbc", name, std::process::id()));
+    fs::write(&path, bytes).unwrap();
+    let output = Command::new(COMPILER).arg("-vm").arg(&path).output().unwrap();
+    fs::remove_file(&path).ok();
+    output
+}
+
+#[test]
+fn valid_module() {
+    let output = vm("valid", &module(0, 8, &[RET]));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn malformed_modules() {
+    let modules = [
+        ("frame", module(0, u32::MAX, &[RET])),
+        ("entry", module(1, 8, &[RET])),
+        ("call", module(0, 8, &[instruction(CALL, 1), vec![RET]].concat())),
+    ];
+    for (name, bytes) in modules {
+        let output = vm(name, &bytes);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(output.status.code(), Some(254), "{}: {}", name, stdout);
+        assert!(stdout.starts_with("Invalid bytecode file"), "{}: {}", name, stdout);
+    }
+}
+
+#[test]
+fn invalid_copy() {
+    let code = [instruction(PUSH, 16), instruction(PUSH, 16), instruction(COPY, -1), vec![RET]].concat();
+    let output = vm("copy", &module(0, 8, &code));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(235), "{}", stdout);
+    assert!(stdout.contains("Runtime error: invalid instruction at 10"), "{}", stdout);
+}
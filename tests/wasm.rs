@@ -0,0 +1,142 @@
+// Generates a WebAssembly text module for the samples of this directory which have an expected
+// output. The module must be well formed; when `wat2wasm` is installed it is assembled, and run with
+// `node` and runtime/minic_host.mjs when node is installed too. A program which does not compile is
+// expected to print its errors. A tool which is missing is reported on the standard error.
+mod common;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{expectation, has_tool, run, sample_name, skip, sources, COMPILER};
+
+// what is wrong with the shape of a module: its parentheses, the nesting of its blocks and the
+// functions it calls
+fn malformation(wat: &str) -> Option<String> {
+    // the parentheses, outside of strings and comments
+    let mut depth = 0;
+    let mut forms = 0;
+    let mut chars = wat.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => { chars.next(); },
+                        '"' => break,
+                        _ => {},
+                    }
+                }
+            },
+            ';' if chars.peek() == Some(&';') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            },
+            '(' => {
+                if depth == 0 {
+                    forms += 1;
+                }
+                depth += 1;
+            },
+            ')' if depth == 0 => return Some("unbalanced )".to_owned()),
+            ')' => depth -= 1,
+            _ => {},
+        }
+    }
+    if depth != 0 {
+        return Some(format!("{} unclosed (", depth));
+    }
+    if forms != 1 || !wat.starts_with("(module") {
+        return Some("not a single module".to_owned());
+    }
+
+    let functions: HashSet<&str> = wat.split("(func $").skip(1)
+        .map(|rest| rest.split([' ', ')', '\n']).next().unwrap())
+        .collect();
+    let mut blocks = 0;
+    for line in wat.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("block" | "loop" | "if") => blocks += 1,
+            Some("end") if blocks == 0 => return Some("end outside of a block".to_owned()),
+            Some("end") => blocks -= 1,
+            Some("call") => {
+                let callee = words.next().unwrap_or("").trim_start_matches('$');
+                if !functions.contains(callee) {
+                    return Some(format!("call to the undefined function {}", callee));
+                }
+            },
+            _ => {},
+        }
+    }
+    if blocks != 0 {
+        return Some(format!("{} unended block", blocks));
+    }
+    None
+}
+
+#[test]
+fn samples() {
+    let host = Path::new(env!("CARGO_MANIFEST_DIR")).join("runtime").join("minic_host.mjs");
+    let out_dir = std::env::temp_dir().join(format!("minic-wasm-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let with_assembler = has_tool("wat2wasm");
+    let with_node = with_assembler && has_tool("node");
+    if !with_assembler {
+        skip("wasm::samples", "wat2wasm", "assembling and running the modules");
+    } else if !with_node {
+        skip("wasm::samples", "node", "running the modules");
+    }
+
+    let mut failures = Vec::new();
+    for source in sources().iter() {
+        let (expected, input) = expectation(source);
+        let name = sample_name(source);
+        let wat = out_dir.join("program.wat");
+        let wasm = out_dir.join("program.wasm");
+        fs::remove_file(&wat).ok();
+
+        let compiled = run(Command::new(COMPILER).arg("-wat").arg(source).arg(&wat), &[]);
+        if !compiled.status.success() {
+            let output = String::from_utf8_lossy(&compiled.stdout);
+            if output != expected {
+                failures.push(format!("{} does not compile:\n{}", name, output));
+            }
+            continue;
+        }
+        let text = fs::read_to_string(&wat).unwrap();
+        if let Some(malformation) = malformation(&text) {
+            failures.push(format!("{} is malformed, {}:\n{}", name, malformation, text));
+            continue;
+        }
+        if with_assembler {
+            let assembled = run(Command::new("wat2wasm").arg(&wat).arg("-o").arg(&wasm), &[]);
+            if !assembled.status.success() {
+                failures.push(format!("{} does not assemble:\n{}", name, String::from_utf8_lossy(&assembled.stderr)));
+                continue;
+            }
+        }
+        if with_node {
+            let output = run(Command::new("node").arg(&host).arg(&wasm), &input);
+            let output = String::from_utf8_lossy(&output.stdout);
+            if output != expected {
+                failures.push(format!("{} on node:\n{}", name, output));
+            }
+        }
+    }
+    fs::remove_dir_all(&out_dir).ok();
+    assert!(failures.is_empty(), "unexpected wasm of\n{}", failures.join("\n"));
+}
+
+#[test]
+fn malformed() {
+    assert_eq!(malformation("(module\n  (func $main\n    block\n    end\n  )\n)\n"), None);
+    assert_eq!(malformation("(module (data (i32.const 16) \")(\\\"\"))"), None);
+    assert!(malformation("(module (func $main)").is_some());
+    assert!(malformation("(module) (module)").is_some());
+    assert!(malformation("(module\n  (func $main\n    loop\n))\n").is_some());
+    assert!(malformation("(module\n  (func $main\n    call $f\n))\n").is_some());
+}